@@ -2,17 +2,30 @@
 pub mod engine;
 pub mod parse_helper;
 pub mod component_configuration;
+pub mod worker;
 
 #[cfg(test)]
 mod config_tests;
 
+use std::path::PathBuf;
+use riv::Error;
+use riv::component::relay::statistics_relay::column_profile::ProfileReport;
+
 /// Messages that the UI layer can send to the background worker.
 /// 
 #[derive(Debug, Clone)]
 pub enum RivCommand {
     Parse,
-    Analyze,
-    Blueprint,
+    Analyze{source: PathBuf},
+    Blueprint{source: PathBuf, out_dir: PathBuf},
     Publish,
     Quit,
 }
+
+/// Results the background worker reports back to the UI layer.
+///
+#[derive(Debug)]
+pub enum RivResponse {
+    Analyze{source: PathBuf, result: Result<ProfileReport, Error>},
+    Blueprint{source: PathBuf, out_dir: PathBuf, result: Result<Vec<PathBuf>, Error>},
+}
@@ -1,9 +1,12 @@
 use riv::component::relay::{RelayConfig};
-use riv::component::source::{SourceConfig, SourceType};
+use riv::component::source::{Source, SourceConfig, SourceType};
 use std::error::Error;
+use std::path::PathBuf;
 use std::sync::mpsc::Sender;
 use riv::component::sink::sink_settings::SinkSettings;
 use riv::component::source::csv_string_source::CsvStringSource;
+use riv::component::source::synthetic_source::SyntheticSource;
+use riv::component::source::synthetic_spec::{SyntheticSpec, SPEC_KEY};
 use zero::component::telemetry::component_metrics::ComponentMetrics;
 use crate::engines::riv::engine::Engine;
 
@@ -25,6 +28,10 @@ impl ComponentConfiguration {
         self.source = Some(src);
     }
     
+    pub fn source_path(&self) -> Option<&PathBuf> {
+        self.source.as_ref().and_then(|boxed| boxed.path_buf())
+    }
+
     pub fn source_configuration_type(&self) -> Option<SourceType > {
         self.source
         	.as_ref()
@@ -57,11 +64,18 @@ impl ComponentConfiguration {
     /// Convert the builder into a usable `ProcessingPipeline`.
     ///
     pub fn build(&self, _: Sender<ComponentMetrics>) -> Result<Engine, Box<dyn Error>> {
-        let source = self.source.as_ref().ok_or("PipelineBuilder must have a source")?;
-        let path   = source.path_buf().ok_or("PipelineBuilder must have a source path")?;
-			let path = path.clone().into_os_string().into_string().expect("Path to string failed");
-        let source = CsvStringSource::new(path);
-        let source = Box::new(source);
+        let config = self.source.as_ref().ok_or("PipelineBuilder must have a source")?;
+        let source: Box<dyn Source> = match config.source_type() {
+            SourceType::Synthetic => {
+                let json = config.string_value(SPEC_KEY).ok_or("Synthetic source must have a spec")?;
+                Box::new(SyntheticSource::new(SyntheticSpec::from_json(&json)?)?)
+            }
+            _ => {
+                let path = config.path_buf().ok_or("PipelineBuilder must have a source path")?;
+                let path = path.clone().into_os_string().into_string().expect("Path to string failed");
+                Box::new(CsvStringSource::new(path))
+            }
+        };
         let relays = vec![];
         let sink = None;
         Ok(Engine {
@@ -1,5 +1,8 @@
 
 use riv::component::source::path_buf_config::PathBufConfig;
+use riv::component::source::SourceType;
+use riv::component::source::synthetic_spec::{ColumnSpec, Distribution, SyntheticSpec};
+use riv::model::ir::value::ValueType;
 use zero::util::file_utils::make_temp_file_named;
 use crate::engines::riv::component_configuration::ComponentConfiguration;
 
@@ -49,3 +52,18 @@ fn with_source_accepts_readable_file() {
 	println!("{:#?}", parse_ok);
 	assert!(parse_ok);
  }
+
+#[test]
+fn build_generates_rows_from_a_synthetic_spec() {
+	let column  = ColumnSpec::new("id", ValueType::Integer, Distribution::Sequence{start: 1, step: 1});
+	let spec    = SyntheticSpec::new(vec![column], 5, 0);
+	let mut cfg = ComponentConfiguration::new();
+	cfg.set_source_configuration(Box::new(spec));
+	assert!(cfg.can_parse());
+	assert_eq!(cfg.source_configuration_type(), Some(SourceType::Synthetic));
+
+	let (tx, _) = std::sync::mpsc::channel();
+	let engine  = cfg.build(tx).unwrap();
+	assert_eq!(engine.source.source_type(), SourceType::Synthetic);
+	assert_eq!(engine.source.count(), 6);                                // Header plus five rows
+}
@@ -0,0 +1,117 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::{info, warn};
+use riv::Error;
+use riv::component::relay::SimpleRelay;
+use riv::component::relay::empty_relay_config::EmptyRelayConfig;
+use riv::component::relay::statistics_relay::StatisticsRelay;
+use riv::component::relay::statistics_relay::column_profile::ProfileReport;
+use riv::model::blueprint::Blueprint;
+use riv::model::ir::atom::Atom;
+use riv::model::schema::inference::DEFAULT_SAMPLE_SIZE;
+use crate::engines::riv::{RivCommand, RivResponse};
+use crate::engines::riv::parse_helper::open_source;
+use crate::utils::format_utils::format_coordinate;
+
+/// Background thread that runs `RivCommand`s off the UI thread.
+///
+/// Commands are handled one at a time, in the order sent, and each one
+/// that produces a result answers with a `RivResponse`. Dropping the
+/// worker sends `Quit` and waits for the thread to finish.
+///
+pub struct Worker {
+	command_tx:  Sender<RivCommand>,
+	response_rx: Receiver<RivResponse>,
+	handle:      Option<JoinHandle<()>>,
+}
+
+impl Default for Worker {
+	fn default() -> Self { Self::spawn() }
+}
+
+impl Worker {
+	pub fn spawn() -> Self {
+		let (command_tx, command_rx)   = mpsc::channel::<RivCommand>();
+		let (response_tx, response_rx) = mpsc::channel::<RivResponse>();
+		let handle = std::thread::Builder::new()
+			.name("riv-worker".to_owned())
+			.spawn(move || run(command_rx, response_tx))
+			.expect("failed to spawn the riv worker thread");
+		Worker{command_tx, response_rx, handle: Some(handle)}
+	}
+
+	pub fn send(&self, command: RivCommand) -> Result<(), Error> {
+		self.command_tx.send(command).map_err(|e| Error::General(format!("Worker is not running: {}", e)))
+	}
+
+	/// The next response, if one is waiting.
+	pub fn try_response(&self) -> Option<RivResponse> { self.response_rx.try_recv().ok() }
+
+	/// The next response, waiting at most `timeout` for it.
+	pub fn wait_response(&self, timeout: Duration) -> Option<RivResponse> {
+		self.response_rx.recv_timeout(timeout).ok()
+	}
+}
+
+impl Drop for Worker {
+	fn drop(&mut self) {
+		let _ = self.command_tx.send(RivCommand::Quit);
+		if let Some(handle) = self.handle.take() {
+			let _ = handle.join();
+		}
+	}
+}
+
+fn run(commands: Receiver<RivCommand>, responses: Sender<RivResponse>) {
+	while let Ok(command) = commands.recv() {
+		let response = match command {
+			RivCommand::Analyze{source}            => {
+				let result = analyze(&source);
+				RivResponse::Analyze{source, result}
+			}
+			RivCommand::Blueprint{source, out_dir} => {
+				let result = blueprint(&source, &out_dir);
+				RivResponse::Blueprint{source, out_dir, result}
+			}
+			RivCommand::Quit                       => break,
+			other                                  => {
+				warn!("Worker does not handle {:?}", other);
+				continue
+			}
+		};
+		if responses.send(response).is_err() { break }
+	}
+	info!("Worker stopped");
+}
+
+/// Sample `source` and write its DDL, JSON Schema and data dictionary
+/// into `out_dir`. Returns the files written.
+fn blueprint(source: &PathBuf, out_dir: &Path) -> Result<Vec<PathBuf>, Error> {
+	let atoms     = open_source(source).map_err(|e| Error::General(format!("Failed to open source: {}", e)))?;
+	let table     = source.file_stem().and_then(|s| s.to_str()).unwrap_or("data");
+	let blueprint = Blueprint::from_atoms(table, atoms.inspect(report_error), DEFAULT_SAMPLE_SIZE);
+	info!("Blueprint sampled {} rows from {}", blueprint.sampled(), source.display());
+	blueprint.write_artifacts(out_dir)
+}
+
+/// Profile every column of `source`.
+fn analyze(source: &PathBuf) -> Result<ProfileReport, Error> {
+	let atoms     = open_source(source).map_err(|e| Error::General(format!("Failed to open source: {}", e)))?;
+	let mut relay = StatisticsRelay::new();
+	relay.initialize(&EmptyRelayConfig)?;
+	atoms.for_each(|atom| { report_error(&atom); relay.accept(atom); });
+	if !relay.finish() {
+		return Err(Error::General(format!("Failed to finish profiling {}", source.display())))
+	}
+	info!("Analyzed {} rows from {}", relay.rows(), source.display());
+	Ok(relay.take_report().unwrap_or_default())
+}
+
+/// Log an `ErrorAtom` from the source with where it occurred.
+fn report_error(atom: &Atom) {
+	if let Atom::ErrorAtom(error, coordinate) = atom {
+		warn!("{}: {}", format_coordinate(coordinate), error);
+	}
+}
@@ -1,26 +1,32 @@
 
 use tracing::{info, instrument, warn};
 use zero::util::file_utils::assert_readable;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::Sender;
+use std::time::Duration;
+use riv::component::relay::statistics_relay::column_profile::ProfileReport;
 use riv::component::sink::sink_settings::SinkSettings;
 use riv::component::source::path_buf_config::PathBufConfig;
 use riv::Error;
 use zero::component::telemetry::component_metrics::ComponentMetrics;
+use crate::engines::riv::{RivCommand, RivResponse};
 use crate::engines::riv::engine::Engine;
 use crate::engines::riv::parse_helper::open_source;
 use crate::engines::riv::component_configuration::ComponentConfiguration;
+use crate::engines::riv::worker::Worker;
 
 pub struct AppState {
 	metric_tx:      Sender<ComponentMetrics>,
 	config:         ComponentConfiguration,
 	engine:         Option<Engine>,
+	profile:        Option<ProfileReport>,
+	worker:         Worker,
 }
 
 impl AppState {
 	pub fn new(metric_tx: Sender<ComponentMetrics>) -> Self {
 		let pipeline_builder = ComponentConfiguration::default();
-		Self{metric_tx, config:pipeline_builder, engine: None}
+		Self{metric_tx, config:pipeline_builder, engine: None, profile: None, worker: Worker::spawn()}
 	}
 }
 
@@ -45,6 +51,55 @@ impl AppState {
 			}
 		}
 	}
+
+	/// Ask the worker to sample the selected source and write its DDL,
+	/// JSON Schema and data dictionary into `out_dir`. The files written
+	/// arrive later as a `RivResponse::Blueprint`.
+	pub fn start_blueprint(&self, out_dir: &Path) -> Result<(), Error> {
+		let source = self.config.source_path()
+			.ok_or_else(|| Error::General("Must have a valid input to start blueprint.".to_string()))?;
+		self.worker.send(RivCommand::Blueprint{source: source.clone(), out_dir: out_dir.to_path_buf()})
+	}
+
+	/// Ask the worker to profile every column of the selected source. The
+	/// report arrives later as a `RivResponse::Analyze`, and is kept for
+	/// display if the source has not changed meanwhile.
+	pub fn start_analyze(&mut self) -> Result<(), Error> {
+		let source = self.config.source_path()
+			.ok_or_else(|| Error::General("Must have a valid input to start analyze.".to_string()))?;
+		self.worker.send(RivCommand::Analyze{source: source.clone()})
+	}
+
+	/// The most recent `start_analyze` result, if the source has not changed since.
+	pub fn profile_report(&self) -> Option<&ProfileReport> { self.profile.as_ref() }
+
+	/// The next response from the worker, if one is waiting.
+	pub fn receive(&mut self) -> Option<RivResponse> {
+		let response = self.worker.try_response()?;
+		Some(self.record(response))
+	}
+
+	/// The next response from the worker, waiting at most `timeout` for it.
+	pub fn receive_timeout(&mut self, timeout: Duration) -> Option<RivResponse> {
+		let response = self.worker.wait_response(timeout)?;
+		Some(self.record(response))
+	}
+
+	/// Keep a profile report of the current source.
+	fn record(&mut self, response: RivResponse) -> RivResponse {
+		if let RivResponse::Analyze{source, result: Ok(report)} = &response
+			&& self.config.source_path() == Some(source) {
+			self.profile = Some(report.clone());
+		}
+		response
+	}
+
+	/// Default location for blueprint artifacts: `<stem>.blueprint` next to the input.
+	pub fn blueprint_directory(&self) -> Option<PathBuf> {
+		let path = self.config.source_path()?;
+		let stem = path.file_stem()?.to_str()?;
+		Some(path.with_file_name(format!("{}.blueprint", stem)))
+	}
 }
 
 impl AppState {
@@ -57,6 +112,7 @@ impl AppState {
 						let config = PathBufConfig::new(selected_file);
 						let config = Box::new(config);
 						self.config.set_source_configuration(config);
+						self.profile = None;
 					}
 					Err(e) => {
 						warn!("⚠ Bad file. Not updating source: {}: {}", selected_file.display(), e);
@@ -83,6 +139,7 @@ impl AppState {
 	
 	pub fn close_source_file(&mut self) {
 		self.config.source_reset();
+		self.profile = None;
 	}
 
 	pub fn clear_relays(&mut self) {
@@ -92,8 +149,8 @@ impl AppState {
 	// Predicates
 	//
 	pub fn can_parse(&self)                -> bool { self.config.can_parse() }
-	pub fn can_analyze(&self)              -> bool { false }
-	pub fn can_blueprint(&self)            -> bool { false }
+	pub fn can_analyze(&self)              -> bool { self.config.can_parse() }
+	pub fn can_blueprint(&self)            -> bool { self.config.can_parse() }
 	pub fn can_publish(&self)              -> bool { self.config.can_publish() }
 
 	pub fn has_selected_relays(&self)      -> bool { false                     }
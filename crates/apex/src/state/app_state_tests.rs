@@ -1,4 +1,7 @@
 
+use std::time::Duration;
+use zero::util::file_utils::make_temp_file_with_content;
+use crate::engines::riv::RivResponse;
 use crate::state::app_state::AppState;
 
  // ---------- tests -------------------------------------------------------
@@ -12,3 +15,46 @@ use crate::state::app_state::AppState;
 	  assert!(!s.can_blueprint());
 	  assert!(!s.can_publish());
  }
+
+ #[test]
+ fn blueprint_writes_artifacts_for_selected_source() {
+	  let (sender, _) = std::sync::mpsc::channel();
+	  let mut s       = AppState::new(sender);
+	  let input       = make_temp_file_with_content("apex_blueprint_orders.csv", "id;name\n1;alpha\n2;\n");
+	  s.set_source_path(input);
+	  assert!(s.can_blueprint());
+
+	  let out_dir = s.blueprint_directory().unwrap();
+	  s.start_blueprint(&out_dir).unwrap();
+	  let written = match s.receive_timeout(Duration::from_secs(10)) {
+		  Some(RivResponse::Blueprint{result, ..}) => result.unwrap(),
+		  other                                    => panic!("expected a blueprint response, got {:?}", other),
+	  };
+	  assert_eq!(written.len(), 4);
+	  let ddl = std::fs::read_to_string(out_dir.join("apex_blueprint_orders.sqlserver.sql")).unwrap();
+	  assert!(ddl.contains("[id] BIGINT NOT NULL"));
+	  assert!(ddl.contains("[name] NVARCHAR(5)"));
+	  std::fs::remove_dir_all(out_dir).unwrap();
+ }
+
+ #[test]
+ fn analyze_profiles_selected_source() {
+	  let (sender, _) = std::sync::mpsc::channel();
+	  let mut s       = AppState::new(sender);
+	  let input       = make_temp_file_with_content("apex_analyze_orders.csv", "id;name\n1;alpha\n2;\n3;alpha\n");
+	  s.set_source_path(input);
+	  assert!(s.can_analyze());
+
+	  s.start_analyze().unwrap();
+	  let report = match s.receive_timeout(Duration::from_secs(10)) {
+		  Some(RivResponse::Analyze{result, ..}) => result.unwrap(),
+		  other                                  => panic!("expected an analyze response, got {:?}", other),
+	  };
+	  assert_eq!(report.rows, 3);
+	  let name = report.column("name").unwrap();
+	  assert_eq!((name.non_null, name.nulls, name.distinct), (2, 1, 1));
+	  assert_eq!(s.profile_report().unwrap().column("id").unwrap().numeric.as_ref().unwrap().max, 3.0);
+
+	  s.close_source_file();
+	  assert!(s.profile_report().is_none());
+ }
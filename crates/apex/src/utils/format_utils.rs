@@ -1,4 +1,5 @@
 use num_format::{Locale, ToFormattedString};
+use riv::model::coordinate::coordinate::Coordinate;
 
 pub fn format_u64(n: u64) -> String {
     n.to_formatted_string(&Locale::en)        // 123 456 789 → "123,456,789"
@@ -7,3 +8,11 @@ pub fn format_u64(n: u64) -> String {
 pub fn format_u32(n: u32) -> String {
     n.to_formatted_string(&Locale::en)
 }
+
+/// Describes where in the source an atom came from, e.g. "line 18,204, byte 2,331,790".
+pub fn format_coordinate(c: &Coordinate) -> String {
+    match c.start() {
+        None    => "unknown location".to_string(),
+        Some(v) => format!("line {}, byte {}", format_u64(v.line), format_u64(v.byte)),
+    }
+}
@@ -29,14 +29,18 @@ fn run() -> Result<(), Error> {
 
 	// Assume you have some Atoms
 	let atoms = vec![];
+	let mut out = Vec::new();
 	for atom in atoms {
-		match relay.accept(atom) {
-			None => {}
-			Some(a) => sink.accept(a)?,
+		relay.accept(atom, &mut out);
+		for a in out.drain(..) {
+			sink.accept(a)?;
 		}
 	}
 
-	let relay_ok = relay.finish();
+	let relay_ok = relay.finish(&mut out).is_ok();
+	for a in out.drain(..) {
+		sink.accept(a)?;
+	}
 	if relay_ok {
 		sink.close();
 		let collected: Vec<Atom> = sink.drain_atoms();
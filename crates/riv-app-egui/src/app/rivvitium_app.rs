@@ -7,7 +7,7 @@ use crate::ui::{dialogs, UiState};
 use crate::ui::menu::create_menu_bar;
 use std::fmt::Debug;
 use std::sync::mpsc;
-use apex::engines::riv::RivCommand;
+use apex::engines::riv::{RivCommand, RivResponse};
 use crate::ui::regions::ApplicationStatus;
 use crate::ui::visuals::colors::ColorTheme;
 use tracing::{info, warn};
@@ -50,8 +50,22 @@ impl RivvitiumApp {
 			}
 		}
 	}
-	pub fn fire_analyze_command(&mut self) {}
-	pub fn fire_blueprint_command(&mut self) {}
+	pub fn fire_analyze_command(&mut self) {
+		match self.app_state.start_analyze() {
+			Ok(())   => info!("Analyze command sent"),
+			Err(err) => warn!("analyze failed: {}", err),
+		}
+	}
+	pub fn fire_blueprint_command(&mut self) {
+		let Some(out_dir) = self.app_state.blueprint_directory() else {
+			warn!("No source selected. Blueprint command was not sent.");
+			return
+		};
+		match self.app_state.start_blueprint(&out_dir) {
+			Ok(())   => info!("Blueprint command sent for {}", out_dir.display()),
+			Err(err) => warn!("blueprint failed: {}", err),
+		}
+	}
 	
 	pub fn fire_publish_command(&mut self) {
 		if !self.app_state.can_publish() {
@@ -114,6 +128,16 @@ impl eframe::App for RivvitiumApp {
  */
             ctx.request_repaint();  // keep UI fluid even if worker is slow
         }
+        // ── 2. pump the worker's responses  ────────────────────────────
+        while let Some(response) = self.app_state.receive() {
+			match response {
+				RivResponse::Analyze{result: Ok(report), ..}           => info!("Analyze profiled {} rows across {} columns", report.rows, report.columns.len()),
+				RivResponse::Analyze{source, result: Err(err)}         => warn!("analyze of {} failed: {}", source.display(), err),
+				RivResponse::Blueprint{out_dir, result: Ok(files), ..} => info!("Blueprint wrote {} artifacts to {}", files.len(), out_dir.display()),
+				RivResponse::Blueprint{source, result: Err(err), ..}   => warn!("blueprint of {} failed: {}", source.display(), err),
+			}
+            ctx.request_repaint();
+        }
         self.ensure_logo_loaded(ctx);
 
 
@@ -40,7 +40,7 @@ pub fn draw_main_screen(app: &mut RivvitiumApp, ctx: &egui::Context) {
             });
         });
         ui.separator();                         // thin line between the rows
-			data_view(ui);
+			data_view(ui, app.app_state.profile_report());
         ui.separator();                         // thin line between the rows
         activity_view(ui, &dummy);
     });
@@ -111,7 +111,12 @@ fn draw_run_menu(ui: &mut egui::Ui, state: &mut RivvitiumApp) {
 				state.fire_parse_command();
 			}
         if ui.button("TODO: Analyze").clicked() {};
-        if ui.button("TODO: Blueprint").clicked() {};
+			let enabled = state.app_state.can_blueprint();
+			let text    = RichText::new("Blueprint selected file");
+			let button  = Button::new(text);
+			if ui.add_enabled(enabled, button).clicked() {
+				state.fire_blueprint_command();
+			}
     });
 }
 
@@ -1,17 +1,22 @@
 use eframe::epaint::StrokeKind;
 use egui::{Color32, Sense, Stroke, Painter, Rect, Pos2, vec2};
+use riv::component::relay::statistics_relay::column_profile::ProfileReport;
 
-/// Temporary stand-in for the real data table / chart.
+/// Shows the column profile from the last Analyze, or a placeholder.
 /// Call with:
 ///
 /// ```rust
-/// data_view(ui);   // inside an `allocate_ui` block
+/// data_view(ui, app.app_state.profile_report());   // inside an `allocate_ui` block
 /// ```
 ///
-/// The widget has no state: each frame it just draws the placeholder.
-pub fn data_view(ui: &mut egui::Ui) {
+/// The widget has no state: each frame it draws whatever it is given.
+pub fn data_view(ui: &mut egui::Ui, report: Option<&ProfileReport>) {
     let remaining = ui.available_size();          
     let desired   = vec2(remaining.x, remaining.y - 150.0);
+    if let Some(report) = report {
+        profile_table(ui, report, desired);
+        return
+    }
     let (rect, _resp) = ui.allocate_exact_size(desired, Sense::hover());
 
     let painter: Painter = ui.painter_at(rect);
@@ -47,4 +52,44 @@ pub fn data_view(ui: &mut egui::Ui) {
         egui::FontId::proportional(16.0),
         Color32::DARK_GRAY,
     );
-}
\ No newline at end of file
+}
+/// One row per column: type, null and distinct counts, lengths, numeric range and top values.
+fn profile_table(ui: &mut egui::Ui, report: &ProfileReport, size: egui::Vec2) {
+    ui.allocate_ui(size, |ui| {
+        ui.label(format!("Profiled {} rows", report.rows));
+        egui::ScrollArea::both().max_height(size.y).show(ui, |ui| {
+            egui::Grid::new("profile_table").striped(true).show(ui, |ui| {
+                for heading in ["Column", "Type", "Non-null", "Nulls", "Empty", "Distinct", "Length", "Min", "Max", "Mean", "Std dev", "Top values"] {
+                    ui.strong(heading);
+                }
+                ui.end_row();
+
+                for column in &report.columns {
+                    let distinct = if column.distinct_exact { column.distinct.to_string() } else { format!("~{}", column.distinct) };
+                    let length   = match (column.min_length, column.max_length) {
+                        (Some(min), Some(max)) => format!("{}–{}", min, max),
+                        _                      => String::new(),
+                    };
+                    let numbers  = column.numeric.as_ref()
+                        .map(|n| [n.min, n.max, n.mean, n.std_dev].map(|v| format!("{:.2}", v)))
+                        .unwrap_or_default();
+                    let top      = column.top_values.iter().take(3)
+                        .map(|v| format!("{} ({})", v.value, v.count))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+
+                    ui.label(column.name.as_str());
+                    ui.label(column.value_type.to_string());
+                    ui.label(column.non_null.to_string());
+                    ui.label(column.nulls.to_string());
+                    ui.label(column.empty.to_string());
+                    ui.label(distinct);
+                    ui.label(length);
+                    for n in numbers { ui.label(n); }
+                    ui.label(top);
+                    ui.end_row();
+                }
+            });
+        });
+    });
+}
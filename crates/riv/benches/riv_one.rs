@@ -1,50 +1,283 @@
+use std::fs::File;
+use std::io::{Cursor, Write};
+use std::sync::Arc;
+use std::time::Duration;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Bencher, BenchmarkGroup, BenchmarkId, Criterion, Throughput};
+use chrono::NaiveDate;
+use criterion::measurement::WallTime;
+use tempfile::{NamedTempFile, TempDir};
+use riv::component::batching::Batched;
+use riv::component::relay::empty_relay_config::EmptyRelayConfig;
+use riv::component::relay::statistics_relay::StatisticsRelay;
+use riv::component::relay::SimpleRelay;
+use riv::component::sink::sink_settings::SinkSettings;
+use riv::component::sink::Sink;
+use riv::component::source::csv_byte_source::CsvByteSource;
+use riv::component::source::csv_string_source::CsvStringSource;
+use riv::component::source::synthetic_source::SyntheticSource;
+use riv::component::source::synthetic_spec::{ColumnSpec, Distribution, SyntheticSpec};
+use riv::model::ir::atom::Atom;
+use riv::model::ir::byte_row::ByteRow;
+use riv::model::ir::row_block::RowBlock;
+use riv::model::ir::value::ValueType;
 
-use criterion::{criterion_group, criterion_main, Criterion};
+// Every suite reports each routine twice: once as MB/s of CSV text and
+// once as rows/s, so either kind of regression is visible.
+//
+// Run a subset with e.g. `cargo bench -p riv --bench riv_one -- sources`.
+//
+
+const WIDTHS:      [usize; 2] = [4, 32];
+const ROW_COUNTS:  [usize; 2] = [10_000, 100_000];
+const SINK_ROWS:   usize      = 10_000;
+const BATCH_ROWS:  usize      = 1_024;
 
-// Example function to benchmark
-fn fibonacci(n: u64) -> u64 {
-    match n {
-        0 => 1,
-        1 => 1,
-        _ => fibonacci(n - 1) + fibonacci(n - 2),
-    }
+/// Synthetic CSV text together with a file holding the same bytes.
+struct Workload {
+	width: usize,
+	rows:  usize,
+	csv:   Vec<u8>,
+	file:  NamedTempFile,
 }
 
-/*
-fn run_pipeline_test() {
-    test_capture_of_start_and_end();
+impl Workload {
+	fn new(width: usize, rows: usize) -> Self {
+		let csv      = synthetic_csv(width, rows);
+		let mut file = NamedTempFile::new().expect("temp file");
+		file.write_all(&csv).expect("write workload");
+		file.flush().expect("flush workload");
+		Workload{width, rows, csv, file}
+	}
+
+	/// `<name>` and `<width>x<rows>`, the two halves of a benchmark id.
+	fn id<'a>(&self, name: &'a str) -> (&'a str, String) {
+		(name, format!("{}x{}", self.width, self.rows))
+	}
+
+	fn path(&self) -> String {
+		self.file.path().to_string_lossy().into_owned()
+	}
+
+	/// Header plus data rows as the byte source produces them.
+	fn parsed(&self) -> (ByteRow, Vec<ByteRow>) {
+		let mut header = None;
+		let mut rows   = Vec::with_capacity(self.rows);
+		for atom in CsvByteSource::new(Cursor::new(self.csv.as_slice())) {
+			match atom {
+				Atom::HeaderRow(h)     => header = Some(h),
+				Atom::ByteRowAtom(row) => rows.push(row),
+				_                      => {}
+			}
+		}
+		let header = header.expect("workload has a header");
+		let names  = header.iter_str().collect::<Vec<_>>();
+		let ends   = names.iter().scan(0, |end, n| { *end += n.len(); Some(*end) }).collect::<Vec<_>>();
+		(ByteRow::new(names.concat().as_bytes(), &ends), rows)
+	}
 }
-*/
 
-// A benchmark function
-// You can group benchmarks for better organization in reports
-// "fib 30" is another benchmark in the same group
-//
-fn bench_fibonacci(c: &mut Criterion) {
-    c.bench_function("fib 20", |b| b.iter(|| fibonacci(20)));
-    c.bench_function("fib 30", |b| b.iter(|| fibonacci(30)));
+/// Columns cycling through integers, decimals, text and dates.
+fn synthetic_spec(width: usize, rows: usize) -> SyntheticSpec {
+	let start   = NaiveDate::from_ymd_opt(2025, 1, 1).expect("valid date");
+	let end     = NaiveDate::from_ymd_opt(2025, 12, 31).expect("valid date");
+	let columns = (0..width)
+		.map(|c| {
+			let name = format!("col_{}", c);
+			match c % 4 {
+				0 => ColumnSpec::new(name, ValueType::Integer, Distribution::Sequence{start: c as i64, step: 31}),
+				1 => ColumnSpec::new(name, ValueType::Decimal, Distribution::Uniform{min: 0.0, max: 10_000.0, scale: 2}),
+				2 => ColumnSpec::new(name, ValueType::String,  Distribution::RandomString{min_len: 6, max_len: 14}),
+				_ => ColumnSpec::new(name, ValueType::Date,    Distribution::Dates{start, end}),
+			}
+		})
+		.collect();
+	SyntheticSpec::new(columns, rows as u64, 42)
 }
 
-// You can create multiple benchmark groups if needed
-//
-fn bench_another_function(c: &mut Criterion) {
-    c.bench_function("some_other_op", |b| b.iter(|| {
-        // Code to benchmark
-        let _ = "hello".to_string() + " world";
-    }));
+/// The synthetic rows as `;` delimited CSV text.
+fn synthetic_csv(width: usize, rows: usize) -> Vec<u8> {
+	let mut csv = Vec::new();
+	for atom in SyntheticSource::new(synthetic_spec(width, rows)).expect("valid spec") {
+		let fields = match &atom {
+			Atom::HeaderRow(header) => header.iter_str().collect::<Vec<_>>(),
+			Atom::ByteRowAtom(row)  => row.iter_str().collect::<Vec<_>>(),
+			_                       => continue,
+		};
+		csv.extend_from_slice(fields.join(";").as_bytes());
+		csv.push(b'\n');
+	}
+	csv
 }
 
+fn group<'a>(c: &'a mut Criterion, name: &str) -> BenchmarkGroup<'a, WallTime> {
+	let mut group = c.benchmark_group(name);
+	group.sample_size(20);
+	group.warm_up_time(Duration::from_secs(1));
+	group.measurement_time(Duration::from_secs(3));
+	group
+}
 
-// Register your benchmark functions with criterion
-// For larger projects, you might split into multiple groups:
-//
-//   criterion_group!(name_of_group_1, bench_func_1, bench_func_2);
-//   criterion_group!(name_of_group_2, bench_func_3);
-//   criterion_main!(name_of_group_1, name_of_group_2);
-//
-criterion_group!(benches, bench_fibonacci, bench_another_function);
+/// Measure `routine` as a byte rate and as a row rate.
+fn bench_rates<F: FnMut(&mut Bencher)>(group: &mut BenchmarkGroup<WallTime>, (name, parameter): (&str, String), bytes: usize, rows: usize, mut routine: F) {
+	group.throughput(Throughput::Bytes(bytes as u64));
+	group.bench_function(BenchmarkId::new(format!("{}/MBps", name), &parameter), &mut routine);
+	group.throughput(Throughput::Elements(rows as u64));
+	group.bench_function(BenchmarkId::new(format!("{}/rows", name), &parameter), &mut routine);
+}
 
-// This macro generates the main function for your benchmarks
-//
-criterion_main!(benches);
+fn atoms_for(header: &ByteRow, rows: &[ByteRow]) -> Vec<Atom> {
+	let mut atoms = Vec::with_capacity(rows.len() + 1);
+	atoms.push(Atom::HeaderRow(header.as_string_row()));
+	atoms.extend(rows.iter().cloned().map(Atom::ByteRowAtom));
+	atoms
+}
+
+fn write_all(mut sink: Box<dyn Sink>, settings: &SinkSettings, atoms: Vec<Atom>) {
+	sink.initialize(settings).expect("sink initialize");
+	for atom in atoms {
+		sink.accept(atom).expect("sink accept");
+	}
+	sink.close();
+}
 
+// ---------------------------------------------------------------------------
+// Sources: CsvByteSource against CsvStringSource over the same files, and
+// the generator that produced them
+// ---------------------------------------------------------------------------
+
+fn bench_sources(c: &mut Criterion) {
+	let mut group = group(c, "sources");
+	for width in WIDTHS {
+		for rows in ROW_COUNTS {
+			let load = Workload::new(width, rows);
+			bench_rates(&mut group, load.id("csv_byte_source"), load.csv.len(), rows, |b| b.iter(|| {
+				let file = File::open(load.file.path()).expect("open workload");
+				black_box(CsvByteSource::new(file).count())
+			}));
+			bench_rates(&mut group, load.id("csv_string_source"), load.csv.len(), rows, |b| b.iter(|| {
+				black_box(CsvStringSource::new(load.path()).count())
+			}));
+			bench_rates(&mut group, load.id("synthetic_source"), load.csv.len(), rows, |b| b.iter(|| {
+				black_box(SyntheticSource::new(synthetic_spec(width, rows)).expect("valid spec").count())
+			}));
+		}
+	}
+	group.finish();
+}
+
+// ---------------------------------------------------------------------------
+// Rows: building rows, and ByteRow → StringRow conversion
+// ---------------------------------------------------------------------------
+
+fn bench_rows(c: &mut Criterion) {
+	let mut group = group(c, "rows");
+	for width in WIDTHS {
+		let load      = Workload::new(width, ROW_COUNTS[0]);
+		let (_, rows) = load.parsed();
+		bench_rates(&mut group, load.id("byte_row_to_string_row"), load.csv.len(), rows.len(), |b| b.iter(|| {
+			for row in &rows {
+				black_box(row.as_string_row());
+			}
+		}));
+
+		// Rows that copy their own bytes against rows that share one block
+		let data = rows.iter().flat_map(|r| r.data().to_vec()).collect::<Vec<_>>();
+		let mut ends = Vec::with_capacity(rows.len() * width);
+		let mut starts = Vec::with_capacity(rows.len());
+		for row in &rows {
+			let base = ends.last().copied().unwrap_or(0);
+			starts.push(base);
+			ends.extend(row.into_iter().scan(base, |end, f| { *end += f.len(); Some(*end) }));
+		}
+		bench_rates(&mut group, load.id("byte_row_copied"), load.csv.len(), rows.len(), |b| b.iter(|| {
+			for (r, start) in starts.iter().enumerate() {
+				let fields = &ends[r * width..(r + 1) * width];
+				let local  = fields.iter().map(|e| e - start).collect::<Vec<_>>();
+				black_box(ByteRow::new(&data[*start..fields[width - 1]], &local));
+			}
+		}));
+		bench_rates(&mut group, load.id("byte_row_shared"), load.csv.len(), rows.len(), |b| b.iter(|| {
+			let block = Arc::new(RowBlock::new(data.clone(), ends.clone()));
+			for (r, start) in starts.iter().enumerate() {
+				black_box(ByteRow::in_block(Arc::clone(&block), *start, r * width..(r + 1) * width));
+			}
+		}));
+	}
+	group.finish();
+}
+
+// ---------------------------------------------------------------------------
+// Sinks: write rates for SqliteSink and CsvSink
+// ---------------------------------------------------------------------------
+
+fn bench_sinks(c: &mut Criterion) {
+	let mut group = group(c, "sinks");
+	for width in WIDTHS {
+		let load           = Workload::new(width, SINK_ROWS);
+		let (header, rows) = load.parsed();
+		let bytes          = load.csv.len();
+
+		bench_rates(&mut group, load.id("csv_sink"), bytes, rows.len(), |b| b.iter_batched(
+			|| (TempDir::new().expect("temp dir"), atoms_for(&header, &rows)),
+			|(dir, atoms)| {
+				let settings = SinkSettings::csv(dir.path().join("out.csv"), ';');
+				let (tx, _)  = std::sync::mpsc::channel();
+				write_all(settings.build_sink(1, tx).expect("csv sink"), &settings, atoms);
+				dir
+			},
+			BatchSize::PerIteration,
+		));
+
+		bench_rates(&mut group, load.id("sqlite_sink_batched"), bytes, rows.len(), |b| b.iter_batched(
+			|| {
+				let atoms = Batched::new(atoms_for(&header, &rows).into_iter(), BATCH_ROWS).collect::<Vec<_>>();
+				(TempDir::new().expect("temp dir"), atoms)
+			},
+			|(dir, atoms)| {
+				let settings = SinkSettings::sqlite(dir.path().join("out.db"), "bench");
+				let (tx, _)  = std::sync::mpsc::channel();
+				write_all(settings.build_sink(2, tx).expect("sqlite sink"), &settings, atoms);
+				dir
+			},
+			BatchSize::PerIteration,
+		));
+	}
+	group.finish();
+}
+
+// ---------------------------------------------------------------------------
+// Pipeline: file → CsvByteSource → StatisticsRelay → CsvSink
+// ---------------------------------------------------------------------------
+
+fn bench_pipeline(c: &mut Criterion) {
+	let mut group = group(c, "pipeline");
+	for width in WIDTHS {
+		let load = Workload::new(width, ROW_COUNTS[1]);
+		bench_rates(&mut group, load.id("csv_to_csv"), load.csv.len(), load.rows, |b| b.iter_batched(
+			|| {
+				let mut relay = StatisticsRelay::new();
+				relay.initialize(&EmptyRelayConfig).expect("relay initialize");
+				(TempDir::new().expect("temp dir"), relay)
+			},
+			|(dir, mut relay)| {
+				let settings = SinkSettings::csv(dir.path().join("out.csv"), ';');
+				let (tx, _)  = std::sync::mpsc::channel();
+				let mut sink = settings.build_sink(3, tx).expect("csv sink");
+				sink.initialize(&settings).expect("sink initialize");
+				let file = File::open(load.file.path()).expect("open workload");
+				for atom in CsvByteSource::new(file) {
+					if let Some(atom) = relay.accept(atom) {
+						sink.accept(atom).expect("sink accept");
+					}
+				}
+				sink.close();
+				(dir, relay)
+			},
+			BatchSize::PerIteration,
+		));
+	}
+	group.finish();
+}
+
+criterion_group!(benches, bench_sources, bench_rows, bench_sinks, bench_pipeline);
+criterion_main!(benches);
@@ -1,3 +1,7 @@
+pub mod batching;
 pub mod relay;
 pub mod source;
 pub mod sink;
+
+#[cfg(test)]
+mod batching_tests;
@@ -0,0 +1,109 @@
+use crate::Error;
+use crate::component::source::{Source, SourceType};
+use crate::model::ir::atom::Atom;
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::row_batch::RowBatch;
+
+pub const DEFAULT_BATCH_ROWS: usize = 1_024;
+
+/// Groups consecutive `ByteRowAtom`s into `RowBatchAtom`s of up to
+/// `batch_rows` rows.
+///
+/// Any other atom closes the batch in progress and follows it, so the
+/// order of the stream is unchanged. A row of a different width than the
+/// batch also closes it and starts the next one.
+///
+/// Wrapping a `Source` yields a `Source`, which is how a source is made
+/// to emit batches.
+///
+pub struct Batched<I> {
+	atoms:      I,
+	batch_rows: usize,
+	carry:      Option<ByteRow>,
+	pending:    Option<Atom>,
+}
+
+impl<I: Iterator<Item = Atom>> Batched<I> {
+	pub fn new(atoms: I, batch_rows: usize) -> Self {
+		let batch_rows = batch_rows.max(1);
+		Batched{atoms, batch_rows, carry: None, pending: None}
+	}
+
+	pub fn into_inner(self) -> I { self.atoms }
+}
+
+impl<I: Iterator<Item = Atom>> Iterator for Batched<I> {
+	type Item = Atom;
+
+	fn next(&mut self) -> Option<Atom> {
+		if let Some(atom) = self.pending.take() { return Some(atom) }
+
+		let mut batch = self.carry.take().map(|row| start_batch(&row));
+		for atom in self.atoms.by_ref() {
+			match atom {
+				Atom::ByteRowAtom(row) => {
+					let current = batch.get_or_insert_with(|| RowBatch::new(row.length() as usize));
+					if current.push_row(&row).is_err() {
+						self.carry = Some(row);                          // Width changed: it starts the next batch
+						break;
+					}
+					if current.len() >= self.batch_rows { break }
+				}
+				other => {
+					if batch.is_none() { return Some(other) }
+					self.pending = Some(other);
+					break;
+				}
+			}
+		}
+		batch.map(Atom::RowBatchAtom)
+	}
+}
+
+impl<S: Source> Source for Batched<S> {
+	fn source_type(&self) -> SourceType { self.atoms.source_type() }
+
+	fn close(&mut self) -> Result<bool, Error> { self.atoms.close() }
+}
+
+/// Splits every `RowBatchAtom` back into `ByteRowAtom`s for consumers
+/// that work a row at a time. Other atoms pass through.
+///
+pub struct Unbatched<I> {
+	atoms: I,
+	rows:  std::vec::IntoIter<ByteRow>,
+}
+
+impl<I: Iterator<Item = Atom>> Unbatched<I> {
+	pub fn new(atoms: I) -> Self {
+		Unbatched{atoms, rows: Vec::new().into_iter()}
+	}
+
+	pub fn into_inner(self) -> I { self.atoms }
+}
+
+impl<I: Iterator<Item = Atom>> Iterator for Unbatched<I> {
+	type Item = Atom;
+
+	fn next(&mut self) -> Option<Atom> {
+		loop {
+			if let Some(row) = self.rows.next() { return Some(Atom::ByteRowAtom(row)) }
+			match self.atoms.next()? {
+				Atom::RowBatchAtom(batch) => self.rows = batch.to_rows().into_iter(),
+				other                     => return Some(other),
+			}
+		}
+	}
+}
+
+impl<S: Source> Source for Unbatched<S> {
+	fn source_type(&self) -> SourceType { self.atoms.source_type() }
+
+	fn close(&mut self) -> Result<bool, Error> { self.atoms.close() }
+}
+
+fn start_batch(row: &ByteRow) -> RowBatch {
+	let mut batch = RowBatch::new(row.length() as usize);
+	let _         = batch.push_row(row);                            // Same width by construction
+	batch
+}
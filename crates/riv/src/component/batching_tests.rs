@@ -0,0 +1,54 @@
+use crate::component::batching::{Batched, Unbatched};
+use crate::component::source::csv_byte_source::CsvByteSource;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::byte_row::ByteRow;
+
+fn kinds(atoms: &[Atom]) -> Vec<String> {
+	atoms.iter()
+		.map(|a| match a {
+			Atom::RowBatchAtom(b) => format!("batch({})", b.len()),
+			Atom::ByteRowAtom(_)  => "row".to_owned(),
+			Atom::HeaderRow(_)    => "header".to_owned(),
+			Atom::ErrorAtom(..)   => "error".to_owned(),
+			other                 => format!("{:?}", other),
+		})
+		.collect()
+}
+
+#[test]
+fn rows_are_grouped_up_to_the_batch_size() {
+	let csv   = "a;b\n1;2\n3;4\n5;6\n";
+	let atoms = Batched::new(CsvByteSource::new(csv.as_bytes()), 2).collect::<Vec<_>>();
+	assert_eq!(kinds(&atoms), ["header", "batch(2)", "batch(1)"]);
+}
+
+#[test]
+fn other_atoms_end_a_batch_and_keep_their_place() {
+	let csv   = "a;b\n1;2\n3;4;extra\n5;6\n";
+	let atoms = Batched::new(CsvByteSource::new(csv.as_bytes()), 10).collect::<Vec<_>>();
+	assert_eq!(kinds(&atoms), ["header", "batch(1)", "error", "batch(1)"]);
+}
+
+#[test]
+fn a_change_of_width_starts_a_new_batch() {
+	let rows  = vec![
+		Atom::ByteRowAtom(ByteRow::new(b"ab", &[1, 2])),
+		Atom::ByteRowAtom(ByteRow::new(b"abc", &[1, 2, 3])),
+	];
+	let atoms = Batched::new(rows.into_iter(), 10).collect::<Vec<_>>();
+	assert_eq!(kinds(&atoms), ["batch(1)", "batch(1)"]);
+}
+
+#[test]
+fn unbatched_restores_the_original_rows() {
+	let csv     = "a;b\n1;2\n3;4\n5;6\n";
+	let direct  = CsvByteSource::new(csv.as_bytes()).collect::<Vec<_>>();
+	let rebuilt = Unbatched::new(Batched::new(CsvByteSource::new(csv.as_bytes()), 2)).collect::<Vec<_>>();
+	assert_eq!(kinds(&rebuilt), kinds(&direct));
+	for (a, b) in direct.iter().zip(&rebuilt) {
+		if let (Atom::ByteRowAtom(a), Atom::ByteRowAtom(b)) = (a, b) {
+			assert_eq!(a.iter_str().collect::<Vec<_>>(), b.iter_str().collect::<Vec<_>>());
+			assert_eq!(a.coordinate(), b.coordinate());
+		}
+	}
+}
@@ -1,18 +1,224 @@
+pub mod aggregate_relay;
 pub mod console_relay;
+pub mod dedupe_relay;
+pub mod derive_relay;
+pub mod filter_relay;
+pub mod fingerprint_relay;
+pub mod header_relay;
 pub mod statistics_relay;
 pub mod empty_relay_config;
+pub mod lookup_relay;
+pub mod map_relay_config;
+pub mod mask_relay;
+pub mod normalize_relay;
+pub mod pivot_relay;
+pub mod project_relay;
+pub mod sample_relay;
+pub mod sort_relay;
+pub mod split_relay;
+pub mod validation_relay;
+
+#[cfg(test)]
+mod aggregate_relay_tests;
+#[cfg(test)]
+mod dedupe_relay_tests;
+#[cfg(test)]
+mod derive_relay_tests;
+#[cfg(test)]
+mod filter_relay_tests;
+#[cfg(test)]
+mod fixtures;
+#[cfg(test)]
+mod fingerprint_relay_tests;
+#[cfg(test)]
+mod header_relay_tests;
+#[cfg(test)]
+mod lookup_relay_tests;
+#[cfg(test)]
+mod mask_relay_tests;
+#[cfg(test)]
+mod normalize_relay_tests;
+#[cfg(test)]
+mod pivot_relay_tests;
+#[cfg(test)]
+mod project_relay_tests;
+#[cfg(test)]
+mod sample_relay_tests;
+#[cfg(test)]
+mod sort_relay_tests;
+#[cfg(test)]
+mod split_relay_tests;
+#[cfg(test)]
+mod statistics_relay_tests;
+#[cfg(test)]
+mod validation_relay_tests;
 
 use std::fmt::{Debug, Display};
+use tracing::warn;
 use crate::Error;
+use crate::model::coordinate::coordinate::Coordinate;
 use crate::model::ir::atom::Atom;
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::row_batch::RowBatch;
+
+/// Receives the atoms a relay produces.
+pub trait Collector {
+	fn emit(&mut self, atom: Atom);
+}
+
+impl Collector for Vec<Atom> {
+	fn emit(&mut self, atom: Atom) { self.push(atom) }
+}
+
+/// `rows` as one `RowBatchAtom`. Rows a batch cannot hold together, being
+/// of different widths, are reported as one `ErrorAtom` rather than lost
+/// without a trace.
+pub(crate) fn batch_atom(relay: &str, rows: &[ByteRow]) -> Atom {
+	match RowBatch::from_rows(rows) {
+		Ok(batch)  => Atom::RowBatchAtom(batch),
+		Err(error) => {
+			let error = Error::General(format!("[{}]: dropped a batch of {} rows: {}", relay, rows.len(), error));
+			warn!("{}", error);
+			Atom::ErrorAtom(error, rows.first().map_or(Coordinate::Undefined, ByteRow::coordinate))
+		}
+	}
+}
 
+/// A processing step between a source and a sink.
+///
+/// `accept` may emit any number of atoms for each one it receives, and
+/// `finish`, called once after the last atom, may emit atoms it held back,
+/// such as the rows of a sort. A relay that only passes, drops or replaces
+/// one atom at a time is simpler to write as a `SimpleRelay`.
 pub trait Relay
+{
+	fn initialize(&mut self, cfg: &dyn RelayConfig)          -> Result<(), Error>;
+	fn accept(&mut self, atom: Atom, out: &mut dyn Collector);
+	/// Emit any atoms still held. An error means the output is incomplete.
+	fn finish(&mut self, out: &mut dyn Collector)            -> Result<(), Error>;
+}
+
+/// A relay that turns each atom into at most one, and holds nothing back.
+/// Every `SimpleRelay` is a `Relay`.
+pub trait SimpleRelay
 {
 	fn initialize(&mut self, cfg: &dyn RelayConfig) -> Result<(), Error>;
 	fn accept(&mut self, atom: Atom)                -> Option<Atom>;
 	fn finish(&mut self)                            -> bool;
 }
 
+impl<R: SimpleRelay> Relay for R {
+	fn initialize(&mut self, cfg: &dyn RelayConfig) -> Result<(), Error> {
+		SimpleRelay::initialize(self, cfg)
+	}
+
+	fn accept(&mut self, atom: Atom, out: &mut dyn Collector) {
+		if let Some(atom) = SimpleRelay::accept(self, atom) {
+			out.emit(atom);
+		}
+	}
+
+	fn finish(&mut self, _out: &mut dyn Collector) -> Result<(), Error> {
+		match SimpleRelay::finish(self) {
+			true  => Ok(()),
+			false => Err(Error::General("Relay failed to finish".to_owned())),
+		}
+	}
+}
+
+/// Columns a relay has resolved against the latest header.
+///
+/// A relay that names columns resolves them against each `HeaderRow`, or
+/// against `column_1`, `column_2`, … when a row arrives before any header.
+/// A header that lacks a column is replaced by one `ErrorAtom`, and rows
+/// are dropped until a header that fits arrives; without a header, the
+/// error is reported at the first dropped row instead.
+///
+pub(crate) struct ColumnBinding<T> {
+	bound:    Option<T>,
+	broken:   Option<Error>,
+	reported: bool,
+}
+
+impl<T> Default for ColumnBinding<T> {
+	fn default() -> Self { ColumnBinding{bound: None, broken: None, reported: false} }
+}
+
+impl<T> ColumnBinding<T> {
+	pub(crate) fn get(&self)         -> Option<&T>     { self.bound.as_ref()  }
+	pub(crate) fn get_mut(&mut self) -> Option<&mut T> { self.bound.as_mut()  }
+	pub(crate) fn is_broken(&self)   -> bool           { self.broken.is_some() }
+	pub(crate) fn take(&mut self)    -> Option<T>      { self.bound.take()     }
+
+	/// Keep what `resolved` bound; an error is returned for the caller to
+	/// emit in place of the header.
+	fn bind(&mut self, resolved: Result<T, Error>, reported: bool) -> Result<(), Error> {
+		self.reported = false;
+		match resolved {
+			Ok(bound)  => {
+				self.bound  = Some(bound);
+				self.broken = None;
+				Ok(())
+			}
+			Err(error) => {
+				warn!("{}", error);
+				self.bound    = None;
+				self.broken   = Some(error.clone());
+				self.reported = reported;
+				Err(error)
+			}
+		}
+	}
+
+	/// The error to emit for a dropped row, once per header.
+	pub(crate) fn reject(&mut self, coordinate: Coordinate) -> Option<Atom> {
+		if self.reported { return None }
+		self.reported = true;
+		self.broken.clone().map(|error| Atom::ErrorAtom(error, coordinate))
+	}
+
+	/// `reject`, emitting the error to `out`.
+	pub(crate) fn reject_into(&mut self, coordinate: Coordinate, out: &mut dyn Collector) {
+		if let Some(error) = self.reject(coordinate) {
+			out.emit(error);
+		}
+	}
+}
+
+/// A relay whose columns are bound by a `ColumnBinding`.
+pub(crate) trait BindsColumns {
+	type Bound;
+
+	/// What the relay's columns are for a header with `names`.
+	fn resolve<S: AsRef<str>>(&mut self, names: &[S]) -> Result<Self::Bound, Error>;
+	fn binding(&mut self) -> &mut ColumnBinding<Self::Bound>;
+
+	/// Bind to a header. The error, if any, replaces the header.
+	fn bind<S: AsRef<str>>(&mut self, names: &[S]) -> Result<(), Error> {
+		let resolved = self.resolve(names);
+		self.binding().bind(resolved, true)
+	}
+
+	/// Bind to positional names unless already bound; false if a row of
+	/// `width` fields must be dropped.
+	fn ensure_bound(&mut self, width: usize) -> bool {
+		if self.binding().bound.is_none() && !self.binding().is_broken() {
+			let names    = (1..=width).map(|i| format!("column_{}", i)).collect::<Vec<_>>();
+			let resolved = self.resolve(&names);
+			let _        = self.binding().bind(resolved, false);
+		}
+		self.binding().bound.is_some()
+	}
+
+	/// Like `ensure_bound`, returning a copy of what is bound.
+	fn bound(&mut self, width: usize) -> Option<Self::Bound> where Self::Bound: Clone {
+		match self.ensure_bound(width) {
+			true  => self.binding().bound.clone(),
+			false => None,
+		}
+	}
+}
+
 enum RelayState<S>
 {
 	Uninitialized,
@@ -26,4 +232,27 @@ pub trait RelayConfig: Debug + Display {
     fn integer_value(&self, name: &str) -> Option<i32>;
     fn float_value  (&self, name: &str) -> Option<f32>;
     fn bool_value   (&self, name: &str) -> Option<bool>;
+
+    /// A comma separated value as a list. Items are trimmed; an item in
+    /// double quotes may contain commas, and `""` inside it is a quote.
+    fn list_value(&self, name: &str) -> Option<Vec<String>> {
+        self.string_value(name).map(|text| split_list(&text))
+    }
+}
+
+pub(crate) fn split_list(text: &str) -> Vec<String> {
+	let mut items   = Vec::new();
+	let mut current = String::new();
+	let mut quoted  = false;
+	let mut chars   = text.chars().peekable();
+	while let Some(c) = chars.next() {
+		match c {
+			'"' if quoted && chars.peek() == Some(&'"') => { current.push('"'); chars.next(); }
+			'"'                                         => quoted = !quoted,
+			',' if !quoted                              => items.push(std::mem::take(&mut current)),
+			c                                           => current.push(c),
+		}
+	}
+	items.push(current);
+	items.into_iter().map(|item| item.trim().to_owned()).filter(|item| !item.is_empty()).collect()
 }
\ No newline at end of file
@@ -0,0 +1,355 @@
+pub mod aggregate;
+
+use std::collections::HashMap;
+use tracing::{info, warn};
+use zero::component::telemetry::component_metrics::ComponentMetrics;
+use zero::component::telemetry::provides_metrics::ProvidesMetrics;
+use crate::Error;
+use crate::component::relay::{BindsColumns, Collector, ColumnBinding, Relay, RelayConfig};
+use crate::component::relay::aggregate_relay::aggregate::{Aggregate, Function, State};
+use crate::component::relay::dedupe_relay::key_hash;
+use crate::model::expression::Fields;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::string_row::StringRow;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+use crate::utils::spill::RowSpill;
+
+/// Config key: columns whose values make up a group. Without any, all rows are one group.
+pub const GROUP_BY_KEY:          &str  = "group_by";
+/// Config key: output columns, each `function(column) [as name]`, e.g. `count(*), sum(amount) as total`.
+pub const AGGREGATES_KEY:        &str  = "aggregates";
+/// Config key: approximate bytes of group state held in memory before rows of new groups spill to disk.
+pub const MEMORY_BUDGET_KEY:     &str  = "memory_budget";
+pub const DEFAULT_MEMORY_BUDGET: usize = 256 * 1024 * 1024;
+
+const PARTITION_BITS: u32   = 4;
+const PARTITIONS:     usize = 1 << PARTITION_BITS;
+/// Spilled partitions are split again at most this many times; past that they stay in memory.
+const MAX_DEPTH:      u32   = 8;
+
+/// Summarises rows into one row per group.
+///
+/// Rows are grouped by the values of the `group_by` columns and each
+/// group accumulates the `aggregates`: `count`, `sum`, `avg`, `min`,
+/// `max` and `count_distinct`. Nulls and empty fields are skipped, and
+/// `sum` and `avg` skip values that are not numbers. Nothing is emitted
+/// until `finish`, which emits a `HeaderRow` naming the group columns
+/// then the aggregates, followed by one `TypedRowAtom` per group. Without
+/// `group_by` there is exactly one output row, even for no input.
+///
+/// Group state is kept in a hash map. Once it outgrows `memory_budget`,
+/// rows of groups not yet in memory are written to disk, split by a hash
+/// of their group into partitions; `finish` emits the groups in memory in
+/// order of first arrival, then aggregates each partition in turn, which
+/// may split it again. If the spill files cannot be written, the relay
+/// warns, counts an error and keeps those rows in memory.
+///
+/// Columns are bound to the first `HeaderRow` only, as `ColumnBinding`
+/// describes, and the input header itself is not passed on; when the
+/// binding fails nothing is emitted at `finish`. Atoms before the first
+/// row pass straight through; any other atom that arrives once rows are
+/// held back, such as `EndTask`, follows the groups.
+///
+pub struct AggregateRelay {
+	group_by:   Vec<String>,
+	aggregates: Vec<Aggregate>,
+	budget:     usize,
+	plan:       ColumnBinding<Plan>,
+	table:      Table,
+	trailing:   Vec<Atom>,
+	metrics:    ComponentMetrics,
+}
+
+impl Default for AggregateRelay {
+	fn default() -> Self { Self::new() }
+}
+
+impl AggregateRelay {
+	pub fn new() -> Self {
+		AggregateRelay {
+			group_by:   Vec::new(),
+			aggregates: Vec::new(),
+			budget:     DEFAULT_MEMORY_BUDGET,
+			plan:       ColumnBinding::default(),
+			table:      Table::new(DEFAULT_MEMORY_BUDGET, 0),
+			trailing:   Vec::new(),
+			metrics:    ComponentMetrics::default(),
+		}
+	}
+
+	pub fn with_group_by(mut self, column: &str) -> Self {
+		self.group_by.push(column.to_owned());
+		self
+	}
+
+	pub fn with_aggregate(mut self, aggregate: Aggregate) -> Self {
+		self.aggregates.push(aggregate);
+		self
+	}
+
+	pub fn with_memory_budget(mut self, bytes: usize) -> Self {
+		self.budget = bytes.max(1);
+		self.table  = Table::new(self.budget, 0);
+		self
+	}
+
+	pub fn group_by(&self)   -> &[String]    { &self.group_by   }
+	pub fn aggregates(&self) -> &[Aggregate] { &self.aggregates }
+
+	/// Partitions written to disk so far.
+	pub fn spilled(&self) -> usize { self.table.partitions.iter().filter(|p| p.disk.is_some()).count() }
+
+	/// Output column names: the group columns, then the aggregates.
+	pub fn output_names(&self) -> Vec<String> {
+		self.group_by.iter().cloned().chain(self.aggregates.iter().map(|a| a.name.clone())).collect()
+	}
+
+	fn add(&mut self, atom: Atom) {
+		let Some(plan) = self.plan.get() else { return };
+		self.metrics.add_records(1);
+		self.table.add(plan, atom, &mut self.metrics);
+	}
+}
+
+impl BindsColumns for AggregateRelay {
+	type Bound = Plan;
+
+	fn resolve<S: AsRef<str>>(&mut self, names: &[S]) -> Result<Plan, Error> {
+		let find = |column: &str| names.iter().position(|n| n.as_ref() == column)
+			.ok_or_else(|| Error::InvalidConfig(format!("[AggregateRelay]: no column '{}' in the header", column)));
+		let keys    = self.group_by.iter().map(|column| find(column)).collect::<Result<Vec<_>, _>>()?;
+		let columns = self.aggregates.iter()
+			.map(|a| a.column.as_deref().map(find).transpose())
+			.collect::<Result<Vec<_>, _>>()?;
+		let functions = self.aggregates.iter().map(|a| a.function).collect();
+		Ok(Plan{keys, columns, functions})
+	}
+
+	fn binding(&mut self) -> &mut ColumnBinding<Plan> { &mut self.plan }
+}
+
+impl Relay for AggregateRelay {
+	fn initialize(&mut self, cfg: &dyn RelayConfig) -> Result<(), Error> {
+		self.group_by.extend(cfg.list_value(GROUP_BY_KEY).unwrap_or_default());
+		for item in cfg.list_value(AGGREGATES_KEY).unwrap_or_default() {
+			self.aggregates.push(Aggregate::parse(&item)?);
+		}
+		if self.aggregates.is_empty() {
+			return Err(Error::InvalidConfig(format!("[AggregateRelay]: '{}' is required", AGGREGATES_KEY)))
+		}
+		self.budget = match cfg.integer_value(MEMORY_BUDGET_KEY) {
+			Some(v) if v < 1 => return Err(Error::InvalidConfig(format!("[AggregateRelay]: {} must be positive, got {}", MEMORY_BUDGET_KEY, v))),
+			Some(v)          => v as usize,
+			None             => self.budget,
+		};
+		self.table = Table::new(self.budget, 0);
+		self.metrics.activate();
+		info!("[AggregateRelay]: initialized with {}", cfg);
+		Ok(())
+	}
+
+	fn accept(&mut self, atom: Atom, out: &mut dyn Collector) {
+		self.metrics.increment_messages();
+		let coordinate = atom.coordinate();
+		let started    = self.metrics.record_count > 0 || self.plan.is_broken();
+		match atom {
+			Atom::HeaderRow(header) if !started && self.plan.get().is_none() => {
+				let names = header.iter_str().collect::<Vec<_>>();
+				if let Err(error) = self.bind(&names) {
+					out.emit(Atom::ErrorAtom(error, coordinate));
+				}
+			}
+			Atom::HeaderRow(_)        => {}                             // Describes input columns, not ours
+			Atom::ByteRowAtom(_) | Atom::StringRowAtom(_) | Atom::TypedRowAtom(_) => {
+				let width = match &atom {
+					Atom::ByteRowAtom(row)   => row.length() as usize,
+					Atom::StringRowAtom(row) => row.count() as usize,
+					Atom::TypedRowAtom(row)  => row.count() as usize,
+					_                        => 0,
+				};
+				match self.ensure_bound(width) {
+					true  => self.add(atom),
+					false => self.plan.reject_into(coordinate, out),
+				}
+			}
+			Atom::RowBatchAtom(batch) => {
+				if batch.is_empty() { return }
+				if !self.ensure_bound(batch.width()) { return self.plan.reject_into(coordinate, out) }
+				for row in batch.to_rows() {
+					self.add(Atom::ByteRowAtom(row));
+				}
+			}
+			other if started          => self.trailing.push(other),
+			other                     => out.emit(other),
+		}
+	}
+
+	fn finish(&mut self, out: &mut dyn Collector) -> Result<(), Error> {
+		let table = std::mem::replace(&mut self.table, Table::new(self.budget, 0));
+		if !self.plan.is_broken() {
+			out.emit(Atom::HeaderRow(StringRow::from_values(self.output_names())));
+			if self.group_by.is_empty() && table.is_empty() {
+				let states = self.aggregates.iter().map(|a| State::new(a.function)).collect();
+				out.emit(Group{key: Vec::new(), states}.into_atom());
+			}
+		}
+		let plan   = self.plan.take().unwrap_or_default();
+		let groups = table.drain(&plan, out, &mut self.metrics).inspect_err(|error| {
+			warn!("[AggregateRelay]: cannot read spilled rows back: {}", error);
+			self.metrics.increment_errors();
+			self.metrics.fail();
+		})?;
+		std::mem::take(&mut self.trailing).into_iter().for_each(|atom| out.emit(atom));
+		self.metrics.complete();
+		info!("[AggregateRelay]: aggregated {} rows into {} groups", self.metrics.record_count, groups);
+		Ok(())
+	}
+}
+
+impl ProvidesMetrics for AggregateRelay {
+	fn metrics(&self) -> ComponentMetrics { self.metrics }
+
+	fn take_metrics(&mut self) -> ComponentMetrics {
+		let metrics = self.metrics;
+		self.metrics.reset();
+		metrics
+	}
+}
+
+/// Column positions bound against a header.
+#[derive(Debug, Default)]
+pub(crate) struct Plan {
+	keys:      Vec<usize>,
+	columns:   Vec<Option<usize>>,
+	functions: Vec<Function>,
+}
+
+struct Group {
+	key:    Vec<Value>,
+	states: Vec<State>,
+}
+
+impl Group {
+	fn size(&self) -> usize {
+		let key = self.key.iter().map(|v| size_of::<Value>() + if let Value::String(s) = v { s.len() } else { 0 }).sum::<usize>();
+		size_of::<Group>() + size_of::<(u128, usize)>() + key + self.states.iter().map(State::size).sum::<usize>()
+	}
+
+	fn into_atom(self) -> Atom {
+		let values = self.key.into_iter().chain(self.states.into_iter().map(State::result)).collect();
+		Atom::TypedRowAtom(TypedRow::new(values))
+	}
+}
+
+/// Rows of the groups that did not fit in memory, kept on disk, or in
+/// memory when the disk cannot be written.
+#[derive(Default)]
+struct Partition {
+	disk:   Option<RowSpill>,
+	memory: Vec<Atom>,
+}
+
+/// Groups in arrival order, found by a hash of their key values.
+struct Table {
+	groups:       Vec<Group>,
+	index:        HashMap<u128, usize>,
+	held:         usize,
+	budget:       usize,
+	depth:        u32,
+	partitions:   Vec<Partition>,
+	spill_failed: bool,
+}
+
+impl Table {
+	fn new(budget: usize, depth: u32) -> Self {
+		Table {
+			groups:       Vec::new(),
+			index:        HashMap::new(),
+			held:         0,
+			budget,
+			depth,
+			partitions:   (0..PARTITIONS).map(|_| Partition::default()).collect(),
+			spill_failed: false,
+		}
+	}
+
+	fn is_empty(&self) -> bool {
+		self.groups.is_empty() && self.partitions.iter().all(|p| p.disk.is_none() && p.memory.is_empty())
+	}
+
+	fn add(&mut self, plan: &Plan, atom: Atom, metrics: &mut ComponentMetrics) {
+		let Some(row) = fields_of(&atom) else { return };
+		let key  = plan.keys.iter().map(|&i| row.value(i)).collect::<Vec<_>>();
+		let hash = group_hash(&key);
+		let slot = match self.index.get(&hash) {
+			Some(&slot)                                                => slot,
+			None if self.held < self.budget || self.depth >= MAX_DEPTH => {
+				let group  = Group{key, states: plan.functions.iter().map(|&f| State::new(f)).collect()};
+				self.held += group.size();
+				self.groups.push(group);
+				self.index.insert(hash, self.groups.len() - 1);
+				self.groups.len() - 1
+			}
+			None                                                       => return self.spill(hash, atom, metrics),
+		};
+		for (state, column) in self.groups[slot].states.iter_mut().zip(&plan.columns) {
+			let value  = column.map(|i| row.value(i));
+			self.held += state.add(value.as_ref());
+		}
+	}
+
+	/// Set a row aside in its group's partition.
+	fn spill(&mut self, hash: u128, atom: Atom, metrics: &mut ComponentMetrics) {
+		let partition = &mut self.partitions[(hash >> (PARTITION_BITS * self.depth)) as usize % PARTITIONS];
+		if self.spill_failed { return partition.memory.push(atom) }
+		let pushed = match &mut partition.disk {
+			Some(disk) => disk.push(&atom),
+			None       => RowSpill::new().and_then(|mut disk| {
+				disk.push(&atom)?;
+				partition.disk = Some(disk);
+				Ok(())
+			}),
+		};
+		if let Err(error) = pushed {
+			warn!("[AggregateRelay]: cannot spill rows to disk, keeping them in memory: {}", error);
+			metrics.increment_errors();
+			self.spill_failed = true;
+			partition.memory.push(atom);
+		}
+	}
+
+	/// Emit every group, those in memory first; returns how many.
+	fn drain(self, plan: &Plan, out: &mut dyn Collector, metrics: &mut ComponentMetrics) -> Result<u64, Error> {
+		let mut count = self.groups.len() as u64;
+		self.groups.into_iter().for_each(|group| out.emit(group.into_atom()));
+		for partition in self.partitions {
+			if partition.disk.is_none() && partition.memory.is_empty() { continue }
+			let mut table = Table::new(self.budget, self.depth + 1);
+			if let Some(disk) = partition.disk {
+				for atom in disk.into_reader()? {
+					table.add(plan, atom?, metrics);
+				}
+			}
+			partition.memory.into_iter().for_each(|atom| table.add(plan, atom, metrics));
+			count += table.drain(plan, out, metrics)?;
+		}
+		Ok(count)
+	}
+}
+
+fn fields_of(atom: &Atom) -> Option<&dyn Fields> {
+	match atom {
+		Atom::ByteRowAtom(row)   => Some(row),
+		Atom::StringRowAtom(row) => Some(row),
+		Atom::TypedRowAtom(row)  => Some(row),
+		_                        => None,
+	}
+}
+
+/// Hash of a group's key values; typed values keep their type, so the
+/// integer 2 and the text "2" are different groups.
+pub(crate) fn group_hash(key: &[Value]) -> u128 {
+	let texts = key.iter().map(|v| format!("{}:{}", v.value_type(), v)).collect::<Vec<_>>();
+	key_hash(&[], |i| texts.get(i).map(String::as_bytes))
+}
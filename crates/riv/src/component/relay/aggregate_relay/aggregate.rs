@@ -0,0 +1,184 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt;
+use crate::Error;
+use crate::component::relay::dedupe_relay::key_hash;
+use crate::model::expression::ast::{arithmetic, compare, number, ArithOp};
+use crate::model::ir::value::Value;
+
+/// What an aggregate computes over the rows of a group.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Function {
+	/// Rows, or with a column, rows where it is not null.
+	Count,
+	Sum,
+	Avg,
+	Min,
+	Max,
+	/// Distinct non-null values, compared as text.
+	CountDistinct,
+}
+
+impl Function {
+	pub fn parse(text: &str) -> Result<Function, Error> {
+		match text.trim().to_ascii_lowercase().as_str() {
+			"count"                       => Ok(Function::Count),
+			"sum"                         => Ok(Function::Sum),
+			"avg" | "mean"                => Ok(Function::Avg),
+			"min"                         => Ok(Function::Min),
+			"max"                         => Ok(Function::Max),
+			"count_distinct" | "distinct" => Ok(Function::CountDistinct),
+			other                         => Err(Error::InvalidConfig(format!("[AggregateRelay]: unknown aggregate function '{}'", other))),
+		}
+	}
+}
+
+impl fmt::Display for Function {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let name = match self {
+			Function::Count         => "count",
+			Function::Sum           => "sum",
+			Function::Avg           => "avg",
+			Function::Min           => "min",
+			Function::Max           => "max",
+			Function::CountDistinct => "count_distinct",
+		};
+		f.write_str(name)
+	}
+}
+
+/// One output column: a function over an input column, or over whole rows
+/// for `count(*)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Aggregate {
+	pub function: Function,
+	pub column:   Option<String>,
+	pub name:     String,
+}
+
+impl Aggregate {
+	/// Without a name the column is called `function_column`, or just
+	/// `count` for `count(*)`.
+	pub fn new(function: Function, column: Option<&str>, name: Option<&str>) -> Self {
+		let name = match (name, column) {
+			(Some(name), _)      => name.to_owned(),
+			(None, Some(column)) => format!("{}_{}", function, column),
+			(None, None)         => function.to_string(),
+		};
+		Aggregate{function, column: column.map(str::to_owned), name}
+	}
+
+	/// Parse `function(column)` or `function(column) as name`; `count(*)`
+	/// counts rows.
+	pub fn parse(text: &str) -> Result<Aggregate, Error> {
+		let invalid = || Error::InvalidConfig(format!("[AggregateRelay]: expected 'function(column) [as name]', got '{}'", text));
+		let text    = text.trim();
+		let (call, name) = match text.rfind(')') {
+			Some(end) => (&text[..=end], text[end + 1..].trim()),
+			None      => return Err(invalid()),
+		};
+		let name = match name.split_once(char::is_whitespace) {
+			None if name.is_empty()                                                                   => None,
+			Some((keyword, alias)) if keyword.eq_ignore_ascii_case("as") && !alias.trim().is_empty() => Some(alias.trim()),
+			_                                                                                         => return Err(invalid()),
+		};
+		let (function, column) = call[..call.len() - 1].split_once('(').ok_or_else(invalid)?;
+		let function = Function::parse(function)?;
+		let column   = match column.trim() {
+			""                                 => return Err(invalid()),
+			"*" if function == Function::Count => None,
+			"*"                                => return Err(Error::InvalidConfig(format!("[AggregateRelay]: only count takes '*', got '{}'", text))),
+			column                             => Some(column),
+		};
+		Ok(Aggregate::new(function, column, name))
+	}
+}
+
+/// The running value of one aggregate for one group.
+#[derive(Debug)]
+pub(crate) enum State {
+	Count(u64),
+	Sum(Value),
+	Avg{sum: f64, count: u64},
+	Min(Value),
+	Max(Value),
+	Distinct(HashSet<u128>),
+}
+
+impl State {
+	pub fn new(function: Function) -> Self {
+		match function {
+			Function::Count         => State::Count(0),
+			Function::Sum           => State::Sum(Value::Null),
+			Function::Avg           => State::Avg{sum: 0.0, count: 0},
+			Function::Min           => State::Min(Value::Null),
+			Function::Max           => State::Max(Value::Null),
+			Function::CountDistinct => State::Distinct(HashSet::new()),
+		}
+	}
+
+	/// Fold in the value of one row, `None` for `count(*)`. Returns the
+	/// bytes the state grew by.
+	pub fn add(&mut self, value: Option<&Value>) -> usize {
+		let before = self.size();
+		match (&mut *self, value) {
+			(State::Count(n), None)               => *n += 1,
+			(_, None)                             => {}
+			(_, Some(Value::Null))                => {}
+			(State::Count(n), Some(_))            => *n += 1,
+			(State::Sum(total), Some(value))      => if let Some(value) = numeric(value) {
+				*total = match total {
+					Value::Null => value,
+					_           => match arithmetic(total, ArithOp::Add, &value) {
+						Value::Null => total.as_f64().zip(value.as_f64()).map_or(Value::Null, |(a, b)| Value::Float(a + b)),
+						sum         => sum,
+					},
+				}
+			},
+			(State::Avg{sum, count}, Some(value)) => if let Some(n) = numeric(value).and_then(|v| v.as_f64()) {
+				*sum   += n;
+				*count += 1;
+			},
+			(State::Min(least), Some(value))      => if least.is_null() || compare(value, least) == Some(Ordering::Less) {
+				*least = value.clone();
+			},
+			(State::Max(most), Some(value))       => if most.is_null() || compare(value, most) == Some(Ordering::Greater) {
+				*most = value.clone();
+			},
+			(State::Distinct(seen), Some(value))  => {
+				let text = value.to_string();
+				seen.insert(key_hash(&[0], |i| (i == 0).then_some(text.as_bytes())));
+			}
+		}
+		self.size().saturating_sub(before)
+	}
+
+	/// Rough bytes the state takes in memory.
+	pub fn size(&self) -> usize {
+		size_of::<State>() + match self {
+			State::Min(Value::String(s)) | State::Max(Value::String(s)) => s.len(),
+			State::Distinct(seen)                                        => seen.len() * size_of::<u128>(),
+			_                                                            => 0,
+		}
+	}
+
+	pub fn result(self) -> Value {
+		match self {
+			State::Count(n)                       => Value::Integer(n as i64),
+			State::Sum(total)                     => total,
+			State::Avg{count: 0, ..}              => Value::Null,
+			State::Avg{sum, count}                => Value::Float(sum / count as f64),
+			State::Min(value) | State::Max(value) => value,
+			State::Distinct(seen)                 => Value::Integer(seen.len() as i64),
+		}
+	}
+}
+
+/// A number, with text parsed; anything else is skipped by `sum` and `avg`.
+fn numeric(value: &Value) -> Option<Value> {
+	match value {
+		Value::String(s)                                        => number(s),
+		Value::Integer(_) | Value::Decimal(_) | Value::Float(_) => Some(value.clone()),
+		_                                                       => None,
+	}
+}
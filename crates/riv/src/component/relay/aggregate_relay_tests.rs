@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use zero::component::telemetry::provides_metrics::ProvidesMetrics;
+use crate::Error;
+use crate::component::relay::Relay;
+use crate::component::relay::aggregate_relay::{AggregateRelay, AGGREGATES_KEY, GROUP_BY_KEY, MEMORY_BUDGET_KEY};
+use crate::component::relay::aggregate_relay::aggregate::{Aggregate, Function};
+use crate::component::relay::fixtures::{fields, header, initialized, row, text};
+use crate::component::relay::map_relay_config::MapRelayConfig;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::row_batch::RowBatch;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+
+fn relay(cfg: MapRelayConfig) -> AggregateRelay {
+	initialized(AggregateRelay::new(), &cfg)
+}
+
+/// Feed `atoms`, which must all be held back, then finish.
+fn run(relay: &mut AggregateRelay, atoms: Vec<Atom>) -> Vec<Atom> {
+	let mut out = Vec::new();
+	for atom in atoms {
+		relay.accept(atom, &mut out);
+	}
+	assert!(out.is_empty(), "expected nothing before finish, got {:?}", out);
+	relay.finish(&mut out).unwrap();
+	out
+}
+
+fn values(atom: &Atom) -> Vec<Value> {
+	match atom {
+		Atom::TypedRowAtom(r) => r.iter().cloned().collect(),
+		other                 => panic!("expected a typed row, got {:?}", other),
+	}
+}
+
+#[test]
+fn aggregates_parse() {
+	assert_eq!(Aggregate::parse("count(*)").unwrap(),                Aggregate::new(Function::Count, None, None));
+	assert_eq!(Aggregate::parse(" SUM( amount ) as total ").unwrap(), Aggregate::new(Function::Sum, Some("amount"), Some("total")));
+	assert_eq!(Aggregate::parse("count_distinct(customer id)").unwrap().name, "count_distinct_customer id");
+	assert_eq!(Aggregate::parse("mean(x)").unwrap().function,         Function::Avg);
+	for bad in ["sum", "sum()", "sum(*)", "median(x)", "sum(x) total", "sum(x) as"] {
+		assert!(matches!(Aggregate::parse(bad), Err(Error::InvalidConfig(_))), "{}", bad);
+	}
+	assert!(matches!(AggregateRelay::new().initialize(&MapRelayConfig::new().with(GROUP_BY_KEY, "a")), Err(Error::InvalidConfig(_))));
+}
+
+#[test]
+fn groups_rows_with_every_function() {
+	let aggregates = "count(*), count(amount), sum(amount) as total, avg(amount), min(amount), max(amount), count_distinct(customer)";
+	let mut relay  = relay(MapRelayConfig::new().with(GROUP_BY_KEY, "region").with(AGGREGATES_KEY, aggregates));
+	let rows       = [["eu", "ann", "10"], ["us", "bo", "5"], ["eu", "cy", "2.5"], ["eu", "ann", ""], ["us", "bo", "n/a"], ["eu", "ann", "9"]];
+	let mut atoms  = vec![header(&["region", "customer", "amount"])];
+	atoms.extend(rows.iter().map(|r| Atom::ByteRowAtom(row(r))));
+	let out = run(&mut relay, atoms);
+
+	assert_eq!(out.len(), 3);
+	assert_eq!(fields(&out[0]), ["region", "count", "count_amount", "total", "avg_amount", "min_amount", "max_amount", "count_distinct_customer"]);
+	assert_eq!(values(&out[1])[..4], [text("eu"), Value::Integer(4), Value::Integer(3), "21.5".parse().map(Value::Decimal).unwrap()]);
+	assert_eq!(values(&out[1])[4..], [Value::Float(21.5 / 3.0), text("2.5"), text("10"), Value::Integer(2)]);
+	assert_eq!(values(&out[2]), [text("us"), Value::Integer(2), Value::Integer(2), Value::Integer(5), Value::Float(5.0), text("5"), text("n/a"), Value::Integer(1)]);
+	assert_eq!(relay.metrics().record_count, 6);
+}
+
+#[test]
+fn without_group_by_there_is_one_row() {
+	let mut empty = relay(MapRelayConfig::new().with(AGGREGATES_KEY, "count(*), sum(column_1)"));
+	let out       = run(&mut empty, vec![]);
+	assert_eq!(fields(&out[0]), ["count", "sum_column_1"]);
+	assert_eq!(values(&out[1]), [Value::Integer(0), Value::Null]);
+
+	let mut relay = relay(MapRelayConfig::new().with(AGGREGATES_KEY, "count(*), sum(column_1)"));
+	let typed     = |v| Atom::TypedRowAtom(TypedRow::new(vec![v]));
+	let out       = run(&mut relay, vec![typed(Value::Integer(i64::MAX)), typed(Value::Integer(1)), typed(Value::Null)]);
+	assert_eq!(out.len(), 2);
+	assert!(matches!(values(&out[1])[..], [Value::Integer(3), Value::Float(_)]));           // Overflow falls back to a float
+}
+
+#[test]
+fn batches_and_typed_keys_group_by_value_and_type() {
+	let mut relay = relay(MapRelayConfig::new().with(GROUP_BY_KEY, "column_1").with(AGGREGATES_KEY, "sum(column_2)"));
+	let batch     = RowBatch::from_rows(&[row(&["2", "1"]), row(&["3", "4"]), row(&["2", "10"])]).unwrap();
+	let typed     = Atom::TypedRowAtom(TypedRow::new(vec![Value::Integer(2), Value::Integer(100)]));
+	let out       = run(&mut relay, vec![Atom::RowBatchAtom(batch), typed]);
+	let groups    = out[1..].iter().map(values).collect::<Vec<_>>();
+	assert_eq!(groups, [
+		vec![text("2"), Value::Integer(11)],
+		vec![text("3"), Value::Integer(4)],
+		vec![Value::Integer(2), Value::Integer(100)],
+	]);
+}
+
+#[test]
+fn groups_beyond_the_memory_budget_spill_and_are_all_emitted() {
+	let cfg       = MapRelayConfig::new().with(GROUP_BY_KEY, "column_1").with(AGGREGATES_KEY, "count(*), sum(column_2)").with(MEMORY_BUDGET_KEY, "4000");
+	let mut relay = relay(cfg);
+	let mut out   = Vec::new();
+	for n in 0..3000 {
+		relay.accept(Atom::ByteRowAtom(row(&[&format!("g{}", n % 700), "2"])), &mut out);
+	}
+	assert!(relay.spilled() > 1, "expected spilled partitions, got {}", relay.spilled());
+	relay.finish(&mut out).unwrap();
+
+	let groups = out[1..].iter().map(values).collect::<Vec<_>>();
+	assert_eq!(groups.len(), 700);
+	assert_eq!(groups[0][0], text("g0"));                                                      // Groups in memory come first
+	let counts = groups.iter().map(|g| (g[0].to_string(), (g[1].clone(), g[2].clone()))).collect::<HashMap<_, _>>();
+	assert_eq!(counts.len(), 700);
+	assert_eq!(counts["g99"],  (Value::Integer(5), Value::Integer(10)));
+	assert_eq!(counts["g699"], (Value::Integer(4), Value::Integer(8)));
+	assert_eq!(relay.metrics().error_count, 0);
+}
+
+#[test]
+fn a_header_without_a_column_drops_rows_and_emits_no_groups() {
+	let mut relay = relay(MapRelayConfig::new().with(GROUP_BY_KEY, "region").with(AGGREGATES_KEY, "count(*)"));
+	let mut out   = Vec::new();
+	relay.accept(Atom::CommentRow("first".into()), &mut out);
+	relay.accept(header(&["id"]), &mut out);
+	relay.accept(Atom::ByteRowAtom(row(&["1"])), &mut out);
+	relay.accept(Atom::EndTask, &mut out);
+	relay.finish(&mut out).unwrap();
+	assert_eq!(out.len(), 3);
+	assert!(matches!(out[0], Atom::CommentRow(_)));
+	assert!(matches!(out[1], Atom::ErrorAtom(Error::InvalidConfig(_), _)));
+	assert!(matches!(out[2], Atom::EndTask));
+}
+
+#[test]
+fn trailing_atoms_follow_the_groups() {
+	let mut relay = relay(MapRelayConfig::new().with(GROUP_BY_KEY, "k").with(AGGREGATES_KEY, "max(v)"));
+	let mut out   = Vec::new();
+	relay.accept(header(&["k", "v"]), &mut out);
+	relay.accept(Atom::ByteRowAtom(row(&["a", "2024-01-05"])), &mut out);
+	relay.accept(Atom::EndTask, &mut out);
+	relay.accept(header(&["k", "v"]), &mut out);                                                // Later headers are dropped
+	relay.accept(Atom::ByteRowAtom(row(&["a", "2024-01-15"])), &mut out);
+	assert!(out.is_empty());
+	relay.finish(&mut out).unwrap();
+	assert_eq!(out.len(), 3);
+	assert_eq!(values(&out[1]), [text("a"), text("2024-01-15")]);
+	assert!(matches!(out[2], Atom::EndTask));
+}
@@ -2,7 +2,7 @@ use std::fmt::{Debug, Display};
 use tracing::{info, instrument};
 use crate::Error;
 use crate::model::ir::atom::Atom;
-use crate::component::relay::{Relay, RelayConfig};
+use crate::component::relay::{RelayConfig, SimpleRelay};
 
 #[derive(Debug)]
 pub struct ConsoleRelay;
@@ -13,7 +13,7 @@ impl ConsoleRelay {
 	}
 }
 
-impl Relay for ConsoleRelay {
+impl SimpleRelay for ConsoleRelay {
 	#[instrument]
 	fn initialize(&mut self, _cfg: &dyn RelayConfig) -> Result<(), Error> {
 		Ok(())
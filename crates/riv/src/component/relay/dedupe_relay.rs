@@ -0,0 +1,375 @@
+pub mod key_set;
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::{DefaultHasher, Hasher};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::rc::Rc;
+use tracing::{info, warn};
+use zero::component::telemetry::component_metrics::ComponentMetrics;
+use zero::component::telemetry::provides_metrics::ProvidesMetrics;
+use crate::Error;
+use crate::error::IoErrorWrapper;
+use crate::model::ir::atom::Atom;
+use crate::component::relay::{BindsColumns, Collector, ColumnBinding, Relay, RelayConfig};
+use crate::component::relay::dedupe_relay::key_set::KeySet;
+use crate::utils::spill::{RowSpill, RowSpillReader};
+
+/// Config key: columns that make up the key. Defaults to the whole row.
+pub const KEY_KEY:              &str  = "key";
+/// Config key: which copy of a duplicate survives, `first` or `last`.
+pub const KEEP_KEY:             &str  = "keep";
+/// Config key: keys (keep-first) or rows (keep-last) held in memory before spilling to disk.
+pub const MEMORY_LIMIT_KEY:     &str  = "memory_limit";
+pub const DEFAULT_MEMORY_LIMIT: usize = 1_000_000;
+
+/// Which of a set of duplicate rows is kept.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Keep {
+	#[default]
+	First,
+	Last,
+}
+
+impl Keep {
+	pub fn parse(text: &str) -> Result<Keep, Error> {
+		match text.trim().to_ascii_lowercase().as_str() {
+			"first" => Ok(Keep::First),
+			"last"  => Ok(Keep::Last),
+			other   => Err(Error::InvalidConfig(format!("[DedupeRelay]: keep must be 'first' or 'last', got '{}'", other))),
+		}
+	}
+}
+
+/// Drops rows whose key has been seen before.
+///
+/// The key is the whole row, or the `key` columns, bound to each header as
+/// `ColumnBinding` describes. Rows are compared by a 128-bit hash of their
+/// key fields, so memory does not depend on how wide the keys are. Two
+/// distinct keys with the same hash would count as duplicates and one row
+/// would be dropped; the key bytes are not kept to rule that out. For a
+/// billion distinct keys the chance of any such collision is below 1 in
+/// 10^20.
+///
+/// With `keep = first` a row passes the moment its key is new; seen keys
+/// spill to disk beyond `memory_limit` (see `KeySet`). With `keep = last`
+/// a row can only be judged at the end, so every row is held back, on
+/// disk beyond `memory_limit`, and `finish` emits the last row for each
+/// key, in arrival order. Keep-last holds one entry per distinct key in
+/// memory, and atoms other than rows and headers that arrive once rows are
+/// held back, such as `EndTask`, follow the kept rows.
+///
+/// Removed duplicates are counted in the metrics' `dropped_count`. If the
+/// spill files cannot be written, the relay warns, counts an error and
+/// carries on in memory: keep-first lets the row through, keep-last
+/// stops spilling, so no row is lost.
+///
+pub struct DedupeRelay {
+	key:      Vec<String>,
+	keep:     Keep,
+	limit:    usize,
+	columns:  ColumnBinding<Rc<[usize]>>,
+	seen:     KeySet,
+	last:     HashMap<u128, u64>,
+	backlog:  Backlog,
+	trailing: Vec<Atom>,
+	metrics:  ComponentMetrics,
+}
+
+impl Default for DedupeRelay {
+	fn default() -> Self { Self::new() }
+}
+
+impl DedupeRelay {
+	pub fn new() -> Self {
+		DedupeRelay {
+			key:      Vec::new(),
+			keep:     Keep::First,
+			limit:    DEFAULT_MEMORY_LIMIT,
+			columns:  ColumnBinding::default(),
+			seen:     KeySet::new(DEFAULT_MEMORY_LIMIT),
+			last:     HashMap::new(),
+			backlog:  Backlog::new(DEFAULT_MEMORY_LIMIT),
+			trailing: Vec::new(),
+			metrics:  ComponentMetrics::default(),
+		}
+	}
+
+	/// Duplicates removed so far; for keep-last, known once `finish` has run.
+	pub fn duplicates(&self) -> u64 { self.metrics.dropped_count }
+
+	/// First sighting of `key`? Spill failures count as new, so no row is lost.
+	fn is_new(seen: &mut KeySet, metrics: &mut ComponentMetrics, key: u128) -> bool {
+		seen.insert(key).unwrap_or_else(|error| {
+			warn!("[DedupeRelay]: cannot track keys on disk: {}", error);
+			metrics.increment_errors();
+			true
+		})
+	}
+
+	fn row(&mut self, key: u128, atom: Atom) -> Option<Atom> {
+		self.metrics.add_records(1);
+		match self.keep {
+			Keep::First => {
+				if Self::is_new(&mut self.seen, &mut self.metrics, key) { return Some(atom) }
+				self.metrics.add_dropped(1);
+				None
+			}
+			Keep::Last  => {
+				self.last.insert(key, self.backlog.rows);
+				if let Err(error) = self.backlog.push(key, atom) {
+					warn!("[DedupeRelay]: cannot hold rows on disk, keeping them in memory: {}", error);
+					self.metrics.increment_errors();
+				}
+				None
+			}
+		}
+	}
+}
+
+impl BindsColumns for DedupeRelay {
+	/// Key positions; empty means the whole row.
+	type Bound = Rc<[usize]>;
+
+	fn resolve<S: AsRef<str>>(&mut self, names: &[S]) -> Result<Rc<[usize]>, Error> {
+		self.key.iter()
+			.map(|name| names.iter().position(|n| n.as_ref() == name)
+				.ok_or_else(|| Error::InvalidConfig(format!("[DedupeRelay]: no key column '{}' in the header", name))))
+			.collect()
+	}
+
+	fn binding(&mut self) -> &mut ColumnBinding<Rc<[usize]>> { &mut self.columns }
+}
+
+impl DedupeRelay {
+	/// Everything but holding rows back for keep-last: at most one atom out.
+	fn pass(&mut self, atom: Atom) -> Option<Atom> {
+		self.metrics.increment_messages();
+		let coordinate = atom.coordinate();
+		match atom {
+			Atom::HeaderRow(header) => {
+				let names = header.iter_str().collect::<Vec<_>>();
+				match self.bind(&names) {
+					Ok(())     => Some(Atom::HeaderRow(header)),
+					Err(error) => Some(Atom::ErrorAtom(error, coordinate)),
+				}
+			}
+			Atom::ByteRowAtom(row) => match self.bound(row.length() as usize) {
+				Some(indices) => {
+					let key = key_hash(&indices, |i| row.get(i));
+					self.row(key, Atom::ByteRowAtom(row))
+				}
+				None          => self.columns.reject(coordinate),
+			},
+			Atom::StringRowAtom(row) => match self.bound(row.count() as usize) {
+				Some(indices) => {
+					let key = key_hash(&indices, |i| row.get(i).map(str::as_bytes));
+					self.row(key, Atom::StringRowAtom(row))
+				}
+				None          => self.columns.reject(coordinate),
+			},
+			Atom::TypedRowAtom(row) => match self.bound(row.count() as usize) {
+				Some(indices) => {
+					let texts = row.iter().map(|v| format!("{}:{}", v.value_type(), v)).collect::<Vec<_>>();   // Type-tagged, so 1 and '1' differ
+					let key   = key_hash(&indices, |i| texts.get(i).map(String::as_bytes));
+					self.row(key, Atom::TypedRowAtom(row))
+				}
+				None          => self.columns.reject(coordinate),
+			},
+			Atom::RowBatchAtom(batch) => {
+				let Some(indices) = self.bound(batch.width()) else { return self.columns.reject(coordinate) };
+				let keys = (0..batch.len()).map(|r| key_hash(&indices, |i| batch.get(r, i))).collect::<Vec<_>>();
+				match self.keep {
+					Keep::First => {
+						let (seen, metrics) = (&mut self.seen, &mut self.metrics);
+						let kept = batch.filter_rows(|r| Self::is_new(seen, metrics, keys[r]));
+						metrics.add_records(batch.len() as u64);
+						metrics.add_dropped((batch.len() - kept.len()) as u64);
+						if kept.is_empty() { None } else { Some(Atom::RowBatchAtom(kept)) }
+					}
+					Keep::Last  => {
+						for (row, key) in batch.to_rows().into_iter().zip(keys) {
+							self.row(key, Atom::ByteRowAtom(row));
+						}
+						None
+					}
+				}
+			}
+			other if self.keep == Keep::Last && self.backlog.rows > 0 => {
+				self.trailing.push(other);
+				None
+			}
+			other => Some(other),
+		}
+	}
+}
+
+impl Relay for DedupeRelay {
+	fn initialize(&mut self, cfg: &dyn RelayConfig) -> Result<(), Error> {
+		self.key  = cfg.list_value(KEY_KEY).unwrap_or_default();
+		self.keep = cfg.string_value(KEEP_KEY).map_or(Ok(Keep::First), |k| Keep::parse(&k))?;
+		self.limit = match cfg.integer_value(MEMORY_LIMIT_KEY) {
+			Some(v) if v < 1 => return Err(Error::InvalidConfig(format!("[DedupeRelay]: {} must be positive, got {}", MEMORY_LIMIT_KEY, v))),
+			Some(v)          => v as usize,
+			None             => DEFAULT_MEMORY_LIMIT,
+		};
+		self.seen    = KeySet::new(self.limit);
+		self.backlog = Backlog::new(self.limit);
+		self.metrics.activate();
+		info!("[DedupeRelay]: initialized with {}", cfg);
+		Ok(())
+	}
+
+	fn accept(&mut self, atom: Atom, out: &mut dyn Collector) {
+		if let Some(atom) = self.pass(atom) {
+			out.emit(atom);
+		}
+	}
+
+	fn finish(&mut self, out: &mut dyn Collector) -> Result<(), Error> {
+		if self.keep == Keep::Last {
+			let held    = self.backlog.rows;
+			let backlog = std::mem::replace(&mut self.backlog, Backlog::new(self.limit));
+			let emitted = backlog.retain(std::mem::take(&mut self.last)).and_then(|mut retained| {
+				self.metrics.add_dropped(held - retained.len);
+				retained.try_for_each(|row| row.map(|row| out.emit(row)))
+			});
+			if let Err(error) = emitted {
+				warn!("[DedupeRelay]: cannot read held rows back: {}", error);
+				self.metrics.increment_errors();
+				self.metrics.fail();
+				return Err(error)
+			}
+		}
+		std::mem::take(&mut self.trailing).into_iter().for_each(|atom| out.emit(atom));
+		self.metrics.complete();
+		info!("[DedupeRelay]: {} rows, {} duplicates removed", self.metrics.record_count, self.metrics.dropped_count);
+		Ok(())
+	}
+}
+
+impl ProvidesMetrics for DedupeRelay {
+	fn metrics(&self) -> ComponentMetrics { self.metrics }
+
+	fn take_metrics(&mut self) -> ComponentMetrics {
+		let metrics = self.metrics;
+		self.metrics.reset();
+		metrics
+	}
+}
+
+/// 128-bit hash of the fields at `indices`, or of every field when empty.
+/// A key column past the end of a short row counts as empty. Distinct keys
+/// share a hash with negligible but nonzero probability, and callers
+/// compare hashes only.
+pub(crate) fn key_hash<'a, F: Fn(usize) -> Option<&'a [u8]>>(indices: &[usize], field: F) -> u128 {
+	let mut hashers = [DefaultHasher::new(), DefaultHasher::new()];
+	hashers[1].write_u8(0x5a);                                      // Two independent 64-bit halves
+	let mut add = |bytes: &[u8]| hashers.iter_mut().for_each(|h| {
+		h.write_usize(bytes.len());
+		h.write(bytes);
+	});
+	if indices.is_empty() {
+		(0..).map_while(&field).for_each(&mut add);
+	} else {
+		indices.iter().for_each(|&i| add(field(i).unwrap_or_default()));
+	}
+	(hashers[0].finish() as u128) << 64 | hashers[1].finish() as u128
+}
+
+/// Keep-last: rows in arrival order with their keys, moved to disk each
+/// time `limit` are held in memory.
+struct Backlog {
+	limit:  usize,
+	memory: Vec<(u128, Atom)>,
+	spill:  Option<(RowSpill, BufWriter<File>)>,
+	failed: bool,
+	rows:   u64,
+}
+
+impl Backlog {
+	fn new(limit: usize) -> Self { Backlog{limit, memory: Vec::new(), spill: None, failed: false, rows: 0} }
+
+	fn push(&mut self, key: u128, atom: Atom) -> Result<(), Error> {
+		self.memory.push((key, atom));
+		self.rows += 1;
+		if self.failed || self.memory.len() < self.limit { return Ok(()) }
+		let spilled = self.spill_memory();
+		self.failed = spilled.is_err();
+		spilled
+	}
+
+	/// Move the rows held in memory to disk; rows not written stay in memory.
+	fn spill_memory(&mut self) -> Result<(), Error> {
+		if self.spill.is_none() {
+			let keys   = BufWriter::new(tempfile::tempfile().map_err(IoErrorWrapper::from)?);
+			self.spill = Some((RowSpill::new()?, keys));
+		}
+		let Some((rows, keys)) = &mut self.spill else { return Ok(()) };
+		let mut written = 0;
+		let mut result  = Ok(());
+		for (key, atom) in &self.memory {
+			result = keys.write_all(&key.to_le_bytes()).map_err(|e| Error::from(IoErrorWrapper::from(e))).and_then(|_| rows.push(atom));
+			if result.is_err() { break }
+			written += 1;
+		}
+		self.memory.drain(..written);
+		result
+	}
+
+	/// The rows whose position is the last recorded for their key.
+	fn retain(self, last: HashMap<u128, u64>) -> Result<Retained, Error> {
+		let len     = last.len() as u64;
+		let spilled = match self.spill {
+			Some((rows, keys)) => {
+				let mut keys = keys.into_inner().map_err(|e| IoErrorWrapper::from(e.into_error()))?;
+				keys.seek(SeekFrom::Start(0)).map_err(IoErrorWrapper::from)?;
+				Some((rows.into_reader()?, BufReader::new(keys)))
+			}
+			None => None,
+		};
+		Ok(Retained{spilled, memory: self.memory.into_iter(), last, position: 0, len})
+	}
+}
+
+/// The rows a keep-last `DedupeRelay` kept, read back in arrival order.
+struct Retained {
+	spilled:  Option<(RowSpillReader, BufReader<File>)>,
+	memory:   std::vec::IntoIter<(u128, Atom)>,
+	last:     HashMap<u128, u64>,
+	position: u64,
+	len:      u64,
+}
+
+impl Retained {
+	fn next_held(&mut self) -> Option<Result<(u128, Atom), Error>> {
+		if let Some((rows, keys)) = &mut self.spilled {
+			match rows.next() {
+				Some(row) => {
+					let mut bytes = [0u8; 16];
+					let key       = keys.read_exact(&mut bytes).map_err(|e| Error::from(IoErrorWrapper::from(e)));
+					return Some(row.and_then(|row| key.map(|_| (u128::from_le_bytes(bytes), row))))
+				}
+				None      => self.spilled = None,
+			}
+		}
+		self.memory.next().map(Ok)
+	}
+}
+
+impl Iterator for Retained {
+	type Item = Result<Atom, Error>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			let held     = self.next_held()?;
+			let position = self.position;
+			self.position += 1;
+			match held {
+				Ok((key, atom)) if self.last.get(&key) == Some(&position) => return Some(Ok(atom)),
+				Ok(_)                                                       => continue,
+				Err(error)                                                  => return Some(Err(error)),
+			}
+		}
+	}
+}
@@ -0,0 +1,138 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use crate::Error;
+use crate::error::IoErrorWrapper;
+
+/// Keys per block of a sorted run; one key per block is kept in memory.
+const BLOCK:    usize = 1024;
+/// Runs on disk before they are merged into one.
+const MAX_RUNS: usize = 8;
+const KEY_SIZE: usize = size_of::<u128>();
+
+/// A set of 128-bit key hashes that holds at most `limit` keys in memory.
+///
+/// When the in-memory part fills up it is written to a temporary file as
+/// a sorted run. A lookup then checks memory and, for each run, reads the
+/// one block that could hold the key, found from a sparse in-memory index.
+/// Runs are merged once there are more than a few of them, so lookups
+/// stay cheap as the set grows.
+///
+pub struct KeySet {
+	memory: HashSet<u128>,
+	limit:  usize,
+	runs:   Vec<SortedRun>,
+	len:    u64,
+}
+
+impl KeySet {
+	pub fn new(limit: usize) -> Self {
+		KeySet{memory: HashSet::new(), limit: limit.max(1), runs: Vec::new(), len: 0}
+	}
+
+	pub fn len(&self)      -> u64   { self.len              }
+	pub fn is_empty(&self) -> bool  { self.len == 0         }
+	/// Sorted runs currently on disk.
+	pub fn runs(&self)     -> usize { self.runs.len()       }
+
+	/// Add `key`; false if it was already in the set.
+	pub fn insert(&mut self, key: u128) -> Result<bool, Error> {
+		if self.memory.contains(&key) { return Ok(false) }
+		for run in &mut self.runs {
+			if run.contains(key)? { return Ok(false) }
+		}
+		self.memory.insert(key);
+		self.len += 1;
+		if self.memory.len() >= self.limit { self.spill()? }
+		Ok(true)
+	}
+
+	fn spill(&mut self) -> Result<(), Error> {
+		let mut keys = self.memory.drain().collect::<Vec<_>>();
+		keys.sort_unstable();
+		if self.runs.len() < MAX_RUNS {
+			self.runs.push(SortedRun::write(keys.into_iter().map(Ok))?);
+			return Ok(())
+		}
+
+		let mut sources = vec![Box::new(keys.into_iter().map(Ok)) as Box<dyn Iterator<Item = Result<u128, Error>>>];
+		for run in std::mem::take(&mut self.runs) {
+			sources.push(Box::new(run.into_keys()?));
+		}
+		self.runs.push(SortedRun::write(Merge::new(sources)?)?);
+		Ok(())
+	}
+}
+
+/// Sorted keys in a temporary file, with the first key of every block.
+struct SortedRun {
+	file:  File,
+	index: Vec<u128>,
+	len:   usize,
+}
+
+impl SortedRun {
+	fn write<I: Iterator<Item = Result<u128, Error>>>(keys: I) -> Result<Self, Error> {
+		let mut writer = BufWriter::new(tempfile::tempfile().map_err(IoErrorWrapper::from)?);
+		let mut index  = Vec::new();
+		let mut len    = 0;
+		for key in keys {
+			let key = key?;
+			if len % BLOCK == 0 { index.push(key) }
+			writer.write_all(&key.to_be_bytes()).map_err(IoErrorWrapper::from)?;
+			len += 1;
+		}
+		let file = writer.into_inner().map_err(|e| IoErrorWrapper::from(e.into_error()))?;
+		Ok(SortedRun{file, index, len})
+	}
+
+	fn contains(&mut self, key: u128) -> Result<bool, Error> {
+		let block = match self.index.partition_point(|&first| first <= key) {
+			0 => return Ok(false),
+			n => n - 1,
+		};
+		let start     = block * BLOCK;
+		let count     = BLOCK.min(self.len - start);
+		let mut bytes = vec![0u8; count * KEY_SIZE];
+		self.file.seek(SeekFrom::Start((start * KEY_SIZE) as u64)).map_err(IoErrorWrapper::from)?;
+		self.file.read_exact(&mut bytes).map_err(IoErrorWrapper::from)?;
+		let keys = bytes.chunks_exact(KEY_SIZE).map(|c| u128::from_be_bytes(c.try_into().unwrap_or_default())).collect::<Vec<_>>();
+		Ok(keys.binary_search(&key).is_ok())
+	}
+
+	fn into_keys(mut self) -> Result<impl Iterator<Item = Result<u128, Error>>, Error> {
+		self.file.seek(SeekFrom::Start(0)).map_err(IoErrorWrapper::from)?;
+		let mut reader = BufReader::new(self.file);
+		Ok((0..self.len).map(move |_| {
+			let mut bytes = [0u8; KEY_SIZE];
+			reader.read_exact(&mut bytes).map_err(IoErrorWrapper::from)?;
+			Ok(u128::from_be_bytes(bytes))
+		}))
+	}
+}
+
+/// Merge of sorted, disjoint key streams into one sorted stream.
+struct Merge {
+	sources: Vec<Box<dyn Iterator<Item = Result<u128, Error>>>>,
+	heads:   Vec<Option<u128>>,
+}
+
+impl Merge {
+	fn new(mut sources: Vec<Box<dyn Iterator<Item = Result<u128, Error>>>>) -> Result<Self, Error> {
+		let heads = sources.iter_mut().map(|s| s.next().transpose()).collect::<Result<_, _>>()?;
+		Ok(Merge{sources, heads})
+	}
+}
+
+impl Iterator for Merge {
+	type Item = Result<u128, Error>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let (i, key) = self.heads.iter().enumerate().filter_map(|(i, h)| h.map(|k| (i, k))).min_by_key(|&(_, k)| k)?;
+		match self.sources[i].next().transpose() {
+			Ok(next)   => self.heads[i] = next,
+			Err(error) => return Some(Err(error)),
+		}
+		Some(Ok(key))
+	}
+}
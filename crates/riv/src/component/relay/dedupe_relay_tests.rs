@@ -0,0 +1,132 @@
+use zero::component::telemetry::provides_metrics::ProvidesMetrics;
+use crate::Error;
+use crate::component::relay::Relay;
+use crate::component::relay::dedupe_relay::{DedupeRelay, KEEP_KEY, KEY_KEY, MEMORY_LIMIT_KEY};
+use crate::component::relay::dedupe_relay::key_set::KeySet;
+use crate::component::relay::empty_relay_config::EmptyRelayConfig;
+use crate::component::relay::fixtures::{fields, finish, header, initialized, one, row};
+use crate::component::relay::map_relay_config::MapRelayConfig;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::row_batch::RowBatch;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+
+fn relay(cfg: MapRelayConfig) -> DedupeRelay {
+	initialized(DedupeRelay::new(), &cfg)
+}
+
+/// Rows `key,n` for n in 0..count, with keys cycling through `distinct` values.
+fn feed(relay: &mut DedupeRelay, count: usize, distinct: usize) -> Vec<Vec<String>> {
+	(0..count)
+		.filter_map(|n| one(relay, Atom::ByteRowAtom(row(&[&format!("k{}", n % distinct), &n.to_string()]))))
+		.map(|atom| fields(&atom))
+		.collect()
+}
+
+#[test]
+fn whole_rows_keep_the_first_copy() {
+	let mut relay = DedupeRelay::new();
+	relay.initialize(&EmptyRelayConfig).unwrap();
+	assert!(one(&mut relay, header(&["a", "b"])).is_some());
+	assert!(one(&mut relay, Atom::ByteRowAtom(row(&["1", "x"]))).is_some());
+	assert!(one(&mut relay, Atom::ByteRowAtom(row(&["1", "x"]))).is_none());
+	assert!(one(&mut relay, Atom::StringRowAtom(row(&["1", "x"]).as_string_row())).is_none());   // Same text, same key
+	assert!(one(&mut relay, Atom::ByteRowAtom(row(&["1x", ""]))).is_some());                      // Field boundaries count
+	assert!(one(&mut relay, Atom::ByteRowAtom(row(&["1", "y"]))).is_some());
+	assert!(matches!(one(&mut relay, Atom::EndTask), Some(Atom::EndTask)));
+	assert!(finish(&mut relay).is_empty());
+	assert_eq!(relay.duplicates(), 2);
+	let metrics = relay.metrics();
+	assert_eq!((metrics.record_count, metrics.dropped_count, metrics.error_count), (5, 2, 0));
+}
+
+#[test]
+fn key_columns_come_from_the_header() {
+	let mut relay = relay(MapRelayConfig::new().with(KEY_KEY, "id, region"));
+	one(&mut relay, header(&["region", "name", "id"]));
+	assert!(one(&mut relay, Atom::ByteRowAtom(row(&["eu", "Ada", "1"]))).is_some());
+	assert!(one(&mut relay, Atom::ByteRowAtom(row(&["eu", "Bo",  "1"]))).is_none());
+	assert!(one(&mut relay, Atom::ByteRowAtom(row(&["us", "Ada", "1"]))).is_some());
+	let typed = |n| Atom::TypedRowAtom(TypedRow::new(vec![Value::String("eu".into()), Value::Null, n]));
+	assert!(one(&mut relay, typed(Value::Integer(2))).is_some());
+	assert!(one(&mut relay, typed(Value::String("2".into()))).is_some());                      // Typed keys keep their type
+	assert!(one(&mut relay, typed(Value::Integer(2))).is_none());
+}
+
+#[test]
+fn keep_last_releases_the_last_copy_after_finish() {
+	let mut relay = relay(MapRelayConfig::new().with(KEY_KEY, "id").with(KEEP_KEY, "last"));
+	assert!(one(&mut relay, header(&["id", "v"])).is_some());
+	for (id, v) in [("a", "1"), ("b", "2"), ("a", "3"), ("c", "4"), ("b", "5")] {
+		assert!(one(&mut relay, Atom::ByteRowAtom(row(&[id, v]))).is_none());
+	}
+	assert!(one(&mut relay, Atom::EndTask).is_none());
+	let mut out = finish(&mut relay);
+	assert!(matches!(out.pop(), Some(Atom::EndTask)));                                      // Held atoms follow the kept rows
+	let rows = out.iter().map(fields).collect::<Vec<_>>();
+	assert_eq!(rows, [["a", "3"], ["c", "4"], ["b", "5"]]);
+	assert_eq!(relay.duplicates(), 2);
+}
+
+#[test]
+fn keys_and_rows_spill_to_disk_beyond_the_memory_limit() {
+	let mut first = relay(MapRelayConfig::new().with(KEY_KEY, "column_1").with(MEMORY_LIMIT_KEY, "4"));
+	let kept      = feed(&mut first, 200, 30);
+	assert_eq!(kept.len(), 30);
+	assert_eq!(kept[29], ["k29", "29"]);
+	assert_eq!((first.duplicates(), first.metrics().error_count), (170, 0));
+
+	let mut last = relay(MapRelayConfig::new().with(KEY_KEY, "column_1").with(KEEP_KEY, "last").with(MEMORY_LIMIT_KEY, "7"));
+	assert!(feed(&mut last, 100, 8).is_empty());
+	let rows = finish(&mut last).iter().map(fields).collect::<Vec<_>>();
+	let expected = (92..100).map(|n| vec![format!("k{}", n % 8), n.to_string()]).collect::<Vec<_>>();
+	assert_eq!(rows, expected);
+	assert_eq!(last.duplicates(), 92);
+}
+
+#[test]
+fn key_set_merges_runs_and_still_finds_every_key() {
+	let mut set = KeySet::new(10);
+	for key in 0..5_000u128 {
+		assert!(set.insert(key * 7919 % 5_003).unwrap());
+	}
+	assert!(set.runs() <= 8);
+	assert_eq!(set.len(), 5_000);
+	for key in (0..5_000u128).step_by(37) {
+		assert!(!set.insert(key * 7919 % 5_003).unwrap());
+	}
+	assert!(set.insert(5_004).unwrap());
+}
+
+#[test]
+fn batches_are_deduplicated_row_by_row() {
+	let mut relay = relay(MapRelayConfig::new().with(KEY_KEY, "column_1"));
+	let batch     = RowBatch::from_rows(&[row(&["1", "a"]), row(&["2", "b"]), row(&["1", "c"])]).unwrap();
+	match one(&mut relay, Atom::RowBatchAtom(batch)) {
+		Some(Atom::RowBatchAtom(kept)) => assert_eq!(kept.column(1).unwrap().iter().collect::<Vec<_>>(), [b"a", b"b"]),
+		other                          => panic!("expected a batch, got {:?}", other),
+	}
+	let again = RowBatch::from_rows(&[row(&["2", "d"])]).unwrap();
+	assert!(one(&mut relay, Atom::RowBatchAtom(again)).is_none());
+	assert_eq!(relay.take_metrics().dropped_count, 2);
+	assert_eq!(relay.metrics().dropped_count, 0);
+}
+
+#[test]
+fn missing_key_column_reports_one_error_and_drops_rows() {
+	let mut relay = relay(MapRelayConfig::new().with(KEY_KEY, "id"));
+	assert!(matches!(one(&mut relay, Atom::ByteRowAtom(row(&["1"]))), Some(Atom::ErrorAtom(Error::InvalidConfig(_), _))));
+	assert!(one(&mut relay, Atom::ByteRowAtom(row(&["2"]))).is_none());
+	assert!(matches!(one(&mut relay, header(&["name"])), Some(Atom::ErrorAtom(Error::InvalidConfig(_), _))));
+	assert!(one(&mut relay, Atom::ByteRowAtom(row(&["3"]))).is_none());
+	assert!(matches!(one(&mut relay, header(&["id"])), Some(Atom::HeaderRow(_))));
+	assert!(one(&mut relay, Atom::ByteRowAtom(row(&["3"]))).is_some());
+}
+
+#[test]
+fn initialize_rejects_bad_settings() {
+	let keep = MapRelayConfig::new().with(KEEP_KEY, "middle");
+	assert!(matches!(DedupeRelay::new().initialize(&keep), Err(Error::InvalidConfig(_))));
+	let limit = MapRelayConfig::new().with(MEMORY_LIMIT_KEY, "0");
+	assert!(matches!(DedupeRelay::new().initialize(&limit), Err(Error::InvalidConfig(_))));
+}
@@ -0,0 +1,208 @@
+use tracing::info;
+use crate::Error;
+use crate::model::coordinate::coordinate::Coordinate;
+use crate::model::expression::parser::CORRELATION_ID;
+use crate::model::expression::{Expression, Fields};
+use crate::model::ir::atom::Atom;
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::external_metadata::TaskMetadata;
+use crate::model::ir::row_batch::RowBatch;
+use crate::model::ir::string_row::StringRow;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+use crate::component::relay::{batch_atom, BindsColumns, ColumnBinding, RelayConfig, SimpleRelay};
+
+/// Config key: `name = expression` definitions separated by `;`, e.g.
+/// `full_name = first || ' ' || last; total = qty * price`.
+pub const DERIVE_KEY: &str = "derive";
+
+/// One computed column.
+#[derive(Clone, Debug)]
+pub struct Derivation {
+	pub name:       String,
+	pub expression: Expression,
+}
+
+/// Where each derived value goes for one header: the output names, and
+/// for each derivation the bound expression and its output position.
+pub(crate) struct Layout {
+	names:   Vec<String>,
+	targets: Vec<(Expression, usize)>,
+}
+
+impl Layout {
+	/// Copy `input` cells through `cell`, widened to the output, then fill in the derived ones.
+	fn fill<T>(&self, row: &dyn Fields, input: usize, cell: impl Fn(usize) -> T, derived: impl Fn(Value) -> T) -> Vec<T> {
+		let mut cells = (0..input).map(cell).collect::<Vec<_>>();
+		cells.resize_with(self.names.len(), || derived(Value::Null));
+		for (expression, target) in &self.targets {
+			cells[*target] = derived(expression.evaluate(row));
+		}
+		cells
+	}
+
+	fn byte_row(&self, row: &ByteRow) -> ByteRow {
+		let fields = self.fill(row, row.length() as usize, |i| row.get(i).unwrap_or_default().to_vec(), |v| v.to_string().into_bytes());
+		ByteRow::from_fields(fields.iter().map(Vec::as_slice)).with_coordinate(row.coordinate())
+	}
+
+	fn string_row(&self, row: &StringRow) -> StringRow {
+		let values = self.fill(row, row.count() as usize, |i| row.get(i).unwrap_or_default().to_owned(), |v| v.to_string());
+		StringRow::from_values(values).with_coordinate(row.coordinate())
+	}
+
+	fn typed_row(&self, row: &TypedRow) -> TypedRow {
+		let values = self.fill(row, row.count() as usize, |i| row.get(i).cloned().unwrap_or(Value::Null), |v| v);
+		TypedRow::new(values).with_coordinate(row.coordinate())
+	}
+
+	fn batch(&self, batch: &RowBatch) -> Atom {
+		let rows = (0..batch.len())
+			.map(|r| {
+				let fields = self.fill(&(batch, r), batch.width(), |i| batch.get(r, i).unwrap_or_default().to_vec(), |v| v.to_string().into_bytes());
+				ByteRow::from_fields(fields.iter().map(Vec::as_slice)).with_coordinate(batch.coordinate(r))
+			})
+			.collect::<Vec<_>>();
+		batch_atom("DeriveRelay", &rows)
+	}
+}
+
+/// Adds columns computed from each row with an `Expression`.
+///
+/// A derivation whose name is already in the header replaces that column
+/// in place; any other is appended, in the order given. Expressions see
+/// the incoming columns only, not each other's results. `CORRELATION_ID()`
+/// is the correlation id of the task's `SourceVariant`.
+///
+/// The `HeaderRow` is extended with the new names; columns are bound as
+/// `ColumnBinding` describes. Byte and string rows get the text of each
+/// value, with null left empty; typed rows get the values themselves.
+///
+#[derive(Default)]
+pub struct DeriveRelay {
+	derivations: Vec<Derivation>,
+	layout:      ColumnBinding<Layout>,
+}
+
+impl DeriveRelay {
+	pub fn new() -> Self { Self::default() }
+
+	/// Add a column named `name` computed by `expression`.
+	pub fn with_column(mut self, name: &str, expression: &str) -> Result<Self, Error> {
+		self.derivations.push(Derivation{name: name.to_owned(), expression: Expression::parse(expression)?});
+		Ok(self)
+	}
+
+	pub fn derivations(&self) -> &[Derivation] { &self.derivations }
+
+	fn layout_for<S: AsRef<str>>(&self, names: &[S]) -> Result<Layout, Error> {
+		let mut output  = names.iter().map(|n| n.as_ref().to_owned()).collect::<Vec<_>>();
+		let mut targets = Vec::with_capacity(self.derivations.len());
+		for derivation in &self.derivations {
+			let target = match output.iter().position(|n| *n == derivation.name) {
+				Some(i) => i,
+				None    => {
+					output.push(derivation.name.clone());
+					output.len() - 1
+				}
+			};
+			targets.push((derivation.expression.bind(names)?, target));
+		}
+		Ok(Layout{names: output, targets})
+	}
+
+	/// `row` with its derived columns; nothing, or the error once, if the layout does not fit.
+	fn derive(&mut self, width: usize, coordinate: Coordinate, row: impl FnOnce(&Layout) -> Atom) -> Option<Atom> {
+		match self.ensure_bound(width) {
+			true  => self.layout.get().map(row),
+			false => self.layout.reject(coordinate),
+		}
+	}
+
+	fn set_correlation_id(&mut self, id: &str) {
+		let value = Value::String(id.to_owned());
+		for derivation in &mut self.derivations {
+			derivation.expression.set_variable(CORRELATION_ID, &value);
+		}
+		for (expression, _) in self.layout.get_mut().into_iter().flat_map(|l| l.targets.iter_mut()) {
+			expression.set_variable(CORRELATION_ID, &value);
+		}
+	}
+}
+
+impl BindsColumns for DeriveRelay {
+	type Bound = Layout;
+
+	fn resolve<S: AsRef<str>>(&mut self, names: &[S]) -> Result<Layout, Error> { self.layout_for(names) }
+	fn binding(&mut self) -> &mut ColumnBinding<Layout> { &mut self.layout }
+}
+
+impl SimpleRelay for DeriveRelay {
+	fn initialize(&mut self, cfg: &dyn RelayConfig) -> Result<(), Error> {
+		if let Some(text) = cfg.string_value(DERIVE_KEY) {
+			for definition in split_definitions(&text) {
+				let (name, expression) = definition.split_once('=')
+					.filter(|(name, _)| !name.trim().is_empty())
+					.ok_or_else(|| Error::InvalidConfig(format!("[DeriveRelay]: '{}' is not name = expression", definition)))?;
+				let name = name.trim();
+				let name = name.strip_prefix('"').and_then(|n| n.strip_suffix('"')).unwrap_or(name);
+				self.derivations.push(Derivation{name: name.to_owned(), expression: Expression::parse(expression.trim())?});
+			}
+		}
+		if self.derivations.is_empty() {
+			return Err(Error::InvalidConfig(format!("[DeriveRelay]: '{}' is required", DERIVE_KEY)))
+		}
+		info!("[DeriveRelay]: initialized with {} derived columns", self.derivations.len());
+		Ok(())
+	}
+
+	fn accept(&mut self, atom: Atom) -> Option<Atom> {
+		let coordinate = atom.coordinate();
+		match atom {
+			Atom::StartTask(source) => {
+				self.set_correlation_id(source.correlation_id());
+				Some(Atom::StartTask(source))
+			}
+			Atom::HeaderRow(header) => {
+				let names = header.iter_str().collect::<Vec<_>>();
+				match self.bind(&names) {
+					Ok(())     => {
+						let names = self.layout.get().map(|l| l.names.clone()).unwrap_or_default();
+						Some(Atom::HeaderRow(StringRow::from_values(names).with_coordinate(coordinate)))
+					}
+					Err(error) => Some(Atom::ErrorAtom(error, coordinate)),
+				}
+			}
+			Atom::ByteRowAtom(row)    => self.derive(row.length() as usize, coordinate, |l| Atom::ByteRowAtom(l.byte_row(&row))),
+			Atom::StringRowAtom(row)  => self.derive(row.count() as usize, coordinate, |l| Atom::StringRowAtom(l.string_row(&row))),
+			Atom::TypedRowAtom(row)   => self.derive(row.count() as usize, coordinate, |l| Atom::TypedRowAtom(l.typed_row(&row))),
+			Atom::RowBatchAtom(batch) => self.derive(batch.width(), coordinate, |l| l.batch(&batch)),
+			other                     => Some(other),
+		}
+	}
+
+	fn finish(&mut self) -> bool {
+		info!("[DeriveRelay]: finished");
+		true
+	}
+}
+
+/// Split on `;` outside single- and double-quoted text; blank items are dropped.
+fn split_definitions(text: &str) -> Vec<&str> {
+	let mut items = Vec::new();
+	let mut quote = None;
+	let mut start = 0;
+	for (i, c) in text.char_indices() {
+		match (quote, c) {
+			(None, '\'' | '"')          => quote = Some(c),
+			(Some(q), c) if q == c      => quote = None,     // A doubled quote closes and reopens
+			(None, ';')                 => {
+				items.push(&text[start..i]);
+				start = i + 1;
+			}
+			_                           => {}
+		}
+	}
+	items.push(&text[start..]);
+	items.into_iter().map(str::trim).filter(|s| !s.is_empty()).collect()
+}
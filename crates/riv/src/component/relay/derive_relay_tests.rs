@@ -0,0 +1,80 @@
+use crate::Error;
+use crate::component::relay::SimpleRelay;
+use crate::component::relay::derive_relay::{DeriveRelay, DERIVE_KEY};
+use crate::component::relay::empty_relay_config::EmptyRelayConfig;
+use crate::component::relay::fixtures::{fields, header, initialized, row};
+use crate::component::relay::map_relay_config::MapRelayConfig;
+use crate::model::coordinate::coordinate::Coordinate;
+use crate::model::coordinate::text_location::TextLocation;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::external_metadata::{BytesMetadata, SourceVariant};
+use crate::model::ir::row_batch::RowBatch;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+
+fn relay(derive: &str) -> DeriveRelay {
+	initialized(DeriveRelay::new(), &MapRelayConfig::new().with(DERIVE_KEY, derive))
+}
+
+#[test]
+fn appends_new_columns_and_replaces_existing_ones() {
+	let mut relay = relay("name = UPPER(name); label = name || ' x' || qty; total = qty * price");
+	assert_eq!(fields(&relay.accept(header(&["name", "qty", "price"])).unwrap()), ["name", "qty", "price", "label", "total"]);
+	assert_eq!(fields(&relay.accept(Atom::ByteRowAtom(row(&["ada", "3", "1.50"]))).unwrap()), ["ADA", "3", "1.50", "ada x3", "4.50"]);
+	assert_eq!(fields(&relay.accept(Atom::StringRowAtom(row(&["bo", "", "2"]).as_string_row())).unwrap()), ["BO", "", "2", "", ""]);
+	assert!(matches!(relay.accept(Atom::EndTask), Some(Atom::EndTask)));
+	assert!(relay.finish());
+}
+
+#[test]
+fn definitions_may_quote_semicolons_and_names() {
+	let mut relay = relay("\"full name\" = first || '; ' || last; ;tier = CASE WHEN n >= 10 THEN 'gold' ELSE 'basic' END");
+	assert_eq!(relay.derivations().len(), 2);
+	assert_eq!(fields(&relay.accept(header(&["first", "last", "n"])).unwrap()), ["first", "last", "n", "full name", "tier"]);
+	assert_eq!(fields(&relay.accept(Atom::ByteRowAtom(row(&["Ada", "Lovelace", "12"]))).unwrap()), ["Ada", "Lovelace", "12", "Ada; Lovelace", "gold"]);
+}
+
+#[test]
+fn correlation_id_comes_from_the_task() {
+	let mut relay = relay("run = CORRELATION_ID()");
+	let source    = SourceVariant::Bytes(BytesMetadata::for_bytes(b"a", Some("job-42".to_owned())));
+	assert!(matches!(relay.accept(Atom::StartTask(source)), Some(Atom::StartTask(_))));
+	assert_eq!(fields(&relay.accept(Atom::ByteRowAtom(row(&["a"]))).unwrap()), ["a", "job-42"]);
+}
+
+#[test]
+fn typed_rows_and_batches_are_derived() {
+	let mut relay = DeriveRelay::new().with_column("double", "column_1 * 2").unwrap();
+	relay.initialize(&EmptyRelayConfig).unwrap();
+	match relay.accept(Atom::TypedRowAtom(TypedRow::new(vec![Value::Integer(4)]))) {
+		Some(Atom::TypedRowAtom(r)) => assert_eq!(r.get(1), Some(&Value::Integer(8))),
+		other                       => panic!("expected a typed row, got {:?}", other),
+	}
+	let at    = Coordinate::at_location(TextLocation::new(6, 2, 1));
+	let batch = RowBatch::from_rows(&[row(&["1"]).with_coordinate(at), row(&["x"])]).unwrap();
+	match relay.accept(Atom::RowBatchAtom(batch)) {
+		Some(Atom::RowBatchAtom(out)) => {
+			assert_eq!(out.column(1).unwrap().iter().collect::<Vec<_>>(), [&b"2"[..], &b""[..]]);
+			assert_eq!(out.coordinate(0), at);                                // Rows keep where they came from
+		}
+		other                         => panic!("expected a batch, got {:?}", other),
+	}
+}
+
+#[test]
+fn missing_column_reports_one_error_and_drops_rows() {
+	let mut relay = relay("x = missing + 1");
+	assert!(matches!(relay.accept(header(&["a"])), Some(Atom::ErrorAtom(Error::InvalidConfig(_), _))));
+	assert!(relay.accept(Atom::ByteRowAtom(row(&["1"]))).is_none());
+	assert_eq!(fields(&relay.accept(header(&["missing"])).unwrap()), ["missing", "x"]);
+	assert_eq!(fields(&relay.accept(Atom::ByteRowAtom(row(&["1"]))).unwrap()), ["1", "2"]);
+}
+
+#[test]
+fn initialize_requires_valid_definitions() {
+	assert!(matches!(DeriveRelay::new().initialize(&EmptyRelayConfig), Err(Error::InvalidConfig(_))));
+	let unnamed = MapRelayConfig::new().with(DERIVE_KEY, "= 1");
+	assert!(matches!(DeriveRelay::new().initialize(&unnamed), Err(Error::InvalidConfig(_))));
+	let bad = MapRelayConfig::new().with(DERIVE_KEY, "x = a +");
+	assert!(matches!(DeriveRelay::new().initialize(&bad), Err(Error::Parse(_))));
+}
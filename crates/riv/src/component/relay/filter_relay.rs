@@ -0,0 +1,135 @@
+use tracing::info;
+use crate::Error;
+use crate::model::coordinate::coordinate::Coordinate;
+use crate::model::expression::parser::CORRELATION_ID;
+use crate::model::expression::{Expression, Fields};
+use crate::model::ir::atom::Atom;
+use crate::model::ir::external_metadata::TaskMetadata;
+use crate::model::ir::value::Value;
+use crate::component::relay::{BindsColumns, ColumnBinding, RelayConfig, SimpleRelay};
+
+/// Config key: the row predicate, e.g. `status = 'open' AND amount > 100`.
+pub const EXPRESSION_KEY: &str = "expression";
+
+/// Drops every data row for which an `Expression` is not true.
+///
+/// Column names are bound as `ColumnBinding` describes. Batches are
+/// filtered row by row; a batch with no rows left is dropped. Atoms other
+/// than rows pass through.
+///
+pub struct FilterRelay {
+	expression: Option<Expression>,
+	bound:      ColumnBinding<Expression>,
+	kept:       u64,
+	dropped:    u64,
+}
+
+impl Default for FilterRelay {
+	fn default() -> Self { Self::new() }
+}
+
+impl FilterRelay {
+	/// A relay whose expression comes from `initialize`.
+	pub fn new() -> Self {
+		FilterRelay{expression: None, bound: ColumnBinding::default(), kept: 0, dropped: 0}
+	}
+
+	pub fn with_expression(text: &str) -> Result<Self, Error> {
+		let mut relay    = Self::new();
+		relay.expression = Some(Expression::parse(text)?);
+		Ok(relay)
+	}
+
+	pub fn kept(&self)    -> u64 { self.kept    }
+	pub fn dropped(&self) -> u64 { self.dropped }
+
+	/// `Some(true)` to keep; `None` if the relay cannot evaluate rows.
+	fn keep(&mut self, row: &dyn Fields, width: usize) -> Option<bool> {
+		if !self.ensure_bound(width) { return None }
+		let keep = self.bound.get()?.matches(row);
+		if keep { self.kept += 1 } else { self.dropped += 1 }
+		Some(keep)
+	}
+
+	/// Drop `rows` rows that cannot be evaluated.
+	fn reject(&mut self, rows: u64, coordinate: Coordinate) -> Option<Atom> {
+		self.dropped += rows;
+		self.bound.reject(coordinate)
+	}
+}
+
+impl BindsColumns for FilterRelay {
+	type Bound = Expression;
+
+	fn resolve<S: AsRef<str>>(&mut self, names: &[S]) -> Result<Expression, Error> {
+		match &self.expression {
+			Some(expression) => expression.bind(names),
+			None             => Err(Error::InvalidConfig("[FilterRelay]: not initialized".to_owned())),
+		}
+	}
+
+	fn binding(&mut self) -> &mut ColumnBinding<Expression> { &mut self.bound }
+}
+
+impl SimpleRelay for FilterRelay {
+	fn initialize(&mut self, cfg: &dyn RelayConfig) -> Result<(), Error> {
+		if let Some(text) = cfg.string_value(EXPRESSION_KEY) {
+			self.expression = Some(Expression::parse(&text)?);
+		}
+		let Some(expression) = &self.expression else {
+			return Err(Error::InvalidConfig(format!("[FilterRelay]: '{}' is required", EXPRESSION_KEY)))
+		};
+		info!("[FilterRelay]: initialized with {}", expression);
+		Ok(())
+	}
+
+	fn accept(&mut self, atom: Atom) -> Option<Atom> {
+		let coordinate = atom.coordinate();
+		match atom {
+			Atom::StartTask(source) => {
+				let id = Value::String(source.correlation_id().to_owned());
+				for expression in self.expression.iter_mut().chain(self.bound.get_mut()) {
+					expression.set_variable(CORRELATION_ID, &id);
+				}
+				Some(Atom::StartTask(source))
+			}
+			Atom::HeaderRow(header) => {
+				let names = header.iter_str().collect::<Vec<_>>();
+				match self.bind(&names) {
+					Ok(())     => Some(Atom::HeaderRow(header)),
+					Err(error) => Some(Atom::ErrorAtom(error, coordinate)),
+				}
+			}
+			Atom::ByteRowAtom(row) => match self.keep(&row, row.length() as usize) {
+				Some(true)  => Some(Atom::ByteRowAtom(row)),
+				Some(false) => None,
+				None        => self.reject(1, coordinate),
+			},
+			Atom::StringRowAtom(row) => match self.keep(&row, row.count() as usize) {
+				Some(true)  => Some(Atom::StringRowAtom(row)),
+				Some(false) => None,
+				None        => self.reject(1, coordinate),
+			},
+			Atom::TypedRowAtom(row) => match self.keep(&row, row.count() as usize) {
+				Some(true)  => Some(Atom::TypedRowAtom(row)),
+				Some(false) => None,
+				None        => self.reject(1, coordinate),
+			},
+			Atom::RowBatchAtom(batch) => {
+				if batch.is_empty() { return None }
+				if !self.ensure_bound(batch.width()) { return self.reject(batch.len() as u64, coordinate) }
+				let expression = self.bound.get()?;
+				let kept       = batch.filter_rows(|r| expression.matches(&(&batch, r)));
+				self.kept     += kept.len() as u64;
+				self.dropped  += (batch.len() - kept.len()) as u64;
+				if kept.is_empty() { None } else { Some(Atom::RowBatchAtom(kept)) }
+			}
+			other => Some(other),
+		}
+	}
+
+	fn finish(&mut self) -> bool {
+		info!("[FilterRelay]: kept {} rows, dropped {}", self.kept, self.dropped);
+		true
+	}
+}
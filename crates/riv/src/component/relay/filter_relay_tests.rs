@@ -0,0 +1,94 @@
+use crate::Error;
+use crate::component::relay::SimpleRelay;
+use crate::component::relay::filter_relay::{FilterRelay, EXPRESSION_KEY};
+use crate::component::relay::empty_relay_config::EmptyRelayConfig;
+use crate::component::relay::fixtures::{header, initialized, row};
+use crate::component::relay::map_relay_config::MapRelayConfig;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::row_batch::RowBatch;
+
+fn relay(expression: &str) -> FilterRelay {
+	initialized(FilterRelay::new(), &MapRelayConfig::new().with(EXPRESSION_KEY, expression))
+}
+
+#[test]
+fn drops_rows_that_do_not_match_and_counts_them() {
+	let mut relay = relay("status = 'open' AND amount > 100");
+	assert!(relay.accept(header(&["status", "amount"])).is_some());
+	assert!(relay.accept(Atom::ByteRowAtom(row(&["open",   "150"]))).is_some());
+	assert!(relay.accept(Atom::ByteRowAtom(row(&["open",   "50"]))).is_none());
+	assert!(relay.accept(Atom::ByteRowAtom(row(&["closed", "500"]))).is_none());
+	assert!(relay.accept(Atom::ByteRowAtom(row(&["open",   ""]))).is_none());
+	assert!(relay.accept(Atom::StringRowAtom(row(&["open", "101"]).as_string_row())).is_some());
+	assert!(matches!(relay.accept(Atom::EndTask), Some(Atom::EndTask)));
+	assert!(relay.finish());
+	assert_eq!((relay.kept(), relay.dropped()), (2, 3));
+}
+
+#[test]
+fn names_follow_the_latest_header() {
+	let mut relay = relay("code = 'b'");
+	relay.accept(header(&["code", "n"]));
+	assert!(relay.accept(Atom::ByteRowAtom(row(&["b", "1"]))).is_some());
+	relay.accept(header(&["n", "code"]));
+	assert!(relay.accept(Atom::ByteRowAtom(row(&["1", "b"]))).is_some());
+	assert!(relay.accept(Atom::ByteRowAtom(row(&["b", "1"]))).is_none());
+}
+
+#[test]
+fn headerless_rows_use_positional_names() {
+	let mut relay = relay("column_2 >= 10");
+	assert!(relay.accept(Atom::ByteRowAtom(row(&["a", "10"]))).is_some());
+	assert!(relay.accept(Atom::ByteRowAtom(row(&["a", "9"]))).is_none());
+}
+
+#[test]
+fn missing_column_reports_one_error_and_drops_rows() {
+	let mut relay = relay("missing = 1");
+	assert!(matches!(relay.accept(header(&["a"])), Some(Atom::ErrorAtom(Error::InvalidConfig(_), _))));
+	assert!(relay.accept(Atom::ByteRowAtom(row(&["1"]))).is_none());
+	assert!(relay.accept(Atom::ByteRowAtom(row(&["1"]))).is_none());
+	assert_eq!(relay.dropped(), 2);
+}
+
+#[test]
+fn headerless_rows_missing_a_column_report_the_error_at_the_first_row() {
+	let mut relay = relay("column_3 = 1");
+	assert!(matches!(relay.accept(Atom::ByteRowAtom(row(&["1"]))), Some(Atom::ErrorAtom(Error::InvalidConfig(_), _))));
+	assert!(relay.accept(Atom::ByteRowAtom(row(&["1"]))).is_none());
+}
+
+#[test]
+fn batches_are_filtered_row_by_row() {
+	let mut relay = relay("n IN (1, 3)");
+	let batch     = RowBatch::from_rows(&[row(&["1"]), row(&["2"]), row(&["3"])]).unwrap();
+	relay.accept(header(&["n"]));
+	match relay.accept(Atom::RowBatchAtom(batch)) {
+		Some(Atom::RowBatchAtom(kept)) => assert_eq!(kept.column(0).unwrap().iter().collect::<Vec<_>>(), [b"1", b"3"]),
+		other                          => panic!("expected a batch, got {:?}", other),
+	}
+	let none = RowBatch::from_rows(&[row(&["5"])]).unwrap();
+	assert!(relay.accept(Atom::RowBatchAtom(none)).is_none());
+	assert_eq!((relay.kept(), relay.dropped()), (2, 2));
+}
+
+#[test]
+fn initialize_requires_a_valid_expression() {
+	assert!(matches!(FilterRelay::new().initialize(&EmptyRelayConfig), Err(Error::InvalidConfig(_))));
+	let bad = MapRelayConfig::new().with(EXPRESSION_KEY, "a = = 1");
+	assert!(matches!(FilterRelay::new().initialize(&bad), Err(Error::Parse(_))));
+	let mut preset = FilterRelay::with_expression("a = 1").unwrap();
+	assert!(preset.initialize(&EmptyRelayConfig).is_ok());
+}
+
+#[test]
+fn runs_as_a_relay_through_a_collector() {
+	let mut relay: Box<dyn crate::component::relay::Relay> = Box::new(relay("amount > 1"));
+	let mut out = Vec::new();
+	relay.accept(header(&["amount"]), &mut out);
+	relay.accept(Atom::ByteRowAtom(row(&["0"])), &mut out);
+	relay.accept(Atom::ByteRowAtom(row(&["2"])), &mut out);
+	assert!(relay.finish(&mut out).is_ok());
+	assert_eq!(out.len(), 2);
+	assert!(matches!(out[1], Atom::ByteRowAtom(_)));
+}
@@ -0,0 +1,240 @@
+use std::fmt;
+use tracing::info;
+use zero::component::telemetry::component_metrics::ComponentMetrics;
+use zero::component::telemetry::provides_metrics::ProvidesMetrics;
+use crate::Error;
+use crate::component::relay::{batch_atom, BindsColumns, Collector, ColumnBinding, Relay, RelayConfig};
+use crate::component::relay::project_relay::glob_match;
+use crate::model::coordinate::coordinate::Coordinate;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::string_row::StringRow;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+use crate::utils::digest::sha256_digest_bytes;
+
+/// Config key: the columns hashed, by name or pattern such as `amount_*`. Defaults to all of them.
+pub const COLUMNS_KEY:    &str = "columns";
+/// Config key: `sha256` (default) or `fnv1a`, a faster 64-bit hash.
+pub const ALGORITHM_KEY:  &str = "algorithm";
+/// Config key: name of the appended column.
+pub const OUTPUT_KEY:     &str = "output";
+pub const DEFAULT_OUTPUT: &str = "fingerprint";
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME:  u64 = 0x0000_0100_0000_01b3;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Algorithm {
+	/// SHA-256, 64 hex digits.
+	#[default]
+	Sha256,
+	/// 64-bit FNV-1a, 16 hex digits; fast, but not collision resistant.
+	Fnv1a,
+}
+
+impl Algorithm {
+	pub fn parse(text: &str) -> Result<Algorithm, Error> {
+		match text.trim().to_ascii_lowercase().replace('-', "").as_str() {
+			"sha256" => Ok(Algorithm::Sha256),
+			"fnv1a"  => Ok(Algorithm::Fnv1a),
+			other    => Err(Error::InvalidConfig(format!("[FingerprintRelay]: algorithm must be sha256 or fnv1a, got '{}'", other))),
+		}
+	}
+
+	/// Hex digest of `input`.
+	pub fn digest(&self, input: &[u8]) -> String {
+		match self {
+			Algorithm::Sha256 => sha256_digest_bytes(input),
+			Algorithm::Fnv1a  => format!("{:016x}", input.iter().fold(FNV_OFFSET, |hash, &b| (hash ^ b as u64).wrapping_mul(FNV_PRIME))),
+		}
+	}
+}
+
+impl fmt::Display for Algorithm {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Algorithm::Sha256 => "sha256",
+			Algorithm::Fnv1a  => "fnv1a",
+		})
+	}
+}
+
+/// Appends a hash of each row's values as a new column.
+///
+/// The hash covers the `columns` given, in the order given with the
+/// columns a pattern matches in header order, or every column but the
+/// output one. Each value is hashed as its text behind its length, so
+/// `ab|c` and `a|bc` differ, and a null, an empty field and a field past
+/// the end of a short row all hash alike. The text of a byte or string
+/// row is its fields as delivered; a typed row's is each value's display
+/// text, so a typed and a text row hash alike only where that text is
+/// what the source held: `Integer(1)` matches `"1"` but not `"01"` or
+/// `"1.0"`. Compare fingerprints of rows taken at the same point of a
+/// pipeline. The hash does not depend on the order of the other columns
+/// and stays the same from one run to the next: storing it with the row
+/// shows whether a row changed between deliveries without comparing
+/// every field.
+///
+/// The `HeaderRow` gets the `output` column at the end. Columns are bound
+/// as `ColumnBinding` describes; dropped rows are counted in the metrics'
+/// `dropped_count`.
+///
+pub struct FingerprintRelay {
+	columns:   Vec<String>,
+	algorithm: Algorithm,
+	output:    String,
+	indices:   ColumnBinding<Vec<usize>>,
+	metrics:   ComponentMetrics,
+}
+
+impl Default for FingerprintRelay {
+	fn default() -> Self { Self::new() }
+}
+
+impl FingerprintRelay {
+	pub fn new() -> Self {
+		FingerprintRelay {
+			columns:   Vec::new(),
+			algorithm: Algorithm::Sha256,
+			output:    DEFAULT_OUTPUT.to_owned(),
+			indices:   ColumnBinding::default(),
+			metrics:   ComponentMetrics::default(),
+		}
+	}
+
+	pub fn with_columns(mut self, columns: &[&str]) -> Self {
+		self.columns = columns.iter().map(|c| c.to_string()).collect();
+		self
+	}
+
+	pub fn with_algorithm(mut self, algorithm: Algorithm) -> Self {
+		self.algorithm = algorithm;
+		self
+	}
+
+	pub fn algorithm(&self) -> Algorithm { self.algorithm }
+
+	/// The fingerprint of `values`, the hashed columns in order.
+	pub fn fingerprint<'a, I: IntoIterator<Item = Option<&'a [u8]>>>(&self, values: I) -> String {
+		let mut input = Vec::new();
+		for value in values {
+			let bytes = value.unwrap_or_default();
+			input.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+			input.extend_from_slice(bytes);
+		}
+		self.algorithm.digest(&input)
+	}
+
+	/// Drop a row without the columns.
+	fn reject(&mut self, coordinate: Coordinate, out: &mut dyn Collector) {
+		self.metrics.add_dropped(1);
+		self.indices.reject_into(coordinate, out);
+	}
+
+	fn byte_row(&mut self, row: &ByteRow, indices: &[usize]) -> ByteRow {
+		self.metrics.add_records(1);
+		let hash = self.fingerprint(indices.iter().map(|&i| row.get(i)));
+		ByteRow::from_fields((0..row.length() as usize).filter_map(|i| row.get(i)).chain([hash.as_bytes()])).with_coordinate(row.coordinate())
+	}
+}
+
+impl BindsColumns for FingerprintRelay {
+	type Bound = Vec<usize>;
+
+	fn resolve<S: AsRef<str>>(&mut self, names: &[S]) -> Result<Vec<usize>, Error> {
+		if self.columns.is_empty() {
+			return Ok((0..names.len()).filter(|&i| names[i].as_ref() != self.output).collect())
+		}
+		let mut selected = Vec::new();
+		for column in &self.columns {
+			let matched = match column.contains(['*', '?']) {
+				true  => (0..names.len()).filter(|&i| glob_match(column, names[i].as_ref())).collect::<Vec<_>>(),
+				false => names.iter().position(|n| n.as_ref() == column).into_iter().collect(),
+			};
+			if matched.is_empty() {
+				return Err(Error::InvalidConfig(format!("[FingerprintRelay]: no column in the header matches '{}'", column)))
+			}
+			selected.extend(matched.into_iter().filter(|i| !selected.contains(i)).collect::<Vec<_>>());
+		}
+		Ok(selected)
+	}
+
+	fn binding(&mut self) -> &mut ColumnBinding<Vec<usize>> { &mut self.indices }
+}
+
+impl Relay for FingerprintRelay {
+	fn initialize(&mut self, cfg: &dyn RelayConfig) -> Result<(), Error> {
+		if let Some(columns) = cfg.list_value(COLUMNS_KEY)  { self.columns   = columns;                  }
+		if let Some(text) = cfg.string_value(ALGORITHM_KEY) { self.algorithm = Algorithm::parse(&text)?; }
+		if let Some(text) = cfg.string_value(OUTPUT_KEY)    { self.output    = text.trim().to_owned();   }
+		if self.output.is_empty() {
+			return Err(Error::InvalidConfig(format!("[FingerprintRelay]: '{}' cannot be empty", OUTPUT_KEY)))
+		}
+		self.metrics.activate();
+		info!("[FingerprintRelay]: initialized with {}", cfg);
+		Ok(())
+	}
+
+	fn accept(&mut self, atom: Atom, out: &mut dyn Collector) {
+		self.metrics.increment_messages();
+		let coordinate = atom.coordinate();
+		match atom {
+			Atom::HeaderRow(header) => {
+				let mut names = header.iter_str().map(str::to_owned).collect::<Vec<_>>();
+				match self.bind(&names) {
+					Ok(())     => {
+						names.push(self.output.clone());
+						out.emit(Atom::HeaderRow(StringRow::from_values(names).with_coordinate(coordinate)));
+					}
+					Err(error) => out.emit(Atom::ErrorAtom(error, coordinate)),
+				}
+			}
+			Atom::ByteRowAtom(row)    => {
+				let Some(indices) = self.bound(row.length() as usize) else { return self.reject(coordinate, out) };
+				let row = self.byte_row(&row, &indices);
+				out.emit(Atom::ByteRowAtom(row));
+			}
+			Atom::StringRowAtom(row)  => {
+				let Some(indices) = self.bound(row.count() as usize) else { return self.reject(coordinate, out) };
+				self.metrics.add_records(1);
+				let hash       = self.fingerprint(indices.iter().map(|&i| row.get(i).map(str::as_bytes)));
+				let mut values = row.iter_str().map(str::to_owned).collect::<Vec<_>>();
+				values.push(hash);
+				out.emit(Atom::StringRowAtom(StringRow::from_values(values).with_coordinate(coordinate)));
+			}
+			Atom::TypedRowAtom(row)   => {
+				let Some(indices) = self.bound(row.count() as usize) else { return self.reject(coordinate, out) };
+				self.metrics.add_records(1);
+				let texts      = indices.iter().map(|&i| row.get(i).map(Value::to_string).unwrap_or_default()).collect::<Vec<_>>();
+				let hash       = self.fingerprint(texts.iter().map(|t| Some(t.as_bytes())));
+				let mut values = row.iter().cloned().collect::<Vec<_>>();
+				values.push(Value::String(hash));
+				out.emit(Atom::TypedRowAtom(TypedRow::new(values).with_coordinate(coordinate)));
+			}
+			Atom::RowBatchAtom(batch) => {
+				let Some(indices) = self.bound(batch.width()) else { return self.reject(coordinate, out) };
+				if batch.is_empty() { return }
+				let rows = batch.to_rows().iter().map(|row| self.byte_row(row, &indices)).collect::<Vec<_>>();
+				out.emit(batch_atom("FingerprintRelay", &rows));
+			}
+			other                     => out.emit(other),
+		}
+	}
+
+	fn finish(&mut self, _out: &mut dyn Collector) -> Result<(), Error> {
+		self.metrics.complete();
+		info!("[FingerprintRelay]: hashed {} rows with {}", self.metrics.record_count, self.algorithm);
+		Ok(())
+	}
+}
+
+impl ProvidesMetrics for FingerprintRelay {
+	fn metrics(&self) -> ComponentMetrics { self.metrics }
+
+	fn take_metrics(&mut self) -> ComponentMetrics {
+		let metrics = self.metrics;
+		self.metrics.reset();
+		metrics
+	}
+}
@@ -0,0 +1,84 @@
+use zero::component::telemetry::provides_metrics::ProvidesMetrics;
+use crate::Error;
+use crate::component::relay::Relay;
+use crate::component::relay::fingerprint_relay::{Algorithm, FingerprintRelay, ALGORITHM_KEY, COLUMNS_KEY, OUTPUT_KEY};
+use crate::component::relay::fixtures::{all, fields, header, initialized, row};
+use crate::component::relay::map_relay_config::MapRelayConfig;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::row_batch::RowBatch;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+use crate::utils::digest::sha256_digest_bytes;
+
+fn relay(cfg: MapRelayConfig) -> FingerprintRelay {
+	initialized(FingerprintRelay::new(), &cfg)
+}
+
+fn last(atom: &Atom) -> String { fields(atom).pop().unwrap() }
+
+#[test]
+fn settings_and_digests() {
+	assert_eq!(Algorithm::parse(" FNV-1a ").unwrap(), Algorithm::Fnv1a);
+	assert!(matches!(Algorithm::parse("md5"), Err(Error::InvalidConfig(_))));
+	assert!(matches!(FingerprintRelay::new().initialize(&MapRelayConfig::new().with(OUTPUT_KEY, " ")), Err(Error::InvalidConfig(_))));
+	assert_eq!(Algorithm::Fnv1a.digest(b""), "cbf29ce484222325");
+	assert_eq!(Algorithm::Fnv1a.digest(b"a"), "af63dc4c8601ec8c");
+
+	let relay    = FingerprintRelay::new();
+	let expected = sha256_digest_bytes(&[&2u64.to_le_bytes()[..], b"ab", &1u64.to_le_bytes(), b"c"].concat());
+	assert_eq!(relay.fingerprint([Some(&b"ab"[..]), Some(b"c")]), expected);
+	assert_ne!(relay.fingerprint([Some(&b"a"[..]), Some(b"bc")]), expected);
+	assert_eq!(relay.fingerprint([None, Some(&b"x"[..])]), relay.fingerprint([Some(&b""[..]), Some(b"x")]));
+}
+
+#[test]
+fn appends_a_column_hashing_every_value() {
+	let mut relay = relay(MapRelayConfig::new());
+	assert_eq!(fields(&all(&mut relay, header(&["id", "amount"]))[0]), ["id", "amount", "fingerprint"]);
+	let bytes     = all(&mut relay, Atom::ByteRowAtom(row(&["1", "9.50"])));
+	assert_eq!(fields(&bytes[0])[..2], ["1", "9.50"]);
+	assert_eq!(last(&bytes[0]).len(), 64);
+
+	let strings   = all(&mut relay, Atom::StringRowAtom(row(&["1", "9.50"]).as_string_row()));
+	let typed     = all(&mut relay, Atom::TypedRowAtom(TypedRow::new(vec![Value::String("1".into()), Value::String("9.50".into())])));
+	assert_eq!(last(&strings[0]), last(&bytes[0]));
+	assert_eq!(last(&typed[0]), last(&bytes[0]));
+	assert_ne!(last(&all(&mut relay, Atom::ByteRowAtom(row(&["1", "9.51"])))[0]), last(&bytes[0]));
+	assert_eq!(relay.metrics().record_count, 4);
+
+	// A typed value hashes as its display text, not as what it was parsed from
+	let integer   = all(&mut relay, Atom::TypedRowAtom(TypedRow::new(vec![Value::Integer(1), Value::String("9.50".into())])));
+	assert_eq!(last(&integer[0]), last(&bytes[0]));
+	assert_ne!(last(&integer[0]), last(&all(&mut relay, Atom::ByteRowAtom(row(&["01", "9.50"])))[0]));
+}
+
+#[test]
+fn selected_columns_ignore_the_others() {
+	let cfg       = MapRelayConfig::new().with(COLUMNS_KEY, "id, amount_*").with(ALGORITHM_KEY, "fnv1a").with(OUTPUT_KEY, "row_hash");
+	let mut relay = relay(cfg);
+	assert_eq!(fields(&all(&mut relay, header(&["amount_eur", "loaded_at", "id"]))[0]), ["amount_eur", "loaded_at", "id", "row_hash"]);
+	let batch     = RowBatch::from_rows(&[row(&["5", "monday", "7"]), row(&["5", "tuesday", "7"]), row(&["6", "tuesday", "7"])]).unwrap();
+	let hashes    = match &all(&mut relay, Atom::RowBatchAtom(batch))[..] {
+		[Atom::RowBatchAtom(hashed)] => hashed.to_rows().iter().map(|r| last(&Atom::ByteRowAtom(r.clone()))).collect::<Vec<_>>(),
+		other                        => panic!("expected one batch, got {:?}", other),
+	};
+	assert_eq!(hashes[0].len(), 16);
+	assert_eq!(hashes[0], hashes[1]);
+	assert_ne!(hashes[1], hashes[2]);
+
+	// The same values in another column order hash alike
+	all(&mut relay, header(&["id", "amount_eur"]));
+	assert_eq!(last(&all(&mut relay, Atom::ByteRowAtom(row(&["7", "5"])))[0]), hashes[0]);
+}
+
+#[test]
+fn missing_columns_are_reported_once() {
+	let mut keyed = relay(MapRelayConfig::new().with(COLUMNS_KEY, "id"));
+	assert!(matches!(all(&mut keyed, header(&["key"]))[..], [Atom::ErrorAtom(Error::InvalidConfig(_), _)]));
+	assert!(all(&mut keyed, Atom::ByteRowAtom(row(&["1"]))).is_empty());
+	assert!(matches!(all(&mut keyed, Atom::EndTask)[..], [Atom::EndTask]));
+	assert_eq!(keyed.metrics().dropped_count, 1);
+
+	let mut positional = relay(MapRelayConfig::new().with(COLUMNS_KEY, "column_2"));
+	assert_eq!(fields(&all(&mut positional, Atom::ByteRowAtom(row(&["a", "b"])))[0]).len(), 3);
+}
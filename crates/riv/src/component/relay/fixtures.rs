@@ -0,0 +1,54 @@
+//! Atoms and helpers shared by the relay tests.
+
+use crate::component::relay::{Relay, RelayConfig};
+use crate::model::ir::atom::Atom;
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::value::Value;
+
+pub(crate) fn row(fields: &[&str]) -> ByteRow {
+	let data = fields.concat();
+	let ends = fields.iter().scan(0, |end, f| { *end += f.len(); Some(*end) }).collect::<Vec<_>>();
+	ByteRow::new(data.as_bytes(), &ends)
+}
+
+pub(crate) fn header(names: &[&str]) -> Atom { Atom::HeaderRow(row(names).as_string_row()) }
+
+pub(crate) fn text(s: &str) -> Value { Value::String(s.to_owned()) }
+
+/// `relay`, initialized with `cfg`, which must be valid.
+pub(crate) fn initialized<R: Relay>(mut relay: R, cfg: &dyn RelayConfig) -> R {
+	relay.initialize(cfg).unwrap();
+	relay
+}
+
+/// Everything the relay emits for `atom`.
+pub(crate) fn all(relay: &mut impl Relay, atom: Atom) -> Vec<Atom> {
+	let mut out = Vec::new();
+	relay.accept(atom, &mut out);
+	out
+}
+
+/// What the relay emits for `atom`, which must be at most one atom.
+pub(crate) fn one(relay: &mut impl Relay, atom: Atom) -> Option<Atom> {
+	let mut out = all(relay, atom);
+	assert!(out.len() <= 1, "expected at most one atom, got {:?}", out);
+	out.pop()
+}
+
+/// Finish the relay and return what it flushed.
+pub(crate) fn finish(relay: &mut impl Relay) -> Vec<Atom> {
+	let mut out = Vec::new();
+	relay.finish(&mut out).unwrap();
+	out
+}
+
+/// The names or fields of a header or row, as text.
+pub(crate) fn fields(atom: &Atom) -> Vec<String> {
+	match atom {
+		Atom::HeaderRow(r)     => r.iter_str().map(str::to_owned).collect(),
+		Atom::ByteRowAtom(r)   => r.as_string_row().iter_str().map(str::to_owned).collect(),
+		Atom::StringRowAtom(r) => r.iter_str().map(str::to_owned).collect(),
+		Atom::TypedRowAtom(r)  => r.iter().map(Value::to_string).collect(),
+		other                  => panic!("expected a header or row, got {:?}", other),
+	}
+}
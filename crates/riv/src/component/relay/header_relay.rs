@@ -0,0 +1,200 @@
+use std::collections::HashSet;
+use std::fmt;
+use icu_normalizer::DecomposingNormalizer;
+use tracing::info;
+use crate::Error;
+use crate::component::relay::{Collector, Relay, RelayConfig};
+use crate::model::ir::atom::Atom;
+use crate::model::ir::string_row::StringRow;
+
+/// Config key: `snake_case` (default), `camel_case`, `sql` or `preserve`.
+pub const CONVENTION_KEY:        &str = "convention";
+/// Name prefix of the bindings that record each column's names.
+pub const HEADER_MAPPING_PREFIX: &str = "header_mapping.";
+
+/// Words that cannot be used unquoted as a column name in `sql` form.
+const RESERVED: [&str; 48] = [
+	"add", "all", "alter", "and", "as", "asc", "between", "by", "case", "check", "column", "constraint",
+	"create", "default", "delete", "desc", "distinct", "drop", "else", "end", "exists", "foreign", "from", "group",
+	"having", "in", "index", "insert", "into", "is", "join", "key", "like", "limit", "not", "null",
+	"offset", "on", "or", "order", "primary", "references", "select", "set", "table", "then", "union", "where",
+];
+
+/// How column names are rewritten.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Convention {
+	/// `trade_date_utc`
+	#[default]
+	Snake,
+	/// `tradeDateUtc`
+	Camel,
+	/// `trade_date_utc` in ASCII, accents dropped, never starting with a digit and never a reserved word.
+	Sql,
+	/// Names as they are; only blanks and duplicates change.
+	Preserve,
+}
+
+impl Convention {
+	pub fn parse(text: &str) -> Result<Convention, Error> {
+		match text.trim().to_ascii_lowercase().as_str() {
+			"snake" | "snake_case" => Ok(Convention::Snake),
+			"camel" | "camel_case" => Ok(Convention::Camel),
+			"sql"                  => Ok(Convention::Sql),
+			"preserve"             => Ok(Convention::Preserve),
+			other                  => Err(Error::InvalidConfig(format!("[HeaderRelay]: convention must be snake_case, camel_case, sql or preserve, got '{}'", other))),
+		}
+	}
+
+	/// `name` in this convention; empty when nothing of it is left.
+	pub fn apply(&self, name: &str) -> String {
+		match self {
+			Convention::Snake    => words(name).join("_"),
+			Convention::Camel    => words(name).iter().enumerate()
+				.map(|(i, word)| if i == 0 { word.clone() } else { capitalize(word) })
+				.collect(),
+			Convention::Sql      => {
+				let ascii = words(name).into_iter()
+					.map(|word| DecomposingNormalizer::new_nfkd().normalize(&word).chars().filter(char::is_ascii_alphanumeric).collect::<String>())
+					.filter(|word| !word.is_empty())
+					.collect::<Vec<_>>();
+				let mut name = ascii.join("_");
+				if name.starts_with(|c: char| c.is_ascii_digit()) { name.insert(0, '_') }
+				if RESERVED.contains(&name.as_str())              { name.push('_')      }
+				name
+			}
+			Convention::Preserve => name.trim().to_owned(),
+		}
+	}
+}
+
+impl fmt::Display for Convention {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Convention::Snake    => "snake_case",
+			Convention::Camel    => "camel_case",
+			Convention::Sql      => "sql",
+			Convention::Preserve => "preserve",
+		})
+	}
+}
+
+/// Rewrites column names into one naming convention.
+///
+/// Each `HeaderRow` is rewritten: names are split into words at
+/// punctuation, spaces and case changes (`TradeDate`, `HTTPStatus`),
+/// then joined as the convention says. A name left blank becomes
+/// `column_N`, N being its position from 1, and a repeated name gets
+/// `_2`, `_3`, … so every name in the header is unique. Rows pass
+/// unchanged.
+///
+/// After the rewritten header the relay emits an `InternalMetadata` atom
+/// recording the mapping, which sinks keep as run metadata. Columns are
+/// keyed by position, since original names may repeat or be blank:
+/// `header_mapping.N.original` and `header_mapping.N.name` hold the
+/// original and new name of column N, counted from 1. The latest mapping
+/// is also available from `mapping`.
+///
+#[derive(Default)]
+pub struct HeaderRelay {
+	convention: Convention,
+	mapping:    Vec<(String, String)>,
+	headers:    u64,
+}
+
+impl HeaderRelay {
+	pub fn new() -> Self { Self::default() }
+
+	pub fn with_convention(mut self, convention: Convention) -> Self {
+		self.convention = convention;
+		self
+	}
+
+	pub fn convention(&self) -> Convention { self.convention }
+
+	/// Original and new name of each column of the latest header.
+	pub fn mapping(&self) -> &[(String, String)] { &self.mapping }
+
+	/// The header `names` rewritten.
+	pub fn rename<S: AsRef<str>>(&self, names: &[S]) -> Vec<String> {
+		let renamed = names.iter().enumerate()
+			.map(|(i, name)| Some(self.convention.apply(name.as_ref())).filter(|n| !n.is_empty()).unwrap_or_else(|| format!("column_{}", i + 1)))
+			.collect::<Vec<_>>();
+		let mut taken = HashSet::new();
+		renamed.iter()
+			.map(|name| {
+				let unique = (1..).map(|n| if n == 1 { name.clone() } else { format!("{}_{}", name, n) })
+					.find(|candidate| !taken.contains(candidate) && (candidate == name || !renamed.contains(candidate)))
+					.unwrap_or_default();
+				taken.insert(unique.clone());
+				unique
+			})
+			.collect()
+	}
+}
+
+impl Relay for HeaderRelay {
+	fn initialize(&mut self, cfg: &dyn RelayConfig) -> Result<(), Error> {
+		if let Some(text) = cfg.string_value(CONVENTION_KEY) {
+			self.convention = Convention::parse(&text)?;
+		}
+		info!("[HeaderRelay]: initialized with convention {}", self.convention);
+		Ok(())
+	}
+
+	fn accept(&mut self, atom: Atom, out: &mut dyn Collector) {
+		match atom {
+			Atom::HeaderRow(header) => {
+				let original = header.iter_str().map(str::to_owned).collect::<Vec<_>>();
+				let renamed  = self.rename(&original);
+				self.mapping = original.into_iter().zip(renamed.iter().cloned()).collect();
+				self.headers += 1;
+				out.emit(Atom::HeaderRow(StringRow::from_values(renamed).with_coordinate(header.coordinate())));
+				out.emit(Atom::InternalMetadata(mapping_bindings(&self.mapping)));
+			}
+			other                   => out.emit(other),
+		}
+	}
+
+	fn finish(&mut self, _out: &mut dyn Collector) -> Result<(), Error> {
+		info!("[HeaderRelay]: rewrote {} headers", self.headers);
+		Ok(())
+	}
+}
+
+/// Two bindings per column: its original and its new name.
+fn mapping_bindings(mapping: &[(String, String)]) -> Vec<(String, String)> {
+	mapping.iter().enumerate()
+		.flat_map(|(i, (original, name))| [
+			(format!("{}{}.original", HEADER_MAPPING_PREFIX, i + 1), original.clone()),
+			(format!("{}{}.name", HEADER_MAPPING_PREFIX, i + 1),     name.clone()),
+		])
+		.collect()
+}
+
+/// The lowercase words of `name`, split at anything not alphanumeric and
+/// where the case changes.
+fn words(name: &str) -> Vec<String> {
+	let chars     = name.chars().collect::<Vec<_>>();
+	let mut words = Vec::new();
+	let mut word  = String::new();
+	for (i, &c) in chars.iter().enumerate() {
+		if !c.is_alphanumeric() {
+			if !word.is_empty() { words.push(std::mem::take(&mut word)) }
+			continue
+		}
+		let previous = i.checked_sub(1).map(|p| chars[p]);
+		let next     = chars.get(i + 1).copied();
+		let boundary = c.is_uppercase() && previous.is_some_and(|p| {
+			(p.is_lowercase() || p.is_numeric()) || (p.is_uppercase() && next.is_some_and(char::is_lowercase))
+		});
+		if boundary && !word.is_empty() { words.push(std::mem::take(&mut word)) }
+		word.extend(c.to_lowercase());
+	}
+	if !word.is_empty() { words.push(word) }
+	words
+}
+
+fn capitalize(word: &str) -> String {
+	let mut chars = word.chars();
+	chars.next().map(|first| first.to_uppercase().chain(chars).collect()).unwrap_or_default()
+}
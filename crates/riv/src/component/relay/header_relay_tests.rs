@@ -0,0 +1,56 @@
+use crate::Error;
+use crate::component::relay::Relay;
+use crate::component::relay::fixtures::{header, initialized};
+use crate::component::relay::header_relay::{Convention, HeaderRelay, CONVENTION_KEY, HEADER_MAPPING_PREFIX};
+use crate::component::relay::map_relay_config::MapRelayConfig;
+use crate::model::ir::atom::Atom;
+
+fn relay(convention: &str) -> HeaderRelay {
+	initialized(HeaderRelay::new(), &MapRelayConfig::new().with(CONVENTION_KEY, convention))
+}
+
+#[test]
+fn conventions_split_words_at_punctuation_and_case() {
+	let names = ["Trade Date (UTC)", "tradeID", "HTTPStatus", "2025 Q1", "Prix (€)", "order", "  Ünïcode-Name "];
+	let apply = |c: Convention| names.iter().map(|n| c.apply(n)).collect::<Vec<_>>();
+	assert_eq!(apply(Convention::Snake),    ["trade_date_utc", "trade_id", "http_status", "2025_q1", "prix", "order", "ünïcode_name"]);
+	assert_eq!(apply(Convention::Camel),    ["tradeDateUtc", "tradeId", "httpStatus", "2025Q1", "prix", "order", "ünïcodeName"]);
+	assert_eq!(apply(Convention::Sql),      ["trade_date_utc", "trade_id", "http_status", "_2025_q1", "prix", "order_", "unicode_name"]);
+	assert_eq!(apply(Convention::Preserve)[6], "Ünïcode-Name");
+
+	assert_eq!(Convention::parse("camelCase").ok(), None);
+	assert_eq!(Convention::parse(" SQL ").unwrap(), Convention::Sql);
+	assert!(matches!(HeaderRelay::new().initialize(&MapRelayConfig::new().with(CONVENTION_KEY, "kebab")), Err(Error::InvalidConfig(_))));
+}
+
+#[test]
+fn blanks_are_filled_and_duplicates_suffixed() {
+	let relay = relay("snake_case");
+	assert_eq!(relay.rename(&["Amount", "", "amount", "AMOUNT", "amount_2", "%%"]), ["amount", "column_2", "amount_3", "amount_4", "amount_2", "column_6"]);
+	assert_eq!(relay.rename(&["a", "a", "a"]), ["a", "a_2", "a_3"]);
+}
+
+#[test]
+fn the_header_is_rewritten_and_the_mapping_recorded() {
+	let mut relay = relay("sql");
+	let mut out   = Vec::new();
+	relay.accept(Atom::CommentRow("vendor file".into()), &mut out);
+	relay.accept(header(&["Trade Date", "Select", ""]), &mut out);
+	relay.accept(Atom::EndTask, &mut out);
+	assert_eq!(out.len(), 4);
+	assert!(matches!(out[0], Atom::CommentRow(_)));
+	match &out[1] {
+		Atom::HeaderRow(h) => assert_eq!(h.iter_str().collect::<Vec<_>>(), ["trade_date", "select_", "column_3"]),
+		other              => panic!("expected a header, got {:?}", other),
+	}
+	match &out[2] {
+		Atom::InternalMetadata(bindings) => {
+			assert_eq!(bindings.len(), 6);
+			assert_eq!(bindings[0], (format!("{}1.original", HEADER_MAPPING_PREFIX), "Trade Date".to_owned()));
+			assert_eq!(bindings[5], (format!("{}3.name", HEADER_MAPPING_PREFIX),     "column_3".to_owned()));
+		}
+		other                            => panic!("expected metadata, got {:?}", other),
+	}
+	assert!(matches!(out[3], Atom::EndTask));
+	assert_eq!(relay.mapping()[2], (String::new(), "column_3".to_owned()));
+}
@@ -0,0 +1,430 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use tracing::info;
+use zero::component::telemetry::component_metrics::ComponentMetrics;
+use zero::component::telemetry::provides_metrics::ProvidesMetrics;
+use crate::Error;
+use crate::error::IoErrorWrapper;
+use crate::component::relay::{batch_atom, BindsColumns, Collector, ColumnBinding, Relay, RelayConfig};
+use crate::component::relay::dedupe_relay::key_hash;
+use crate::component::source::csv_byte_source::CsvByteSource;
+use crate::component::source::sqlite_source::SqliteSource;
+use crate::model::coordinate::coordinate::Coordinate;
+use crate::model::expression::Fields;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::row_batch::RowBatch;
+use crate::model::ir::string_row::StringRow;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+
+/// Config key: path of the reference data, a `.csv` file or a SQLite database.
+pub const REFERENCE_KEY:       &str = "reference";
+/// Config key: table to read when the reference is a SQLite database.
+pub const REFERENCE_TABLE_KEY: &str = "reference_table";
+/// Config key: key columns, each `column` or `input_column = reference_column`.
+pub const ON_KEY:              &str = "on";
+/// Config key: reference columns to append, each `column [as name]`. Defaults to every non-key column.
+pub const COLUMNS_KEY:         &str = "columns";
+/// Config key: `left` (default) or `inner`.
+pub const JOIN_KEY:            &str = "join";
+/// Config key: what a left join does with a row that has no match: `null`, `default`, `drop` or `error`.
+pub const ON_MISSING_KEY:      &str = "on_missing";
+/// Config key: text appended for every column when `on_missing = default`.
+pub const DEFAULT_KEY:         &str = "default";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Join {
+	/// Rows without a match are handled by `OnMissing`.
+	#[default]
+	Left,
+	/// Rows without a match are dropped.
+	Inner,
+}
+
+impl Join {
+	pub fn parse(text: &str) -> Result<Join, Error> {
+		match text.trim().to_ascii_lowercase().as_str() {
+			"left"  => Ok(Join::Left),
+			"inner" => Ok(Join::Inner),
+			other   => Err(Error::InvalidConfig(format!("[LookupRelay]: join must be 'left' or 'inner', got '{}'", other))),
+		}
+	}
+}
+
+/// What happens to a row whose key is not in the reference.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnMissing {
+	/// Append nulls.
+	#[default]
+	Null,
+	/// Append the configured default.
+	Default,
+	/// Drop the row.
+	Drop,
+	/// Drop the row and emit an `ErrorAtom` for it.
+	Error,
+}
+
+impl OnMissing {
+	pub fn parse(text: &str) -> Result<OnMissing, Error> {
+		match text.trim().to_ascii_lowercase().as_str() {
+			"null"    => Ok(OnMissing::Null),
+			"default" => Ok(OnMissing::Default),
+			"drop"    => Ok(OnMissing::Drop),
+			"error"   => Ok(OnMissing::Error),
+			other     => Err(Error::InvalidConfig(format!("[LookupRelay]: on_missing must be null, default, drop or error, got '{}'", other))),
+		}
+	}
+}
+
+/// A key column of the incoming rows and the reference column it matches.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyPair {
+	pub input:     String,
+	pub reference: String,
+}
+
+impl KeyPair {
+	pub fn new(input: &str, reference: &str) -> Self {
+		KeyPair{input: input.to_owned(), reference: reference.to_owned()}
+	}
+
+	/// Parse `column`, the same name on both sides, or `input = reference`.
+	pub fn parse(text: &str) -> Result<KeyPair, Error> {
+		let (input, reference) = text.split_once('=').unwrap_or((text, text));
+		let (input, reference) = (input.trim(), reference.trim());
+		if input.is_empty() || reference.is_empty() {
+			return Err(Error::InvalidConfig(format!("[LookupRelay]: expected 'column' or 'input = reference', got '{}'", text)))
+		}
+		Ok(KeyPair::new(input, reference))
+	}
+}
+
+/// A reference column to append, and its name in the output.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Selection {
+	pub column: String,
+	pub name:   String,
+}
+
+impl Selection {
+	pub fn new(column: &str, name: &str) -> Self {
+		Selection{column: column.to_owned(), name: name.to_owned()}
+	}
+
+	/// Parse `column` or `column as name`.
+	pub fn parse(text: &str) -> Result<Selection, Error> {
+		let text   = text.trim();
+		let padded = format!("{} ", text.to_ascii_lowercase());
+		let (column, name) = match padded.rfind(" as ") {
+			Some(at) => (text[..at].trim(), text[(at + 4).min(text.len())..].trim()),
+			None     => (text, text),
+		};
+		if column.is_empty() || name.is_empty() {
+			return Err(Error::InvalidConfig(format!("[LookupRelay]: expected 'column [as name]', got '{}'", text)))
+		}
+		Ok(Selection::new(column, name))
+	}
+}
+
+/// Appends columns from a reference dataset to each row, matched on key
+/// columns.
+///
+/// The reference is read whole at `initialize`, from a `Source` given with
+/// `with_reference` or from the `reference` path: a CSV file, read as
+/// `CsvByteSource` reads it, or a table of a SQLite database (`.db`,
+/// `.sqlite`, `.sqlite3`). Its rows are indexed by a 128-bit hash of their
+/// key values, compared as text, so the text "42" matches the integer 42.
+/// When several reference rows share a key the first is used. Rows with a
+/// null key never match.
+///
+/// Key columns are bound as `ColumnBinding` describes, and the header is
+/// extended with the appended names. Byte and string rows get the text of
+/// each value, with null left empty; typed rows get the values themselves.
+///
+/// An inner join drops rows without a match; a left join keeps them as
+/// `on_missing` says. Dropped rows are counted in the metrics'
+/// `dropped_count`, and rows reported as errors in `error_count`.
+///
+pub struct LookupRelay {
+	keys:       Vec<KeyPair>,
+	selections: Vec<Selection>,
+	join:       Join,
+	on_missing: OnMissing,
+	default:    String,
+	pending:    Option<Box<dyn Iterator<Item = Atom>>>,
+	index:      HashMap<u128, Vec<Value>>,
+	indices:    ColumnBinding<Vec<usize>>,
+	matched:    u64,
+	metrics:    ComponentMetrics,
+}
+
+impl Default for LookupRelay {
+	fn default() -> Self { Self::new() }
+}
+
+impl LookupRelay {
+	pub fn new() -> Self {
+		LookupRelay {
+			keys:       Vec::new(),
+			selections: Vec::new(),
+			join:       Join::Left,
+			on_missing: OnMissing::Null,
+			default:    String::new(),
+			pending:    None,
+			index:      HashMap::new(),
+			indices:    ColumnBinding::default(),
+			matched:    0,
+			metrics:    ComponentMetrics::default(),
+		}
+	}
+
+	pub fn with_key(mut self, key: KeyPair) -> Self {
+		self.keys.push(key);
+		self
+	}
+
+	pub fn with_column(mut self, selection: Selection) -> Self {
+		self.selections.push(selection);
+		self
+	}
+
+	pub fn with_join(mut self, join: Join) -> Self {
+		self.join = join;
+		self
+	}
+
+	pub fn with_on_missing(mut self, on_missing: OnMissing, default: &str) -> Self {
+		self.on_missing = on_missing;
+		self.default    = default.to_owned();
+		self
+	}
+
+	/// Read the reference from `source` at `initialize`, instead of from the `reference` path.
+	pub fn with_reference<S: Iterator<Item = Atom> + 'static>(mut self, source: S) -> Self {
+		self.pending = Some(Box::new(source));
+		self
+	}
+
+	pub fn keys(&self)       -> &[KeyPair]   { &self.keys       }
+	pub fn selections(&self) -> &[Selection] { &self.selections }
+
+	/// Distinct keys in the reference.
+	pub fn reference_len(&self) -> usize { self.index.len() }
+
+	/// Rows that found a match so far.
+	pub fn matched(&self) -> u64 { self.matched }
+
+	/// Index the reference rows by key, keeping the selected columns.
+	fn load(&mut self, reference: Box<dyn Iterator<Item = Atom>>) -> Result<(), Error> {
+		let mut columns    = None;
+		let mut duplicates = 0u64;
+		for atom in reference {
+			let row: &dyn Fields = match &atom {
+				Atom::HeaderRow(header) if columns.is_none() => {
+					columns = Some(self.resolve_reference(&header.iter_str().collect::<Vec<_>>())?);
+					continue
+				}
+				Atom::ByteRowAtom(row)    => row,
+				Atom::StringRowAtom(row)  => row,
+				Atom::TypedRowAtom(row)   => row,
+				Atom::ErrorAtom(error, _) => return Err(error.clone()),
+				_                         => continue,
+			};
+			if columns.is_none() {
+				let width = match &atom {
+					Atom::ByteRowAtom(row)   => row.length() as usize,
+					Atom::StringRowAtom(row) => row.count() as usize,
+					Atom::TypedRowAtom(row)  => row.count() as usize,
+					_                        => 0,
+				};
+				columns = Some(self.resolve_reference(&(1..=width).map(|i| format!("column_{}", i)).collect::<Vec<_>>())?);
+			}
+			let Some((keys, selected)) = &columns else { continue };
+			let Some(hash) = key_of(row, keys) else { continue };
+			match self.index.contains_key(&hash) {
+				true  => duplicates += 1,
+				false => { self.index.insert(hash, selected.iter().map(|&i| row.value(i)).collect()); }
+			}
+		}
+		info!("[LookupRelay]: indexed {} reference keys, ignoring {} duplicates", self.index.len(), duplicates);
+		Ok(())
+	}
+
+	/// Positions of the reference key columns and the selected columns.
+	/// Without any selection, every non-key column is selected.
+	fn resolve_reference<S: AsRef<str>>(&mut self, names: &[S]) -> Result<(Vec<usize>, Vec<usize>), Error> {
+		let find = |column: &str| names.iter().position(|n| n.as_ref() == column)
+			.ok_or_else(|| Error::InvalidConfig(format!("[LookupRelay]: no column '{}' in the reference", column)));
+		let keys = self.keys.iter().map(|k| find(&k.reference)).collect::<Result<Vec<_>, _>>()?;
+		if self.selections.is_empty() {
+			self.selections = names.iter().enumerate()
+				.filter(|(i, _)| !keys.contains(i))
+				.map(|(_, n)| Selection::new(n.as_ref(), n.as_ref()))
+				.collect();
+		}
+		let selected = self.selections.iter().map(|s| find(&s.column)).collect::<Result<Vec<_>, _>>()?;
+		Ok((keys, selected))
+	}
+
+	/// The values to append to `row`, or `None` to drop it.
+	fn look_up(&mut self, row: &dyn Fields, indices: &[usize], coordinate: Coordinate, out: &mut dyn Collector) -> Option<Vec<Value>> {
+		self.metrics.add_records(1);
+		if let Some(values) = key_of(row, indices).and_then(|hash| self.index.get(&hash)) {
+			self.matched += 1;
+			return Some(values.clone())
+		}
+		let on_missing = if self.join == Join::Inner { OnMissing::Drop } else { self.on_missing };
+		match on_missing {
+			OnMissing::Null    => Some(vec![Value::Null; self.selections.len()]),
+			OnMissing::Default => Some(vec![Value::String(self.default.clone()); self.selections.len()]),
+			OnMissing::Drop    => {
+				self.metrics.add_dropped(1);
+				None
+			}
+			OnMissing::Error   => {
+				let key = indices.iter().map(|&i| row.value(i).to_string()).collect::<Vec<_>>().join(", ");
+				self.metrics.increment_errors();
+				self.metrics.add_dropped(1);
+				out.emit(Atom::ErrorAtom(Error::NotFound(format!("[LookupRelay]: no reference row for key '{}'", key)), coordinate));
+				None
+			}
+		}
+	}
+
+	fn batch(&mut self, batch: RowBatch, out: &mut dyn Collector) {
+		let Some(indices) = self.bound(batch.width()) else { return self.indices.reject_into(batch.coordinate(0), out) };
+		let mut rows = Vec::new();
+		for r in 0..batch.len() {
+			if let Some(values) = self.look_up(&(&batch, r), &indices, batch.coordinate(r), out) {
+				let fields = (0..batch.width()).map(|i| batch.get(r, i).unwrap_or_default().to_vec());
+				rows.push(extend_byte_row(fields, values).with_coordinate(batch.coordinate(r)));
+			}
+		}
+		if !rows.is_empty() {
+			out.emit(batch_atom("LookupRelay", &rows));
+		}
+	}
+}
+
+impl BindsColumns for LookupRelay {
+	type Bound = Vec<usize>;
+
+	fn resolve<S: AsRef<str>>(&mut self, names: &[S]) -> Result<Vec<usize>, Error> {
+		self.keys.iter()
+			.map(|key| names.iter().position(|n| n.as_ref() == key.input)
+				.ok_or_else(|| Error::InvalidConfig(format!("[LookupRelay]: no key column '{}' in the header", key.input))))
+			.collect()
+	}
+
+	fn binding(&mut self) -> &mut ColumnBinding<Vec<usize>> { &mut self.indices }
+}
+
+impl Relay for LookupRelay {
+	fn initialize(&mut self, cfg: &dyn RelayConfig) -> Result<(), Error> {
+		for item in cfg.list_value(ON_KEY).unwrap_or_default() {
+			self.keys.push(KeyPair::parse(&item)?);
+		}
+		if self.keys.is_empty() {
+			return Err(Error::InvalidConfig(format!("[LookupRelay]: '{}' is required", ON_KEY)))
+		}
+		for item in cfg.list_value(COLUMNS_KEY).unwrap_or_default() {
+			self.selections.push(Selection::parse(&item)?);
+		}
+		if let Some(text) = cfg.string_value(JOIN_KEY)       { self.join       = Join::parse(&text)?;      }
+		if let Some(text) = cfg.string_value(ON_MISSING_KEY) { self.on_missing = OnMissing::parse(&text)?; }
+		if let Some(text) = cfg.string_value(DEFAULT_KEY)    { self.default    = text;                     }
+
+		let reference = match (self.pending.take(), cfg.string_value(REFERENCE_KEY)) {
+			(Some(source), _)  => source,
+			(None, Some(path)) => open_reference(Path::new(&path), cfg.string_value(REFERENCE_TABLE_KEY).as_deref())?,
+			(None, None)       => return Err(Error::InvalidConfig(format!("[LookupRelay]: '{}' is required", REFERENCE_KEY))),
+		};
+		self.load(reference)?;
+		self.metrics.activate();
+		info!("[LookupRelay]: initialized with {}", cfg);
+		Ok(())
+	}
+
+	fn accept(&mut self, atom: Atom, out: &mut dyn Collector) {
+		self.metrics.increment_messages();
+		let coordinate = atom.coordinate();
+		match atom {
+			Atom::HeaderRow(header) => {
+				let names = header.iter_str().map(str::to_owned).collect::<Vec<_>>();
+				match self.bind(&names) {
+					Ok(())     => {
+						let names = names.into_iter().chain(self.selections.iter().map(|s| s.name.clone())).collect();
+						out.emit(Atom::HeaderRow(StringRow::from_values(names).with_coordinate(coordinate)));
+					}
+					Err(error) => out.emit(Atom::ErrorAtom(error, coordinate)),
+				}
+			}
+			Atom::ByteRowAtom(row)    => {
+				let Some(indices) = self.bound(row.length() as usize) else { return self.indices.reject_into(coordinate, out) };
+				if let Some(values) = self.look_up(&row, &indices, coordinate, out) {
+					let fields = (0..row.length() as usize).map(|i| row.get(i).unwrap_or_default().to_vec());
+					out.emit(Atom::ByteRowAtom(extend_byte_row(fields, values).with_coordinate(coordinate)));
+				}
+			}
+			Atom::StringRowAtom(row)  => {
+				let Some(indices) = self.bound(row.count() as usize) else { return self.indices.reject_into(coordinate, out) };
+				if let Some(values) = self.look_up(&row, &indices, coordinate, out) {
+					let values = row.iter_str().map(str::to_owned).chain(values.iter().map(Value::to_string)).collect();
+					out.emit(Atom::StringRowAtom(StringRow::from_values(values).with_coordinate(coordinate)));
+				}
+			}
+			Atom::TypedRowAtom(row)   => {
+				let Some(indices) = self.bound(row.count() as usize) else { return self.indices.reject_into(coordinate, out) };
+				if let Some(values) = self.look_up(&row, &indices, coordinate, out) {
+					let values = row.iter().cloned().chain(values).collect();
+					out.emit(Atom::TypedRowAtom(TypedRow::new(values).with_coordinate(coordinate)));
+				}
+			}
+			Atom::RowBatchAtom(batch) => if !batch.is_empty() { self.batch(batch, out) },
+			other                     => out.emit(other),
+		}
+	}
+
+	fn finish(&mut self, _out: &mut dyn Collector) -> Result<(), Error> {
+		self.metrics.complete();
+		info!("[LookupRelay]: matched {} of {} rows", self.matched, self.metrics.record_count);
+		Ok(())
+	}
+}
+
+impl ProvidesMetrics for LookupRelay {
+	fn metrics(&self) -> ComponentMetrics { self.metrics }
+
+	fn take_metrics(&mut self) -> ComponentMetrics {
+		let metrics = self.metrics;
+		self.metrics.reset();
+		metrics
+	}
+}
+
+/// Open the reference at `path`, chosen by its extension.
+fn open_reference(path: &Path, table: Option<&str>) -> Result<Box<dyn Iterator<Item = Atom>>, Error> {
+	let extension = path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase).unwrap_or_default();
+	match extension.as_str() {
+		"csv"                      => Ok(Box::new(CsvByteSource::new(File::open(path).map_err(IoErrorWrapper::from)?))),
+		"db" | "sqlite" | "sqlite3" => {
+			let table = table.ok_or_else(|| Error::InvalidConfig(format!("[LookupRelay]: '{}' is required for a SQLite reference", REFERENCE_TABLE_KEY)))?;
+			Ok(Box::new(SqliteSource::new(path, table)?))
+		}
+		other                      => Err(Error::InvalidConfig(format!("[LookupRelay]: cannot read a reference of type '{}'", other))),
+	}
+}
+
+/// Hash of a row's key values as text; `None` when any is null.
+fn key_of(row: &dyn Fields, indices: &[usize]) -> Option<u128> {
+	let texts = indices.iter().map(|&i| Some(row.value(i)).filter(|v| !v.is_null()).map(|v| v.to_string())).collect::<Option<Vec<_>>>()?;
+	Some(key_hash(&[], |i| texts.get(i).map(String::as_bytes)))
+}
+
+fn extend_byte_row(fields: impl Iterator<Item = Vec<u8>>, values: Vec<Value>) -> ByteRow {
+	let fields = fields.chain(values.iter().map(|v| v.to_string().into_bytes())).collect::<Vec<_>>();
+	ByteRow::from_fields(fields.iter().map(Vec::as_slice))
+}
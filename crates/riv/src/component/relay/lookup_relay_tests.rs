@@ -0,0 +1,124 @@
+use rusqlite::Connection;
+use tempfile::TempDir;
+use zero::component::telemetry::provides_metrics::ProvidesMetrics;
+use crate::Error;
+use crate::component::relay::Relay;
+use crate::component::relay::empty_relay_config::EmptyRelayConfig;
+use crate::component::relay::fixtures::{all, fields, header, initialized, row};
+use crate::component::relay::lookup_relay::{Join, KeyPair, LookupRelay, OnMissing, Selection, COLUMNS_KEY, DEFAULT_KEY, JOIN_KEY, ON_KEY, ON_MISSING_KEY, REFERENCE_KEY, REFERENCE_TABLE_KEY};
+use crate::component::relay::map_relay_config::MapRelayConfig;
+use crate::component::source::vector_source::VectorSource;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::row_batch::RowBatch;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+
+/// Counterparties `id, name, country`; ids 1 and 2.
+fn counterparties() -> VectorSource {
+	VectorSource::new(vec![
+		header(&["id", "name", "country"]),
+		Atom::ByteRowAtom(row(&["1", "Acme", "GB"])),
+		Atom::ByteRowAtom(row(&["2", "Bolt", "US"])),
+		Atom::ByteRowAtom(row(&["2", "Bolt again", "US"])),
+	])
+}
+
+fn relay(cfg: MapRelayConfig) -> LookupRelay {
+	initialized(LookupRelay::new().with_reference(counterparties()), &cfg)
+}
+
+#[test]
+fn settings_parse() {
+	assert_eq!(KeyPair::parse("id").unwrap(),           KeyPair::new("id", "id"));
+	assert_eq!(KeyPair::parse(" cpty = id ").unwrap(),  KeyPair::new("cpty", "id"));
+	assert_eq!(Selection::parse("name AS cpty_name").unwrap(), Selection::new("name", "cpty_name"));
+	assert_eq!(Selection::parse("legal name").unwrap(), Selection::new("legal name", "legal name"));
+	assert_eq!(Join::parse("Inner").unwrap(),           Join::Inner);
+	assert_eq!(OnMissing::parse("error").unwrap(),      OnMissing::Error);
+	assert!(matches!(KeyPair::parse("= id"),            Err(Error::InvalidConfig(_))));
+	assert!(matches!(Selection::parse("name as"),       Err(Error::InvalidConfig(_))));
+	assert!(matches!(OnMissing::parse("skip"),          Err(Error::InvalidConfig(_))));
+}
+
+#[test]
+fn left_join_appends_reference_columns_and_nulls_for_misses() {
+	let mut relay = relay(MapRelayConfig::new().with(ON_KEY, "cpty = id"));
+	assert_eq!(fields(&all(&mut relay, header(&["trade", "cpty"]))[0]), ["trade", "cpty", "name", "country"]);
+	assert_eq!(fields(&all(&mut relay, Atom::ByteRowAtom(row(&["t1", "2"])))[0]), ["t1", "2", "Bolt", "US"]);        // First of a duplicate key
+	assert_eq!(fields(&all(&mut relay, Atom::ByteRowAtom(row(&["t2", "9"])))[0]), ["t2", "9", "", ""]);
+	assert_eq!(fields(&all(&mut relay, Atom::ByteRowAtom(row(&["t3", ""])))[0]),  ["t3", "", "", ""]);                // Null keys never match
+
+	let typed = all(&mut relay, Atom::TypedRowAtom(TypedRow::new(vec![Value::String("t4".into()), Value::Integer(1)])));
+	match &typed[0] {
+		Atom::TypedRowAtom(r) => assert_eq!(r.iter().skip(2).cloned().collect::<Vec<_>>(), [Value::String("Acme".into()), Value::String("GB".into())]),
+		other                 => panic!("expected a typed row, got {:?}", other),
+	}
+	assert_eq!((relay.reference_len(), relay.matched(), relay.metrics().record_count), (2, 2, 4));
+}
+
+#[test]
+fn missing_keys_follow_on_missing_and_inner_join_drops() {
+	let miss = |cfg: MapRelayConfig| {
+		let mut relay = relay(cfg.with(ON_KEY, "column_1 = id").with(COLUMNS_KEY, "name"));
+		all(&mut relay, Atom::ByteRowAtom(row(&["7"])))
+	};
+	assert_eq!(fields(&miss(MapRelayConfig::new().with(ON_MISSING_KEY, "default").with(DEFAULT_KEY, "unknown"))[0]), ["7", "unknown"]);
+	assert!(miss(MapRelayConfig::new().with(ON_MISSING_KEY, "drop")).is_empty());
+	assert!(miss(MapRelayConfig::new().with(JOIN_KEY, "inner").with(ON_MISSING_KEY, "default")).is_empty());
+
+	let mut relay = relay(MapRelayConfig::new().with(ON_KEY, "column_1 = id").with(COLUMNS_KEY, "name").with(ON_MISSING_KEY, "error"));
+	match &all(&mut relay, Atom::ByteRowAtom(row(&["7"])))[..] {
+		[Atom::ErrorAtom(Error::NotFound(message), _)] => assert!(message.contains("key '7'"), "{}", message),
+		other                                          => panic!("expected one error, got {:?}", other),
+	}
+	let metrics = relay.metrics();
+	assert_eq!((metrics.dropped_count, metrics.error_count), (1, 1));
+}
+
+#[test]
+fn batches_keep_matched_rows_after_reporting_misses() {
+	let mut relay = relay(MapRelayConfig::new().with(ON_KEY, "id").with(COLUMNS_KEY, "country as cc").with(ON_MISSING_KEY, "error"));
+	all(&mut relay, header(&["id", "amount"]));
+	let batch = RowBatch::from_rows(&[row(&["1", "10"]), row(&["5", "20"]), row(&["2", "30"])]).unwrap();
+	let out   = all(&mut relay, Atom::RowBatchAtom(batch));
+	assert_eq!(out.len(), 2);
+	assert!(matches!(out[0], Atom::ErrorAtom(Error::NotFound(_), _)));
+	match &out[1] {
+		Atom::RowBatchAtom(kept) => {
+			assert_eq!((kept.len(), kept.width()), (2, 3));
+			assert_eq!(kept.get(1, 2), Some(b"US".as_slice()));
+		}
+		other                    => panic!("expected a batch, got {:?}", other),
+	}
+}
+
+#[test]
+fn reference_is_read_from_a_csv_file_or_a_sqlite_table() {
+	let dir = TempDir::new().unwrap();
+	let csv = dir.path().join("cpty.csv");
+	std::fs::write(&csv, "id;name\n42;Zed\n").unwrap();
+	let db  = dir.path().join("reference.sqlite");
+	Connection::open(&db).unwrap().execute_batch("CREATE TABLE cpty (id INTEGER, name TEXT); INSERT INTO cpty VALUES (42, 'Zed');").unwrap();
+
+	let from_csv    = MapRelayConfig::new().with(ON_KEY, "id").with(REFERENCE_KEY, csv.to_str().unwrap());
+	let from_sqlite = MapRelayConfig::new().with(ON_KEY, "id").with(REFERENCE_KEY, db.to_str().unwrap()).with(REFERENCE_TABLE_KEY, "cpty");
+	for cfg in [from_csv, from_sqlite] {
+		let mut relay = LookupRelay::new();
+		relay.initialize(&cfg).unwrap();
+		all(&mut relay, header(&["id"]));
+		assert_eq!(fields(&all(&mut relay, Atom::StringRowAtom(row(&["42"]).as_string_row()))[0]), ["42", "Zed"]);   // Text matches an integer key
+	}
+}
+
+#[test]
+fn missing_columns_are_reported() {
+	let mut relay = relay(MapRelayConfig::new().with(ON_KEY, "id"));
+	assert!(matches!(all(&mut relay, header(&["trade"]))[..], [Atom::ErrorAtom(Error::InvalidConfig(_), _)]));
+	assert!(all(&mut relay, Atom::ByteRowAtom(row(&["1"]))).is_empty());
+
+	let mut relay = LookupRelay::new().with_reference(counterparties());
+	assert!(matches!(relay.initialize(&MapRelayConfig::new().with(ON_KEY, "id").with(COLUMNS_KEY, "rating")), Err(Error::InvalidConfig(_))));
+	assert!(matches!(LookupRelay::new().initialize(&MapRelayConfig::new().with(ON_KEY, "id")), Err(Error::InvalidConfig(_))));
+	assert!(matches!(LookupRelay::new().initialize(&EmptyRelayConfig), Err(Error::InvalidConfig(_))));
+}
+
@@ -0,0 +1,40 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Display};
+use crate::component::relay::RelayConfig;
+
+/// Relay configuration held as text, keyed by name.
+///
+/// Numbers and booleans are parsed on lookup, so a value that does not
+/// parse reads as absent.
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MapRelayConfig {
+	values: BTreeMap<String, String>,
+}
+
+impl MapRelayConfig {
+	pub fn new() -> Self { Self::default() }
+
+	pub fn with(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+		self.set(name, value);
+		self
+	}
+
+	pub fn set(&mut self, name: impl Into<String>, value: impl Into<String>) {
+		self.values.insert(name.into(), value.into());
+	}
+}
+
+impl Display for MapRelayConfig {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let pairs = self.values.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>();
+		write!(f, "{{{}}}", pairs.join(", "))
+	}
+}
+
+impl RelayConfig for MapRelayConfig {
+	fn string_value (&self, name: &str) -> Option<String> { self.values.get(name).cloned()                          }
+	fn integer_value(&self, name: &str) -> Option<i32>    { self.values.get(name)?.trim().parse().ok()              }
+	fn float_value  (&self, name: &str) -> Option<f32>    { self.values.get(name)?.trim().parse().ok()              }
+	fn bool_value   (&self, name: &str) -> Option<bool>   { self.values.get(name)?.trim().to_ascii_lowercase().parse().ok() }
+}
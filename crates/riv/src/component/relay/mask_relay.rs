@@ -0,0 +1,307 @@
+use tracing::info;
+use zero::component::telemetry::component_metrics::ComponentMetrics;
+use zero::component::telemetry::provides_metrics::ProvidesMetrics;
+use crate::Error;
+use crate::component::relay::{batch_atom, BindsColumns, ColumnBinding, RelayConfig, SimpleRelay};
+use crate::model::coordinate::coordinate::Coordinate;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::row_batch::RowBatch;
+use crate::model::ir::string_row::StringRow;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::{Value, ValueType};
+use crate::utils::digest::hmac_sha256_digest;
+
+/// Config key: `column = method` pairs, e.g. `ssn = partial(4), email = hash, dob = generalize`.
+pub const MASK_KEY:         &str = "mask";
+/// Config key: the secret that keys `hash` and `tokenize`.
+pub const SECRET_KEY:       &str = "secret";
+/// Config key: an environment variable holding the secret, used when `secret` is absent.
+pub const SECRET_ENV_KEY:   &str = "secret_env";
+/// What `redact` and `partial` write in place of hidden text.
+pub const REDACTED:         &str = "****";
+pub const DEFAULT_KEEP:     usize = 4;
+pub const DEFAULT_BUCKET:   u64   = 10;
+/// The widest bucket `generalize` accepts; integers are bucketed as `i64`.
+pub const MAX_BUCKET:       u64   = i64::MAX as u64;
+const TOKEN_PREFIX:         &str  = "tok_";
+const TOKEN_HEX_DIGITS:     usize = 16;
+
+/// How one column is masked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+	/// Replace the value with `REDACTED`.
+	Redact,
+	/// Mask all but the last `n` characters with `*`; a value of `n` characters or fewer is masked whole.
+	Partial(usize),
+	/// The hex HMAC-SHA256 of the value under the secret.
+	Hash,
+	/// A short token, `tok_` and 16 hex digits, derived from the value under the secret.
+	Tokenize,
+	/// Dates and timestamps to their month, `2024-03`; numbers to the lower bound of a bucket this wide.
+	/// Anything else is redacted.
+	Generalize(u64),
+}
+
+impl Method {
+	/// Parse `redact`, `partial`, `partial(n)`, `hash`, `tokenize`, `generalize` or `generalize(width)`.
+	pub fn parse(text: &str) -> Result<Method, Error> {
+		let text             = text.trim().to_ascii_lowercase();
+		let (name, argument) = match text.split_once('(') {
+			Some((name, rest)) => {
+				let argument = rest.strip_suffix(')').ok_or_else(|| invalid_method(&text))?.trim();
+				(name.trim(), Some(argument.parse::<u64>().map_err(|_| invalid_method(&text))?))
+			}
+			None               => (text.as_str(), None),
+		};
+		match (name, argument) {
+			("redact", None)           => Ok(Method::Redact),
+			("partial", n)             => Ok(Method::Partial(n.map_or(DEFAULT_KEEP, |n| n as usize))),
+			("hash", None)             => Ok(Method::Hash),
+			("tokenize", None)         => Ok(Method::Tokenize),
+			("generalize", Some(0))    => Err(Error::InvalidConfig(format!("[MaskRelay]: bucket width must be positive in '{}'", text))),
+			("generalize", Some(w))
+				if w > MAX_BUCKET      => Err(Error::InvalidConfig(format!("[MaskRelay]: bucket width must be at most {} in '{}'", MAX_BUCKET, text))),
+			("generalize", width)      => Ok(Method::Generalize(width.unwrap_or(DEFAULT_BUCKET))),
+			_                          => Err(invalid_method(&text)),
+		}
+	}
+
+	fn keyed(&self) -> bool { matches!(self, Method::Hash | Method::Tokenize) }
+}
+
+fn invalid_method(text: &str) -> Error {
+	Error::InvalidConfig(format!("[MaskRelay]: unknown mask '{}'; expected redact, partial(n), hash, tokenize or generalize(width)", text))
+}
+
+/// A column and how it is masked.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Masking {
+	pub column: String,
+	pub method: Method,
+}
+
+/// Masks personal data in the configured columns.
+///
+/// Each method is deterministic: `hash` and `tokenize` depend only on the
+/// value and the secret, so the same value gets the same digest or token
+/// in every run and every file masked with that secret, and joins on a
+/// masked column still work. They ignore the column name, so a value
+/// joins across differently named columns too. Empty values stay empty.
+///
+/// Columns are bound as `ColumnBinding` describes; rows are dropped
+/// rather than passed unmasked while the header lacks a masked column.
+/// Typed rows keep their types where the method allows: a generalized
+/// number is an integer, anything else masked becomes text. Dropped rows
+/// are counted in the metrics' `dropped_count`.
+///
+#[derive(Default)]
+pub struct MaskRelay {
+	maskings: Vec<Masking>,
+	secret:   Vec<u8>,
+	targets:  ColumnBinding<Vec<(usize, Method)>>,
+	masked:   u64,
+	metrics:  ComponentMetrics,
+}
+
+impl MaskRelay {
+	pub fn new() -> Self { Self::default() }
+
+	pub fn with_masking(mut self, column: &str, method: Method) -> Self {
+		self.maskings.push(Masking{column: column.to_owned(), method});
+		self
+	}
+
+	pub fn with_secret(mut self, secret: &[u8]) -> Self {
+		self.secret = secret.to_vec();
+		self
+	}
+
+	pub fn maskings(&self) -> &[Masking] { &self.maskings }
+
+	/// Values masked so far.
+	pub fn masked(&self) -> u64 { self.masked }
+
+	/// Only masked fields are read as text; the rest keep their bytes.
+	fn byte_row(&mut self, row: &ByteRow) -> ByteRow {
+		let mut cells = (0..row.length() as usize).map(|i| row.get(i).unwrap_or_default().to_vec()).collect::<Vec<_>>();
+		for &(index, method) in self.targets.get().into_iter().flatten() {
+			if let Some(cell) = cells.get_mut(index) && !cell.is_empty() {
+				*cell = mask_text(method, &self.secret, &String::from_utf8_lossy(cell)).into_bytes();
+				self.masked += 1;
+			}
+		}
+		ByteRow::from_fields(cells.iter().map(Vec::as_slice)).with_coordinate(row.coordinate())
+	}
+
+	fn string_row(&mut self, row: &StringRow) -> StringRow {
+		let mut cells = row.iter_str().map(str::to_owned).collect::<Vec<_>>();
+		for &(index, method) in self.targets.get().into_iter().flatten() {
+			if let Some(cell) = cells.get_mut(index) && !cell.is_empty() {
+				*cell = mask_text(method, &self.secret, cell);
+				self.masked += 1;
+			}
+		}
+		StringRow::from_values(cells).with_coordinate(row.coordinate())
+	}
+
+	fn typed_row(&mut self, row: &TypedRow) -> TypedRow {
+		let mut values = row.iter().cloned().collect::<Vec<_>>();
+		for &(index, method) in self.targets.get().into_iter().flatten() {
+			if let Some(value) = values.get_mut(index) && !value.is_null() {
+				*value = mask_value(method, &self.secret, value);
+				self.masked += 1;
+			}
+		}
+		TypedRow::new(values).with_coordinate(row.coordinate())
+	}
+
+	fn batch(&mut self, batch: &RowBatch) -> Atom {
+		let rows = batch.to_rows().iter().map(|row| self.byte_row(row)).collect::<Vec<_>>();
+		batch_atom("MaskRelay", &rows)
+	}
+
+	fn reject(&mut self, rows: usize, coordinate: Coordinate) -> Option<Atom> {
+		self.metrics.add_dropped(rows as u64);
+		self.targets.reject(coordinate)
+	}
+}
+
+impl BindsColumns for MaskRelay {
+	type Bound = Vec<(usize, Method)>;
+
+	fn resolve<S: AsRef<str>>(&mut self, names: &[S]) -> Result<Vec<(usize, Method)>, Error> {
+		self.maskings.iter()
+			.map(|m| names.iter().position(|n| n.as_ref() == m.column)
+				.map(|i| (i, m.method))
+				.ok_or_else(|| Error::InvalidConfig(format!("[MaskRelay]: no column '{}' to mask in the header", m.column))))
+			.collect()
+	}
+
+	fn binding(&mut self) -> &mut ColumnBinding<Vec<(usize, Method)>> { &mut self.targets }
+}
+
+impl SimpleRelay for MaskRelay {
+	fn initialize(&mut self, cfg: &dyn RelayConfig) -> Result<(), Error> {
+		for item in cfg.list_value(MASK_KEY).unwrap_or_default() {
+			let (column, method) = item.split_once('=')
+				.filter(|(column, _)| !column.trim().is_empty())
+				.ok_or_else(|| Error::InvalidConfig(format!("[MaskRelay]: '{}' is not column = method", item)))?;
+			self.maskings.push(Masking{column: column.trim().to_owned(), method: Method::parse(method)?});
+		}
+		if self.maskings.is_empty() {
+			return Err(Error::InvalidConfig(format!("[MaskRelay]: '{}' is required", MASK_KEY)))
+		}
+		if let Some(secret) = cfg.string_value(SECRET_KEY) {
+			self.secret = secret.into_bytes();
+		} else if let Some(variable) = cfg.string_value(SECRET_ENV_KEY) {
+			self.secret = std::env::var(&variable)
+				.map_err(|_| Error::InvalidConfig(format!("[MaskRelay]: environment variable '{}' is not set", variable)))?
+				.into_bytes();
+		}
+		if self.secret.is_empty() && self.maskings.iter().any(|m| m.method.keyed()) {
+			return Err(Error::InvalidConfig(format!("[MaskRelay]: hash and tokenize need '{}' or '{}'", SECRET_KEY, SECRET_ENV_KEY)))
+		}
+		self.metrics.activate();
+		info!("[MaskRelay]: initialized with {} masked columns", self.maskings.len());
+		Ok(())
+	}
+
+	fn accept(&mut self, atom: Atom) -> Option<Atom> {
+		self.metrics.increment_messages();
+		let coordinate = atom.coordinate();
+		match atom {
+			Atom::HeaderRow(header) => {
+				let names = header.iter_str().collect::<Vec<_>>();
+				match self.bind(&names) {
+					Ok(())     => Some(Atom::HeaderRow(header)),
+					Err(error) => Some(Atom::ErrorAtom(error, coordinate)),
+				}
+			}
+			Atom::ByteRowAtom(row)    => match self.ensure_bound(row.length() as usize) {
+				true  => { self.metrics.add_records(1); Some(Atom::ByteRowAtom(self.byte_row(&row))) }
+				false => self.reject(1, coordinate),
+			},
+			Atom::StringRowAtom(row)  => match self.ensure_bound(row.count() as usize) {
+				true  => { self.metrics.add_records(1); Some(Atom::StringRowAtom(self.string_row(&row))) }
+				false => self.reject(1, coordinate),
+			},
+			Atom::TypedRowAtom(row)   => match self.ensure_bound(row.count() as usize) {
+				true  => { self.metrics.add_records(1); Some(Atom::TypedRowAtom(self.typed_row(&row))) }
+				false => self.reject(1, coordinate),
+			},
+			Atom::RowBatchAtom(batch) => match self.ensure_bound(batch.width()) {
+				true  => { self.metrics.add_records(batch.len() as u64); Some(self.batch(&batch)) }
+				false => self.reject(batch.len(), coordinate),
+			},
+			other                     => Some(other),
+		}
+	}
+
+	fn finish(&mut self) -> bool {
+		self.metrics.complete();
+		info!("[MaskRelay]: masked {} values in {} rows", self.masked, self.metrics.record_count);
+		true
+	}
+}
+
+impl ProvidesMetrics for MaskRelay {
+	fn metrics(&self) -> ComponentMetrics { self.metrics }
+
+	fn take_metrics(&mut self) -> ComponentMetrics {
+		let metrics = self.metrics;
+		self.metrics.reset();
+		metrics
+	}
+}
+
+/// Mask non-empty `text`.
+pub fn mask_text(method: Method, secret: &[u8], text: &str) -> String {
+	match method {
+		Method::Redact        => REDACTED.to_owned(),
+		Method::Partial(keep) => {
+			let count = text.chars().count();
+			if count <= keep { return "*".repeat(count) }
+			"*".repeat(count - keep) + &text.chars().skip(count - keep).collect::<String>()
+		}
+		Method::Hash          => hmac_sha256_digest(secret, text.as_bytes()),
+		Method::Tokenize      => token(secret, text),
+		Method::Generalize(w) => generalize(&Value::String(text.to_owned()), w).to_string(),
+	}
+}
+
+/// Mask a non-null typed value.
+pub fn mask_value(method: Method, secret: &[u8], value: &Value) -> Value {
+	match method {
+		Method::Generalize(w) => generalize(value, w),
+		method                => Value::String(mask_text(method, secret, &value.to_string())),
+	}
+}
+
+fn token(secret: &[u8], text: &str) -> String {
+	let digest = hmac_sha256_digest(secret, format!("token:{}", text).as_bytes());   // Unrelated to the value's hash
+	format!("{}{}", TOKEN_PREFIX, &digest[..TOKEN_HEX_DIGITS])
+}
+
+fn generalize(value: &Value, width: u64) -> Value {
+	let width = width.max(1);
+	match value {
+		Value::Integer(i)   => match i.div_euclid(width as i64).checked_mul(width as i64) {
+			Some(floor) => Value::Integer(floor),
+			None        => Value::String(REDACTED.to_owned()),                // The bucket starts below i64::MIN
+		},
+		Value::Float(_) |
+		Value::Decimal(_)   => match value.as_f64() {
+			Some(f) if f.is_finite() => Value::Integer(((f / width as f64).floor() * width as f64) as i64),
+			_                        => Value::String(REDACTED.to_owned()),
+		},
+		Value::Date(d)      => Value::String(d.format("%Y-%m").to_string()),
+		Value::Timestamp(t) => Value::String(t.format("%Y-%m").to_string()),
+		Value::String(s)    => {
+			let parsed = [ValueType::Integer, ValueType::Decimal, ValueType::Date, ValueType::Timestamp].iter()
+				.find_map(|&t| Value::parse(s, t).ok().filter(|v| !v.is_null()));
+			parsed.map_or_else(|| Value::String(REDACTED.to_owned()), |v| generalize(&v, width))
+		}
+		_                   => Value::String(REDACTED.to_owned()),
+	}
+}
@@ -0,0 +1,124 @@
+use chrono::NaiveDate;
+use zero::component::telemetry::provides_metrics::ProvidesMetrics;
+use crate::Error;
+use crate::component::relay::SimpleRelay;
+use crate::component::relay::fixtures::{fields, header, initialized, row};
+use crate::component::relay::map_relay_config::MapRelayConfig;
+use crate::component::relay::mask_relay::{mask_text, MaskRelay, Method, MASK_KEY, REDACTED, SECRET_KEY};
+use crate::model::ir::atom::Atom;
+use crate::model::ir::row_batch::RowBatch;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+use crate::utils::digest::hmac_sha256_digest;
+
+fn relay(mask: &str, secret: &str) -> MaskRelay {
+	initialized(MaskRelay::new(), &MapRelayConfig::new().with(MASK_KEY, mask).with(SECRET_KEY, secret))
+}
+
+#[test]
+fn methods_parse() {
+	assert_eq!(Method::parse("redact").unwrap(),          Method::Redact);
+	assert_eq!(Method::parse(" Partial ").unwrap(),       Method::Partial(4));
+	assert_eq!(Method::parse("partial(2)").unwrap(),      Method::Partial(2));
+	assert_eq!(Method::parse("generalize").unwrap(),      Method::Generalize(10));
+	assert_eq!(Method::parse("generalize( 25 )").unwrap(), Method::Generalize(25));
+	assert_eq!(Method::parse(&format!("generalize({})", i64::MAX)).unwrap(), Method::Generalize(i64::MAX as u64));
+	for bad in ["shred", "hash(3)", "partial(x)", "partial(2", "generalize(0)", "generalize(9223372036854775808)"] {
+		assert!(matches!(Method::parse(bad), Err(Error::InvalidConfig(_))), "{}", bad);
+	}
+}
+
+#[test]
+fn text_masks() {
+	assert_eq!(mask_text(Method::Redact, b"", "Ann"), REDACTED);
+	assert_eq!(mask_text(Method::Partial(4), b"", "4111-1111-1111-1234"), "***************1234");
+	assert_eq!(mask_text(Method::Partial(4), b"", "1234"), "****");
+	assert_eq!(mask_text(Method::Partial(2), b"", "Zoë"), "*oë");
+	assert_eq!(mask_text(Method::Hash, b"k", "ann@x.com"), hmac_sha256_digest(b"k", b"ann@x.com"));
+
+	let token = mask_text(Method::Tokenize, b"k", "ann@x.com");
+	assert!(token.starts_with("tok_") && token.len() == 20, "{}", token);
+	assert_eq!(mask_text(Method::Tokenize, b"k", "ann@x.com"), token);
+	assert_ne!(mask_text(Method::Tokenize, b"other", "ann@x.com"), token);
+
+	assert_eq!(mask_text(Method::Generalize(10), b"", "37"), "30");
+	assert_eq!(mask_text(Method::Generalize(10), b"", "-3"), "-10");
+	assert_eq!(mask_text(Method::Generalize(1000), b"", "2500.75"), "2000");
+	assert_eq!(mask_text(Method::Generalize(10), b"", "1984-07-21"), "1984-07");
+	assert_eq!(mask_text(Method::Generalize(10), b"", "2024-03-05 10:00:00"), "2024-03");
+	assert_eq!(mask_text(Method::Generalize(10), b"", "Leeds"), REDACTED);
+	assert_eq!(mask_text(Method::Generalize(10), b"", &i64::MIN.to_string()), REDACTED);                 // Its bucket starts below i64::MIN
+	assert_eq!(mask_text(Method::Generalize(i64::MAX as u64), b"", &i64::MIN.to_string()), REDACTED);
+	assert_eq!(mask_text(Method::Generalize(i64::MAX as u64), b"", "-1"), (-i64::MAX).to_string());
+}
+
+#[test]
+fn rows_are_masked_the_same_in_every_run() {
+	let mask  = "ssn = partial, email = hash, name = tokenize, dob = generalize, age = generalize(5), note = redact";
+	let input = ["123-45-6789", "ann@x.com", "Ann", "1984-07-21", "37", "call after 5"];
+	let run   = || {
+		let mut relay = relay(mask, "s3cret");
+		relay.accept(header(&["ssn", "email", "name", "dob", "age", "note", "id"]));
+		let mut cells = input.to_vec();
+		cells.push("7");
+		fields(&relay.accept(Atom::ByteRowAtom(row(&cells))).unwrap())
+	};
+	let first = run();
+	assert_eq!(first[0], "*******6789");
+	assert_eq!(first[1], hmac_sha256_digest(b"s3cret", b"ann@x.com"));
+	assert_eq!(&first[3..], ["1984-07", "35", REDACTED, "7"]);
+	assert_eq!(run(), first);
+}
+
+#[test]
+fn empty_values_stay_empty_and_types_are_kept() {
+	let mut relay = relay("column_1 = generalize, column_2 = partial(1)", "k");
+	assert_eq!(fields(&relay.accept(Atom::StringRowAtom(row(&["", "abc"]).as_string_row())).unwrap()), ["", "**c"]);
+
+	let typed = TypedRow::new(vec![Value::Integer(42), Value::Null]);
+	match relay.accept(Atom::TypedRowAtom(typed)) {
+		Some(Atom::TypedRowAtom(r)) => assert_eq!(r.iter().cloned().collect::<Vec<_>>(), [Value::Integer(40), Value::Null]),
+		other                       => panic!("expected a typed row, got {:?}", other),
+	}
+	let typed = TypedRow::new(vec![Value::Date(NaiveDate::from_ymd_opt(2020, 2, 29).unwrap()), Value::Integer(12345)]);
+	match relay.accept(Atom::TypedRowAtom(typed)) {
+		Some(Atom::TypedRowAtom(r)) => assert_eq!(r.iter().cloned().collect::<Vec<_>>(), [Value::String("2020-02".into()), Value::String("****5".into())]),
+		other                       => panic!("expected a typed row, got {:?}", other),
+	}
+	assert_eq!(relay.masked(), 4);
+}
+
+#[test]
+fn batches_are_masked() {
+	let mut relay = relay("card = partial", "k");
+	relay.accept(header(&["id", "card"]));
+	let batch = RowBatch::from_rows(&[row(&["1", "4111111111111111"]), row(&["2", ""])]).unwrap();
+	match relay.accept(Atom::RowBatchAtom(batch)) {
+		Some(Atom::RowBatchAtom(b)) => {
+			assert_eq!(b.get(0, 1), Some(b"************1111".as_slice()));
+			assert_eq!(b.get(1, 1), Some(b"".as_slice()));
+			assert_eq!(b.get(1, 0), Some(b"2".as_slice()));
+		}
+		other                       => panic!("expected a batch, got {:?}", other),
+	}
+	assert_eq!(relay.metrics().record_count, 2);
+}
+
+#[test]
+fn a_header_without_the_column_stops_rows() {
+	let mut relay = relay("email = hash", "k");
+	assert!(matches!(relay.accept(header(&["id", "mail"])), Some(Atom::ErrorAtom(..))));
+	assert!(relay.accept(Atom::ByteRowAtom(row(&["1", "ann@x.com"]))).is_none());
+	relay.accept(header(&["id", "email"]));
+	assert_eq!(fields(&relay.accept(Atom::ByteRowAtom(row(&["1", "ann@x.com"]))).unwrap())[1].len(), 64);
+	assert_eq!((relay.metrics().record_count, relay.metrics().dropped_count), (1, 1));
+}
+
+#[test]
+fn keyed_methods_need_a_secret() {
+	let mut relay = MaskRelay::new();
+	assert!(matches!(relay.initialize(&MapRelayConfig::new().with(MASK_KEY, "email = tokenize")), Err(Error::InvalidConfig(_))));
+	let mut relay = MaskRelay::new();
+	assert!(relay.initialize(&MapRelayConfig::new().with(MASK_KEY, "email = redact")).is_ok());
+	assert!(matches!(MaskRelay::new().initialize(&MapRelayConfig::new()), Err(Error::InvalidConfig(_))));
+}
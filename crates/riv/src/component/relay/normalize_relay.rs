@@ -0,0 +1,489 @@
+use std::borrow::Cow;
+use std::fmt;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
+use icu_normalizer::{ComposingNormalizer, DecomposingNormalizer};
+use tracing::info;
+use zero::component::telemetry::component_metrics::ComponentMetrics;
+use zero::component::telemetry::provides_metrics::ProvidesMetrics;
+use crate::Error;
+use crate::component::relay::{batch_atom, BindsColumns, Collector, ColumnBinding, Relay, RelayConfig};
+use crate::model::coordinate::coordinate::Coordinate;
+use crate::model::expression::ast::number;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::row_batch::RowBatch;
+use crate::model::ir::string_row::StringRow;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+
+/// Config key: `column = kind` pairs, where kind is `text`, `number`, `date`, `timestamp` or `boolean`.
+pub const COLUMNS_KEY:             &str = "columns";
+/// Config key: trim surrounding whitespace. Defaults to true.
+pub const TRIM_KEY:                &str = "trim";
+/// Config key: empty text becomes null. Defaults to true.
+pub const EMPTY_AS_NULL_KEY:       &str = "empty_as_null";
+/// Config key: a locale such as `en`, `de` or `fr-CH` choosing the number separators.
+pub const LOCALE_KEY:              &str = "locale";
+/// Config key: the decimal separator, overriding the locale's.
+pub const DECIMAL_SEPARATOR_KEY:   &str = "decimal_separator";
+/// Config key: the thousands separator, `space` or `none`, overriding the locale's.
+pub const THOUSANDS_SEPARATOR_KEY: &str = "thousands_separator";
+/// Config key: chrono formats tried in order for `date` columns.
+pub const DATE_FORMATS_KEY:        &str = "date_formats";
+/// Config key: chrono formats tried in order for `timestamp` columns, after RFC 3339.
+pub const TIMESTAMP_FORMATS_KEY:   &str = "timestamp_formats";
+/// Config key: `nfc` (default), `nfkc`, `nfd`, `nfkd` or `none`.
+pub const UNICODE_KEY:             &str = "unicode";
+/// Config key: what happens to a cell that does not parse: `null` (default), `keep` or `drop`.
+pub const ON_ERROR_KEY:            &str = "on_error";
+
+pub const DEFAULT_DATE_FORMATS:      [&str; 5] = ["%Y-%m-%d", "%Y/%m/%d", "%m/%d/%Y", "%d.%m.%Y", "%d %b %Y"];
+pub const DEFAULT_TIMESTAMP_FORMATS: [&str; 6] = [
+	"%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M", "%m/%d/%Y %H:%M:%S", "%m/%d/%Y %H:%M",
+];
+
+const TRUE_WORDS:  [&str; 6] = ["true", "t", "yes", "y", "1", "on"];
+const FALSE_WORDS: [&str; 6] = ["false", "f", "no", "n", "0", "off"];
+
+/// What a column's text is read as.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Kind {
+	/// Trimmed and normalized text.
+	#[default]
+	Text,
+	Number,
+	Date,
+	Timestamp,
+	Boolean,
+}
+
+impl Kind {
+	pub fn parse(text: &str) -> Result<Kind, Error> {
+		match text.trim().to_ascii_lowercase().as_str() {
+			"text" | "string"  => Ok(Kind::Text),
+			"number"           => Ok(Kind::Number),
+			"date"             => Ok(Kind::Date),
+			"timestamp"        => Ok(Kind::Timestamp),
+			"boolean" | "bool" => Ok(Kind::Boolean),
+			other              => Err(Error::InvalidConfig(format!("[NormalizeRelay]: unknown kind '{}'; expected text, number, date, timestamp or boolean", other))),
+		}
+	}
+}
+
+impl fmt::Display for Kind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Kind::Text      => "text",
+			Kind::Number    => "number",
+			Kind::Date      => "date",
+			Kind::Timestamp => "timestamp",
+			Kind::Boolean   => "boolean",
+		})
+	}
+}
+
+/// The Unicode normalization form applied to every text cell.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnicodeForm {
+	#[default]
+	Nfc,
+	Nfkc,
+	Nfd,
+	Nfkd,
+	None,
+}
+
+impl UnicodeForm {
+	pub fn parse(text: &str) -> Result<UnicodeForm, Error> {
+		match text.trim().to_ascii_lowercase().as_str() {
+			"nfc"  => Ok(UnicodeForm::Nfc),
+			"nfkc" => Ok(UnicodeForm::Nfkc),
+			"nfd"  => Ok(UnicodeForm::Nfd),
+			"nfkd" => Ok(UnicodeForm::Nfkd),
+			"none" => Ok(UnicodeForm::None),
+			other  => Err(Error::InvalidConfig(format!("[NormalizeRelay]: unicode must be nfc, nfkc, nfd, nfkd or none, got '{}'", other))),
+		}
+	}
+
+	pub fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+		match self {
+			UnicodeForm::Nfc  => ComposingNormalizer::new_nfc().normalize(text),
+			UnicodeForm::Nfkc => ComposingNormalizer::new_nfkc().normalize(text),
+			UnicodeForm::Nfd  => DecomposingNormalizer::new_nfd().normalize(text),
+			UnicodeForm::Nfkd => DecomposingNormalizer::new_nfkd().normalize(text),
+			UnicodeForm::None => Cow::Borrowed(text),
+		}
+	}
+}
+
+/// The characters that group thousands and mark decimals in numbers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Separators {
+	pub decimal:   char,
+	pub thousands: Option<char>,
+}
+
+impl Default for Separators {
+	fn default() -> Self { Separators{decimal: '.', thousands: Some(',')} }
+}
+
+impl Separators {
+	/// The separators of a locale tag such as `en`, `de_DE` or `fr-CH`.
+	pub fn for_locale(tag: &str) -> Result<Separators, Error> {
+		let tag                = tag.trim().to_ascii_lowercase().replace('_', "-");
+		let (language, region) = tag.split_once('-').unwrap_or((&tag, ""));
+		let separators         = |decimal, thousands| Ok(Separators{decimal, thousands: Some(thousands)});
+		match (language, region) {
+			(_, "ch" | "li")                                                    => separators('.', '\''),
+			("en" | "ja" | "zh" | "ko" | "he" | "th" | "hi", _)                 => separators('.', ','),
+			("de" | "nl" | "it" | "es" | "pt" | "id" | "tr" | "da" | "el" | "ro", _) => separators(',', '.'),
+			("fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "no" | "uk" | "hu" | "bg", _) => separators(',', ' '),
+			_                                                                   => Err(Error::InvalidConfig(format!("[NormalizeRelay]: no number separators known for locale '{}'", tag))),
+		}
+	}
+
+	/// Read a number written with these separators, e.g. `-1,234.50` or `1.234,5e3`.
+	pub fn parse(&self, text: &str) -> Option<Value> {
+		let (sign, body)         = match text.strip_prefix('-') {
+			Some(rest) => ("-", rest),
+			None       => ("", text.strip_prefix('+').unwrap_or(text)),
+		};
+		let (mantissa, exponent) = body.split_at(body.find(['e', 'E']).unwrap_or(body.len()));
+		let (whole, fraction)    = match mantissa.split_once(self.decimal) {
+			Some((whole, fraction)) => (whole, Some(fraction)),
+			None                    => (mantissa, None),
+		};
+		let whole  = self.ungroup(whole)?;
+		let digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+		if !fraction.is_none_or(digits) || (whole.is_empty() && fraction.is_none_or(str::is_empty)) {
+			return None
+		}
+		if let Some(power) = exponent.get(1..) {
+			let power = power.strip_prefix(['+', '-']).unwrap_or(power);
+			if power.is_empty() || !digits(power) { return None }
+		}
+		let fraction = fraction.map(|f| format!(".{}", f)).unwrap_or_default();
+		number(&format!("{}{}{}{}", sign, whole, fraction, exponent))
+	}
+
+	/// The digits of the whole part, which must be grouped in threes if grouped at all.
+	fn ungroup(&self, whole: &str) -> Option<String> {
+		let groups = match self.thousands {
+			Some(' ') => whole.split([' ', '\u{a0}', '\u{202f}']).collect::<Vec<_>>(),   // Also the no-break spaces
+			Some(sep) => whole.split(sep).collect(),
+			None      => vec![whole],
+		};
+		if !groups.iter().all(|g| g.bytes().all(|b| b.is_ascii_digit())) { return None }
+		if groups.len() > 1 && (groups[0].is_empty() || groups[0].len() > 3 || groups[1..].iter().any(|g| g.len() != 3)) {
+			return None
+		}
+		Some(groups.concat())
+	}
+}
+
+/// What happens to a cell that does not parse as its column's kind.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnError {
+	/// Replace it with null.
+	#[default]
+	Null,
+	/// Keep its text as it arrived.
+	Keep,
+	/// Drop the whole row.
+	Drop,
+}
+
+impl OnError {
+	pub fn parse(text: &str) -> Result<OnError, Error> {
+		match text.trim().to_ascii_lowercase().as_str() {
+			"null" => Ok(OnError::Null),
+			"keep" => Ok(OnError::Keep),
+			"drop" => Ok(OnError::Drop),
+			other  => Err(Error::InvalidConfig(format!("[NormalizeRelay]: on_error must be null, keep or drop, got '{}'", other))),
+		}
+	}
+}
+
+/// Cleans up text values and reads typed columns into canonical form.
+///
+/// Every text cell is trimmed, Unicode normalized (NFC by default) and,
+/// when empty, made null. Columns given a kind are then parsed: numbers
+/// with the locale's thousands and decimal separators, so `1.234,50`
+/// under `de` becomes `1234.50`; dates and timestamps with the first
+/// candidate format that fits, written back as ISO 8601; booleans from
+/// `true/false`, `yes/no`, `y/n`, `t/f`, `on/off` and `1/0` in any case.
+/// A timestamp with an offset is converted to UTC, and a bare date in a
+/// timestamp column is read as midnight.
+///
+/// Byte and string rows get the canonical text, with null left empty;
+/// typed rows get typed values, and values that are already typed pass
+/// unchanged. A cell that does not parse is reported as an `ErrorAtom`
+/// at the row's coordinate, naming the column, and then handled as
+/// `on_error` says. Reported cells are counted in the metrics'
+/// `error_count`, dropped rows in `dropped_count`. Columns are bound as
+/// `ColumnBinding` describes.
+///
+pub struct NormalizeRelay {
+	columns:           Vec<(String, Kind)>,
+	trim:              bool,
+	empty_as_null:     bool,
+	separators:        Separators,
+	date_formats:      Vec<String>,
+	timestamp_formats: Vec<String>,
+	unicode:           UnicodeForm,
+	on_error:          OnError,
+	names:             Vec<String>,
+	kinds:             ColumnBinding<Vec<Kind>>,
+	metrics:           ComponentMetrics,
+}
+
+impl Default for NormalizeRelay {
+	fn default() -> Self { Self::new() }
+}
+
+impl NormalizeRelay {
+	pub fn new() -> Self {
+		NormalizeRelay {
+			columns:           Vec::new(),
+			trim:              true,
+			empty_as_null:     true,
+			separators:        Separators::default(),
+			date_formats:      DEFAULT_DATE_FORMATS.map(str::to_owned).to_vec(),
+			timestamp_formats: DEFAULT_TIMESTAMP_FORMATS.map(str::to_owned).to_vec(),
+			unicode:           UnicodeForm::Nfc,
+			on_error:          OnError::Null,
+			names:             Vec::new(),
+			kinds:             ColumnBinding::default(),
+			metrics:           ComponentMetrics::default(),
+		}
+	}
+
+	pub fn with_column(mut self, column: &str, kind: Kind) -> Self {
+		self.columns.push((column.to_owned(), kind));
+		self
+	}
+
+	pub fn with_separators(mut self, separators: Separators) -> Self {
+		self.separators = separators;
+		self
+	}
+
+	pub fn with_date_formats(mut self, formats: &[&str]) -> Self {
+		self.date_formats = formats.iter().map(|f| f.to_string()).collect();
+		self
+	}
+
+	pub fn with_unicode(mut self, unicode: UnicodeForm) -> Self {
+		self.unicode = unicode;
+		self
+	}
+
+	pub fn with_on_error(mut self, on_error: OnError) -> Self {
+		self.on_error = on_error;
+		self
+	}
+
+	pub fn columns(&self) -> &[(String, Kind)] { &self.columns }
+
+	/// `text` in canonical form for a column of `kind`, or `None` when it does not parse.
+	pub fn normalize(&self, text: &str, kind: Kind) -> Option<Value> {
+		let text = self.unicode.apply(if self.trim { text.trim() } else { text });
+		if text.is_empty() {
+			return Some(match kind == Kind::Text && !self.empty_as_null {
+				true  => Value::String(String::new()),
+				false => Value::Null,
+			})
+		}
+		match kind {
+			Kind::Text      => Some(Value::String(text.into_owned())),
+			Kind::Number    => self.separators.parse(&text),
+			Kind::Date      => self.date(&text).map(Value::Date),
+			Kind::Timestamp => self.timestamp(&text).map(Value::Timestamp),
+			Kind::Boolean   => boolean(&text).map(Value::Boolean),
+		}
+	}
+
+	fn date(&self, text: &str) -> Option<NaiveDate> {
+		self.date_formats.iter().find_map(|format| NaiveDate::parse_from_str(text, format).ok())
+	}
+
+	fn timestamp(&self, text: &str) -> Option<NaiveDateTime> {
+		DateTime::parse_from_rfc3339(text).ok().map(|t| t.naive_utc())
+			.or_else(|| self.timestamp_formats.iter().find_map(|format| NaiveDateTime::parse_from_str(text, format).ok()))
+			.or_else(|| self.date(text).map(|d| d.and_time(NaiveTime::MIN)))
+	}
+
+	/// Normalize the text values of a row, or `None` to drop it.
+	fn row(&mut self, values: Vec<Value>, coordinate: Coordinate, out: &mut dyn Collector) -> Option<Vec<Value>> {
+		self.metrics.add_records(1);
+		let mut keep   = true;
+		let mut result = Vec::with_capacity(values.len());
+		for (index, value) in values.into_iter().enumerate() {
+			let Value::String(text) = value else {
+				result.push(value);
+				continue
+			};
+			let kind = self.kinds.get().and_then(|kinds| kinds.get(index).copied()).unwrap_or_default();
+			match self.normalize(&text, kind) {
+				Some(value) => result.push(value),
+				None        => {
+					let column = self.names.get(index).cloned().unwrap_or_else(|| format!("column_{}", index + 1));
+					let error  = Error::Parse(format!("[NormalizeRelay]: column '{}' value '{}' is not a valid {}", column, text, kind));
+					self.metrics.increment_errors();
+					out.emit(Atom::ErrorAtom(error, coordinate));
+					match self.on_error {
+						OnError::Null => result.push(Value::Null),
+						OnError::Keep => result.push(Value::String(text)),
+						OnError::Drop => keep = false,
+					}
+				}
+			}
+		}
+		if !keep {
+			self.metrics.add_dropped(1);
+			return None
+		}
+		Some(result)
+	}
+
+	fn byte_row(&mut self, row: &ByteRow, out: &mut dyn Collector) -> Option<ByteRow> {
+		let coordinate = row.coordinate();
+		let values     = (0..row.length() as usize)
+			.map(|i| Value::String(String::from_utf8_lossy(row.get(i).unwrap_or_default()).into_owned()))
+			.collect();
+		let texts      = self.row(values, coordinate, out)?.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+		Some(ByteRow::from_fields(texts.iter().map(String::as_bytes)).with_coordinate(coordinate))
+	}
+
+	fn batch(&mut self, batch: RowBatch, out: &mut dyn Collector) {
+		let rows = batch.to_rows().iter().filter_map(|row| self.byte_row(row, out)).collect::<Vec<_>>();
+		if !rows.is_empty() {
+			out.emit(batch_atom("NormalizeRelay", &rows));
+		}
+	}
+}
+
+impl BindsColumns for NormalizeRelay {
+	type Bound = Vec<Kind>;
+
+	fn resolve<S: AsRef<str>>(&mut self, names: &[S]) -> Result<Vec<Kind>, Error> {
+		self.names    = names.iter().map(|n| n.as_ref().to_owned()).collect();
+		let mut kinds = vec![Kind::Text; names.len()];
+		for (column, kind) in &self.columns {
+			let index = self.names.iter().position(|n| n == column)
+				.ok_or_else(|| Error::InvalidConfig(format!("[NormalizeRelay]: no column '{}' in the header", column)))?;
+			kinds[index] = *kind;
+		}
+		Ok(kinds)
+	}
+
+	fn binding(&mut self) -> &mut ColumnBinding<Vec<Kind>> { &mut self.kinds }
+}
+
+impl Relay for NormalizeRelay {
+	fn initialize(&mut self, cfg: &dyn RelayConfig) -> Result<(), Error> {
+		for item in cfg.list_value(COLUMNS_KEY).unwrap_or_default() {
+			let (column, kind) = item.split_once('=')
+				.filter(|(column, _)| !column.trim().is_empty())
+				.ok_or_else(|| Error::InvalidConfig(format!("[NormalizeRelay]: '{}' is not column = kind", item)))?;
+			self.columns.push((column.trim().to_owned(), Kind::parse(kind)?));
+		}
+		if let Some(trim) = cfg.bool_value(TRIM_KEY)           { self.trim          = trim;  }
+		if let Some(empty) = cfg.bool_value(EMPTY_AS_NULL_KEY) { self.empty_as_null = empty; }
+		if let Some(locale) = cfg.string_value(LOCALE_KEY) {
+			self.separators = Separators::for_locale(&locale)?;
+		}
+		if let Some(text) = cfg.string_value(DECIMAL_SEPARATOR_KEY) {
+			self.separators.decimal = separator(&text, DECIMAL_SEPARATOR_KEY)?
+				.ok_or_else(|| Error::InvalidConfig(format!("[NormalizeRelay]: '{}' cannot be none", DECIMAL_SEPARATOR_KEY)))?;
+		}
+		if let Some(text) = cfg.string_value(THOUSANDS_SEPARATOR_KEY) {
+			self.separators.thousands = separator(&text, THOUSANDS_SEPARATOR_KEY)?;
+		}
+		if self.separators.thousands == Some(self.separators.decimal) {
+			return Err(Error::InvalidConfig(format!("[NormalizeRelay]: thousands and decimal separators are both '{}'", self.separators.decimal)))
+		}
+		if let Some(formats) = cfg.list_value(DATE_FORMATS_KEY)      { self.date_formats      = formats; }
+		if let Some(formats) = cfg.list_value(TIMESTAMP_FORMATS_KEY) { self.timestamp_formats = formats; }
+		if let Some(text) = cfg.string_value(UNICODE_KEY)            { self.unicode  = UnicodeForm::parse(&text)?; }
+		if let Some(text) = cfg.string_value(ON_ERROR_KEY)           { self.on_error = OnError::parse(&text)?;     }
+		self.metrics.activate();
+		info!("[NormalizeRelay]: initialized with {}", cfg);
+		Ok(())
+	}
+
+	fn accept(&mut self, atom: Atom, out: &mut dyn Collector) {
+		self.metrics.increment_messages();
+		let coordinate = atom.coordinate();
+		match atom {
+			Atom::HeaderRow(header) => {
+				match self.bind(&header.iter_str().collect::<Vec<_>>()) {
+					Ok(())     => out.emit(Atom::HeaderRow(header)),
+					Err(error) => out.emit(Atom::ErrorAtom(error, coordinate)),
+				}
+			}
+			Atom::ByteRowAtom(row)    => {
+				if !self.ensure_bound(row.length() as usize) { return self.kinds.reject_into(coordinate, out) }
+				if let Some(row) = self.byte_row(&row, out) {
+					out.emit(Atom::ByteRowAtom(row));
+				}
+			}
+			Atom::StringRowAtom(row)  => {
+				if !self.ensure_bound(row.count() as usize) { return self.kinds.reject_into(coordinate, out) }
+				let values = row.iter_str().map(|s| Value::String(s.to_owned())).collect();
+				if let Some(values) = self.row(values, coordinate, out) {
+					let texts = values.iter().map(Value::to_string).collect();
+					out.emit(Atom::StringRowAtom(StringRow::from_values(texts).with_coordinate(coordinate)));
+				}
+			}
+			Atom::TypedRowAtom(row)   => {
+				if !self.ensure_bound(row.count() as usize) { return self.kinds.reject_into(coordinate, out) }
+				if let Some(values) = self.row(row.iter().cloned().collect(), coordinate, out) {
+					out.emit(Atom::TypedRowAtom(TypedRow::new(values).with_coordinate(coordinate)));
+				}
+			}
+			Atom::RowBatchAtom(batch) => {
+				if !self.ensure_bound(batch.width()) { return self.kinds.reject_into(coordinate, out) }
+				if !batch.is_empty() { self.batch(batch, out) }
+			}
+			other                     => out.emit(other),
+		}
+	}
+
+	fn finish(&mut self, _out: &mut dyn Collector) -> Result<(), Error> {
+		self.metrics.complete();
+		info!("[NormalizeRelay]: normalized {} rows, {} cells did not parse", self.metrics.record_count, self.metrics.error_count);
+		Ok(())
+	}
+}
+
+impl ProvidesMetrics for NormalizeRelay {
+	fn metrics(&self) -> ComponentMetrics { self.metrics }
+
+	fn take_metrics(&mut self) -> ComponentMetrics {
+		let metrics = self.metrics;
+		self.metrics.reset();
+		metrics
+	}
+}
+
+fn boolean(text: &str) -> Option<bool> {
+	let text = text.to_lowercase();
+	if TRUE_WORDS.contains(&text.as_str())  { return Some(true)  }
+	if FALSE_WORDS.contains(&text.as_str()) { return Some(false) }
+	None
+}
+
+/// A separator setting: one character, `space`, or `none`.
+fn separator(text: &str, key: &str) -> Result<Option<char>, Error> {
+	match text.to_ascii_lowercase().as_str() {
+		"space" | " " => return Ok(Some(' ')),
+		"none" | ""   => return Ok(None),
+		_             => {}
+	}
+	let mut chars = text.trim().chars();
+	match (chars.next(), chars.next()) {
+		(Some(c), None) => Ok(Some(c)),
+		_               => Err(Error::InvalidConfig(format!("[NormalizeRelay]: '{}' must be one character, space or none, got '{}'", key, text))),
+	}
+}
@@ -0,0 +1,129 @@
+use chrono::NaiveDate;
+use zero::component::telemetry::provides_metrics::ProvidesMetrics;
+use crate::Error;
+use crate::component::relay::Relay;
+use crate::component::relay::fixtures::{all, fields, header, initialized, row, text};
+use crate::component::relay::map_relay_config::MapRelayConfig;
+use crate::component::relay::normalize_relay::{Kind, NormalizeRelay, OnError, Separators, UnicodeForm, COLUMNS_KEY, DATE_FORMATS_KEY, EMPTY_AS_NULL_KEY, LOCALE_KEY, ON_ERROR_KEY, THOUSANDS_SEPARATOR_KEY, UNICODE_KEY};
+use crate::model::ir::atom::Atom;
+use crate::model::ir::row_batch::RowBatch;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+
+fn relay(cfg: MapRelayConfig) -> NormalizeRelay {
+	initialized(NormalizeRelay::new(), &cfg)
+}
+
+#[test]
+fn settings_parse() {
+	assert_eq!(Kind::parse(" Timestamp ").unwrap(),      Kind::Timestamp);
+	assert_eq!(UnicodeForm::parse("NFKC").unwrap(),      UnicodeForm::Nfkc);
+	assert_eq!(OnError::parse("drop").unwrap(),          OnError::Drop);
+	assert_eq!(Separators::for_locale("de_DE").unwrap(), Separators{decimal: ',', thousands: Some('.')});
+	assert_eq!(Separators::for_locale("fr-CH").unwrap(), Separators{decimal: '.', thousands: Some('\'')});
+	assert!(matches!(Kind::parse("money"),               Err(Error::InvalidConfig(_))));
+	assert!(matches!(Separators::for_locale("xx"),       Err(Error::InvalidConfig(_))));
+
+	let clash = MapRelayConfig::new().with(LOCALE_KEY, "de").with(THOUSANDS_SEPARATOR_KEY, ",");
+	assert!(matches!(NormalizeRelay::new().initialize(&clash),                                         Err(Error::InvalidConfig(_))));
+	assert!(matches!(NormalizeRelay::new().initialize(&MapRelayConfig::new().with(COLUMNS_KEY, "a")), Err(Error::InvalidConfig(_))));
+}
+
+#[test]
+fn numbers_follow_the_locale_separators() {
+	let en = Separators::default();
+	assert_eq!(en.parse("1,234.50").map(|v| v.to_string()), Some("1234.50".to_owned()));
+	assert_eq!(en.parse("-1,234,567"),                      Some(Value::Integer(-1_234_567)));
+	assert_eq!(en.parse("+.5e3"),                           Some(Value::Float(500.0)));
+	for bad in ["1,23", "1,2345", ",123", "1.2.3", "12a", "-", "1e", "."] {
+		assert_eq!(en.parse(bad), None, "{}", bad);
+	}
+
+	let de = Separators::for_locale("de").unwrap();
+	assert_eq!(de.parse("1.234,5").map(|v| v.to_string()),   Some("1234.5".to_owned()));
+	let fr = Separators::for_locale("fr").unwrap();
+	assert_eq!(fr.parse("1\u{202f}234 567,25").map(|v| v.to_string()), Some("1234567.25".to_owned()));   // Any space groups
+	let plain = Separators{decimal: '.', thousands: None};
+	assert_eq!(plain.parse("1,234"), None);
+}
+
+#[test]
+fn dates_timestamps_and_booleans_become_canonical() {
+	let relay = relay(MapRelayConfig::new());
+	let date  = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+	assert_eq!(relay.normalize("03/17/2025", Kind::Date),  Some(Value::Date(date(2025, 3, 17))));
+	assert_eq!(relay.normalize("17.03.2025", Kind::Date),  Some(Value::Date(date(2025, 3, 17))));
+	assert_eq!(relay.normalize("2025-03-17T10:30:00+02:00", Kind::Timestamp), Some(Value::Timestamp(date(2025, 3, 17).and_hms_opt(8, 30, 0).unwrap())));
+	assert_eq!(relay.normalize("2025-03-17", Kind::Timestamp).unwrap().to_string(), "2025-03-17T00:00:00");
+	assert_eq!(relay.normalize(" TRUE ", Kind::Boolean),   Some(Value::Boolean(true)));
+	assert_eq!(relay.normalize("No", Kind::Boolean),       Some(Value::Boolean(false)));
+	assert_eq!(relay.normalize("maybe", Kind::Boolean),    None);
+	assert_eq!(relay.normalize("17/03/2025", Kind::Date),  None);
+	assert_eq!(relay.normalize("   ", Kind::Date),         Some(Value::Null));
+
+	let european = self::relay(MapRelayConfig::new().with(DATE_FORMATS_KEY, "%d/%m/%Y, \"%d %B, %Y\""));
+	assert_eq!(european.normalize("17/03/2025", Kind::Date),     Some(Value::Date(date(2025, 3, 17))));
+	assert_eq!(european.normalize("17 March, 2025", Kind::Date), Some(Value::Date(date(2025, 3, 17))));
+}
+
+#[test]
+fn rows_are_trimmed_normalized_and_parsed_by_column() {
+	let cfg       = MapRelayConfig::new().with(COLUMNS_KEY, "amount = number, booked = date, active = boolean");
+	let mut relay = relay(cfg);
+	all(&mut relay, header(&["name", "amount", "booked", "active"]));
+	let out = all(&mut relay, Atom::ByteRowAtom(row(&["  Cafe\u{301} ", "1,234.50", "03/17/2025", "YES"])));
+	assert_eq!(fields(&out[0]), ["Caf\u{e9}", "1234.50", "2025-03-17", "true"]);                        // Composed to NFC
+	assert_eq!(fields(&all(&mut relay, Atom::StringRowAtom(row(&[" ", " 42 ", "", "0"]).as_string_row()))[0]), ["", "42", "", "false"]);
+
+	let typed = TypedRow::new(vec![text("\u{fb01}"), Value::Integer(7), text("2025-01-02"), text("")]);
+	match &all(&mut relay, Atom::TypedRowAtom(typed))[..] {
+		[Atom::TypedRowAtom(r)] => assert_eq!(r.iter().cloned().collect::<Vec<_>>(), [
+			text("\u{fb01}"), Value::Integer(7), Value::Date(NaiveDate::from_ymd_opt(2025, 1, 2).unwrap()), Value::Null,
+		]),
+		other                   => panic!("expected a typed row, got {:?}", other),
+	}
+
+	let mut nfkc = self::relay(MapRelayConfig::new().with(UNICODE_KEY, "nfkc").with(EMPTY_AS_NULL_KEY, "false"));
+	match &all(&mut nfkc, Atom::TypedRowAtom(TypedRow::new(vec![text("\u{fb01}"), text(" ")])))[..] {
+		[Atom::TypedRowAtom(r)] => assert_eq!(r.iter().cloned().collect::<Vec<_>>(), [text("fi"), text("")]),
+		other                   => panic!("expected a typed row, got {:?}", other),
+	}
+	assert_eq!(relay.metrics().record_count, 3);
+}
+
+#[test]
+fn unparseable_cells_are_reported_with_their_column() {
+	let config = |on_error: &str| MapRelayConfig::new().with(COLUMNS_KEY, "column_2 = number").with(ON_ERROR_KEY, on_error);
+	let bad    = || Atom::ByteRowAtom(row(&["a", "n/a"]));
+
+	let out = all(&mut relay(config("null")), bad());
+	match &out[0] {
+		Atom::ErrorAtom(Error::Parse(message), _) => assert!(message.contains("column 'column_2' value 'n/a' is not a valid number"), "{}", message),
+		other                                     => panic!("expected an error, got {:?}", other),
+	}
+	assert_eq!(fields(&out[1]), ["a", ""]);
+	assert_eq!(fields(&all(&mut relay(config("keep")), bad())[1]), ["a", "n/a"]);
+
+	let mut relay = relay(config("drop"));
+	assert!(matches!(all(&mut relay, bad())[..], [Atom::ErrorAtom(Error::Parse(_), _)]));
+	let batch = RowBatch::from_rows(&[row(&["x", "1"]), row(&["y", "?"]), row(&["z", "2,000"])]).unwrap();
+	let out   = all(&mut relay, Atom::RowBatchAtom(batch));
+	assert_eq!(out.len(), 2);
+	assert!(matches!(out[0], Atom::ErrorAtom(Error::Parse(_), _)));
+	match &out[1] {
+		Atom::RowBatchAtom(kept) => assert_eq!((kept.len(), kept.get(1, 1)), (2, Some(b"2000".as_slice()))),
+		other                    => panic!("expected a batch, got {:?}", other),
+	}
+	let metrics = relay.metrics();
+	assert_eq!((metrics.record_count, metrics.dropped_count, metrics.error_count), (4, 2, 2));
+}
+
+#[test]
+fn missing_columns_are_reported() {
+	let mut relay = relay(MapRelayConfig::new().with(COLUMNS_KEY, "amount = number"));
+	assert!(matches!(all(&mut relay, header(&["id"]))[..], [Atom::ErrorAtom(Error::InvalidConfig(_), _)]));
+	assert!(all(&mut relay, Atom::ByteRowAtom(row(&["1"]))).is_empty());
+	assert!(matches!(all(&mut relay, Atom::EndTask)[..], [Atom::EndTask]));
+	all(&mut relay, header(&["id", "amount"]));
+	assert_eq!(fields(&all(&mut relay, Atom::ByteRowAtom(row(&["1", "1,5"])))[1]), ["1", ""]);
+}
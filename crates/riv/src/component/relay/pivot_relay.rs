@@ -0,0 +1,403 @@
+use std::collections::HashMap;
+use tracing::info;
+use zero::component::telemetry::component_metrics::ComponentMetrics;
+use zero::component::telemetry::provides_metrics::ProvidesMetrics;
+use crate::Error;
+use crate::component::relay::{batch_atom, BindsColumns, Collector, ColumnBinding, Relay, RelayConfig};
+use crate::component::relay::aggregate_relay::group_hash;
+use crate::component::relay::project_relay::glob_match;
+use crate::model::coordinate::coordinate::Coordinate;
+use crate::model::expression::Fields;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::string_row::StringRow;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+
+/// Config key: `unpivot` (or `melt`, the default) or `pivot`.
+pub const MODE_KEY:           &str = "mode";
+/// Config key: the columns to unpivot, by name or pattern such as `2025-*`.
+pub const COLUMNS_KEY:        &str = "columns";
+/// Config key: the key column, written by unpivot and read by pivot. Defaults to `key`.
+pub const KEY_KEY:            &str = "key";
+/// Config key: the value column, written by unpivot and read by pivot. Defaults to `value`.
+pub const VALUE_KEY:          &str = "value";
+/// Config key: the columns identifying a pivoted row. Defaults to every column but the key and value.
+pub const GROUP_BY_KEY:       &str = "group_by";
+/// Config key: unpivot leaves out null and empty values. Defaults to false.
+pub const DROP_NULLS_KEY:     &str = "drop_nulls";
+pub const DEFAULT_KEY_NAME:   &str = "key";
+pub const DEFAULT_VALUE_NAME: &str = "value";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+	/// Wide to long: one row per unpivoted column of each row.
+	#[default]
+	Unpivot,
+	/// Long to wide: one row per group, one column per key.
+	Pivot,
+}
+
+impl Mode {
+	pub fn parse(text: &str) -> Result<Mode, Error> {
+		match text.trim().to_ascii_lowercase().as_str() {
+			"unpivot" | "melt" => Ok(Mode::Unpivot),
+			"pivot"            => Ok(Mode::Pivot),
+			other              => Err(Error::InvalidConfig(format!("[PivotRelay]: mode must be 'unpivot' or 'pivot', got '{}'", other))),
+		}
+	}
+}
+
+/// Column positions bound against a header.
+#[derive(Clone, Debug)]
+pub(crate) enum Plan {
+	Unpivot{ids: Vec<usize>, columns: Vec<(usize, String)>},
+	Pivot{groups: Vec<usize>, key: usize, value: usize},
+}
+
+/// Pivoted rows held until `finish`.
+#[derive(Default)]
+struct Table {
+	groups:    Vec<String>,
+	columns:   Vec<String>,
+	positions: HashMap<String, usize>,
+	index:     HashMap<u128, usize>,
+	rows:      Vec<(Vec<Value>, Vec<Option<Value>>)>,
+}
+
+/// Reshapes rows between wide and long form.
+///
+/// `unpivot` turns each row into one row per `columns` column, holding
+/// the other columns, the column's name under `key` and its value under
+/// `value`; a report with a column per month becomes a row per month.
+/// Rows are emitted as they arrive, in the form they came in, and each
+/// `HeaderRow` is rewritten to match.
+///
+/// `pivot` does the reverse. Rows are grouped by the `group_by` columns,
+/// whose typed values keep their type, and each row's `value` is placed in
+/// the column named by its `key`. Nothing is emitted until `finish`, which
+/// emits a `HeaderRow` of the group columns then the keys, in order of
+/// first arrival, followed by one `TypedRowAtom` per group in order of
+/// first arrival, with null where a group has no value for a key. Every
+/// group is held in memory. Columns are named by the first `HeaderRow` and
+/// later headers are dropped; atoms before the first row pass straight
+/// through, and any that arrive once rows are held back, such as
+/// `EndTask`, follow the groups. A row with a null key, or a second value
+/// for the same group and key, is reported as an `ErrorAtom` and dropped,
+/// keeping the first.
+///
+/// Columns are bound as `ColumnBinding` describes, and rows dropped for
+/// lack of them are counted in the metrics' `dropped_count`.
+///
+pub struct PivotRelay {
+	mode:       Mode,
+	columns:    Vec<String>,
+	key:        String,
+	value:      String,
+	group_by:   Vec<String>,
+	drop_nulls: bool,
+	plan:       ColumnBinding<Plan>,
+	table:      Table,
+	trailing:   Vec<Atom>,
+	metrics:    ComponentMetrics,
+}
+
+impl Default for PivotRelay {
+	fn default() -> Self { Self::new() }
+}
+
+impl PivotRelay {
+	pub fn new() -> Self {
+		PivotRelay {
+			mode:       Mode::Unpivot,
+			columns:    Vec::new(),
+			key:        DEFAULT_KEY_NAME.to_owned(),
+			value:      DEFAULT_VALUE_NAME.to_owned(),
+			group_by:   Vec::new(),
+			drop_nulls: false,
+			plan:       ColumnBinding::default(),
+			table:      Table::default(),
+			trailing:   Vec::new(),
+			metrics:    ComponentMetrics::default(),
+		}
+	}
+
+	pub fn with_mode(mut self, mode: Mode) -> Self {
+		self.mode = mode;
+		self
+	}
+
+	pub fn with_column(mut self, column: &str) -> Self {
+		self.columns.push(column.to_owned());
+		self
+	}
+
+	pub fn with_names(mut self, key: &str, value: &str) -> Self {
+		self.key   = key.to_owned();
+		self.value = value.to_owned();
+		self
+	}
+
+	pub fn with_group_by(mut self, column: &str) -> Self {
+		self.group_by.push(column.to_owned());
+		self
+	}
+
+	pub fn mode(&self) -> Mode { self.mode }
+
+	/// Groups held for `pivot`.
+	pub fn groups(&self) -> usize { self.table.rows.len() }
+
+	/// Drop `rows` rows whose columns are missing.
+	fn reject(&mut self, rows: usize, coordinate: Coordinate, out: &mut dyn Collector) {
+		self.metrics.add_dropped(rows as u64);
+		self.plan.reject_into(coordinate, out);
+	}
+
+	/// The unpivoted columns of a row worth emitting.
+	fn melted<'a>(&self, row: &dyn Fields, columns: &'a [(usize, String)]) -> impl Iterator<Item = &'a (usize, String)> {
+		let keep = columns.iter().map(|(i, _)| !self.drop_nulls || !row.value(*i).is_null()).collect::<Vec<_>>();
+		columns.iter().zip(keep).filter_map(|(column, keep)| keep.then_some(column))
+	}
+
+	fn unpivot(&mut self, atom: Atom, ids: &[usize], columns: &[(usize, String)], out: &mut dyn Collector) {
+		let coordinate = atom.coordinate();
+		match atom {
+			Atom::ByteRowAtom(row)    => {
+				self.metrics.add_records(1);
+				for (i, name) in self.melted(&row, columns) {
+					out.emit(Atom::ByteRowAtom(melt_byte_row(&row, ids, *i, name).with_coordinate(coordinate)));
+				}
+			}
+			Atom::StringRowAtom(row)  => {
+				self.metrics.add_records(1);
+				for (i, name) in self.melted(&row, columns) {
+					let values = ids.iter().map(|&c| row.get(c).unwrap_or_default().to_owned())
+						.chain([name.clone(), row.get(*i).unwrap_or_default().to_owned()])
+						.collect();
+					out.emit(Atom::StringRowAtom(StringRow::from_values(values).with_coordinate(coordinate)));
+				}
+			}
+			Atom::TypedRowAtom(row)   => {
+				self.metrics.add_records(1);
+				for (i, name) in self.melted(&row, columns) {
+					let values = ids.iter().map(|&c| row.get(c).cloned().unwrap_or(Value::Null))
+						.chain([Value::String(name.clone()), row.get(*i).cloned().unwrap_or(Value::Null)])
+						.collect();
+					out.emit(Atom::TypedRowAtom(TypedRow::new(values).with_coordinate(coordinate)));
+				}
+			}
+			Atom::RowBatchAtom(batch) => {
+				let mut rows = Vec::new();
+				for row in batch.to_rows() {
+					self.metrics.add_records(1);
+					rows.extend(self.melted(&row, columns).map(|(i, name)| melt_byte_row(&row, ids, *i, name).with_coordinate(row.coordinate())));
+				}
+				if !rows.is_empty() {
+					out.emit(batch_atom("PivotRelay", &rows));
+				}
+			}
+			other                     => out.emit(other),
+		}
+	}
+
+	/// Place one row's value in its group.
+	fn pivot(&mut self, row: &dyn Fields, groups: &[usize], key: usize, value: usize, coordinate: Coordinate, out: &mut dyn Collector) {
+		self.metrics.add_records(1);
+		let name = row.value(key);
+		if name.is_null() {
+			return self.drop_row(Error::InvalidInput(format!("[PivotRelay]: no value in key column '{}'", self.key)), coordinate, out)
+		}
+		let name   = name.to_string();
+		let values = groups.iter().map(|&i| row.value(i)).collect::<Vec<_>>();
+		let hash   = group_hash(&values);
+		let table  = &mut self.table;
+		let column = *table.positions.entry(name.clone()).or_insert_with(|| {
+			table.columns.push(name.clone());
+			table.columns.len() - 1
+		});
+		let group  = *table.index.entry(hash).or_insert_with(|| {
+			table.rows.push((values, Vec::new()));
+			table.rows.len() - 1
+		});
+		let cells = &mut table.rows[group].1;
+		if cells.len() <= column { cells.resize(column + 1, None) }
+		match cells[column] {
+			Some(_) => {
+				let group = self.table.rows[group].0.iter().map(Value::to_string).collect::<Vec<_>>().join(", ");
+				self.drop_row(Error::InvalidInput(format!("[PivotRelay]: a second value for key '{}' in group '{}'", name, group)), coordinate, out)
+			}
+			None    => cells[column] = Some(row.value(value)),
+		}
+	}
+
+	fn drop_row(&mut self, error: Error, coordinate: Coordinate, out: &mut dyn Collector) {
+		self.metrics.increment_errors();
+		self.metrics.add_dropped(1);
+		out.emit(Atom::ErrorAtom(error, coordinate));
+	}
+
+	fn accept_pivot(&mut self, atom: Atom, out: &mut dyn Collector) {
+		let coordinate    = atom.coordinate();
+		let started       = self.metrics.record_count > 0 || self.plan.is_broken();
+		let (width, rows) = match &atom {
+			Atom::ByteRowAtom(row)    => (row.length() as usize, 1),
+			Atom::StringRowAtom(row)  => (row.count() as usize, 1),
+			Atom::TypedRowAtom(row)   => (row.count() as usize, 1),
+			Atom::RowBatchAtom(batch) => (batch.width(), batch.len()),
+			_                         => (0, 0),
+		};
+		let plan = match &atom {
+			Atom::ByteRowAtom(_) | Atom::StringRowAtom(_) | Atom::TypedRowAtom(_) | Atom::RowBatchAtom(_) => match self.bound(width) {
+				Some(Plan::Pivot{groups, key, value}) => Some((groups, key, value)),
+				_                                     => return self.reject(rows, coordinate, out),
+			},
+			_                                                                                            => None,
+		};
+		match (atom, plan) {
+			(Atom::HeaderRow(header), _) if !started && self.plan.get().is_none() => {
+				let names = header.iter_str().collect::<Vec<_>>();
+				if let Err(error) = self.bind(&names) {
+					out.emit(Atom::ErrorAtom(error, coordinate));
+				}
+			}
+			(Atom::HeaderRow(_), _)                         => {}         // Describes input columns, not ours
+			(Atom::ByteRowAtom(row), Some((g, k, v)))       => self.pivot(&row, &g, k, v, coordinate, out),
+			(Atom::StringRowAtom(row), Some((g, k, v)))     => self.pivot(&row, &g, k, v, coordinate, out),
+			(Atom::TypedRowAtom(row), Some((g, k, v)))      => self.pivot(&row, &g, k, v, coordinate, out),
+			(Atom::RowBatchAtom(batch), Some((g, k, v)))    => {
+				for r in 0..batch.len() {
+					self.pivot(&(&batch, r), &g, k, v, batch.coordinate(r), out);
+				}
+			}
+			(other, _) if started                           => self.trailing.push(other),
+			(other, _)                                      => out.emit(other),
+		}
+	}
+}
+
+impl BindsColumns for PivotRelay {
+	type Bound = Plan;
+
+	fn resolve<S: AsRef<str>>(&mut self, names: &[S]) -> Result<Plan, Error> {
+		let find = |column: &str| names.iter().position(|n| n.as_ref() == column)
+			.ok_or_else(|| Error::InvalidConfig(format!("[PivotRelay]: no column '{}' in the header", column)));
+		match self.mode {
+			Mode::Unpivot => {
+				let mut selected = Vec::new();
+				for column in &self.columns {
+					let matched = match column.contains(['*', '?']) {
+						true  => (0..names.len()).filter(|&i| glob_match(column, names[i].as_ref())).collect(),
+						false => vec![find(column)?],
+					};
+					selected.extend(matched.into_iter().filter(|i| !selected.contains(i)).collect::<Vec<_>>());
+				}
+				if selected.is_empty() {
+					return Err(Error::InvalidConfig(format!("[PivotRelay]: no column in the header matches '{}'", self.columns.join(", "))))
+				}
+				selected.sort_unstable();
+				let ids     = (0..names.len()).filter(|i| !selected.contains(i)).collect();
+				let columns = selected.into_iter().map(|i| (i, names[i].as_ref().to_owned())).collect();
+				Ok(Plan::Unpivot{ids, columns})
+			}
+			Mode::Pivot   => {
+				let (key, value) = (find(&self.key)?, find(&self.value)?);
+				let groups       = match self.group_by.is_empty() {
+					true  => (0..names.len()).filter(|&i| i != key && i != value).collect(),
+					false => self.group_by.iter().map(|column| find(column)).collect::<Result<Vec<_>, _>>()?,
+				};
+				self.table.groups = groups.iter().map(|&i| names[i].as_ref().to_owned()).collect();
+				Ok(Plan::Pivot{groups, key, value})
+			}
+		}
+	}
+
+	fn binding(&mut self) -> &mut ColumnBinding<Plan> { &mut self.plan }
+}
+
+impl Relay for PivotRelay {
+	fn initialize(&mut self, cfg: &dyn RelayConfig) -> Result<(), Error> {
+		if let Some(text) = cfg.string_value(MODE_KEY)         { self.mode       = Mode::parse(&text)?; }
+		if let Some(name) = cfg.string_value(KEY_KEY)          { self.key        = name.trim().to_owned(); }
+		if let Some(name) = cfg.string_value(VALUE_KEY)        { self.value      = name.trim().to_owned(); }
+		if let Some(drop) = cfg.bool_value(DROP_NULLS_KEY)     { self.drop_nulls = drop; }
+		self.columns.extend(cfg.list_value(COLUMNS_KEY).unwrap_or_default());
+		self.group_by.extend(cfg.list_value(GROUP_BY_KEY).unwrap_or_default());
+		if self.mode == Mode::Unpivot && self.columns.is_empty() {
+			return Err(Error::InvalidConfig(format!("[PivotRelay]: '{}' is required to unpivot", COLUMNS_KEY)))
+		}
+		if self.key.is_empty() || self.value.is_empty() || self.key == self.value {
+			return Err(Error::InvalidConfig(format!("[PivotRelay]: '{}' and '{}' must be two different names", KEY_KEY, VALUE_KEY)))
+		}
+		self.metrics.activate();
+		info!("[PivotRelay]: initialized with {}", cfg);
+		Ok(())
+	}
+
+	fn accept(&mut self, atom: Atom, out: &mut dyn Collector) {
+		self.metrics.increment_messages();
+		if self.mode == Mode::Pivot { return self.accept_pivot(atom, out) }
+		let coordinate = atom.coordinate();
+		match atom {
+			Atom::HeaderRow(header) => {
+				let names = header.iter_str().map(str::to_owned).collect::<Vec<_>>();
+				match (self.bind(&names), self.plan.get()) {
+					(Ok(()), Some(Plan::Unpivot{ids, ..})) => {
+						let names = ids.iter().map(|&i| names[i].clone()).chain([self.key.clone(), self.value.clone()]).collect();
+						out.emit(Atom::HeaderRow(StringRow::from_values(names).with_coordinate(coordinate)));
+					}
+					(Err(error), _)                        => out.emit(Atom::ErrorAtom(error, coordinate)),
+					_                                      => {}
+				}
+			}
+			Atom::ByteRowAtom(_) | Atom::StringRowAtom(_) | Atom::TypedRowAtom(_) | Atom::RowBatchAtom(_) => {
+				let (width, rows) = match &atom {
+					Atom::ByteRowAtom(row)    => (row.length() as usize, 1),
+					Atom::StringRowAtom(row)  => (row.count() as usize, 1),
+					Atom::TypedRowAtom(row)   => (row.count() as usize, 1),
+					Atom::RowBatchAtom(batch) => (batch.width(), batch.len()),
+					_                         => (0, 0),
+				};
+				match self.bound(width) {
+					Some(Plan::Unpivot{ids, columns}) => self.unpivot(atom, &ids, &columns, out),
+					_                                 => self.reject(rows, coordinate, out),
+				}
+			}
+			other                   => out.emit(other),
+		}
+	}
+
+	fn finish(&mut self, out: &mut dyn Collector) -> Result<(), Error> {
+		if self.mode == Mode::Pivot && self.plan.get().is_some() {
+			let table = std::mem::take(&mut self.table);
+			let names = table.groups.iter().chain(&table.columns).cloned().collect();
+			out.emit(Atom::HeaderRow(StringRow::from_values(names)));
+			for (group, mut cells) in table.rows {
+				cells.resize(table.columns.len(), None);
+				let values = group.into_iter().chain(cells.into_iter().map(|c| c.unwrap_or(Value::Null))).collect();
+				out.emit(Atom::TypedRowAtom(TypedRow::new(values)));
+			}
+		}
+		std::mem::take(&mut self.trailing).into_iter().for_each(|atom| out.emit(atom));
+		self.metrics.complete();
+		info!("[PivotRelay]: reshaped {} rows", self.metrics.record_count);
+		Ok(())
+	}
+}
+
+impl ProvidesMetrics for PivotRelay {
+	fn metrics(&self) -> ComponentMetrics { self.metrics }
+
+	fn take_metrics(&mut self) -> ComponentMetrics {
+		let metrics = self.metrics;
+		self.metrics.reset();
+		metrics
+	}
+}
+
+/// The `ids` fields of `row`, then `name`, then field `column`.
+fn melt_byte_row(row: &ByteRow, ids: &[usize], column: usize, name: &str) -> ByteRow {
+	let fields = ids.iter().map(|&i| row.get(i).unwrap_or_default())
+		.chain([name.as_bytes(), row.get(column).unwrap_or_default()]);
+	ByteRow::from_fields(fields)
+}
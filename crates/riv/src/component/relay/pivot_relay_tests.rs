@@ -0,0 +1,123 @@
+use zero::component::telemetry::provides_metrics::ProvidesMetrics;
+use crate::Error;
+use crate::component::relay::Relay;
+use crate::component::relay::fixtures::{all, fields, header, initialized, row};
+use crate::component::relay::map_relay_config::MapRelayConfig;
+use crate::component::relay::pivot_relay::{Mode, PivotRelay, COLUMNS_KEY, DROP_NULLS_KEY, GROUP_BY_KEY, KEY_KEY, MODE_KEY, VALUE_KEY};
+use crate::model::ir::atom::Atom;
+use crate::model::ir::row_batch::RowBatch;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+
+fn relay(cfg: MapRelayConfig) -> PivotRelay {
+	initialized(PivotRelay::new(), &cfg)
+}
+
+#[test]
+fn settings_are_checked() {
+	assert_eq!(Mode::parse(" Melt ").unwrap(), Mode::Unpivot);
+	assert!(matches!(Mode::parse("transpose"), Err(Error::InvalidConfig(_))));
+	assert!(matches!(PivotRelay::new().initialize(&MapRelayConfig::new()), Err(Error::InvalidConfig(_))));                  // Nothing to unpivot
+	let same = MapRelayConfig::new().with(MODE_KEY, "pivot").with(KEY_KEY, "v").with(VALUE_KEY, "v");
+	assert!(matches!(PivotRelay::new().initialize(&same), Err(Error::InvalidConfig(_))));
+}
+
+#[test]
+fn unpivot_emits_a_row_per_value_column() {
+	let mut relay = relay(MapRelayConfig::new().with(COLUMNS_KEY, "2025-*").with(KEY_KEY, "month").with(VALUE_KEY, "amount"));
+	assert_eq!(fields(&all(&mut relay, header(&["account", "2025-01", "desk", "2025-02"]))[0]), ["account", "desk", "month", "amount"]);
+
+	let out = all(&mut relay, Atom::ByteRowAtom(row(&["cash", "10", "fx", ""])));
+	assert_eq!(out.iter().map(fields).collect::<Vec<_>>(), [["cash", "fx", "2025-01", "10"], ["cash", "fx", "2025-02", ""]]);
+
+	let out = all(&mut relay, Atom::StringRowAtom(row(&["bonds", "1", "rates", "2"]).as_string_row()));
+	assert_eq!(fields(&out[1]), ["bonds", "rates", "2025-02", "2"]);
+
+	let typed = Atom::TypedRowAtom(TypedRow::new(vec![Value::String("fees".into()), Value::Integer(3), Value::Null, Value::Integer(4)]));
+	match &all(&mut relay, typed)[0] {
+		Atom::TypedRowAtom(r) => assert_eq!(r.iter().cloned().collect::<Vec<_>>(), [Value::String("fees".into()), Value::Null, Value::String("2025-01".into()), Value::Integer(3)]),
+		other                 => panic!("expected a typed row, got {:?}", other),
+	}
+	assert_eq!(relay.metrics().record_count, 3);
+}
+
+#[test]
+fn unpivot_batches_and_dropping_nulls() {
+	let mut relay = relay(MapRelayConfig::new().with(COLUMNS_KEY, "column_2, column_3").with(DROP_NULLS_KEY, "true"));
+	let batch     = RowBatch::from_rows(&[row(&["a", "1", ""]), row(&["b", " ", "2"])]).unwrap();
+	match &all(&mut relay, Atom::RowBatchAtom(batch))[..] {
+		[Atom::RowBatchAtom(melted)] => {
+			assert_eq!(melted.to_rows().iter().map(|r| fields(&Atom::ByteRowAtom(r.clone()))).collect::<Vec<_>>(), [["a", "column_2", "1"], ["b", "column_3", "2"]]);
+		}
+		other                        => panic!("expected one batch, got {:?}", other),
+	}
+}
+
+#[test]
+fn pivot_builds_a_row_per_group_at_finish() {
+	let mut relay = relay(MapRelayConfig::new().with(MODE_KEY, "pivot").with(KEY_KEY, "month").with(VALUE_KEY, "amount"));
+	let mut out   = Vec::new();
+	relay.accept(Atom::CommentRow("long form".into()), &mut out);
+	relay.accept(header(&["account", "month", "amount"]), &mut out);
+	for r in [["cash", "jan", "10"], ["cash", "feb", "11"], ["bonds", "feb", "5"], ["cash", "mar", ""]] {
+		relay.accept(Atom::ByteRowAtom(row(&r)), &mut out);
+	}
+	relay.accept(Atom::EndTask, &mut out);
+	assert_eq!(out.len(), 1);
+	relay.finish(&mut out).unwrap();
+
+	assert_eq!(out.len(), 5);
+	assert_eq!(fields(&out[1]), ["account", "jan", "feb", "mar"]);
+	assert_eq!(fields(&out[2]), ["cash", "10", "11", ""]);
+	assert_eq!(fields(&out[3]), ["bonds", "", "5", ""]);
+	assert!(matches!(&out[3], Atom::TypedRowAtom(r) if r.get(1) == Some(&Value::Null)));
+	assert!(matches!(out[4], Atom::EndTask));
+	assert_eq!(relay.groups(), 0);
+}
+
+#[test]
+fn pivot_reports_null_keys_and_repeated_values() {
+	let cfg       = MapRelayConfig::new().with(MODE_KEY, "pivot").with(GROUP_BY_KEY, "column_1").with(KEY_KEY, "column_2").with(VALUE_KEY, "column_3");
+	let mut relay = relay(cfg);
+	let batch     = RowBatch::from_rows(&[row(&["a", "x", "1", "ignored"]), row(&["a", "", "2", ""]), row(&["a", "x", "3", ""])]).unwrap();
+	let out       = all(&mut relay, Atom::RowBatchAtom(batch));
+	assert_eq!(out.len(), 2);
+	assert!(matches!(&out[0], Atom::ErrorAtom(Error::InvalidInput(m), _) if m.contains("key column 'column_2'")));
+	assert!(matches!(&out[1], Atom::ErrorAtom(Error::InvalidInput(m), _) if m.contains("key 'x' in group 'a'")));
+
+	let mut out = Vec::new();
+	relay.finish(&mut out).unwrap();
+	assert_eq!(out.iter().map(fields).collect::<Vec<_>>(), [["column_1", "x"], ["a", "1"]]);
+	let metrics = relay.metrics();
+	assert_eq!((metrics.record_count, metrics.dropped_count, metrics.error_count), (3, 2, 2));
+}
+
+#[test]
+fn pivot_groups_keep_their_type() {
+	let cfg       = MapRelayConfig::new().with(MODE_KEY, "pivot").with(GROUP_BY_KEY, "column_1").with(KEY_KEY, "column_2").with(VALUE_KEY, "column_3");
+	let mut relay = relay(cfg);
+	let typed     = |group: Value, value: i64| Atom::TypedRowAtom(TypedRow::new(vec![group, Value::String("x".into()), Value::Integer(value)]));
+	for (group, value) in [(Value::Integer(1), 1), (Value::String("1".into()), 2), (Value::Null, 3), (Value::String("".into()), 4)] {
+		assert!(all(&mut relay, typed(group, value)).is_empty());                            // No group gets a second value
+	}
+	let mut out = Vec::new();
+	relay.finish(&mut out).unwrap();
+	assert_eq!(out.iter().skip(1).map(fields).collect::<Vec<_>>(), [["1", "1"], ["1", "2"], ["", "3"], ["", "4"]]);
+}
+
+#[test]
+fn missing_columns_are_reported() {
+	let mut relay = relay(MapRelayConfig::new().with(COLUMNS_KEY, "q1"));
+	assert!(matches!(all(&mut relay, header(&["q2"]))[..], [Atom::ErrorAtom(Error::InvalidConfig(_), _)]));
+	assert!(all(&mut relay, Atom::ByteRowAtom(row(&["1"]))).is_empty());
+	assert!(all(&mut relay, Atom::RowBatchAtom(RowBatch::from_rows(&[row(&["2"]), row(&["3"])]).unwrap())).is_empty());
+	assert_eq!(relay.metrics().dropped_count, 3);                                           // Every row of the batch counts
+	assert_eq!(fields(&all(&mut relay, header(&["id", "q1"]))[0]), ["id", "key", "value"]);
+
+	let mut pivot = PivotRelay::new().with_mode(Mode::Pivot);
+	pivot.initialize(&MapRelayConfig::new()).unwrap();
+	assert!(matches!(all(&mut pivot, header(&["id", "key"]))[..], [Atom::ErrorAtom(Error::InvalidConfig(_), _)]));
+	let mut out = Vec::new();
+	pivot.finish(&mut out).unwrap();
+	assert!(out.is_empty());
+}
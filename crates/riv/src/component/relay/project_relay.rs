@@ -0,0 +1,243 @@
+use std::collections::HashSet;
+use tracing::info;
+use crate::Error;
+use crate::model::coordinate::coordinate::Coordinate;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::row_batch::RowBatch;
+use crate::model::ir::string_row::StringRow;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+use crate::component::relay::{batch_atom, BindsColumns, ColumnBinding, RelayConfig, SimpleRelay};
+
+/// Config key: columns to keep, in output order. Defaults to `*`.
+pub const SELECT_KEY:  &str = "select";
+/// Config key: columns to leave out.
+pub const EXCLUDE_KEY: &str = "exclude";
+/// Config key: `old=new` pairs naming output columns.
+pub const RENAME_KEY:  &str = "rename";
+/// Config key: the input's column names, checked against the projection
+/// by `initialize`. Rows that arrive before any `HeaderRow` use them.
+pub const HEADER_KEY:  &str = "header";
+
+/// One item of a select or exclude list.
+///
+/// `#3` is the third column, `*` every column the select list does not
+/// name explicitly, and a name containing `*` or `?` a pattern over names.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum Selector {
+	Name(String),
+	Index(usize),
+	Pattern(String),
+	Rest,
+}
+
+impl Selector {
+	pub fn parse(item: &str) -> Result<Selector, Error> {
+		if item == "*" { return Ok(Selector::Rest) }
+		if let Some(digits) = item.strip_prefix('#') && !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+			return match digits.parse::<usize>() {
+				Ok(n) if n > 0 => Ok(Selector::Index(n)),
+				_              => Err(Error::InvalidConfig(format!("[ProjectRelay]: column numbers start at #1, got {}", item))),
+			}
+		}
+		if item.contains(['*', '?']) { return Ok(Selector::Pattern(item.to_owned())) }
+		Ok(Selector::Name(item.to_owned()))
+	}
+
+	/// Positions of the columns this selects. Names and numbers must exist.
+	fn resolve<S: AsRef<str>>(&self, names: &[S]) -> Result<Vec<usize>, Error> {
+		let missing = |what: String| Error::InvalidConfig(format!("[ProjectRelay]: no column {} in the header", what));
+		match self {
+			Selector::Name(name)       => names.iter().position(|n| n.as_ref() == name).map(|i| vec![i]).ok_or_else(|| missing(format!("'{}'", name))),
+			Selector::Index(n)         => if *n <= names.len() { Ok(vec![n - 1]) } else { Err(missing(format!("#{}", n))) },
+			Selector::Pattern(pattern) => Ok((0..names.len()).filter(|&i| glob_match(pattern, names[i].as_ref())).collect()),
+			Selector::Rest             => Ok((0..names.len()).collect()),
+		}
+	}
+}
+
+/// The columns a `ProjectRelay` emits for one header: where each comes
+/// from, and what it is called.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Projection {
+	pub indices: Vec<usize>,
+	pub names:   Vec<String>,
+}
+
+impl Projection {
+	fn byte_row(&self, row: &ByteRow) -> ByteRow {
+		ByteRow::from_fields(self.indices.iter().map(|&i| row.get(i).unwrap_or_default())).with_coordinate(row.coordinate())
+	}
+
+	fn string_row(&self, row: &StringRow) -> StringRow {
+		let values = self.indices.iter().map(|&i| row.get(i).unwrap_or_default().to_owned()).collect();
+		StringRow::from_values(values).with_coordinate(row.coordinate())
+	}
+
+	fn typed_row(&self, row: &TypedRow) -> TypedRow {
+		let values = self.indices.iter().map(|&i| row.get(i).cloned().unwrap_or(Value::Null)).collect();
+		TypedRow::new(values).with_coordinate(row.coordinate())
+	}
+
+	fn batch(&self, batch: &RowBatch) -> Atom {
+		if self.indices.iter().all(|&i| i < batch.width()) {
+			return Atom::RowBatchAtom(batch.select_columns(&self.indices))
+		}
+		let rows = batch.to_rows().iter().map(|row| self.byte_row(row)).collect::<Vec<_>>();   // Narrow batch: missing columns are empty
+		batch_atom("ProjectRelay", &rows)
+	}
+}
+
+/// Selects, renames and reorders columns.
+///
+/// The projection is resolved against each `HeaderRow`, which is replaced
+/// by one carrying the output names. A header that lacks a column named
+/// by `select`, `exclude` or `rename` is replaced by an `ErrorAtom`, and
+/// rows are dropped until a header that fits arrives. Without a header,
+/// columns are named by the `header` setting, or `column_1`, `column_2`, …
+///
+/// Whatever can be checked before the first row is checked by
+/// `initialize`: the settings themselves, and, when `header` is given,
+/// that every name, number and rename in them is a column of it.
+///
+#[derive(Default)]
+pub struct ProjectRelay {
+	select:     Vec<Selector>,
+	exclude:    Vec<Selector>,
+	renames:    Vec<(String, String)>,
+	projection: ColumnBinding<Projection>,
+}
+
+impl ProjectRelay {
+	pub fn new() -> Self { Self::default() }
+
+	pub fn projection(&self) -> Option<&Projection> { self.projection.get() }
+
+	/// Work out the output columns for a header with `names`.
+	pub fn resolve<S: AsRef<str>>(&self, names: &[S]) -> Result<Projection, Error> {
+		let mut explicit = HashSet::new();
+		for selector in self.select.iter().filter(|s| !matches!(s, Selector::Rest)) {
+			explicit.extend(selector.resolve(names)?);
+		}
+		let mut excluded = HashSet::new();
+		for selector in &self.exclude {
+			excluded.extend(selector.resolve(names)?);
+		}
+		let mut indices = Vec::new();
+		for selector in &self.select {
+			match selector {
+				Selector::Rest => indices.extend((0..names.len()).filter(|i| !explicit.contains(i))),
+				other          => indices.extend(other.resolve(names)?),
+			}
+		}
+		indices.retain(|i| !excluded.contains(i));
+		if indices.is_empty() {
+			return Err(Error::InvalidConfig("[ProjectRelay]: the projection selects no columns".to_owned()))
+		}
+
+		for (old, _) in &self.renames {
+			if !names.iter().any(|n| n.as_ref() == old) {
+				return Err(Error::InvalidConfig(format!("[ProjectRelay]: cannot rename '{}'; it is not in the header", old)))
+			}
+		}
+		let names = indices.iter()
+			.map(|&i| {
+				let name = names[i].as_ref();
+				self.renames.iter().find(|(old, _)| old == name).map_or(name, |(_, new)| new.as_str()).to_owned()
+			})
+			.collect();
+		Ok(Projection{indices, names})
+	}
+
+	/// `row`, projected; nothing, or the error once, if the projection does not fit.
+	fn project(&mut self, width: usize, coordinate: Coordinate, row: impl FnOnce(&Projection) -> Atom) -> Option<Atom> {
+		match self.ensure_bound(width) {
+			true  => self.projection.get().map(row),
+			false => self.projection.reject(coordinate),
+		}
+	}
+}
+
+impl BindsColumns for ProjectRelay {
+	type Bound = Projection;
+
+	fn resolve<S: AsRef<str>>(&mut self, names: &[S]) -> Result<Projection, Error> { ProjectRelay::resolve(self, names) }
+	fn binding(&mut self) -> &mut ColumnBinding<Projection> { &mut self.projection }
+}
+
+impl SimpleRelay for ProjectRelay {
+	fn initialize(&mut self, cfg: &dyn RelayConfig) -> Result<(), Error> {
+		let list = |key: &str| cfg.list_value(key).unwrap_or_default();
+		self.select  = list(SELECT_KEY).iter().map(|s| Selector::parse(s)).collect::<Result<_, _>>()?;
+		self.exclude = list(EXCLUDE_KEY).iter().map(|s| Selector::parse(s)).collect::<Result<_, _>>()?;
+		self.renames = list(RENAME_KEY).iter()
+			.map(|pair| match pair.split_once('=') {
+				Some((old, new)) if !old.trim().is_empty() && !new.trim().is_empty() => Ok((old.trim().to_owned(), new.trim().to_owned())),
+				_ => Err(Error::InvalidConfig(format!("[ProjectRelay]: rename '{}' is not old=new", pair))),
+			})
+			.collect::<Result<_, _>>()?;
+		if self.select.is_empty() { self.select.push(Selector::Rest) }
+		if self.exclude.contains(&Selector::Rest) {
+			return Err(Error::InvalidConfig("[ProjectRelay]: excluding * leaves no columns".to_owned()))
+		}
+		if let Some(header) = cfg.list_value(HEADER_KEY) {
+			self.bind(&header)?;
+		}
+		info!("[ProjectRelay]: initialized with {}", cfg);
+		Ok(())
+	}
+
+	fn accept(&mut self, atom: Atom) -> Option<Atom> {
+		let coordinate = atom.coordinate();
+		match atom {
+			Atom::HeaderRow(header) => {
+				let names = header.iter_str().collect::<Vec<_>>();
+				match self.bind(&names) {
+					Ok(())     => {
+						let names = self.projection.get().map(|p| p.names.clone()).unwrap_or_default();
+						Some(Atom::HeaderRow(StringRow::from_values(names).with_coordinate(coordinate)))
+					}
+					Err(error) => Some(Atom::ErrorAtom(error, coordinate)),
+				}
+			}
+			Atom::ByteRowAtom(row)    => self.project(row.length() as usize, coordinate, |p| Atom::ByteRowAtom(p.byte_row(&row))),
+			Atom::StringRowAtom(row)  => self.project(row.count() as usize, coordinate, |p| Atom::StringRowAtom(p.string_row(&row))),
+			Atom::TypedRowAtom(row)   => self.project(row.count() as usize, coordinate, |p| Atom::TypedRowAtom(p.typed_row(&row))),
+			Atom::RowBatchAtom(batch) => self.project(batch.width(), coordinate, |p| p.batch(&batch)),
+			other                     => Some(other),
+		}
+	}
+
+	fn finish(&mut self) -> bool {
+		info!("[ProjectRelay]: finished");
+		true
+	}
+}
+
+/// Shell-style match: `*` is any run of characters, `?` any one.
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+	let pattern = pattern.chars().collect::<Vec<_>>();
+	let text    = text.chars().collect::<Vec<_>>();
+	let (mut p, mut t)       = (0, 0);
+	let (mut star, mut mark) = (None, 0);
+	while t < text.len() {
+		if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+			p += 1;
+			t += 1;
+		} else if p < pattern.len() && pattern[p] == '*' {
+			star = Some(p);
+			mark = t;
+			p   += 1;
+		} else if let Some(s) = star {
+			p     = s + 1;
+			mark += 1;
+			t     = mark;
+		} else {
+			return false
+		}
+	}
+	pattern[p..].iter().all(|&c| c == '*')
+}
@@ -0,0 +1,102 @@
+use crate::Error;
+use crate::component::relay::{batch_atom, split_list, SimpleRelay};
+use crate::component::relay::fixtures::{fields, initialized, row};
+use crate::component::relay::map_relay_config::MapRelayConfig;
+use crate::component::relay::project_relay::{glob_match, ProjectRelay, EXCLUDE_KEY, HEADER_KEY, RENAME_KEY, SELECT_KEY};
+use crate::model::ir::atom::Atom;
+use crate::model::ir::row_batch::RowBatch;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+
+const HEADER: [&str; 5] = ["id", "name", "amount_usd", "amount_eur", "note"];
+
+fn relay(config: MapRelayConfig) -> ProjectRelay {
+	initialized(ProjectRelay::new(), &config)
+}
+
+#[test]
+fn selects_reorders_and_renames() {
+	let config    = MapRelayConfig::new().with(SELECT_KEY, "note, #1, amount_*").with(RENAME_KEY, "note=comment, id = key");
+	let mut relay = relay(config);
+	assert_eq!(fields(&relay.accept(Atom::HeaderRow(row(&HEADER).as_string_row())).unwrap()), ["comment", "key", "amount_usd", "amount_eur"]);
+	assert_eq!(fields(&relay.accept(Atom::ByteRowAtom(row(&["1", "ann", "10", "9", "hi"]))).unwrap()), ["hi", "1", "10", "9"]);
+	assert_eq!(fields(&relay.accept(Atom::StringRowAtom(row(&["2", "bo", "20", "18", ""]).as_string_row())).unwrap()), ["", "2", "20", "18"]);
+}
+
+#[test]
+fn wildcard_takes_the_rest_minus_exclusions() {
+	let mut relay = relay(MapRelayConfig::new().with(SELECT_KEY, "name, *").with(EXCLUDE_KEY, "note, amount_e*"));
+	assert_eq!(fields(&relay.accept(Atom::HeaderRow(row(&HEADER).as_string_row())).unwrap()), ["name", "id", "amount_usd"]);
+	assert_eq!(relay.projection().unwrap().indices, [1, 0, 2]);
+}
+
+#[test]
+fn typed_rows_and_batches_are_projected() {
+	let mut relay = relay(MapRelayConfig::new().with(SELECT_KEY, "#2, #1"));
+	let typed     = TypedRow::new(vec![Value::Integer(1), Value::String("x".into())]);
+	match relay.accept(Atom::TypedRowAtom(typed)) {
+		Some(Atom::TypedRowAtom(t)) => assert_eq!(t.iter().cloned().collect::<Vec<_>>(), [Value::String("x".into()), Value::Integer(1)]),
+		other                       => panic!("expected a typed row, got {:?}", other),
+	}
+	let batch = RowBatch::from_rows(&[row(&["1", "a"]), row(&["2", "b"])]).unwrap();
+	match relay.accept(Atom::RowBatchAtom(batch)) {
+		Some(Atom::RowBatchAtom(b)) => assert_eq!((b.get(0, 0), b.get(1, 1)), (Some(&b"a"[..]), Some(&b"2"[..]))),
+		other                       => panic!("expected a batch, got {:?}", other),
+	}
+}
+
+#[test]
+fn missing_columns_replace_the_header_with_an_error() {
+	let mut relay = relay(MapRelayConfig::new().with(SELECT_KEY, "id, missing"));
+	assert!(matches!(relay.accept(Atom::HeaderRow(row(&HEADER).as_string_row())), Some(Atom::ErrorAtom(Error::InvalidConfig(m), _)) if m.contains("'missing'")));
+	assert!(relay.accept(Atom::ByteRowAtom(row(&["1", "a", "", "", ""]))).is_none());
+
+	let renamer = relay_with_rename("nope=x");
+	assert!(renamer.resolve(&HEADER).is_err());
+	assert!(relay_with_rename("id=x").resolve(&["id"]).is_ok());
+	assert!(ProjectRelay::new().initialize(&MapRelayConfig::new().with(RENAME_KEY, "id")).is_err());
+	assert!(ProjectRelay::new().initialize(&MapRelayConfig::new().with(SELECT_KEY, "#0")).is_err());
+}
+
+#[test]
+fn headerless_rows_missing_a_column_report_one_error() {
+	let mut relay = relay(MapRelayConfig::new().with(SELECT_KEY, "#3"));
+	assert!(matches!(relay.accept(Atom::ByteRowAtom(row(&["1", "a"]))), Some(Atom::ErrorAtom(Error::InvalidConfig(m), _)) if m.contains("#3")));
+	assert!(relay.accept(Atom::ByteRowAtom(row(&["2", "b"]))).is_none());
+	assert!(relay.accept(Atom::RowBatchAtom(RowBatch::from_rows(&[row(&["3", "c"])]).unwrap())).is_none());
+}
+
+#[test]
+fn rows_that_cannot_share_a_batch_are_reported() {
+	assert!(matches!(batch_atom("ProjectRelay", &[row(&["1", "2"]), row(&["3", "4"])]), Atom::RowBatchAtom(b) if b.len() == 2));
+	match batch_atom("ProjectRelay", &[row(&["1", "2"]), row(&["3"])]) {
+		Atom::ErrorAtom(Error::General(message), _) => assert!(message.contains("dropped a batch of 2 rows")),
+		other                                       => panic!("expected an error, got {:?}", other),
+	}
+}
+
+#[test]
+fn a_configured_header_is_checked_at_initialize() {
+	let header = HEADER.join(", ");
+	for (key, value) in [(SELECT_KEY, "id, missing"), (SELECT_KEY, "#6"), (EXCLUDE_KEY, "nope"), (RENAME_KEY, "nope=x")] {
+		let config = MapRelayConfig::new().with(HEADER_KEY, &header).with(key, value);
+		assert!(matches!(ProjectRelay::new().initialize(&config), Err(Error::InvalidConfig(_))), "{} = {}", key, value);
+	}
+
+	let mut keyed = relay(MapRelayConfig::new().with(HEADER_KEY, &header).with(SELECT_KEY, "#5, name"));
+	assert_eq!(keyed.projection().unwrap().names, ["note", "name"]);
+	assert_eq!(fields(&keyed.accept(Atom::ByteRowAtom(row(&["1", "ann", "10", "9", "hi"]))).unwrap()), ["hi", "ann"]);
+}
+
+fn relay_with_rename(rename: &str) -> ProjectRelay {
+	relay(MapRelayConfig::new().with(RENAME_KEY, rename))
+}
+
+#[test]
+fn lists_and_patterns() {
+	assert_eq!(split_list(" a, \"b, c\" ,,\"d\"\"e\" "), ["a", "b, c", "d\"e"]);
+	assert!(glob_match("amount_*", "amount_usd"));
+	assert!(glob_match("*_?sd", "amount_usd"));
+	assert!(!glob_match("amount_*", "amounts"));
+	assert!(glob_match("*", ""));
+}
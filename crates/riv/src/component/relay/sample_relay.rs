@@ -0,0 +1,221 @@
+use tracing::info;
+use zero::component::telemetry::component_metrics::ComponentMetrics;
+use zero::component::telemetry::provides_metrics::ProvidesMetrics;
+use crate::Error;
+use crate::component::relay::{batch_atom, Collector, Relay, RelayConfig};
+use crate::model::ir::atom::Atom;
+use crate::model::ir::row_batch::RowBatch;
+use crate::utils::random::SplitMix64;
+
+/// Config key: rows to skip before any are kept.
+pub const SKIP_KEY:     &str = "skip";
+/// Config key: keep at most this many rows, the first that pass `skip` and `every`.
+pub const LIMIT_KEY:    &str = "limit";
+/// Config key: keep every Nth row after `skip`, starting with the first. Defaults to 1.
+pub const EVERY_KEY:    &str = "every";
+/// Config key: keep a random sample of this many rows, instead of a `limit`.
+pub const SAMPLE_KEY:   &str = "sample";
+/// Config key: seed of the random sample.
+pub const SEED_KEY:     &str = "seed";
+pub const DEFAULT_SEED: u64  = 0;
+
+/// What becomes of one row.
+enum Verdict {
+	Drop,
+	Pass,
+	/// Offered to the sample.
+	Hold,
+}
+
+/// Keeps a subset of the rows: a range, a stride, or a random sample.
+///
+/// Rows are counted in arrival order, the rows of a `RowBatchAtom` one by
+/// one. The first `skip` rows are dropped, then every `every`th of the
+/// rest is a candidate: with a `limit` the first candidates are kept, and
+/// with a `sample` a reservoir sample of that many candidates is drawn,
+/// seeded by `seed` so the same input always gives the same sample.
+/// Without either every candidate is kept, so `skip` and `every` alone
+/// thin a stream out.
+///
+/// Kept rows pass through as they arrive, except for a sample, which is
+/// emitted at `finish` in arrival order, with sampled rows of a batch as
+/// single byte rows; atoms that arrive once it has started to fill, such
+/// as `EndTask`, follow it. Headers and other
+/// atoms always pass. Once `is_done`, no further row can be kept, so a
+/// preview or a `head` command can stop reading its source. Rows left out
+/// are counted in the metrics' `dropped_count`.
+///
+pub struct SampleRelay {
+	skip:       u64,
+	every:      u64,
+	limit:      Option<u64>,
+	sample:     Option<usize>,
+	random:     SplitMix64,
+	seen:       u64,
+	kept:       u64,
+	candidates: u64,
+	reservoir:  Vec<(u64, Atom)>,
+	trailing:   Vec<Atom>,
+	metrics:    ComponentMetrics,
+}
+
+impl Default for SampleRelay {
+	fn default() -> Self { Self::new() }
+}
+
+impl SampleRelay {
+	pub fn new() -> Self {
+		SampleRelay {
+			skip:       0,
+			every:      1,
+			limit:      None,
+			sample:     None,
+			random:     SplitMix64::new(DEFAULT_SEED),
+			seen:       0,
+			kept:       0,
+			candidates: 0,
+			reservoir:  Vec::new(),
+			trailing:   Vec::new(),
+			metrics:    ComponentMetrics::default(),
+		}
+	}
+
+	/// The first `limit` rows, like `head`.
+	pub fn head(limit: u64) -> Self { Self::new().with_limit(limit) }
+
+	pub fn with_skip(mut self, skip: u64) -> Self {
+		self.skip = skip;
+		self
+	}
+
+	pub fn with_every(mut self, every: u64) -> Self {
+		self.every = every.max(1);
+		self
+	}
+
+	pub fn with_limit(mut self, limit: u64) -> Self {
+		self.limit = Some(limit);
+		self
+	}
+
+	pub fn with_sample(mut self, size: usize, seed: u64) -> Self {
+		self.sample = Some(size);
+		self.random = SplitMix64::new(seed);
+		self
+	}
+
+	/// Rows kept so far; for a sample, the candidates drawn from.
+	pub fn kept(&self) -> u64 { self.kept }
+
+	/// True once the limit is reached and no later row can be kept.
+	pub fn is_done(&self) -> bool { self.limit.is_some_and(|limit| self.kept >= limit) }
+
+	/// Whether the next row is a candidate, after `skip` and `every`.
+	fn candidate(&mut self) -> bool {
+		let position = self.seen;
+		self.seen += 1;
+		position >= self.skip && (position - self.skip).is_multiple_of(self.every)
+	}
+
+	/// Count one row and decide what becomes of it.
+	fn admit(&mut self) -> Verdict {
+		self.metrics.add_records(1);
+		if !self.candidate() || self.is_done() {
+			self.metrics.add_dropped(1);
+			return Verdict::Drop
+		}
+		self.kept += 1;
+		match self.sample {
+			Some(_) => Verdict::Hold,
+			None    => Verdict::Pass,
+		}
+	}
+
+	/// Offer a candidate to the reservoir, by Algorithm R.
+	fn hold(&mut self, atom: Atom) {
+		let size     = self.sample.unwrap_or_default();
+		let position = self.candidates;
+		self.candidates += 1;
+		if self.reservoir.len() < size {
+			self.reservoir.push((position, atom));
+		} else {
+			let slot = self.random.below(position + 1) as usize;
+			if slot < size { self.reservoir[slot] = (position, atom) }
+		}
+	}
+
+	fn batch(&mut self, batch: RowBatch, out: &mut dyn Collector) {
+		let mut rows = Vec::new();
+		for row in batch.to_rows() {
+			match self.admit() {
+				Verdict::Pass => rows.push(row),
+				Verdict::Hold => self.hold(Atom::ByteRowAtom(row)),
+				Verdict::Drop => {}
+			}
+		}
+		if !rows.is_empty() {
+			out.emit(batch_atom("SampleRelay", &rows));
+		}
+	}
+}
+
+impl Relay for SampleRelay {
+	fn initialize(&mut self, cfg: &dyn RelayConfig) -> Result<(), Error> {
+		let count = |key: &str, least: i32| match cfg.integer_value(key) {
+			Some(v) if v < least => Err(Error::InvalidConfig(format!("[SampleRelay]: {} must be at least {}, got {}", key, least, v))),
+			other                => Ok(other.map(|v| v as u64)),
+		};
+		if let Some(skip)  = count(SKIP_KEY, 0)?   { self.skip   = skip;                }
+		if let Some(every) = count(EVERY_KEY, 1)?  { self.every  = every;               }
+		if let Some(limit) = count(LIMIT_KEY, 0)?  { self.limit  = Some(limit);         }
+		if let Some(size)  = count(SAMPLE_KEY, 1)? { self.sample = Some(size as usize); }
+		if let Some(text) = cfg.string_value(SEED_KEY) {
+			let seed = text.trim().parse::<u64>()
+				.map_err(|_| Error::InvalidConfig(format!("[SampleRelay]: {} must be a whole number, got '{}'", SEED_KEY, text)))?;
+			self.random = SplitMix64::new(seed);
+		}
+		if self.limit.is_some() && self.sample.is_some() {
+			return Err(Error::InvalidConfig(format!("[SampleRelay]: '{}' and '{}' cannot be combined", LIMIT_KEY, SAMPLE_KEY)))
+		}
+		self.metrics.activate();
+		info!("[SampleRelay]: initialized with {}", cfg);
+		Ok(())
+	}
+
+	fn accept(&mut self, atom: Atom, out: &mut dyn Collector) {
+		self.metrics.increment_messages();
+		match atom {
+			Atom::ByteRowAtom(_) | Atom::StringRowAtom(_) | Atom::TypedRowAtom(_) => {
+				match self.admit() {
+					Verdict::Pass => out.emit(atom),
+					Verdict::Hold => self.hold(atom),
+					Verdict::Drop => {}
+				}
+			}
+			Atom::RowBatchAtom(batch)                     => if !batch.is_empty() { self.batch(batch, out) },
+			other if self.sample.is_some() && self.seen > 0 => self.trailing.push(other),
+			other                                         => out.emit(other),
+		}
+	}
+
+	fn finish(&mut self, out: &mut dyn Collector) -> Result<(), Error> {
+		let mut reservoir = std::mem::take(&mut self.reservoir);
+		reservoir.sort_unstable_by_key(|(position, _)| *position);
+		self.metrics.add_dropped(self.candidates.saturating_sub(reservoir.len() as u64));
+		reservoir.into_iter().for_each(|(_, atom)| out.emit(atom));
+		std::mem::take(&mut self.trailing).into_iter().for_each(|atom| out.emit(atom));
+		self.metrics.complete();
+		info!("[SampleRelay]: kept {} of {} rows", self.metrics.record_count - self.metrics.dropped_count, self.metrics.record_count);
+		Ok(())
+	}
+}
+
+impl ProvidesMetrics for SampleRelay {
+	fn metrics(&self) -> ComponentMetrics { self.metrics }
+
+	fn take_metrics(&mut self) -> ComponentMetrics {
+		let metrics = self.metrics;
+		self.metrics.reset();
+		metrics
+	}
+}
@@ -0,0 +1,95 @@
+use zero::component::telemetry::provides_metrics::ProvidesMetrics;
+use crate::Error;
+use crate::component::relay::Relay;
+use crate::component::relay::fixtures::initialized;
+use crate::component::relay::map_relay_config::MapRelayConfig;
+use crate::component::relay::sample_relay::{SampleRelay, EVERY_KEY, LIMIT_KEY, SAMPLE_KEY, SEED_KEY, SKIP_KEY};
+use crate::model::ir::atom::Atom;
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::row_batch::RowBatch;
+
+fn row(n: usize) -> ByteRow {
+	let text = n.to_string();
+	ByteRow::new(text.as_bytes(), &[text.len()])
+}
+
+fn relay(cfg: MapRelayConfig) -> SampleRelay {
+	initialized(SampleRelay::new(), &cfg)
+}
+
+/// Feed rows `0..count` and finish, returning the numbers of the rows kept.
+fn run(relay: &mut SampleRelay, count: usize) -> Vec<usize> {
+	let mut out = Vec::new();
+	for n in 0..count {
+		relay.accept(Atom::ByteRowAtom(row(n)), &mut out);
+	}
+	relay.finish(&mut out).unwrap();
+	out.iter().map(|atom| match atom {
+		Atom::ByteRowAtom(r) => r.as_string_row().iter_str().next().unwrap().parse().unwrap(),
+		other                => panic!("expected a row, got {:?}", other),
+	}).collect()
+}
+
+#[test]
+fn settings_are_checked() {
+	for (key, value) in [(SKIP_KEY, "-1"), (EVERY_KEY, "0"), (SAMPLE_KEY, "0"), (SEED_KEY, "abc")] {
+		assert!(matches!(SampleRelay::new().initialize(&MapRelayConfig::new().with(key, value)), Err(Error::InvalidConfig(_))), "{}", key);
+	}
+	let both = MapRelayConfig::new().with(LIMIT_KEY, "5").with(SAMPLE_KEY, "5");
+	assert!(matches!(SampleRelay::new().initialize(&both), Err(Error::InvalidConfig(_))));
+}
+
+#[test]
+fn skip_every_and_limit_select_a_range() {
+	assert_eq!(run(&mut relay(MapRelayConfig::new().with(LIMIT_KEY, "3")), 10), [0, 1, 2]);
+	assert_eq!(run(&mut relay(MapRelayConfig::new().with(SKIP_KEY, "8")), 10),  [8, 9]);
+	assert_eq!(run(&mut relay(MapRelayConfig::new().with(EVERY_KEY, "4")), 10), [0, 4, 8]);
+
+	let mut relay = relay(MapRelayConfig::new().with(SKIP_KEY, "1").with(EVERY_KEY, "3").with(LIMIT_KEY, "2"));
+	assert_eq!(run(&mut relay, 10), [1, 4]);
+	assert!(relay.is_done());
+	let metrics = relay.metrics();
+	assert_eq!((metrics.record_count, metrics.dropped_count), (10, 8));
+}
+
+#[test]
+fn head_stops_early_and_passes_other_atoms() {
+	let mut relay = SampleRelay::head(1);
+	let mut out   = Vec::new();
+	relay.accept(Atom::HeaderRow(row(0).as_string_row()), &mut out);
+	assert!(!relay.is_done());
+	relay.accept(Atom::RowBatchAtom(RowBatch::from_rows(&[row(1), row(2)]).unwrap()), &mut out);
+	assert!(relay.is_done());
+	relay.accept(Atom::EndTask, &mut out);
+	assert_eq!(out.len(), 3);
+	assert!(matches!(&out[1], Atom::RowBatchAtom(b) if b.len() == 1 && b.get(0, 0) == Some(b"1".as_slice())));
+	assert!(matches!(out[2], Atom::EndTask));
+}
+
+#[test]
+fn samples_are_reproducible_and_in_arrival_order() {
+	let sample = |seed: &str| run(&mut relay(MapRelayConfig::new().with(SAMPLE_KEY, "5").with(SEED_KEY, seed)), 1_000);
+	let first  = sample("7");
+	assert_eq!(first.len(), 5);
+	assert_eq!(first, sample("7"));
+	assert_ne!(first, sample("8"));
+	assert!(first.windows(2).all(|w| w[0] < w[1]));
+	assert!(first.iter().any(|&n| n >= 5), "expected the reservoir to be replaced, got {:?}", first);
+
+	assert_eq!(run(&mut relay(MapRelayConfig::new().with(SAMPLE_KEY, "5")), 3), [0, 1, 2]);     // Fewer rows than the sample
+}
+
+#[test]
+fn a_sample_holds_later_atoms_until_finish() {
+	let mut relay = relay(MapRelayConfig::new().with(SAMPLE_KEY, "2").with(SKIP_KEY, "1"));
+	let mut out   = Vec::new();
+	relay.accept(Atom::CommentRow("before".into()), &mut out);
+	relay.accept(Atom::RowBatchAtom(RowBatch::from_rows(&[row(0), row(1), row(2)]).unwrap()), &mut out);
+	relay.accept(Atom::EndTask, &mut out);
+	assert_eq!(out.len(), 1);
+	relay.finish(&mut out).unwrap();
+	assert_eq!(out.len(), 4);
+	assert!(matches!(&out[1], Atom::ByteRowAtom(r) if r.get(0) == Some(b"1".as_slice())));
+	assert!(matches!(out[3], Atom::EndTask));
+	assert_eq!(relay.metrics().dropped_count, 1);
+}
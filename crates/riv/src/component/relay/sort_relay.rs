@@ -0,0 +1,420 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::rc::Rc;
+use chrono::NaiveDateTime;
+use tracing::{info, warn};
+use zero::component::telemetry::component_metrics::ComponentMetrics;
+use zero::component::telemetry::provides_metrics::ProvidesMetrics;
+use crate::Error;
+use crate::component::relay::{BindsColumns, Collector, ColumnBinding, Relay, RelayConfig};
+use crate::model::coordinate::coordinate::Coordinate;
+use crate::model::expression::Fields;
+use crate::model::expression::ast::number;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::value::{Value, ValueType};
+use crate::utils::spill::RowSpill;
+
+/// Config key: sort keys, most significant first, each `column [asc|desc] [string|numeric|date]`,
+/// e.g. `region, amount desc numeric, opened date`.
+pub const SORT_KEY:              &str  = "sort";
+/// Config key: approximate bytes of rows held in memory before a sorted run is spilled to disk.
+pub const MEMORY_BUDGET_KEY:     &str  = "memory_budget";
+pub const DEFAULT_MEMORY_BUDGET: usize = 256 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Direction {
+	#[default]
+	Ascending,
+	Descending,
+}
+
+/// How the values of a sort key are compared.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Comparison {
+	/// By text, byte for byte.
+	#[default]
+	String,
+	/// As numbers.
+	Numeric,
+	/// As dates or timestamps.
+	Date,
+}
+
+/// One column to sort by.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SortKey {
+	pub column:     String,
+	pub direction:  Direction,
+	pub comparison: Comparison,
+}
+
+impl SortKey {
+	pub fn new(column: &str, direction: Direction, comparison: Comparison) -> Self {
+		SortKey{column: column.to_owned(), direction, comparison}
+	}
+
+	/// Parse `column [asc|desc] [string|numeric|date]`. Modifiers are read
+	/// from the end, so a column name may contain spaces.
+	pub fn parse(text: &str) -> Result<SortKey, Error> {
+		let mut key  = SortKey::new("", Direction::Ascending, Comparison::String);
+		let mut rest = text.trim();
+		while let Some((head, last)) = rest.rsplit_once(char::is_whitespace) {
+			match last.to_ascii_lowercase().as_str() {
+				"asc"                => key.direction  = Direction::Ascending,
+				"desc"               => key.direction  = Direction::Descending,
+				"string" | "text"    => key.comparison = Comparison::String,
+				"numeric" | "number" => key.comparison = Comparison::Numeric,
+				"date"               => key.comparison = Comparison::Date,
+				_                    => break,
+			}
+			rest = head.trim_end();
+		}
+		if rest.is_empty() {
+			return Err(Error::InvalidConfig(format!("[SortRelay]: '{}' names no column", text)))
+		}
+		key.column = rest.to_owned();
+		Ok(key)
+	}
+}
+
+/// A sort key resolved against a header.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Bound {
+	index:      usize,
+	direction:  Direction,
+	comparison: Comparison,
+}
+
+/// The value of one sort key in one row. Values that are empty, or do not
+/// read as the key's comparison, are `Null` and sort last either way.
+#[derive(Clone, Debug)]
+enum KeyValue {
+	Null,
+	Text(String),
+	Number(f64),
+	Time(NaiveDateTime),
+}
+
+impl KeyValue {
+	fn of(value: Value, comparison: Comparison) -> KeyValue {
+		match (comparison, value) {
+			(_, Value::Null)                          => KeyValue::Null,
+			(Comparison::String, value)               => KeyValue::Text(value.to_string()),
+			(Comparison::Numeric, Value::String(s))   => number(&s).and_then(|n| n.as_f64()).map_or(KeyValue::Null, KeyValue::Number),
+			(Comparison::Numeric, value)              => value.as_f64().map_or(KeyValue::Null, KeyValue::Number),
+			(Comparison::Date, Value::Date(d))        => KeyValue::Time(d.and_time(Default::default())),
+			(Comparison::Date, Value::Timestamp(t))   => KeyValue::Time(t),
+			(Comparison::Date, Value::String(s))      => match Value::parse(&s, ValueType::Date).or_else(|_| Value::parse(&s, ValueType::Timestamp)) {
+				Ok(value) if !value.is_null() => KeyValue::of(value, Comparison::Date),
+				_                             => KeyValue::Null,
+			},
+			(Comparison::Date, _)                     => KeyValue::Null,
+		}
+	}
+
+	fn size(&self) -> usize {
+		size_of::<KeyValue>() + if let KeyValue::Text(s) = self { s.len() } else { 0 }
+	}
+
+	/// Ascending order; nulls last.
+	fn compare(&self, other: &KeyValue) -> Ordering {
+		match (self, other) {
+			(KeyValue::Null, KeyValue::Null)           => Ordering::Equal,
+			(KeyValue::Null, _)                        => Ordering::Greater,
+			(_, KeyValue::Null)                        => Ordering::Less,
+			(KeyValue::Text(a), KeyValue::Text(b))     => a.cmp(b),
+			(KeyValue::Number(a), KeyValue::Number(b)) => a.total_cmp(b),
+			(KeyValue::Time(a), KeyValue::Time(b))     => a.cmp(b),
+			_                                          => Ordering::Equal,       // One key has one comparison
+		}
+	}
+}
+
+fn compare(a: &[KeyValue], b: &[KeyValue], bound: &[Bound]) -> Ordering {
+	for ((a, b), key) in a.iter().zip(b).zip(bound) {
+		let order = match (a, b, key.direction) {
+			(KeyValue::Null, _, _) | (_, KeyValue::Null, _) => a.compare(b),
+			(_, _, Direction::Ascending)                    => a.compare(b),
+			(_, _, Direction::Descending)                   => b.compare(a),
+		};
+		if order != Ordering::Equal { return order }
+	}
+	Ordering::Equal
+}
+
+fn keys_of(atom: &Atom, bound: &[Bound]) -> Vec<KeyValue> {
+	let extract = |row: &dyn Fields| bound.iter().map(|key| KeyValue::of(row.value(key.index), key.comparison)).collect();
+	match atom {
+		Atom::ByteRowAtom(row)   => extract(row),
+		Atom::StringRowAtom(row) => extract(row),
+		Atom::TypedRowAtom(row)  => extract(row),
+		_                        => Vec::new(),
+	}
+}
+
+/// Rough bytes an atom takes in memory.
+fn footprint(atom: &Atom) -> usize {
+	size_of::<Atom>() + match atom {
+		Atom::ByteRowAtom(row)   => row.data().len() + row.length() as usize * size_of::<usize>(),
+		Atom::StringRowAtom(row) => row.iter_str().map(|s| s.len() + size_of::<String>()).sum(),
+		Atom::TypedRowAtom(row)  => row.iter().map(|v| size_of::<Value>() + if let Value::String(s) = v { s.len() } else { 0 }).sum(),
+		_                        => 0,
+	}
+}
+
+/// Sorts the rows of a stream, spilling to disk when they outgrow memory.
+///
+/// Rows are held until `finish`, which emits them in order. Up to
+/// `memory_budget` bytes of rows are sorted in memory; beyond that each
+/// full buffer is written to a temporary file as a sorted run, and
+/// `finish` merges the runs as it emits them. The sort is stable, so rows
+/// with equal keys keep their arrival order.
+///
+/// Sort keys are bound to the first `HeaderRow` only, as `ColumnBinding`
+/// describes. Atoms before the first row pass straight through; any other
+/// atom that arrives once rows are held back, such as `EndTask`, follows
+/// the sorted rows. The rows of a `RowBatchAtom` are sorted with the rest
+/// and come out as byte rows.
+///
+pub struct SortRelay {
+	keys:         Vec<SortKey>,
+	budget:       usize,
+	columns:      ColumnBinding<Rc<[Bound]>>,
+	buffer:       Vec<(Vec<KeyValue>, Atom)>,
+	held:         usize,
+	runs:         Vec<RowSpill>,
+	spill_failed: bool,
+	trailing:     Vec<Atom>,
+	metrics:      ComponentMetrics,
+}
+
+impl Default for SortRelay {
+	fn default() -> Self { Self::new() }
+}
+
+impl SortRelay {
+	pub fn new() -> Self {
+		SortRelay {
+			keys:         Vec::new(),
+			budget:       DEFAULT_MEMORY_BUDGET,
+			columns:      ColumnBinding::default(),
+			buffer:       Vec::new(),
+			held:         0,
+			runs:         Vec::new(),
+			spill_failed: false,
+			trailing:     Vec::new(),
+			metrics:      ComponentMetrics::default(),
+		}
+	}
+
+	pub fn with_key(mut self, key: SortKey) -> Self {
+		self.keys.push(key);
+		self
+	}
+
+	pub fn with_memory_budget(mut self, bytes: usize) -> Self {
+		self.budget = bytes.max(1);
+		self
+	}
+
+	pub fn keys(&self) -> &[SortKey] { &self.keys }
+
+	/// Sorted runs written to disk so far.
+	pub fn runs(&self) -> usize { self.runs.len() }
+
+	fn hold(&mut self, bound: &[Bound], atom: Atom) {
+		let keys = keys_of(&atom, bound);
+		self.metrics.add_records(1);
+		self.held += footprint(&atom) + keys.iter().map(KeyValue::size).sum::<usize>();
+		self.buffer.push((keys, atom));
+		if self.held >= self.budget && !self.spill_failed && let Err(error) = self.spill(bound) {
+			warn!("[SortRelay]: cannot spill rows to disk, keeping them in memory: {}", error);
+			self.metrics.increment_errors();
+			self.spill_failed = true;
+		}
+	}
+
+	/// Write the buffer to disk as one sorted run.
+	fn spill(&mut self, bound: &[Bound]) -> Result<(), Error> {
+		self.buffer.sort_by(|a, b| compare(&a.0, &b.0, bound));
+		let mut run = RowSpill::new()?;
+		for (_, atom) in &self.buffer {
+			run.push(atom)?;
+		}
+		self.runs.push(run);
+		self.buffer.clear();
+		self.held = 0;
+		Ok(())
+	}
+
+	/// The held rows in order: the buffer sorted in memory, merged with any runs on disk.
+	fn sorted(&mut self) -> Result<Box<dyn Iterator<Item = Atom>>, Error> {
+		let bound      = self.columns.get().cloned().unwrap_or_else(|| Rc::from(Vec::new()));
+		let mut buffer = std::mem::take(&mut self.buffer);
+		buffer.sort_by(|a, b| compare(&a.0, &b.0, &bound));
+		let memory = buffer.into_iter().map(|(_, atom)| atom);
+		if self.runs.is_empty() { return Ok(Box::new(memory)) }
+
+		let mut sources = Vec::<Box<dyn Iterator<Item = Result<Atom, Error>>>>::new();
+		for run in std::mem::take(&mut self.runs) {
+			sources.push(Box::new(run.into_reader()?));
+		}
+		sources.push(Box::new(memory.map(Ok)));       // The newest rows, so last for stability
+		Ok(Box::new(Merge::new(sources, bound)))
+	}
+}
+
+impl BindsColumns for SortRelay {
+	type Bound = Rc<[Bound]>;
+
+	fn resolve<S: AsRef<str>>(&mut self, names: &[S]) -> Result<Rc<[Bound]>, Error> {
+		self.keys.iter()
+			.map(|key| names.iter().position(|n| n.as_ref() == key.column)
+				.map(|index| Bound{index, direction: key.direction, comparison: key.comparison})
+				.ok_or_else(|| Error::InvalidConfig(format!("[SortRelay]: no sort column '{}' in the header", key.column))))
+			.collect()
+	}
+
+	fn binding(&mut self) -> &mut ColumnBinding<Rc<[Bound]>> { &mut self.columns }
+}
+
+impl Relay for SortRelay {
+	fn initialize(&mut self, cfg: &dyn RelayConfig) -> Result<(), Error> {
+		for item in cfg.list_value(SORT_KEY).unwrap_or_default() {
+			self.keys.push(SortKey::parse(&item)?);
+		}
+		if self.keys.is_empty() {
+			return Err(Error::InvalidConfig(format!("[SortRelay]: '{}' is required", SORT_KEY)))
+		}
+		self.budget = match cfg.integer_value(MEMORY_BUDGET_KEY) {
+			Some(v) if v < 1 => return Err(Error::InvalidConfig(format!("[SortRelay]: {} must be positive, got {}", MEMORY_BUDGET_KEY, v))),
+			Some(v)          => v as usize,
+			None             => self.budget,
+		};
+		self.metrics.activate();
+		info!("[SortRelay]: initialized with {}", cfg);
+		Ok(())
+	}
+
+	fn accept(&mut self, atom: Atom, out: &mut dyn Collector) {
+		self.metrics.increment_messages();
+		let coordinate = atom.coordinate();
+		let started    = !self.buffer.is_empty() || !self.runs.is_empty();
+		match atom {
+			Atom::HeaderRow(header) if !started && self.columns.get().is_none() => {
+				let names = header.iter_str().collect::<Vec<_>>();
+				match self.bind(&names) {
+					Ok(())     => out.emit(Atom::HeaderRow(header)),
+					Err(error) => out.emit(Atom::ErrorAtom(error, coordinate)),
+				}
+			}
+			Atom::ByteRowAtom(_) | Atom::StringRowAtom(_) | Atom::TypedRowAtom(_) => {
+				let width = match &atom {
+					Atom::ByteRowAtom(row)   => row.length() as usize,
+					Atom::StringRowAtom(row) => row.count() as usize,
+					Atom::TypedRowAtom(row)  => row.count() as usize,
+					_                        => 0,
+				};
+				match self.bound(width) {
+					Some(bound) => self.hold(&bound, atom),
+					None        => self.columns.reject_into(coordinate, out),
+				}
+			}
+			Atom::RowBatchAtom(batch) => {
+				if batch.is_empty() { return }
+				let Some(bound) = self.bound(batch.width()) else { return self.columns.reject_into(coordinate, out) };
+				for row in batch.to_rows() {
+					self.hold(&bound, Atom::ByteRowAtom(row));
+				}
+			}
+			other if started => self.trailing.push(other),
+			other            => out.emit(other),
+		}
+	}
+
+	fn finish(&mut self, out: &mut dyn Collector) -> Result<(), Error> {
+		let sorted = self.sorted().inspect_err(|error| {
+			warn!("[SortRelay]: cannot read sorted runs back: {}", error);
+			self.metrics.increment_errors();
+			self.metrics.fail();
+		})?;
+		sorted.chain(std::mem::take(&mut self.trailing)).for_each(|atom| out.emit(atom));
+		self.metrics.complete();
+		info!("[SortRelay]: sorted {} rows", self.metrics.record_count);
+		Ok(())
+	}
+}
+
+impl ProvidesMetrics for SortRelay {
+	fn metrics(&self) -> ComponentMetrics { self.metrics }
+
+	fn take_metrics(&mut self) -> ComponentMetrics {
+		let metrics = self.metrics;
+		self.metrics.reset();
+		metrics
+	}
+}
+
+/// The head row of one sorted run. Heads order so that `BinaryHeap`, a
+/// max-heap, pops the smallest; ties go to the earlier run.
+struct Head {
+	keys:  Vec<KeyValue>,
+	run:   usize,
+	atom:  Atom,
+	bound: Rc<[Bound]>,
+}
+
+impl Ord for Head {
+	fn cmp(&self, other: &Self) -> Ordering {
+		compare(&other.keys, &self.keys, &self.bound).then(other.run.cmp(&self.run))
+	}
+}
+
+impl PartialOrd for Head {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl PartialEq for Head {
+	fn eq(&self, other: &Self) -> bool { self.cmp(other) == Ordering::Equal }
+}
+
+impl Eq for Head {}
+
+/// K-way merge of sorted runs. A run that cannot be read yields one
+/// `ErrorAtom` and ends.
+struct Merge {
+	readers: Vec<Box<dyn Iterator<Item = Result<Atom, Error>>>>,
+	heap:    BinaryHeap<Head>,
+	errors:  Vec<Atom>,
+	bound:   Rc<[Bound]>,
+}
+
+impl Merge {
+	fn new(readers: Vec<Box<dyn Iterator<Item = Result<Atom, Error>>>>, bound: Rc<[Bound]>) -> Self {
+		let mut merge = Merge{readers, heap: BinaryHeap::new(), errors: Vec::new(), bound};
+		(0..merge.readers.len()).for_each(|run| merge.advance(run));
+		merge
+	}
+
+	fn advance(&mut self, run: usize) {
+		match self.readers[run].next() {
+			Some(Ok(atom))   => {
+				let keys = keys_of(&atom, &self.bound);
+				self.heap.push(Head{keys, run, atom, bound: Rc::clone(&self.bound)});
+			}
+			Some(Err(error)) => self.errors.push(Atom::ErrorAtom(error, Coordinate::Undefined)),
+			None             => {}
+		}
+	}
+}
+
+impl Iterator for Merge {
+	type Item = Atom;
+
+	fn next(&mut self) -> Option<Atom> {
+		if let Some(error) = self.errors.pop() { return Some(error) }
+		let head = self.heap.pop()?;
+		self.advance(head.run);
+		Some(head.atom)
+	}
+}
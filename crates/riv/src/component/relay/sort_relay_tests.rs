@@ -0,0 +1,91 @@
+use crate::Error;
+use crate::component::relay::Relay;
+use crate::component::relay::fixtures::{fields, finish, header, initialized, one, row};
+use crate::component::relay::map_relay_config::MapRelayConfig;
+use crate::component::relay::sort_relay::{Comparison, Direction, SortKey, SortRelay, MEMORY_BUDGET_KEY, SORT_KEY};
+use crate::model::ir::atom::Atom;
+use crate::model::ir::row_batch::RowBatch;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+
+fn relay(cfg: MapRelayConfig) -> SortRelay {
+	initialized(SortRelay::new(), &cfg)
+}
+
+/// Feed `rows`, finish, and return the first field of each row drained.
+fn sort(relay: &mut SortRelay, rows: &[&[&str]]) -> Vec<String> {
+	for fields in rows {
+		assert!(one(relay, Atom::ByteRowAtom(row(fields))).is_none());
+	}
+	finish(relay).iter().map(|atom| fields(atom)[0].clone()).collect()
+}
+
+#[test]
+fn sort_keys_parse() {
+	assert_eq!(SortKey::parse("name").unwrap(),                  SortKey::new("name", Direction::Ascending, Comparison::String));
+	assert_eq!(SortKey::parse(" amount DESC numeric ").unwrap(), SortKey::new("amount", Direction::Descending, Comparison::Numeric));
+	assert_eq!(SortKey::parse("opened on date desc").unwrap(),   SortKey::new("opened on", Direction::Descending, Comparison::Date));
+	assert!(matches!(SortKey::parse(""), Err(Error::InvalidConfig(_))));
+	assert!(matches!(SortRelay::new().initialize(&MapRelayConfig::new()), Err(Error::InvalidConfig(_))));
+	assert!(matches!(SortRelay::new().initialize(&MapRelayConfig::new().with(SORT_KEY, "a").with(MEMORY_BUDGET_KEY, "0")), Err(Error::InvalidConfig(_))));
+}
+
+#[test]
+fn sorts_by_several_keys_with_nulls_last() {
+	let mut relay = relay(MapRelayConfig::new().with(SORT_KEY, "region, amount desc numeric"));
+	assert!(matches!(one(&mut relay, header(&["id", "region", "amount"])), Some(Atom::HeaderRow(_))));
+	let rows: &[&[&str]] = &[&["1", "west", "9"], &["2", "east", "10"], &["3", "east", "9.5"], &["4", "", "1"], &["5", "east", ""], &["6", "west", "100"]];
+	assert_eq!(sort(&mut relay, rows), ["2", "3", "5", "6", "1", "4"]);
+}
+
+#[test]
+fn compares_as_text_numbers_or_dates() {
+	let rows: &[&[&str]] = &[&["10", "2024-02-01"], &["9", "2023-12-31 23:00:00"], &["100", "2024-01-15"]];
+	assert_eq!(sort(&mut relay(MapRelayConfig::new().with(SORT_KEY, "column_1")), rows), ["10", "100", "9"]);
+	assert_eq!(sort(&mut relay(MapRelayConfig::new().with(SORT_KEY, "column_1 numeric")), rows), ["9", "10", "100"]);
+	assert_eq!(sort(&mut relay(MapRelayConfig::new().with(SORT_KEY, "column_2 date desc")), rows), ["10", "100", "9"]);
+
+	let mut relay = relay(MapRelayConfig::new().with(SORT_KEY, "column_1 numeric desc"));
+	one(&mut relay, Atom::TypedRowAtom(TypedRow::new(vec![Value::Integer(2)])));
+	one(&mut relay, Atom::TypedRowAtom(TypedRow::new(vec![Value::Float(2.5)])));
+	one(&mut relay, Atom::TypedRowAtom(TypedRow::new(vec![Value::Null])));
+	assert_eq!(finish(&mut relay).iter().map(|a| fields(a)[0].clone()).collect::<Vec<_>>(), ["2.5", "2", ""]);
+}
+
+#[test]
+fn spilled_runs_merge_stably() {
+	let mut relay = relay(MapRelayConfig::new().with(SORT_KEY, "column_2 numeric").with(MEMORY_BUDGET_KEY, "4000"));
+	let rows  = (0..500).map(|n| [n.to_string(), ((n * 7919) % 50).to_string()]).collect::<Vec<_>>();
+	let input = rows.iter().map(|r| [r[0].as_str(), r[1].as_str()]).collect::<Vec<_>>();
+	for fields in &input {
+		assert!(one(&mut relay, Atom::ByteRowAtom(row(fields))).is_none());
+	}
+	assert!(relay.runs() > 2, "expected several runs, got {}", relay.runs());
+	let sorted   = finish(&mut relay).iter().map(fields).collect::<Vec<_>>();
+	let mut want = rows.iter().map(|r| r.to_vec()).collect::<Vec<_>>();
+	want.sort_by_key(|r| r[1].parse::<u32>().unwrap());                                   // Stable, like the relay
+	assert_eq!(sorted, want);
+}
+
+#[test]
+fn batches_are_sorted_and_trailing_atoms_follow() {
+	let mut relay = relay(MapRelayConfig::new().with(SORT_KEY, "name desc"));
+	assert!(matches!(one(&mut relay, Atom::CommentRow("first".into())), Some(Atom::CommentRow(_))));
+	one(&mut relay, header(&["name"]));
+	let batch = RowBatch::from_rows(&[row(&["ann"]), row(&["cy"])]).unwrap();
+	assert!(one(&mut relay, Atom::RowBatchAtom(batch)).is_none());
+	assert!(one(&mut relay, Atom::ByteRowAtom(row(&["bo"]))).is_none());
+	assert!(one(&mut relay, Atom::EndTask).is_none());
+	let out = finish(&mut relay);
+	assert_eq!(out.len(), 4);
+	assert_eq!(out[..3].iter().map(|a| fields(a)[0].clone()).collect::<Vec<_>>(), ["cy", "bo", "ann"]);
+	assert!(matches!(out[3], Atom::EndTask));
+}
+
+#[test]
+fn a_header_without_the_key_drops_rows() {
+	let mut relay = relay(MapRelayConfig::new().with(SORT_KEY, "amount"));
+	assert!(matches!(one(&mut relay, header(&["id"])), Some(Atom::ErrorAtom(..))));
+	assert!(one(&mut relay, Atom::ByteRowAtom(row(&["1"]))).is_none());
+	assert!(finish(&mut relay).is_empty());
+}
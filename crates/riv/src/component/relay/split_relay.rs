@@ -0,0 +1,379 @@
+use regex::Regex;
+use tracing::info;
+use zero::component::telemetry::component_metrics::ComponentMetrics;
+use zero::component::telemetry::provides_metrics::ProvidesMetrics;
+use crate::Error;
+use crate::component::relay::{batch_atom, BindsColumns, Collector, ColumnBinding, Relay, RelayConfig};
+use crate::model::coordinate::coordinate::Coordinate;
+use crate::model::expression::Fields;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::row_batch::RowBatch;
+use crate::model::ir::string_row::StringRow;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+
+/// Config key: `split` (into columns), `explode` (into rows) or `extract` (regex groups into columns).
+pub const MODE_KEY:          &str = "mode";
+/// Config key: the column to split or match.
+pub const COLUMN_KEY:        &str = "column";
+/// Config key: what separates the items of a value. Defaults to `;`.
+pub const DELIMITER_KEY:     &str = "delimiter";
+/// Config key: names of the columns `split` produces.
+pub const NAMES_KEY:         &str = "names";
+/// Config key: how many columns `split` produces, named `column_1`, `column_2`, … after the column.
+pub const PARTS_KEY:         &str = "parts";
+/// Config key: a regular expression whose named groups become columns.
+pub const PATTERN_KEY:       &str = "pattern";
+/// Config key: keep the source column next to the new ones. Defaults to true.
+pub const KEEP_KEY:          &str = "keep";
+/// Config key: trim the items of a split. Defaults to true.
+pub const TRIM_KEY:          &str = "trim";
+/// Config key: report values that do not fit, and drop their rows. Defaults to false.
+pub const STRICT_KEY:        &str = "strict";
+pub const DEFAULT_DELIMITER: &str = ";";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+	/// One value into several columns.
+	#[default]
+	Split,
+	/// One value into several rows.
+	Explode,
+	/// Named capture groups into columns.
+	Extract,
+}
+
+impl Mode {
+	pub fn parse(text: &str) -> Result<Mode, Error> {
+		match text.trim().to_ascii_lowercase().as_str() {
+			"split"   => Ok(Mode::Split),
+			"explode" => Ok(Mode::Explode),
+			"extract" => Ok(Mode::Extract),
+			other     => Err(Error::InvalidConfig(format!("[SplitRelay]: mode must be split, explode or extract, got '{}'", other))),
+		}
+	}
+}
+
+/// Where each output field comes from.
+enum Cell {
+	/// The input field at this position.
+	Field(usize),
+	/// A new value; `None` is null.
+	New(Option<String>),
+}
+
+/// Splits packed values of one column into columns or rows, or extracts
+/// parts of them with a regular expression.
+///
+/// `split` cuts the value at each `delimiter` into the columns named by
+/// `names`, or `parts` columns named after the source column; the last
+/// column takes whatever is left, and columns without a part are null.
+/// `explode` emits one row per item in place of the value, and a null
+/// value keeps its row. `extract` matches `pattern` anywhere in the value
+/// and fills one column per named group, such as `(?P<year>\d{4})`, with
+/// null for a group that did not take part. Items are trimmed unless
+/// `trim` is false.
+///
+/// New columns follow the source column, which is kept unless `keep` is
+/// false, and the `HeaderRow` is rewritten to match; `explode` leaves the
+/// header as it is. A value that does not fit, with more or fewer parts
+/// than columns or no match, fills the columns as above unless `strict`,
+/// in which case it is reported as an `ErrorAtom` at the row's
+/// coordinate and the row is dropped. Null values always fit.
+///
+/// The column is bound as `ColumnBinding` describes. Dropped rows are
+/// counted in the metrics' `dropped_count` and reported values in
+/// `error_count`.
+///
+pub struct SplitRelay {
+	mode:      Mode,
+	column:    String,
+	delimiter: String,
+	names:     Vec<String>,
+	pattern:   Option<Regex>,
+	keep:      bool,
+	trim:      bool,
+	strict:    bool,
+	index:     ColumnBinding<usize>,
+	metrics:   ComponentMetrics,
+}
+
+impl Default for SplitRelay {
+	fn default() -> Self { Self::new() }
+}
+
+impl SplitRelay {
+	pub fn new() -> Self {
+		SplitRelay {
+			mode:      Mode::Split,
+			column:    String::new(),
+			delimiter: DEFAULT_DELIMITER.to_owned(),
+			names:     Vec::new(),
+			pattern:   None,
+			keep:      true,
+			trim:      true,
+			strict:    false,
+			index:     ColumnBinding::default(),
+			metrics:   ComponentMetrics::default(),
+		}
+	}
+
+	pub fn split(column: &str, delimiter: &str, names: &[&str]) -> Self {
+		let mut relay   = Self::new();
+		relay.column    = column.to_owned();
+		relay.delimiter = delimiter.to_owned();
+		relay.names     = names.iter().map(|n| n.to_string()).collect();
+		relay
+	}
+
+	pub fn explode(column: &str, delimiter: &str) -> Self {
+		let mut relay   = Self::split(column, delimiter, &[]);
+		relay.mode      = Mode::Explode;
+		relay
+	}
+
+	pub fn extract(column: &str, pattern: Regex) -> Self {
+		let mut relay = Self::new();
+		relay.mode    = Mode::Extract;
+		relay.column  = column.to_owned();
+		relay.names   = group_names(&pattern);
+		relay.pattern = Some(pattern);
+		relay
+	}
+
+	pub fn with_keep(mut self, keep: bool) -> Self {
+		self.keep = keep;
+		self
+	}
+
+	pub fn with_strict(mut self, strict: bool) -> Self {
+		self.strict = strict;
+		self
+	}
+
+	pub fn mode(&self) -> Mode { self.mode }
+
+	/// Names of the columns added after the source column.
+	pub fn names(&self) -> &[String] { &self.names }
+
+	fn check(&self) -> Result<(), Error> {
+		if self.column.is_empty() {
+			return Err(Error::InvalidConfig(format!("[SplitRelay]: '{}' is required", COLUMN_KEY)))
+		}
+		match self.mode {
+			Mode::Split | Mode::Explode if self.delimiter.is_empty() => Err(Error::InvalidConfig(format!("[SplitRelay]: '{}' cannot be empty", DELIMITER_KEY))),
+			Mode::Split if self.names.is_empty()                     => Err(Error::InvalidConfig(format!("[SplitRelay]: split needs '{}' or '{}'", NAMES_KEY, PARTS_KEY))),
+			Mode::Extract if self.names.is_empty()                   => Err(Error::InvalidConfig(format!("[SplitRelay]: extract needs a '{}' with named groups", PATTERN_KEY))),
+			_                                                        => Ok(()),
+		}
+	}
+
+	/// Drop a row without the column.
+	fn reject(&mut self, coordinate: Coordinate, out: &mut dyn Collector) {
+		self.metrics.add_dropped(1);
+		self.index.reject_into(coordinate, out);
+	}
+
+	fn header(&self, names: Vec<String>, index: usize) -> Vec<String> {
+		if self.mode == Mode::Explode { return names }
+		let (before, after) = names.split_at(index.min(names.len()));
+		let kept            = after.first().filter(|_| self.keep).cloned();
+		before.iter().cloned()
+			.chain(kept)
+			.chain(self.names.iter().cloned())
+			.chain(after.iter().skip(1).cloned())
+			.collect()
+	}
+
+	fn item(&self, text: &str) -> String {
+		match self.trim {
+			true  => text.trim().to_owned(),
+			false => text.to_owned(),
+		}
+	}
+
+	/// The new values for one input value: one list per output row, or
+	/// `Err` with why it does not fit.
+	fn shape(&self, value: Option<&str>) -> Result<Vec<Vec<Option<String>>>, String> {
+		let Some(text) = value else {
+			return Ok(vec![vec![None; if self.mode == Mode::Explode { 1 } else { self.names.len() }]])
+		};
+		match self.mode {
+			Mode::Split   => {
+				let parts = text.split(self.delimiter.as_str()).count();
+				if self.strict && parts != self.names.len() {
+					return Err(format!("has {} parts, expected {}", parts, self.names.len()))
+				}
+				let mut items = text.splitn(self.names.len(), self.delimiter.as_str()).map(|p| Some(self.item(p))).collect::<Vec<_>>();
+				items.resize(self.names.len(), None);
+				Ok(vec![items])
+			}
+			Mode::Explode => Ok(text.split(self.delimiter.as_str()).map(|p| vec![Some(self.item(p))]).collect()),
+			Mode::Extract => {
+				let pattern = self.pattern.as_ref().ok_or_else(|| "has no pattern to match".to_owned())?;
+				match pattern.captures(text) {
+					Some(captures) => Ok(vec![self.names.iter().map(|name| captures.name(name).map(|m| m.as_str().to_owned())).collect()]),
+					None if self.strict => Err(format!("does not match /{}/", pattern)),
+					None                => Ok(vec![vec![None; self.names.len()]]),
+				}
+			}
+		}
+	}
+
+	/// The output rows for a row `width` fields wide, or none when it is dropped.
+	fn rows(&mut self, row: &dyn Fields, width: usize, index: usize, coordinate: Coordinate, out: &mut dyn Collector) -> Vec<Vec<Cell>> {
+		self.metrics.add_records(1);
+		let value = row.value(index);
+		let text  = (!value.is_null()).then(|| value.to_string());
+		match self.shape(text.as_deref()) {
+			Ok(shapes) => shapes.into_iter().map(|values| self.layout(width, index, values)).collect(),
+			Err(why)   => {
+				let error = Error::InvalidInput(format!("[SplitRelay]: column '{}' value '{}' {}", self.column, text.unwrap_or_default(), why));
+				self.metrics.increment_errors();
+				self.metrics.add_dropped(1);
+				out.emit(Atom::ErrorAtom(error, coordinate));
+				Vec::new()
+			}
+		}
+	}
+
+	fn layout(&self, width: usize, index: usize, values: Vec<Option<String>>) -> Vec<Cell> {
+		let keep = self.keep && self.mode != Mode::Explode;
+		(0..index).map(Cell::Field)
+			.chain(keep.then_some(Cell::Field(index)))
+			.chain(values.into_iter().map(Cell::New))
+			.chain((index + 1..width).map(Cell::Field))
+			.collect()
+	}
+
+	fn batch(&mut self, batch: RowBatch, index: usize, out: &mut dyn Collector) {
+		let mut rows = Vec::new();
+		for row in batch.to_rows() {
+			let width = row.length() as usize;
+			for cells in self.rows(&row, width, index, row.coordinate(), out) {
+				rows.push(byte_row(&row, &cells));
+			}
+		}
+		if !rows.is_empty() {
+			out.emit(batch_atom("SplitRelay", &rows));
+		}
+	}
+}
+
+impl BindsColumns for SplitRelay {
+	type Bound = usize;
+
+	fn resolve<S: AsRef<str>>(&mut self, names: &[S]) -> Result<usize, Error> {
+		names.iter().position(|n| n.as_ref() == self.column)
+			.ok_or_else(|| Error::InvalidConfig(format!("[SplitRelay]: no column '{}' in the header", self.column)))
+	}
+
+	fn binding(&mut self) -> &mut ColumnBinding<usize> { &mut self.index }
+}
+
+impl Relay for SplitRelay {
+	fn initialize(&mut self, cfg: &dyn RelayConfig) -> Result<(), Error> {
+		if let Some(text) = cfg.string_value(MODE_KEY)      { self.mode      = Mode::parse(&text)?;    }
+		if let Some(text) = cfg.string_value(COLUMN_KEY)    { self.column    = text.trim().to_owned(); }
+		if let Some(text) = cfg.string_value(DELIMITER_KEY) { self.delimiter = text;                   }
+		if let Some(keep) = cfg.bool_value(KEEP_KEY)        { self.keep      = keep;                   }
+		if let Some(trim) = cfg.bool_value(TRIM_KEY)        { self.trim      = trim;                   }
+		if let Some(strict) = cfg.bool_value(STRICT_KEY)    { self.strict    = strict;                 }
+		if let Some(names) = cfg.list_value(NAMES_KEY)      { self.names     = names;                  }
+		if let Some(parts) = cfg.integer_value(PARTS_KEY) && self.names.is_empty() {
+			if parts < 1 {
+				return Err(Error::InvalidConfig(format!("[SplitRelay]: {} must be positive, got {}", PARTS_KEY, parts)))
+			}
+			self.names = (1..=parts).map(|i| format!("{}_{}", self.column, i)).collect();
+		}
+		if let Some(text) = cfg.string_value(PATTERN_KEY) {
+			let pattern = Regex::new(&text).map_err(|e| Error::InvalidConfig(format!("[SplitRelay]: invalid pattern '{}': {}", text, e)))?;
+			self.names   = group_names(&pattern);
+			self.pattern = Some(pattern);
+		}
+		self.check()?;
+		self.metrics.activate();
+		info!("[SplitRelay]: initialized with {}", cfg);
+		Ok(())
+	}
+
+	fn accept(&mut self, atom: Atom, out: &mut dyn Collector) {
+		self.metrics.increment_messages();
+		let coordinate = atom.coordinate();
+		match atom {
+			Atom::HeaderRow(header) => {
+				let names = header.iter_str().map(str::to_owned).collect::<Vec<_>>();
+				match self.bind(&names) {
+					Ok(())     => {
+						let names = self.header(names, self.index.get().copied().unwrap_or_default());
+						out.emit(Atom::HeaderRow(StringRow::from_values(names).with_coordinate(coordinate)));
+					}
+					Err(error) => out.emit(Atom::ErrorAtom(error, coordinate)),
+				}
+			}
+			Atom::ByteRowAtom(row)    => {
+				let width = row.length() as usize;
+				let Some(index) = self.bound(width) else { return self.reject(coordinate, out) };
+				for cells in self.rows(&row, width, index, coordinate, out) {
+					out.emit(Atom::ByteRowAtom(byte_row(&row, &cells)));
+				}
+			}
+			Atom::StringRowAtom(row)  => {
+				let width = row.count() as usize;
+				let Some(index) = self.bound(width) else { return self.reject(coordinate, out) };
+				for cells in self.rows(&row, width, index, coordinate, out) {
+					let values = cells.into_iter().map(|cell| match cell {
+						Cell::Field(i) => row.get(i).unwrap_or_default().to_owned(),
+						Cell::New(v)   => v.unwrap_or_default(),
+					}).collect();
+					out.emit(Atom::StringRowAtom(StringRow::from_values(values).with_coordinate(coordinate)));
+				}
+			}
+			Atom::TypedRowAtom(row)   => {
+				let width = row.count() as usize;
+				let Some(index) = self.bound(width) else { return self.reject(coordinate, out) };
+				for cells in self.rows(&row, width, index, coordinate, out) {
+					let values = cells.into_iter().map(|cell| match cell {
+						Cell::Field(i) => row.get(i).cloned().unwrap_or(Value::Null),
+						Cell::New(v)   => v.map_or(Value::Null, Value::String),
+					}).collect();
+					out.emit(Atom::TypedRowAtom(TypedRow::new(values).with_coordinate(coordinate)));
+				}
+			}
+			Atom::RowBatchAtom(batch) => {
+				let Some(index) = self.bound(batch.width()) else { return self.reject(coordinate, out) };
+				if !batch.is_empty() { self.batch(batch, index, out) }
+			}
+			other                     => out.emit(other),
+		}
+	}
+
+	fn finish(&mut self, _out: &mut dyn Collector) -> Result<(), Error> {
+		self.metrics.complete();
+		info!("[SplitRelay]: split {} rows, {} did not fit", self.metrics.record_count, self.metrics.error_count);
+		Ok(())
+	}
+}
+
+impl ProvidesMetrics for SplitRelay {
+	fn metrics(&self) -> ComponentMetrics { self.metrics }
+
+	fn take_metrics(&mut self) -> ComponentMetrics {
+		let metrics = self.metrics;
+		self.metrics.reset();
+		metrics
+	}
+}
+
+fn group_names(pattern: &Regex) -> Vec<String> {
+	pattern.capture_names().flatten().map(str::to_owned).collect()
+}
+
+fn byte_row(row: &ByteRow, cells: &[Cell]) -> ByteRow {
+	let fields = cells.iter().map(|cell| match cell {
+		Cell::Field(i) => row.get(*i).unwrap_or_default(),
+		Cell::New(v)   => v.as_deref().unwrap_or_default().as_bytes(),
+	});
+	ByteRow::from_fields(fields).with_coordinate(row.coordinate())
+}
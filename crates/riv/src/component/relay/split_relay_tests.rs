@@ -0,0 +1,92 @@
+use regex::Regex;
+use zero::component::telemetry::provides_metrics::ProvidesMetrics;
+use crate::Error;
+use crate::component::relay::Relay;
+use crate::component::relay::fixtures::{all, fields, header, initialized, row};
+use crate::component::relay::map_relay_config::MapRelayConfig;
+use crate::component::relay::split_relay::{Mode, SplitRelay, COLUMN_KEY, DELIMITER_KEY, KEEP_KEY, MODE_KEY, NAMES_KEY, PARTS_KEY, PATTERN_KEY, STRICT_KEY};
+use crate::model::ir::atom::Atom;
+use crate::model::ir::row_batch::RowBatch;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+
+fn relay(cfg: MapRelayConfig) -> SplitRelay {
+	initialized(SplitRelay::new(), &cfg)
+}
+
+#[test]
+fn settings_are_checked() {
+	assert_eq!(Mode::parse(" Explode ").unwrap(), Mode::Explode);
+	assert!(matches!(Mode::parse("shred"), Err(Error::InvalidConfig(_))));
+	assert!(matches!(SplitRelay::new().initialize(&MapRelayConfig::new().with(NAMES_KEY, "a, b")), Err(Error::InvalidConfig(_))));      // No column
+	assert!(matches!(SplitRelay::new().initialize(&MapRelayConfig::new().with(COLUMN_KEY, "x")), Err(Error::InvalidConfig(_))));        // No names
+	assert!(matches!(SplitRelay::new().initialize(&MapRelayConfig::new().with(COLUMN_KEY, "x").with(PARTS_KEY, "0")), Err(Error::InvalidConfig(_))));
+	let unnamed = MapRelayConfig::new().with(COLUMN_KEY, "x").with(MODE_KEY, "extract").with(PATTERN_KEY, r"(\d+)");
+	assert!(matches!(SplitRelay::new().initialize(&unnamed), Err(Error::InvalidConfig(_))));
+	let broken  = MapRelayConfig::new().with(COLUMN_KEY, "x").with(MODE_KEY, "extract").with(PATTERN_KEY, "(?P<a>");
+	assert!(matches!(SplitRelay::new().initialize(&broken), Err(Error::InvalidConfig(_))));
+}
+
+#[test]
+fn split_adds_columns_after_the_source() {
+	let mut relay = relay(MapRelayConfig::new().with(COLUMN_KEY, "name").with(DELIMITER_KEY, ",").with(NAMES_KEY, "last, first"));
+	assert_eq!(fields(&all(&mut relay, header(&["id", "name", "city"]))[0]), ["id", "name", "last", "first", "city"]);
+	assert_eq!(fields(&all(&mut relay, Atom::ByteRowAtom(row(&["1", "Doe, Jane", "Oslo"])))[0]), ["1", "Doe, Jane", "Doe", "Jane", "Oslo"]);
+	assert_eq!(fields(&all(&mut relay, Atom::ByteRowAtom(row(&["2", "Roe, Ann, Jr", "Rome"])))[0]), ["2", "Roe, Ann, Jr", "Roe", "Ann, Jr", "Rome"]);
+
+	let typed = Atom::TypedRowAtom(TypedRow::new(vec![Value::Integer(3), Value::String("Poe".into()), Value::Null]));
+	match &all(&mut relay, typed)[0] {
+		Atom::TypedRowAtom(r) => assert_eq!(r.iter().cloned().collect::<Vec<_>>(), [Value::Integer(3), Value::String("Poe".into()), Value::String("Poe".into()), Value::Null, Value::Null]),
+		other                 => panic!("expected a typed row, got {:?}", other),
+	}
+	assert_eq!(relay.metrics().record_count, 3);
+}
+
+#[test]
+fn split_by_parts_without_the_source() {
+	let cfg       = MapRelayConfig::new().with(COLUMN_KEY, "column_1").with(DELIMITER_KEY, "-").with(PARTS_KEY, "3").with(KEEP_KEY, "false");
+	let mut relay = relay(cfg);
+	assert_eq!(relay.names(), ["column_1_1", "column_1_2", "column_1_3"]);
+	let batch     = RowBatch::from_rows(&[row(&["2025-01-31", "x"]), row(&["", "y"])]).unwrap();
+	match &all(&mut relay, Atom::RowBatchAtom(batch))[..] {
+		[Atom::RowBatchAtom(split)] => {
+			assert_eq!(split.to_rows().iter().map(|r| fields(&Atom::ByteRowAtom(r.clone()))).collect::<Vec<_>>(), [["2025", "01", "31", "x"], ["", "", "", "y"]]);
+		}
+		other                       => panic!("expected one batch, got {:?}", other),
+	}
+}
+
+#[test]
+fn explode_emits_a_row_per_item() {
+	let mut relay = relay(MapRelayConfig::new().with(MODE_KEY, "explode").with(COLUMN_KEY, "tags"));
+	assert_eq!(fields(&all(&mut relay, header(&["id", "tags"]))[0]), ["id", "tags"]);
+	let out       = all(&mut relay, Atom::StringRowAtom(row(&["1", "red; green;blue"]).as_string_row()));
+	assert_eq!(out.iter().map(fields).collect::<Vec<_>>(), [["1", "red"], ["1", "green"], ["1", "blue"]]);
+	assert_eq!(all(&mut relay, Atom::ByteRowAtom(row(&["2", ""]))).iter().map(fields).collect::<Vec<_>>(), [["2", ""]]);
+}
+
+#[test]
+fn extract_fills_named_groups_and_strict_reports_misses() {
+	let pattern   = Regex::new(r"(?P<year>\d{4})-(?P<month>\d{2})(?:-(?P<day>\d{2}))?").unwrap();
+	let mut dates = SplitRelay::extract("when", pattern).with_keep(false).with_strict(true);
+	dates.initialize(&MapRelayConfig::new()).unwrap();
+	assert_eq!(fields(&all(&mut dates, header(&["when", "what"]))[0]), ["year", "month", "day", "what"]);
+	assert_eq!(fields(&all(&mut dates, Atom::ByteRowAtom(row(&["on 2025-03", "launch"])))[0]), ["2025", "03", "", "launch"]);
+	assert!(matches!(&all(&mut dates, Atom::ByteRowAtom(row(&["soon", "party"])))[..], [Atom::ErrorAtom(Error::InvalidInput(m), _)] if m.contains("'soon'")));
+
+	let mut lenient = relay(MapRelayConfig::new().with(COLUMN_KEY, "column_1").with(NAMES_KEY, "a, b"));
+	assert_eq!(fields(&all(&mut lenient, Atom::ByteRowAtom(row(&["one"])))[0]), ["one", "one", ""]);
+	let mut strict  = relay(MapRelayConfig::new().with(COLUMN_KEY, "column_1").with(NAMES_KEY, "a, b").with(STRICT_KEY, "true"));
+	assert!(matches!(&all(&mut strict, Atom::ByteRowAtom(row(&["1;2;3"])))[..], [Atom::ErrorAtom(Error::InvalidInput(m), _)] if m.contains("3 parts")));
+	let metrics = strict.metrics();
+	assert_eq!((metrics.record_count, metrics.dropped_count, metrics.error_count), (1, 1, 1));
+}
+
+#[test]
+fn missing_column_is_reported_once() {
+	let mut relay = relay(MapRelayConfig::new().with(COLUMN_KEY, "tags").with(MODE_KEY, "explode"));
+	assert!(matches!(all(&mut relay, header(&["id"]))[..], [Atom::ErrorAtom(Error::InvalidConfig(_), _)]));
+	assert!(all(&mut relay, Atom::ByteRowAtom(row(&["1"]))).is_empty());
+	assert!(matches!(all(&mut relay, Atom::EndTask)[..], [Atom::EndTask]));
+	assert_eq!(relay.metrics().dropped_count, 1);
+}
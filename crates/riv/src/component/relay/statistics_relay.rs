@@ -1,37 +1,124 @@
-use std::fmt::Display;
+pub mod column_profile;
+pub mod hyper_log_log;
+
+use tracing::info;
 use crate::Error;
 use crate::model::ir::atom::Atom;
-use crate::component::relay::{Relay, RelayConfig};
+use crate::model::ir::value::Value;
+use crate::component::relay::{RelayConfig, SimpleRelay};
+use crate::component::relay::statistics_relay::column_profile::{ColumnProfiler, ProfileReport};
 
+/// Config key: distinct values counted exactly before switching to an estimate.
+pub const DISTINCT_LIMIT_KEY:     &str  = "distinct_limit";
+/// Config key: how many of the most frequent values to report per column.
+pub const TOP_N_KEY:              &str  = "top_n";
+pub const DEFAULT_DISTINCT_LIMIT: usize = 10_000;
+pub const DEFAULT_TOP_N:          usize = 10;
 
-//
-// FIXME: Implement this class to capture statistics
-//
+/// Profiles every column of the rows passing through, unchanged.
+///
+/// Columns are named from the `HeaderRow`, or `column_1`, `column_2`, …
+/// when data arrives without one. Typed rows are profiled by the text of
+/// their values. The `ProfileReport` is built by `finish`
+/// and can be read with `report` from then on.
+///
+pub struct StatisticsRelay {
+	distinct_limit: usize,
+	top_n:          usize,
+	names:          Vec<String>,
+	columns:        Vec<ColumnProfiler>,
+	rows:           u64,
+	report:         Option<ProfileReport>,
+}
 
-pub struct StatisticsRelay{
-	total: u64,
+impl Default for StatisticsRelay {
+	fn default() -> Self { Self::new() }
 }
 
 impl StatisticsRelay {
 	pub fn new() -> Self {
-		StatisticsRelay{ total: 0 }
+		StatisticsRelay {
+			distinct_limit: DEFAULT_DISTINCT_LIMIT,
+			top_n:          DEFAULT_TOP_N,
+			names:          Vec::new(),
+			columns:        Vec::new(),
+			rows:           0,
+			report:         None,
+		}
+	}
+
+	/// Rows profiled so far.
+	pub fn rows(&self) -> u64 { self.rows }
+
+	/// The profile of everything accepted, once `finish` has been called.
+	pub fn report(&self) -> Option<&ProfileReport> { self.report.as_ref() }
+
+	pub fn take_report(&mut self) -> Option<ProfileReport> { self.report.take() }
+
+	fn observe_row<'a, I: Iterator<Item = &'a [u8]>>(&mut self, fields: I) {
+		let mut width = 0;
+		for (i, value) in fields.enumerate() {
+			if i == self.columns.len() {
+				let mut column = ColumnProfiler::new();
+				column.observe_missing(self.rows);                       // Earlier rows were missing this column
+				self.columns.push(column);
+			}
+			self.columns[i].observe(value, self.distinct_limit);
+			width = i + 1;
+		}
+		for column in &mut self.columns[width..] {
+			column.observe_missing(1);                                   // Short rows leave trailing columns empty
+		}
+		self.rows += 1;
+	}
+
+	fn build_report(&self) -> ProfileReport {
+		let columns = self.columns.iter().enumerate()
+			.map(|(i, column)| {
+				let name = self.names.get(i).cloned().unwrap_or_else(|| format!("column_{}", i + 1));
+				column.profile(&name, self.top_n)
+			})
+			.collect();
+		ProfileReport{rows: self.rows, columns}
 	}
 }
 
-impl Relay for StatisticsRelay {
+impl SimpleRelay for StatisticsRelay {
 	fn initialize(&mut self, cfg: &dyn RelayConfig) -> Result<(), Error> {
-		println!("--- StatisticsRelay initialized --- {}", cfg);
+		let limit = |key: &str, default: usize| match cfg.integer_value(key) {
+			Some(v) if v < 0 => Err(Error::InvalidConfig(format!("[StatisticsRelay]: {} must not be negative, got {}", key, v))),
+			Some(v)          => Ok(v as usize),
+			None             => Ok(default),
+		};
+		self.distinct_limit = limit(DISTINCT_LIMIT_KEY, DEFAULT_DISTINCT_LIMIT)?;
+		self.top_n          = limit(TOP_N_KEY, DEFAULT_TOP_N)?;
+		info!("[StatisticsRelay]: initialized with {}", cfg);
 		Ok(())
 	}
 
 	fn accept(&mut self, atom: Atom) -> Option<Atom> {
-		self.total += 1;
+		match &atom {
+			Atom::HeaderRow(header)   => self.names = header.iter_str().map(str::to_owned).collect(),
+			Atom::ByteRowAtom(row)    => self.observe_row(row.into_iter()),
+			Atom::StringRowAtom(row)  => self.observe_row(row.iter_bytes()),
+			Atom::TypedRowAtom(row)   => {
+				let texts = row.iter().map(Value::to_string).collect::<Vec<_>>();     // Null is empty, as in text rows
+				self.observe_row(texts.iter().map(String::as_bytes));
+			}
+			Atom::RowBatchAtom(batch) => {
+				for r in 0..batch.len() {
+					self.observe_row(batch.columns().iter().map(|c| c.get(r).unwrap_or_default()));
+				}
+			}
+			_ => {}
+		}
 		Some(atom) // pass the atom along unmodified
 	}
 
 	fn finish(&mut self) -> bool {
-		println!("--- StatisticsRelay finished ---");
+		let report = self.build_report();
+		info!("[StatisticsRelay]: profiled {} rows across {} columns", report.rows, report.columns.len());
+		self.report = Some(report);
 		true
 	}
-	
 }
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use serde::Serialize;
+use crate::component::relay::statistics_relay::hyper_log_log::HyperLogLog;
+use crate::model::ir::value::ValueType;
+use crate::model::schema::inference::ColumnInference;
+
+/// Mean, spread and range of the values in a column that parse as numbers.
+///
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct NumericSummary {
+	pub count:   u64,
+	pub min:     f64,
+	pub max:     f64,
+	pub mean:    f64,
+	pub std_dev: f64,
+}
+
+/// A value and how many times it occurred.
+///
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ValueCount {
+	pub value: String,
+	pub count: u64,
+}
+
+/// Everything `StatisticsRelay` learned about one column.
+///
+/// Blank (empty or whitespace) fields count as nulls; `empty` is the subset
+/// that were exactly zero length. Lengths are in characters over non-null
+/// values.
+///
+/// `distinct` is exact while `distinct_exact` holds. Past the configured
+/// limit it becomes a HyperLogLog estimate, and `top_values` only counts
+/// values first seen before the limit was reached.
+///
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ColumnProfile {
+	pub name:           String,
+	pub value_type:     ValueType,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub format:         Option<&'static str>,
+	pub non_null:       u64,
+	pub nulls:          u64,
+	pub empty:          u64,
+	pub distinct:       u64,
+	pub distinct_exact: bool,
+	pub min_length:     Option<usize>,
+	pub max_length:     Option<usize>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub numeric:        Option<NumericSummary>,
+	pub top_values:     Vec<ValueCount>,
+}
+
+/// The result of profiling a whole stream.
+///
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ProfileReport {
+	pub rows:    u64,
+	pub columns: Vec<ColumnProfile>,
+}
+
+impl ProfileReport {
+	pub fn column(&self, name: &str) -> Option<&ColumnProfile> {
+		self.columns.iter().find(|c| c.name == name)
+	}
+}
+
+/// Accumulates a `ColumnProfile` one value at a time.
+///
+#[derive(Clone, Debug)]
+pub(crate) struct ColumnProfiler {
+	inference:  ColumnInference,
+	empty:      u64,
+	min_length: Option<usize>,
+	max_length: usize,
+	counts:     HashMap<Box<[u8]>, u64>,
+	sketch:     Option<HyperLogLog>,
+	numbers:    u64,
+	min:        f64,
+	max:        f64,
+	mean:       f64,
+	m2:         f64,
+}
+
+impl ColumnProfiler {
+	pub(crate) fn new() -> Self {
+		ColumnProfiler {
+			inference:  ColumnInference::new(),
+			empty:      0,
+			min_length: None,
+			max_length: 0,
+			counts:     HashMap::new(),
+			sketch:     None,
+			numbers:    0,
+			min:        f64::INFINITY,
+			max:        f64::NEG_INFINITY,
+			mean:       0.0,
+			m2:         0.0,
+		}
+	}
+
+	/// Record `count` nulls, e.g. for rows that were too short to reach this column.
+	pub(crate) fn observe_missing(&mut self, count: u64) {
+		for _ in 0..count {
+			self.inference.observe("");
+		}
+	}
+
+	pub(crate) fn observe(&mut self, value: &[u8], distinct_limit: usize) {
+		let text = String::from_utf8_lossy(value);
+		self.inference.observe(&text);
+		if value.is_empty() { self.empty += 1 }
+		if text.trim().is_empty() { return }
+
+		let length      = text.chars().count();
+		self.min_length = Some(self.min_length.map_or(length, |m| m.min(length)));
+		self.max_length = self.max_length.max(length);
+
+		if let Some(count) = self.counts.get_mut(value) {
+			*count += 1;
+		} else if self.sketch.is_none() && self.counts.len() < distinct_limit {
+			self.counts.insert(value.into(), 1);
+		} else if self.sketch.is_none() {
+			let mut sketch = HyperLogLog::default();                 // Too many to track: estimate from here on
+			self.counts.keys().for_each(|k| sketch.insert(k));
+			self.sketch = Some(sketch);
+		}
+		if let Some(sketch) = &mut self.sketch { sketch.insert(value) }
+
+		if let Some(number) = parse_number(text.trim()) {
+			self.numbers += 1;                                       // Welford's running mean and variance
+			let delta  = number - self.mean;
+			self.mean += delta / self.numbers as f64;
+			self.m2   += delta * (number - self.mean);
+			self.min   = self.min.min(number);
+			self.max   = self.max.max(number);
+		}
+	}
+
+	pub(crate) fn profile(&self, name: &str, top_n: usize) -> ColumnProfile {
+		let distinct = match &self.sketch {
+			Some(sketch) => sketch.estimate().max(self.counts.len() as u64),
+			None         => self.counts.len() as u64,
+		};
+		let numeric = (self.numbers > 0).then(|| NumericSummary {
+			count:   self.numbers,
+			min:     self.min,
+			max:     self.max,
+			mean:    self.mean,
+			std_dev: if self.numbers > 1 { (self.m2 / (self.numbers - 1) as f64).sqrt() } else { 0.0 },
+		});
+		let mut top_values = self.counts.iter()
+			.map(|(value, &count)| ValueCount{value: String::from_utf8_lossy(value).into_owned(), count})
+			.collect::<Vec<_>>();
+		top_values.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+		top_values.truncate(top_n);
+
+		ColumnProfile {
+			name:           name.to_owned(),
+			value_type:     self.inference.value_type(),
+			format:         self.inference.format(),
+			non_null:       self.inference.values(),
+			nulls:          self.inference.nulls(),
+			empty:          self.empty,
+			distinct,
+			distinct_exact: self.sketch.is_none(),
+			min_length:     self.min_length,
+			max_length:     self.min_length.map(|_| self.max_length),
+			numeric,
+			top_values,
+		}
+	}
+}
+
+/// Numbers as people write them in data files; `inf` and `NaN` are text.
+fn parse_number(text: &str) -> Option<f64> {
+	if !text.bytes().any(|b| b.is_ascii_digit()) { return None }
+	text.parse::<f64>().ok().filter(|n| n.is_finite())
+}
@@ -0,0 +1,50 @@
+use std::hash::{DefaultHasher, Hasher};
+
+pub const DEFAULT_PRECISION: u8 = 14;
+
+/// Estimates how many distinct values have been inserted using a fixed
+/// `2^precision` bytes, whatever the cardinality.
+///
+/// At the default precision of 14 the standard error is about 0.8%.
+///
+#[derive(Clone, Debug)]
+pub struct HyperLogLog {
+	precision: u8,
+	registers: Vec<u8>,
+}
+
+impl Default for HyperLogLog {
+	fn default() -> Self { Self::new(DEFAULT_PRECISION) }
+}
+
+impl HyperLogLog {
+	/// `precision` is clamped to `4..=18`.
+	pub fn new(precision: u8) -> Self {
+		let precision = precision.clamp(4, 18);
+		HyperLogLog{precision, registers: vec![0; 1 << precision]}
+	}
+
+	pub fn insert(&mut self, value: &[u8]) {
+		let mut hasher = DefaultHasher::new();                       // Fixed keys, so estimates are repeatable
+		hasher.write(value);
+		let hash  = hasher.finish();
+		let index = (hash >> (64 - self.precision)) as usize;
+		let rest  = (hash << self.precision) | (1 << (self.precision - 1));
+		let rank  = rest.leading_zeros() as u8 + 1;
+		self.registers[index] = self.registers[index].max(rank);
+	}
+
+	pub fn estimate(&self) -> u64 {
+		let m     = self.registers.len() as f64;
+		let alpha = 0.7213 / (1.0 + 1.079 / m);
+		let sum   = self.registers.iter().map(|&r| 2f64.powi(-(r as i32))).sum::<f64>();
+		let raw   = alpha * m * m / sum;
+		let zeros = self.registers.iter().filter(|&&r| r == 0).count();
+		let estimate = if raw <= 2.5 * m && zeros > 0 {
+			m * (m / zeros as f64).ln()                              // Linear counting for small sets
+		} else {
+			raw
+		};
+		estimate.round() as u64
+	}
+}
@@ -0,0 +1,137 @@
+use std::fmt;
+use crate::Error;
+use crate::component::relay::{RelayConfig, SimpleRelay};
+use crate::component::relay::empty_relay_config::EmptyRelayConfig;
+use crate::component::relay::fixtures::row;
+use crate::component::relay::statistics_relay::{StatisticsRelay, DISTINCT_LIMIT_KEY, TOP_N_KEY};
+use crate::component::relay::statistics_relay::column_profile::ValueCount;
+use crate::component::relay::statistics_relay::hyper_log_log::HyperLogLog;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::row_batch::RowBatch;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::{Value, ValueType};
+
+#[derive(Debug)]
+struct Limits(i32, i32);
+
+impl fmt::Display for Limits {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "{:?}", self) }
+}
+
+impl RelayConfig for Limits {
+	fn string_value (&self, _name: &str) -> Option<String> { None }
+	fn float_value  (&self, _name: &str) -> Option<f32>    { None }
+	fn bool_value   (&self, _name: &str) -> Option<bool>   { None }
+	fn integer_value(&self, name: &str)  -> Option<i32> {
+		match name {
+			DISTINCT_LIMIT_KEY => Some(self.0),
+			TOP_N_KEY          => Some(self.1),
+			_                  => None,
+		}
+	}
+}
+
+fn profile(relay: &mut StatisticsRelay, header: &[&str], rows: &[&[&str]]) {
+	relay.accept(Atom::HeaderRow(row(header).as_string_row()));
+	for r in rows {
+		assert!(relay.accept(Atom::ByteRowAtom(row(r))).is_some());
+	}
+	assert!(relay.finish());
+}
+
+#[test]
+fn report_is_only_available_after_finish() {
+	let mut relay = StatisticsRelay::new();
+	relay.initialize(&EmptyRelayConfig).unwrap();
+	relay.accept(Atom::ByteRowAtom(row(&["1"])));
+	assert!(relay.report().is_none());
+	relay.finish();
+	assert_eq!(relay.report().unwrap().rows, 1);
+	assert_eq!(relay.report().unwrap().columns[0].name, "column_1");
+}
+
+#[test]
+fn profiles_counts_lengths_and_numbers() {
+	let mut relay = StatisticsRelay::new();
+	profile(&mut relay, &["qty", "name"], &[
+		&["4",  "apple"],
+		&["8",  ""],
+		&["",   "  "],
+		&["12", "kiwi"],
+	]);
+	let report = relay.report().unwrap();
+	let qty    = report.column("qty").unwrap();
+	assert_eq!((qty.non_null, qty.nulls, qty.empty), (3, 1, 1));
+	assert_eq!(qty.value_type, ValueType::Integer);
+	let numbers = qty.numeric.as_ref().unwrap();
+	assert_eq!((numbers.count, numbers.min, numbers.max, numbers.mean, numbers.std_dev), (3, 4.0, 12.0, 8.0, 4.0));
+
+	let name = report.column("name").unwrap();
+	assert_eq!((name.non_null, name.nulls, name.empty), (2, 2, 1));
+	assert_eq!((name.min_length, name.max_length), (Some(4), Some(5)));
+	assert_eq!(name.value_type, ValueType::String);
+	assert!(name.numeric.is_none());
+}
+
+#[test]
+fn top_values_are_ordered_by_frequency() {
+	let mut relay = StatisticsRelay::new();
+	relay.initialize(&Limits(100, 2)).unwrap();
+	profile(&mut relay, &["status"], &[&["open"], &["closed"], &["open"], &["held"], &["open"], &["closed"]]);
+	let status = relay.report().unwrap().column("status").unwrap().clone();
+	assert_eq!((status.distinct, status.distinct_exact), (3, true));
+	assert_eq!(status.top_values, [
+		ValueCount{value: "open".into(),   count: 3},
+		ValueCount{value: "closed".into(), count: 2},
+	]);
+}
+
+#[test]
+fn distinct_count_is_estimated_past_the_limit() {
+	let mut relay = StatisticsRelay::new();
+	relay.initialize(&Limits(1_000, 5)).unwrap();
+	relay.accept(Atom::HeaderRow(row(&["id"]).as_string_row()));
+	for i in 0..50_000 {
+		relay.accept(Atom::ByteRowAtom(row(&[&i.to_string()])));
+	}
+	relay.finish();
+	let id = relay.report().unwrap().column("id").unwrap().clone();
+	assert!(!id.distinct_exact);
+	assert!((48_000..52_000).contains(&id.distinct), "estimated {}", id.distinct);
+}
+
+#[test]
+fn batches_and_ragged_rows_are_profiled() {
+	let mut relay = StatisticsRelay::new();
+	let batch     = RowBatch::from_rows(&[row(&["1", "a"]), row(&["2", "b"])]).unwrap();
+	relay.accept(Atom::HeaderRow(row(&["n", "s"]).as_string_row()));
+	relay.accept(Atom::RowBatchAtom(batch));
+	relay.accept(Atom::ByteRowAtom(row(&["3"])));
+	relay.accept(Atom::ByteRowAtom(row(&["4", "c", "extra"])));
+	relay.accept(Atom::TypedRowAtom(TypedRow::new(vec![Value::Integer(5), Value::Null])));
+	relay.finish();
+	let report = relay.report().unwrap();
+	assert_eq!(report.rows, 5);
+	assert_eq!(report.column("n").unwrap().value_type, ValueType::Integer);
+	assert_eq!(report.column("s").unwrap().nulls, 2);
+	assert_eq!(report.columns[2].name, "column_3");
+	assert_eq!((report.columns[2].non_null, report.columns[2].nulls), (1, 4));
+}
+
+#[test]
+fn negative_limits_are_rejected() {
+	let mut relay = StatisticsRelay::new();
+	assert!(matches!(relay.initialize(&Limits(-1, 5)), Err(Error::InvalidConfig(_))));
+}
+
+#[test]
+fn hyper_log_log_estimates_within_a_few_percent() {
+	let mut sketch = HyperLogLog::default();
+	assert_eq!(sketch.estimate(), 0);
+	for i in 0..200_000u32 {
+		sketch.insert(&i.to_le_bytes());
+		sketch.insert(&i.to_le_bytes());                                 // Repeats do not count
+	}
+	let estimate = sketch.estimate() as f64;
+	assert!((estimate / 200_000.0 - 1.0).abs() < 0.03, "estimated {}", estimate);
+}
@@ -0,0 +1,306 @@
+pub mod rule_set;
+
+use std::iter;
+use tracing::{info, warn};
+use zero::component::telemetry::component_metrics::ComponentMetrics;
+use zero::component::telemetry::provides_metrics::ProvidesMetrics;
+use crate::Error;
+use crate::error::IoErrorWrapper;
+use crate::component::relay::{batch_atom, BindsColumns, Collector, ColumnBinding, Relay, RelayConfig};
+use crate::component::relay::validation_relay::rule_set::{Failure, RuleSet, Validator};
+use crate::component::sink::Sink;
+use crate::model::coordinate::coordinate::Coordinate;
+use crate::model::expression::Fields;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::row_batch::RowBatch;
+use crate::model::ir::string_row::StringRow;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+
+/// Config key: the rule set as JSON (see `RuleSet`).
+pub const RULES_KEY:           &str = "rules";
+/// Config key: path of a JSON file holding the rule set, used when `rules` is absent.
+pub const RULES_FILE_KEY:      &str = "rules_file";
+/// Config key: what happens to a failing row, `drop`, `flag` or `quarantine`.
+pub const ON_FAILURE_KEY:      &str = "on_failure";
+/// Config key: name of the column that carries the failures in `flag` and `quarantine` mode.
+pub const FLAG_COLUMN_KEY:     &str = "flag_column";
+pub const DEFAULT_FLAG_COLUMN: &str = "validation_errors";
+
+/// What the relay does with a row that breaks a rule.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnFailure {
+	/// Replace the row with an `ErrorAtom`.
+	#[default]
+	Drop,
+	/// Pass every row on, with the failures in an extra column.
+	Flag,
+	/// Send the row, with its failures, to the quarantine sink and an `ErrorAtom` downstream.
+	Quarantine,
+}
+
+impl OnFailure {
+	pub fn parse(text: &str) -> Result<OnFailure, Error> {
+		match text.trim().to_ascii_lowercase().as_str() {
+			"drop"       => Ok(OnFailure::Drop),
+			"flag"       => Ok(OnFailure::Flag),
+			"quarantine" => Ok(OnFailure::Quarantine),
+			other        => Err(Error::InvalidConfig(format!("[ValidationRelay]: on_failure must be 'drop', 'flag' or 'quarantine', got '{}'", other))),
+		}
+	}
+}
+
+/// Checks every row against a `RuleSet`.
+///
+/// Rules name columns of the latest `HeaderRow`, or `column_1`, `column_2`,
+/// … without one. A failing row becomes an `ErrorAtom` at the row's
+/// coordinate whose message lists each column and rule it broke; in `flag`
+/// mode it passes on instead, with that list in `flag_column` (empty for a
+/// valid row), and the header gains the column. In `quarantine` mode the
+/// header and the failing rows, with the same extra column, also go to the
+/// sink given to `with_quarantine`, which is closed by `finish`.
+///
+/// A `RowBatchAtom` keeps its valid rows, and each failing row of it gets
+/// its own `ErrorAtom`, emitted before the batch. Failing rows are counted
+/// in the metrics' `error_count`, and those held back in `dropped_count`.
+///
+pub struct ValidationRelay {
+	rules:       RuleSet,
+	validator:   Option<Validator>,
+	bound:       ColumnBinding<()>,
+	on_failure:  OnFailure,
+	flag_column: String,
+	quarantine:  Option<Box<dyn Sink>>,
+	metrics:     ComponentMetrics,
+}
+
+impl Default for ValidationRelay {
+	fn default() -> Self { Self::new() }
+}
+
+impl ValidationRelay {
+	pub fn new() -> Self {
+		ValidationRelay {
+			rules:       RuleSet::default(),
+			validator:   None,
+			bound:       ColumnBinding::default(),
+			on_failure:  OnFailure::Drop,
+			flag_column: DEFAULT_FLAG_COLUMN.to_owned(),
+			quarantine:  None,
+			metrics:     ComponentMetrics::default(),
+		}
+	}
+
+	pub fn with_rules(mut self, rules: RuleSet) -> Self {
+		self.rules = rules;
+		self
+	}
+
+	/// Route failing rows to `sink`, which must be ready to accept atoms.
+	pub fn with_quarantine(mut self, sink: Box<dyn Sink>) -> Self {
+		self.quarantine = Some(sink);
+		self.on_failure = OnFailure::Quarantine;
+		self
+	}
+
+	pub fn rules(&self) -> &RuleSet { &self.rules }
+
+	/// The quarantine sink, handed back so its output can be read.
+	pub fn take_quarantine(&mut self) -> Option<Box<dyn Sink>> { self.quarantine.take() }
+
+	fn failures(&mut self, row: &dyn Fields, width: usize) -> Vec<Failure> {
+		self.ensure_bound(width);
+		self.metrics.add_records(1);
+		self.validator.as_mut().map(|v| v.check(row, width)).unwrap_or_default()
+	}
+
+	fn quarantine(&mut self, atom: Atom) {
+		let Some(sink) = &mut self.quarantine else { return };
+		if let Err(error) = sink.accept(atom) {
+			warn!("[ValidationRelay]: quarantine sink refused a row: {}", error);
+		}
+	}
+
+	/// The outcome for a row that was checked; `extend` appends a text column to it.
+	fn settle(&mut self, atom: Atom, failures: Vec<Failure>, coordinate: Coordinate) -> Option<Atom> {
+		if failures.is_empty() {
+			return Some(if self.on_failure == OnFailure::Flag { extend(atom, "") } else { atom })
+		}
+		let reason = describe(&failures);
+		self.metrics.increment_errors();
+		match self.on_failure {
+			OnFailure::Flag       => Some(extend(atom, &reason)),
+			OnFailure::Drop       => {
+				self.metrics.add_dropped(1);
+				Some(Atom::ErrorAtom(Error::InvalidInput(format!("[ValidationRelay]: {}", reason)), coordinate))
+			}
+			OnFailure::Quarantine => {
+				self.metrics.add_dropped(1);
+				self.quarantine(extend(atom, &reason));
+				Some(Atom::ErrorAtom(Error::InvalidInput(format!("[ValidationRelay]: {}", reason)), coordinate))
+			}
+		}
+	}
+
+	fn batch(&mut self, batch: RowBatch, out: &mut dyn Collector) {
+		self.ensure_bound(batch.width());
+		self.metrics.add_records(batch.len() as u64);
+		let reasons = match &mut self.validator {
+			Some(validator) => (0..batch.len()).map(|r| describe(&validator.check(&(&batch, r), batch.width()))).collect::<Vec<_>>(),
+			None            => vec![String::new(); batch.len()],
+		};
+		let failed = reasons.iter().filter(|r| !r.is_empty()).count();
+		for _ in 0..failed { self.metrics.increment_errors(); }
+
+		if self.on_failure == OnFailure::Flag {
+			let rows = batch.to_rows().iter().zip(&reasons).map(|(row, reason)| extend_byte_row(row, reason)).collect::<Vec<_>>();
+			out.emit(batch_atom("ValidationRelay", &rows));
+			return
+		}
+		if self.on_failure == OnFailure::Quarantine {
+			for (row, reason) in batch.to_rows().iter().zip(&reasons).filter(|(_, r)| !r.is_empty()) {
+				self.quarantine(Atom::ByteRowAtom(extend_byte_row(row, reason)));
+			}
+		}
+		self.metrics.add_dropped(failed as u64);
+		for (r, reason) in reasons.iter().enumerate().filter(|(_, r)| !r.is_empty()) {
+			out.emit(Atom::ErrorAtom(Error::InvalidInput(format!("[ValidationRelay]: {}", reason)), batch.coordinate(r)));
+		}
+		let kept = batch.filter_rows(|r| reasons[r].is_empty());
+		if !kept.is_empty() {
+			out.emit(Atom::RowBatchAtom(kept));
+		}
+	}
+
+	/// Handle one atom; a batch emits its errors and rows itself.
+	fn pass(&mut self, atom: Atom, out: &mut dyn Collector) -> Option<Atom> {
+		self.metrics.increment_messages();
+		let coordinate = atom.coordinate();
+		match atom {
+			Atom::HeaderRow(header) => {
+				let names = header.iter_str().collect::<Vec<_>>();
+				let _     = self.bind(&names);
+				let extended = names.iter().map(|n| n.to_string()).chain(iter::once(self.flag_column.clone())).collect::<Vec<_>>();
+				let extended = StringRow::from_values(extended).with_coordinate(coordinate);
+				match self.on_failure {
+					OnFailure::Drop       => Some(Atom::HeaderRow(header)),
+					OnFailure::Flag       => Some(Atom::HeaderRow(extended)),
+					OnFailure::Quarantine => {
+						self.quarantine(Atom::HeaderRow(extended));
+						Some(Atom::HeaderRow(header))
+					}
+				}
+			}
+			Atom::ByteRowAtom(row) => {
+				let failures = self.failures(&row, row.length() as usize);
+				self.settle(Atom::ByteRowAtom(row), failures, coordinate)
+			}
+			Atom::StringRowAtom(row) => {
+				let failures = self.failures(&row, row.count() as usize);
+				self.settle(Atom::StringRowAtom(row), failures, coordinate)
+			}
+			Atom::TypedRowAtom(row) => {
+				let failures = self.failures(&row, row.count() as usize);
+				self.settle(Atom::TypedRowAtom(row), failures, coordinate)
+			}
+			Atom::RowBatchAtom(batch) => {
+				if !batch.is_empty() { self.batch(batch, out) }
+				None
+			}
+			other => Some(other),
+		}
+	}
+}
+
+/// Binding never fails: rules on columns the header lacks fail every row.
+impl BindsColumns for ValidationRelay {
+	type Bound = ();
+
+	fn resolve<S: AsRef<str>>(&mut self, names: &[S]) -> Result<(), Error> {
+		if let Some(validator) = &mut self.validator {
+			validator.bind(names);
+		}
+		Ok(())
+	}
+
+	fn binding(&mut self) -> &mut ColumnBinding<()> { &mut self.bound }
+}
+
+impl Relay for ValidationRelay {
+	fn initialize(&mut self, cfg: &dyn RelayConfig) -> Result<(), Error> {
+		if let Some(json) = cfg.string_value(RULES_KEY) {
+			self.rules = RuleSet::from_json(&json)?;
+		} else if let Some(path) = cfg.string_value(RULES_FILE_KEY) {
+			self.rules = RuleSet::from_json(&std::fs::read_to_string(path).map_err(IoErrorWrapper::from)?)?;
+		}
+		if self.rules.rules.is_empty() {
+			return Err(Error::InvalidConfig(format!("[ValidationRelay]: '{}' or '{}' is required", RULES_KEY, RULES_FILE_KEY)))
+		}
+		if let Some(mode) = cfg.string_value(ON_FAILURE_KEY) {
+			self.on_failure = OnFailure::parse(&mode)?;
+		}
+		if self.on_failure == OnFailure::Quarantine && self.quarantine.is_none() {
+			return Err(Error::InvalidConfig("[ValidationRelay]: quarantine mode needs a quarantine sink".to_owned()))
+		}
+		if let Some(column) = cfg.string_value(FLAG_COLUMN_KEY) {
+			self.flag_column = column;
+		}
+		self.validator = Some(Validator::new(&self.rules)?);
+		self.metrics.activate();
+		info!("[ValidationRelay]: initialized with {} rules, failing rows are {:?}", self.rules.rules.len(), self.on_failure);
+		Ok(())
+	}
+
+	fn accept(&mut self, atom: Atom, out: &mut dyn Collector) {
+		if let Some(atom) = self.pass(atom, out) {
+			out.emit(atom);
+		}
+	}
+
+	fn finish(&mut self, _out: &mut dyn Collector) -> Result<(), Error> {
+		if let Some(sink) = &mut self.quarantine {
+			sink.close();
+		}
+		self.metrics.complete();
+		info!("[ValidationRelay]: {} rows, {} failed validation", self.metrics.record_count, self.metrics.error_count);
+		Ok(())
+	}
+}
+
+impl ProvidesMetrics for ValidationRelay {
+	fn metrics(&self) -> ComponentMetrics { self.metrics }
+
+	fn take_metrics(&mut self) -> ComponentMetrics {
+		let metrics = self.metrics;
+		self.metrics.reset();
+		metrics
+	}
+}
+
+/// The failures of one row as one line; empty when there are none.
+fn describe(failures: &[Failure]) -> String {
+	failures.iter().map(Failure::to_string).collect::<Vec<_>>().join("; ")
+}
+
+fn extend_byte_row(row: &ByteRow, text: &str) -> ByteRow {
+	let fields = (0..row.length() as usize).map(|i| row.get(i).unwrap_or_default()).chain(iter::once(text.as_bytes()));
+	ByteRow::from_fields(fields).with_coordinate(row.coordinate())
+}
+
+/// A data row with `text` appended; a typed row gets null for empty text.
+fn extend(atom: Atom, text: &str) -> Atom {
+	match atom {
+		Atom::ByteRowAtom(row)   => Atom::ByteRowAtom(extend_byte_row(&row, text)),
+		Atom::StringRowAtom(row) => {
+			let values = row.iter_str().map(str::to_owned).chain(iter::once(text.to_owned())).collect();
+			Atom::StringRowAtom(StringRow::from_values(values).with_coordinate(row.coordinate()))
+		}
+		Atom::TypedRowAtom(row)  => {
+			let value  = if text.is_empty() { Value::Null } else { Value::String(text.to_owned()) };
+			let values = row.iter().cloned().chain(iter::once(value)).collect();
+			Atom::TypedRowAtom(TypedRow::new(values).with_coordinate(row.coordinate()))
+		}
+		other                    => other,
+	}
+}
@@ -0,0 +1,279 @@
+use std::collections::HashSet;
+use std::fmt;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use crate::Error;
+use crate::component::relay::dedupe_relay::key_hash;
+use crate::component::relay::dedupe_relay::key_set::KeySet;
+use crate::model::expression::ast::{number, truth};
+use crate::model::expression::{Expression, Fields};
+use crate::model::ir::value::{Value, ValueType};
+
+/// Keys a `unique` rule holds in memory before spilling to disk.
+const UNIQUE_MEMORY_LIMIT: usize = 1_000_000;
+
+/// One data-quality rule.
+///
+/// Every rule but `required` and `not_null` passes an empty value, so a
+/// rule says what a value must look like when there is one. A `check`
+/// fails only when its expression is false, like an SQL `CHECK`.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum Rule {
+	/// The column must be in the header and in every row.
+	Required {column: String},
+	NotNull  {column: String},
+	/// The text must parse as the type; `format` is a chrono pattern for dates and timestamps.
+	Type     {column: String, #[serde(rename = "type")] value_type: ValueType, #[serde(default)] format: Option<String>},
+	Pattern  {column: String, regex: String},
+	Range    {column: String, #[serde(default)] min: Option<f64>, #[serde(default)] max: Option<f64>},
+	OneOf    {column: String, values: Vec<String>, #[serde(default)] ignore_case: bool},
+	/// No two rows may share the values of these columns.
+	Unique   {columns: Vec<String>},
+	/// A cross-field condition such as `shipped >= ordered`.
+	Check    {expression: String, #[serde(default)] name: Option<String>},
+}
+
+impl Rule {
+	pub fn name(&self) -> &'static str {
+		match self {
+			Rule::Required{..} => "required",
+			Rule::NotNull{..}  => "not_null",
+			Rule::Type{..}     => "type",
+			Rule::Pattern{..}  => "pattern",
+			Rule::Range{..}    => "range",
+			Rule::OneOf{..}    => "one_of",
+			Rule::Unique{..}   => "unique",
+			Rule::Check{..}    => "check",
+		}
+	}
+}
+
+/// A rule a row broke.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Failure {
+	pub rule:    &'static str,
+	pub column:  Option<String>,
+	pub message: String,
+}
+
+impl fmt::Display for Failure {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match &self.column {
+			Some(column) => write!(f, "column '{}' failed {}: {}", column, self.rule, self.message),
+			None         => write!(f, "{} failed: {}", self.rule, self.message),
+		}
+	}
+}
+
+/// The rules a `ValidationRelay` applies, as written in its JSON config:
+///
+/// ```json
+/// {"rules": [
+///   {"rule": "not_null", "column": "id"},
+///   {"rule": "range",    "column": "amount", "min": 0},
+///   {"rule": "check",    "expression": "shipped >= ordered"}
+/// ]}
+/// ```
+///
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RuleSet {
+	pub rules: Vec<Rule>,
+}
+
+impl RuleSet {
+	pub fn new(rules: Vec<Rule>) -> Self { RuleSet{rules} }
+
+	/// Compile patterns and expressions, reporting the first that is invalid.
+	pub fn validate(&self) -> Result<(), Error> {
+		Validator::new(self).map(|_| ())
+	}
+
+	pub fn to_json(&self) -> Result<String, Error> {
+		serde_json::to_string_pretty(self).map_err(|e| Error::General(format!("Failed to serialize rule set: {}", e)))
+	}
+
+	pub fn from_json(json: &str) -> Result<RuleSet, Error> {
+		serde_json::from_str(json).map_err(|e| Error::InvalidConfig(format!("Invalid rule set: {}", e)))
+	}
+}
+
+/// A rule ready to run: patterns compiled, expressions parsed, and column
+/// positions resolved against the current header.
+enum Compiled {
+	Required {column: String, index: Option<usize>},
+	NotNull  {column: String, index: Option<usize>},
+	Type     {column: String, index: Option<usize>, value_type: ValueType, format: Option<String>},
+	Pattern  {column: String, index: Option<usize>, regex: Regex},
+	Range    {column: String, index: Option<usize>, min: Option<f64>, max: Option<f64>},
+	OneOf    {column: String, index: Option<usize>, values: HashSet<String>, ignore_case: bool},
+	Unique   {columns: Vec<String>, indices: Option<Vec<usize>>, seen: KeySet},
+	Check    {label: String, expression: Expression, bound: Option<Expression>},
+}
+
+pub(crate) struct Validator {
+	checks: Vec<Compiled>,
+}
+
+impl Validator {
+	pub(crate) fn new(rule_set: &RuleSet) -> Result<Self, Error> {
+		let checks = rule_set.rules.iter().map(|rule| {
+			let check = match rule.clone() {
+				Rule::Required{column}                  => Compiled::Required{column, index: None},
+				Rule::NotNull{column}                   => Compiled::NotNull{column, index: None},
+				Rule::Type{column, value_type, format}  => Compiled::Type{column, index: None, value_type, format},
+				Rule::Pattern{column, regex}            => {
+					let regex = Regex::new(&regex).map_err(|e| Error::InvalidConfig(format!("Invalid pattern for column '{}': {}", column, e)))?;
+					Compiled::Pattern{column, index: None, regex}
+				}
+				Rule::Range{column, min, max}           => {
+					if let (Some(lo), Some(hi)) = (min, max) && lo > hi {
+						return Err(Error::InvalidConfig(format!("Range for column '{}' has min {} above max {}", column, lo, hi)))
+					}
+					Compiled::Range{column, index: None, min, max}
+				}
+				Rule::OneOf{column, values, ignore_case} => {
+					let values = values.iter().map(|v| if ignore_case { v.to_lowercase() } else { v.clone() }).collect();
+					Compiled::OneOf{column, index: None, values, ignore_case}
+				}
+				Rule::Unique{columns}                   => {
+					if columns.is_empty() { return Err(Error::InvalidConfig("A unique rule needs at least one column".to_owned())) }
+					Compiled::Unique{columns, indices: None, seen: KeySet::new(UNIQUE_MEMORY_LIMIT)}
+				}
+				Rule::Check{expression, name}           => {
+					let parsed = Expression::parse(&expression)?;
+					Compiled::Check{label: name.unwrap_or(expression), expression: parsed, bound: None}
+				}
+			};
+			Ok(check)
+		});
+		Ok(Validator{checks: checks.collect::<Result<_, Error>>()?})
+	}
+
+	/// Resolve column names against a header. Rules on columns the header
+	/// lacks fail for every row, so they are reported rather than skipped.
+	pub(crate) fn bind<S: AsRef<str>>(&mut self, names: &[S]) {
+		let find = |column: &str| names.iter().position(|n| n.as_ref() == column);
+		for check in &mut self.checks {
+			match check {
+				Compiled::Required{column, index} |
+				Compiled::NotNull{column, index}  |
+				Compiled::Type{column, index, ..} |
+				Compiled::Pattern{column, index, ..} |
+				Compiled::Range{column, index, ..} |
+				Compiled::OneOf{column, index, ..} => *index = find(column),
+				Compiled::Unique{columns, indices, ..} => *indices = columns.iter().map(|c| find(c)).collect(),
+				Compiled::Check{expression, bound, ..} => *bound = expression.bind(names).ok(),
+			}
+		}
+	}
+
+	/// Every rule `row`, of `width` fields, breaks.
+	pub(crate) fn check(&mut self, row: &dyn Fields, width: usize) -> Vec<Failure> {
+		let mut failures = Vec::new();
+		let value = |index: &Option<usize>| index.map_or(Value::Null, |i| row.value(i));
+		for check in &mut self.checks {
+			let failure = match check {
+				Compiled::Required{column, index} => match index {
+					None                => Some((column.clone(), "the header has no such column".to_owned())),
+					Some(i) if *i >= width => Some((column.clone(), "the row is too short to have it".to_owned())),
+					_                   => None,
+				},
+				Compiled::NotNull{column, index} => value(index).is_null().then(|| (column.clone(), "value is empty".to_owned())),
+				Compiled::Type{column, index, value_type, format} => {
+					let v = value(index);
+					(!conforms(&v, *value_type, format.as_deref())).then(|| (column.clone(), format!("'{}' is not a valid {}", v, value_type)))
+				}
+				Compiled::Pattern{column, index, regex} => match value(index) {
+					Value::Null => None,
+					v           => (!regex.is_match(&v.to_string())).then(|| (column.clone(), format!("'{}' does not match {}", v, regex))),
+				},
+				Compiled::Range{column, index, min, max} => {
+					let v = value(index);
+					if v.is_null() { continue }
+					match numeric(&v) {
+						None                                    => Some((column.clone(), format!("'{}' is not a number", v))),
+						Some(n) if min.is_some_and(|lo| n < lo) => Some((column.clone(), format!("{} is below {}", v, min.unwrap_or_default()))),
+						Some(n) if max.is_some_and(|hi| n > hi) => Some((column.clone(), format!("{} is above {}", v, max.unwrap_or_default()))),
+						Some(_)                                 => None,
+					}
+				}
+				Compiled::OneOf{column, index, values, ignore_case} => match value(index) {
+					Value::Null => None,
+					v           => {
+						let text = if *ignore_case { v.to_string().to_lowercase() } else { v.to_string() };
+						(!values.contains(&text)).then(|| (column.clone(), format!("'{}' is not an allowed value", v)))
+					}
+				},
+				Compiled::Unique{columns, indices, seen} => {
+					let Some(indices) = indices else {
+						failures.push(Failure{rule: "unique", column: Some(columns.join(", ")), message: "the header lacks a key column".to_owned()});
+						continue
+					};
+					let texts = indices.iter().map(|&i| tagged(&row.value(i))).collect::<Vec<_>>();
+					let key   = key_hash(&(0..texts.len()).collect::<Vec<_>>(), |i| texts.get(i).map(String::as_bytes));
+					match seen.insert(key) {
+						Ok(true)   => None,
+						Ok(false)  => Some((columns.join(", "), "duplicates an earlier row".to_owned())),
+						Err(error) => Some((columns.join(", "), format!("cannot track keys: {}", error))),
+					}
+				}
+				Compiled::Check{label, bound, ..} => {
+					let failed = match bound {
+						Some(expression) => truth(&expression.evaluate(row)) == Some(false),
+						None             => true,
+					};
+					if failed {
+						let message = if bound.is_some() { label.clone() } else { format!("{} refers to a column the header lacks", label) };
+						failures.push(Failure{rule: "check", column: None, message});
+					}
+					continue
+				}
+			};
+			if let Some((column, message)) = failure {
+				failures.push(Failure{rule: rule_name(check), column: Some(column), message});
+			}
+		}
+		failures
+	}
+}
+
+fn rule_name(check: &Compiled) -> &'static str {
+	match check {
+		Compiled::Required{..} => "required",
+		Compiled::NotNull{..}  => "not_null",
+		Compiled::Type{..}     => "type",
+		Compiled::Pattern{..}  => "pattern",
+		Compiled::Range{..}    => "range",
+		Compiled::OneOf{..}    => "one_of",
+		Compiled::Unique{..}   => "unique",
+		Compiled::Check{..}    => "check",
+	}
+}
+
+/// Does `value` read as `value_type`? Text is parsed; typed values must
+/// have the type, though an integer serves where a decimal or float is due.
+fn conforms(value: &Value, value_type: ValueType, format: Option<&str>) -> bool {
+	match value {
+		Value::Null      => true,
+		Value::String(s) => Value::parse_with_format(s, value_type, format).is_ok(),
+		v                => v.value_type() == value_type || (v.value_type() == ValueType::Integer && value_type.is_numeric()),
+	}
+}
+
+fn numeric(value: &Value) -> Option<f64> {
+	match value {
+		Value::String(s) => number(s)?.as_f64(),
+		v                => v.as_f64(),
+	}
+}
+
+/// Text of a value tagged with its type, so `1` and `'1'` differ in a typed row.
+fn tagged(value: &Value) -> String {
+	match value {
+		Value::Null      => String::from("\0"),
+		Value::String(s) => s.clone(),
+		v                => format!("{}:{}", v.value_type(), v),
+	}
+}
@@ -0,0 +1,159 @@
+use std::sync::mpsc;
+use zero::component::telemetry::provides_metrics::ProvidesMetrics;
+use crate::Error;
+use crate::component::relay::Relay;
+use crate::component::relay::fixtures::{all, fields, header, initialized, one, row};
+use crate::component::relay::map_relay_config::MapRelayConfig;
+use crate::component::relay::validation_relay::{ValidationRelay, FLAG_COLUMN_KEY, ON_FAILURE_KEY, RULES_KEY};
+use crate::component::relay::validation_relay::rule_set::{Rule, RuleSet};
+use crate::component::sink::capture_sink::CaptureSink;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::row_batch::RowBatch;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::{Value, ValueType};
+
+const RULES: &str = r#"{"rules": [
+	{"rule": "not_null", "column": "id"},
+	{"rule": "type",     "column": "id", "type": "integer"},
+	{"rule": "range",    "column": "amount", "min": 0, "max": 100},
+	{"rule": "one_of",   "column": "status", "values": ["new", "done"], "ignore_case": true},
+	{"rule": "check",    "expression": "shipped >= ordered", "name": "shipped after ordered"}
+]}"#;
+
+const HEADER: [&str; 5] = ["id", "amount", "status", "ordered", "shipped"];
+
+fn relay(cfg: MapRelayConfig) -> ValidationRelay {
+	initialized(ValidationRelay::new(), &cfg.with(RULES_KEY, RULES))
+}
+
+fn error(atom: Option<Atom>) -> String {
+	match atom {
+		Some(Atom::ErrorAtom(Error::InvalidInput(message), _)) => message,
+		other                                                  => panic!("expected an error, got {:?}", other),
+	}
+}
+
+#[test]
+fn rule_set_round_trips_through_json() {
+	let rules = RuleSet::from_json(RULES).unwrap();
+	assert_eq!(rules.rules.len(), 5);
+	assert_eq!(rules.rules[1], Rule::Type{column: "id".to_owned(), value_type: ValueType::Integer, format: None});
+	assert_eq!(RuleSet::from_json(&rules.to_json().unwrap()).unwrap(), rules);
+
+	assert!(RuleSet::from_json(r#"{"rules": [{"rule": "sometimes", "column": "a"}]}"#).is_err());
+	assert!(RuleSet::new(vec![Rule::Pattern{column: "a".to_owned(), regex: "(".to_owned()}]).validate().is_err());
+	assert!(RuleSet::new(vec![Rule::Range{column: "a".to_owned(), min: Some(5.0), max: Some(1.0)}]).validate().is_err());
+	assert!(RuleSet::new(vec![Rule::Check{expression: "a >".to_owned(), name: None}]).validate().is_err());
+}
+
+#[test]
+fn failing_rows_become_errors_naming_column_and_rule() {
+	let mut relay = relay(MapRelayConfig::new());
+	assert!(matches!(one(&mut relay, header(&HEADER)), Some(Atom::HeaderRow(_))));
+
+	let valid = one(&mut relay, Atom::ByteRowAtom(row(&["1", "50", "NEW", "2024-01-01", "2024-01-02"]))).unwrap();
+	assert_eq!(fields(&valid), ["1", "50", "NEW", "2024-01-01", "2024-01-02"]);
+
+	let message = error(one(&mut relay, Atom::ByteRowAtom(row(&["x", "150", "lost", "2024-01-05", "2024-01-02"]))));
+	assert!(message.contains("column 'id' failed type"), "{}", message);
+	assert!(message.contains("column 'amount' failed range: 150 is above 100"), "{}", message);
+	assert!(message.contains("column 'status' failed one_of"), "{}", message);
+	assert!(message.contains("check failed: shipped after ordered"), "{}", message);
+
+	let message = error(one(&mut relay, Atom::ByteRowAtom(row(&["", "", "", "", ""]))));
+	assert_eq!(message, "[ValidationRelay]: column 'id' failed not_null: value is empty");
+
+	assert!(relay.finish(&mut Vec::new()).is_ok());
+	let metrics = relay.metrics();
+	assert_eq!((metrics.record_count, metrics.error_count, metrics.dropped_count), (3, 2, 2));
+}
+
+#[test]
+fn flag_mode_passes_rows_with_their_failures() {
+	let mut relay = relay(MapRelayConfig::new().with(ON_FAILURE_KEY, "flag").with(FLAG_COLUMN_KEY, "problems"));
+	assert_eq!(fields(&one(&mut relay, header(&HEADER)).unwrap()), ["id", "amount", "status", "ordered", "shipped", "problems"]);
+	assert_eq!(fields(&one(&mut relay, Atom::ByteRowAtom(row(&["1", "5", "done", "1", "2"]))).unwrap()), ["1", "5", "done", "1", "2", ""]);
+	let flagged = fields(&one(&mut relay, Atom::StringRowAtom(row(&["2", "-1", "done", "1", "2"]).as_string_row())).unwrap());
+	assert_eq!(flagged[5], "column 'amount' failed range: -1 is below 0");
+
+	match one(&mut relay, Atom::TypedRowAtom(TypedRow::new(vec![Value::Integer(3), Value::Float(1.5), Value::String("new".into()), Value::Integer(1), Value::Integer(1)]))) {
+		Some(Atom::TypedRowAtom(r)) => assert_eq!(r.get(5), Some(&Value::Null)),
+		other                       => panic!("expected a typed row, got {:?}", other),
+	}
+}
+
+#[test]
+fn quarantine_mode_routes_failing_rows_to_the_sink() {
+	let (tx, _rx) = mpsc::channel();
+	let mut relay = ValidationRelay::new().with_quarantine(Box::new(CaptureSink::new(1, tx)));
+	relay.initialize(&MapRelayConfig::new().with(RULES_KEY, RULES)).unwrap();
+
+	assert_eq!(fields(&one(&mut relay, header(&HEADER)).unwrap()), HEADER);
+	assert!(one(&mut relay, Atom::ByteRowAtom(row(&["1", "5", "new", "1", "2"]))).is_some_and(|a| matches!(a, Atom::ByteRowAtom(_))));
+	error(one(&mut relay, Atom::ByteRowAtom(row(&["2", "5", "new", "3", "2"]))));
+	assert!(relay.finish(&mut Vec::new()).is_ok());
+
+	let quarantined = relay.take_quarantine().unwrap().drain_atoms();
+	assert_eq!(quarantined.len(), 2);
+	assert_eq!(fields(&quarantined[0]), ["id", "amount", "status", "ordered", "shipped", "validation_errors"]);
+	assert_eq!(fields(&quarantined[1]), ["2", "5", "new", "3", "2", "check failed: shipped after ordered"]);
+}
+
+#[test]
+fn quarantine_mode_needs_a_sink() {
+	let mut relay = ValidationRelay::new();
+	let cfg       = MapRelayConfig::new().with(RULES_KEY, RULES).with(ON_FAILURE_KEY, "quarantine");
+	assert!(matches!(relay.initialize(&cfg), Err(Error::InvalidConfig(_))));
+	assert!(matches!(ValidationRelay::new().initialize(&MapRelayConfig::new()), Err(Error::InvalidConfig(_))));
+}
+
+#[test]
+fn missing_columns_and_duplicates_fail() {
+	let rules     = r#"{"rules": [{"rule": "required", "column": "email"}, {"rule": "unique", "columns": ["id"]}, {"rule": "pattern", "column": "id", "regex": "^[a-z]\\d+$"}]}"#;
+	let mut relay = ValidationRelay::new();
+	relay.initialize(&MapRelayConfig::new().with(RULES_KEY, rules)).unwrap();
+	one(&mut relay, header(&["id", "email"]));
+	assert!(one(&mut relay, Atom::ByteRowAtom(row(&["a1", "x@y"]))).is_some_and(|a| matches!(a, Atom::ByteRowAtom(_))));
+	assert!(error(one(&mut relay, Atom::ByteRowAtom(row(&["a1", "z@y"])))).contains("column 'id' failed unique"));
+	assert!(error(one(&mut relay, Atom::ByteRowAtom(row(&["b2"])))).contains("column 'email' failed required"));
+	assert!(error(one(&mut relay, Atom::ByteRowAtom(row(&["33", "q@y"])))).contains("failed pattern"));
+
+	one(&mut relay, header(&["id"]));
+	assert!(error(one(&mut relay, Atom::ByteRowAtom(row(&["c3"])))).contains("the header has no such column"));
+}
+
+#[test]
+fn batches_keep_their_valid_rows() {
+	let mut relay = relay(MapRelayConfig::new());
+	let rows      = [row(&["1", "5", "new", "1", "2"]), row(&["2", "500", "new", "1", "2"]), row(&["3", "", "", "", ""])];
+	one(&mut relay, header(&HEADER));
+	let mut out = all(&mut relay, Atom::RowBatchAtom(RowBatch::from_rows(&rows).unwrap()));
+	match out.pop() {
+		Some(Atom::RowBatchAtom(kept)) => {
+			assert_eq!(kept.len(), 2);
+			assert_eq!(kept.get(1, 0), Some(b"3".as_slice()));
+		}
+		other                          => panic!("expected a batch, got {:?}", other),
+	}
+	assert!(error(out.pop()).contains("column 'amount' failed range"));                // Each failing row is reported before the batch
+	assert!(out.is_empty());
+	assert_eq!((relay.metrics().error_count, relay.metrics().dropped_count), (1, 1));
+
+	let mut relay = relay_flagging();
+	match one(&mut relay, Atom::RowBatchAtom(RowBatch::from_rows(&rows).unwrap())) {
+		Some(Atom::RowBatchAtom(flagged)) => {
+			assert_eq!((flagged.len(), flagged.width()), (3, 6));
+			assert_eq!(flagged.get(0, 5), Some(b"".as_slice()));
+			assert!(flagged.get(1, 5).is_some_and(|f| f.starts_with(b"column 'column_2' failed range")));
+		}
+		other                             => panic!("expected a batch, got {:?}", other),
+	}
+}
+
+/// Positional names: the rules here refer to the second column.
+fn relay_flagging() -> ValidationRelay {
+	let mut relay = ValidationRelay::new();
+	let rules     = r#"{"rules": [{"rule": "range", "column": "column_2", "max": 100}]}"#;
+	relay.initialize(&MapRelayConfig::new().with(RULES_KEY, rules).with(ON_FAILURE_KEY, "flag")).unwrap();
+	relay
+}
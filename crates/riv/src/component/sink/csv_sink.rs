@@ -89,6 +89,17 @@ impl Sink for CsvSink {
 				let data = vec.into_boxed_slice();
 				writer.write_record(data).map_err(e_map)?;
 			},
+			Atom::TypedRowAtom(typed_row) => {
+				let vec  = typed_row.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+				let data = vec.into_boxed_slice();
+				writer.write_record(data).map_err(e_map)?;
+			},
+			Atom::RowBatchAtom(batch) => {
+				for row in 0..batch.len() {
+					let fields = batch.columns().iter().map(|c| c.get(row).unwrap_or_default());
+					writer.write_record(fields).map_err(e_map)?;
+				}
+			},
 			Atom::HeaderRow(header_row) => {
 				let vec  = header_row.into_iter().collect::<Vec<_>>();
 				let data = vec.into_boxed_slice();
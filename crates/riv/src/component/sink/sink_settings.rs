@@ -10,6 +10,7 @@ use crate::component::sink::json_sink::JsonSink;
 use crate::component::sink::kafka_sink::KafkaSink;
 use crate::component::sink::sql_server_sink::SqlServerSink;
 use crate::component::sink::sqlite_sink::SqliteSink;
+use crate::model::schema::Schema;
 use crate::Error;
 
 /// One strongly typed configuration value covering every supported sink.
@@ -54,10 +55,12 @@ pub enum SinkSettings {
         topic:  String,
     },    
     
-    /// Persists to SQLite table.
+    /// Persists to SQLite table, with column types from `schema` or
+    /// inferred from the first rows.
     Sqlite {
         db_path: PathBuf,
         table:   String,
+        schema:  Option<Schema>,
     },
     
     /// Persists to Sql Server database.
@@ -110,6 +113,15 @@ impl SinkSettings {
         Self::Sqlite {
             db_path: db_path.into(),
             table:   table.into(),
+            schema:  None,
+        }
+    }
+
+    pub fn sqlite_with_schema<P: Into<PathBuf>>(db_path: P, table: impl Into<String>, schema: Schema) -> Self {
+        Self::Sqlite {
+            db_path: db_path.into(),
+            table:   table.into(),
+            schema:  Some(schema),
         }
     }
 
@@ -181,10 +193,14 @@ impl SinkSettings {
 					Ok(sink)
             }
 
-            SinkSettings::Sqlite {db_path, table} => {
+            SinkSettings::Sqlite {db_path, table, schema} => {
 	            let file_path = db_path.clone();
 	            let table     = table.clone();
             	let sink   = SqliteSink::new(component_id, file_path, table, tx);
+            	let sink   = match schema {
+            		Some(schema) => sink.with_schema(schema.clone()),
+            		None         => sink,
+            	};
             	let sink   = Box::new(sink);
 					Ok(sink)
             }
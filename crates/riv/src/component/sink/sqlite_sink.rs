@@ -13,11 +13,32 @@ use crate::component::sink::{Sink, SinkKind};
 use crate::component::sink::sink_settings::SinkSettings;
 use crate::model::ir::atom::Atom;
 use crate::model::ir::atom_type::AtomType;
+use crate::model::blueprint::ddl::sqlite_column_type;
+use crate::model::ir::value::{Value, ValueType};
+use crate::model::schema::Schema;
+use crate::model::schema::inference::{SchemaInference, DEFAULT_SAMPLE_SIZE};
+use rusqlite::types::{ToSql, ToSqlOutput, ValueRef};
+use rusqlite::Params;
+
+/// Table holding the run's `InternalMetadata` bindings.
+pub const RUN_METADATA_TABLE: &str = "run_metadata";
 
 /// A Sink that writes rows into a SQLite database.  
 /// 
 /// It expects to see a `HeaderRowAtom` first, which defines column names.  
-/// Subsequent row atoms (`ByteRowAtom` or `StringRowAtom`) are inserted into the table.
+/// Subsequent row atoms (`ByteRowAtom`, `StringRowAtom`, `TypedRowAtom` or
+/// `RowBatchAtom`) are inserted into the table.
+///
+/// Column types come from the `Schema` given with `with_schema`. Without
+/// one, the sink holds the first `DEFAULT_SAMPLE_SIZE` rows, infers a
+/// schema from them, and creates the table before inserting them. Text
+/// cells are converted to their column's type and bound natively; a cell
+/// that does not convert is stored as text. Typed rows are bound as they
+/// are.
+///
+/// `InternalMetadata` bindings, such as a header mapping, are the run's
+/// metadata: they are written to `RUN_METADATA_TABLE` as name and value,
+/// a later binding of the same name replacing the earlier one.
 pub struct SqliteSink {
 	component_id:     u32,
     /// Filesystem path to the SQLite database file
@@ -29,6 +50,16 @@ pub struct SqliteSink {
     cx: Option<Connection>,
     /// Column names, populated when a `HeaderRowAtom` is accepted
     columns: Vec<String>,
+    /// Whether the table for the current header exists yet
+    table_created: bool,
+    /// Column types given up front
+    schema: Option<Schema>,
+    /// Samples the first rows when no schema was given
+    inference: Option<SchemaInference>,
+    /// Rows held while the sample is taken
+    pending: Vec<Atom>,
+    /// Type and format of each column, once the table exists
+    layout: Vec<(ValueType, Option<String>)>,
 	created_utc:      Instant,
 	started_utc:      Instant,
 	metrics:          ComponentMetrics,
@@ -46,12 +77,75 @@ impl SqliteSink {
             table,
             cx: None,
             columns: Vec::new(),
+            table_created: false,
+            schema: None,
+            inference: None,
+            pending: Vec::new(),
+            layout: Vec::new(),
             tx,
             created_utc,
             started_utc,
             metrics,
         }
     }
+
+    /// Declare the column types instead of inferring them. Columns are
+    /// matched to fields by name; a column without a field is TEXT.
+    pub fn with_schema(mut self, schema: Schema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    /// Create the table from the schema, then insert any rows held for the sample.
+    fn create(&mut self) -> Result<(), Error> {
+        let schema = match (&self.schema, &self.inference) {
+            (Some(schema), _)       => schema.clone(),
+            (None, Some(inference)) => inference.schema(),
+            (None, None)            => Schema::default(),
+        };
+        self.layout = self.columns.iter()
+            .map(|c| match schema.field(c) {
+                Some(field) if field.value_type != ValueType::Null => (field.value_type, field.format.clone()),
+                _                                                  => (ValueType::String, None),
+            })
+            .collect();
+        let cx    = self.cx.as_ref().ok_or_else(|| Error::General("SqliteSink used before initialize".into()))?;
+        let types = self.layout.iter().map(|(t, _)| *t).collect::<Vec<_>>();
+        create_table(cx, &self.table, &self.columns, &types)?;
+        self.table_created = true;
+        self.inference     = None;
+        for atom in std::mem::take(&mut self.pending) {
+            self.insert(atom)?;
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, atom: Atom) -> Result<(), Error> {
+        let cx = self.cx.as_mut().ok_or_else(|| Error::General("SqliteSink used before initialize".into()))?;
+        match atom {
+            Atom::StringRowAtom(row) => {
+                let vals = row.iter_str().zip(&self.layout).map(|(text, column)| convert(text.as_bytes(), column));
+                insert_row(cx, &self.table, &self.columns, params_from_iter(vals))
+            }
+            Atom::ByteRowAtom(row)   => {
+                let vals = row.into_iter().zip(&self.layout).map(|(bytes, column)| convert(bytes, column));
+                insert_row(cx, &self.table, &self.columns, params_from_iter(vals))
+            }
+            Atom::TypedRowAtom(row)  => insert_row(cx, &self.table, &self.columns, params_from_iter(row.iter())),
+            Atom::RowBatchAtom(batch) => {
+                // A batch is inserted in a single transaction
+                let tx = cx.transaction()
+                    .map_err(|e| Error::General(format!("Failed to begin transaction: {}", e)))?;
+                for row in 0..batch.len() {
+                    let vals = batch.columns().iter().zip(&self.layout).map(|(c, column)| convert(c.get(row).unwrap_or_default(), column));
+                    insert_row(&tx, &self.table, &self.columns, params_from_iter(vals))?;
+                }
+                tx.commit()
+                    .map_err(|e| Error::General(format!("Failed to commit batch: {}", e)))
+            }
+            _                        => Ok(()),
+        }
+    }
 }
 
 impl Sink for SqliteSink {
@@ -72,121 +166,80 @@ impl Sink for SqliteSink {
 
     fn accept(&mut self, atom: Atom) -> Result<(), Error> {
         // If Sink not initialized yet, that's a logic error
-        let cx = self.cx.as_mut().ok_or_else(|| {
-            Error::General("SqliteSink.accept called before initialize".into())
-        })?;
+        if self.cx.is_none() {
+            return Err(Error::General("SqliteSink.accept called before initialize".into()));
+        }
 
         // Skip control atoms
         if atom.atom_type() == AtomType::Control {
             return Ok(());
         }
 
-        match atom {
+        let (kind, width) = match &atom {
             Atom::HeaderRow(header_row) => {
                 // 2) Extract column names from StringRow
-                //let cols: Vec<String> = header_row.into_iter().cloned().collect();
-                let cols: Vec<String> = header_row.into_iter().as_slice().to_vec();
+                let cols: Vec<String> = header_row.iter_str().map(str::to_owned).collect();
                 if cols.is_empty() {
                     return Err(Error::General("Header row is empty".into()));
                 }
-                self.columns = cols.clone();
-
-                // 3) Build and execute CREATE TABLE statement
-                //    Columns are all TEXT type for simplicity
-                let col_defs: Vec<String> = cols.iter()
-                    .map(|c| format!("\"{}\" TEXT", c.replace('"', "\"\"")))
-                    .collect();
-                let create_sql = format!(
-                    "CREATE TABLE IF NOT EXISTS \"{}\" ({})",
-                    self.table,
-                    col_defs.join(", ")
-                );
-                cx.execute_batch(&create_sql)
-                    .map_err(|e| Error::General(format!("Failed to create table: {}", e)))?;
-            }
-
-            Atom::StringRowAtom(string_row) => {
-                // 4) Ensure header seen
-                if self.columns.is_empty() {
-                    return Err(Error::General(
-                        "Received StringRowAtom before HeaderRowAtom".into(),
-                    ));
-                }
-                let vals: Vec<String> = string_row.into_iter().collect();
-                if vals.len() != self.columns.len() {
-                    return Err(Error::General(format!(
-                        "Row has {} columns but header has {}",
-                        vals.len(),
-                        self.columns.len()
-                    )));
+                // 3) Rows held for the previous header are written first; the
+                //    table is created once column types are known
+                if !self.columns.is_empty() && !self.table_created {
+                    self.create()?;
                 }
-
-                // 5) Build INSERT statement with ? placeholders
-                let placeholders = vec!["?"; vals.len()].join(", ");
-                let insert_sql = format!(
-                    "INSERT INTO \"{}\" ({}) VALUES ({})",
-                    self.table,
-                    self.columns.iter()
-                        .map(|c| format!("\"{}\"", c.replace('"', "\"\"")))
-                        .collect::<Vec<_>>()
-                        .join(", "),
-                    placeholders
-                );
-                let mut stmt = cx.prepare(&insert_sql)
-                    .map_err(|e| Error::General(format!("Failed to prepare INSERT: {}", e)))?;
-
-                // 6) Bind and execute
-                stmt.execute(params_from_iter(vals.iter()))
-                    .map_err(|e| Error::General(format!("Failed to insert row: {}", e)))?;
+                self.columns       = cols;
+                self.table_created = false;
+                self.pending.clear();
+                self.inference     = match self.schema {
+                    Some(_) => None,
+                    None    => {
+                        let mut inference = SchemaInference::new(DEFAULT_SAMPLE_SIZE);
+                        inference.accept(&atom);
+                        Some(inference)
+                    }
+                };
+                return Ok(());
             }
-
-            Atom::ByteRowAtom(byte_row) => {
-                // Similar to StringRow, but convert bytes to String (UTF-8 lossy)
-                if self.columns.is_empty() {
-                    return Err(Error::General(
-                        "Received ByteRowAtom before HeaderRowAtom".into(),
-                    ));
-                }
-                let mut vals: Vec<String> = Vec::new();
-                for b in byte_row.into_iter() {
-                    let s = String::from_utf8_lossy(b).into_owned();
-                    vals.push(s);
-                }
-                if vals.len() != self.columns.len() {
-                    return Err(Error::General(format!(
-                        "Row has {} columns but header has {}",
-                        vals.len(),
-                        self.columns.len()
-                    )));
-                }
-
-                // Build and execute same INSERT as above
-                let placeholders = vec!["?"; vals.len()].join(", ");
-                let insert_sql = format!(
-                    "INSERT INTO \"{}\" ({}) VALUES ({})",
-                    self.table,
-                    self.columns.iter()
-                        .map(|c| format!("\"{}\"", c.replace('"', "\"\"")))
-                        .collect::<Vec<_>>()
-                        .join(", "),
-                    placeholders
-                );
-                let mut stmt = cx.prepare(&insert_sql)
-                    .map_err(|e| Error::General(format!("Failed to prepare INSERT: {}", e)))?;
-                stmt.execute(params_from_iter(vals.iter()))
-                    .map_err(|e| Error::General(format!("Failed to insert row: {}", e)))?;
+            Atom::InternalMetadata(bindings) => {
+                let cx = self.cx.as_ref().ok_or_else(|| Error::General("SqliteSink used before initialize".into()))?;
+                return record_metadata(cx, bindings);
             }
+            Atom::StringRowAtom(row)  => ("StringRowAtom", row.count() as usize),
+            Atom::ByteRowAtom(row)    => ("ByteRowAtom",   row.length() as usize),
+            Atom::TypedRowAtom(row)   => ("TypedRowAtom",  row.count() as usize),
+            Atom::RowBatchAtom(batch) => ("RowBatchAtom",  batch.width()),
+            _                         => return Ok(()),   // Other atom types are ignored
+        };
 
-            _ => {
-                // Other atom types are ignored
+        // 4) Ensure header seen
+        if self.columns.is_empty() {
+            return Err(Error::General(format!("Received {} before HeaderRowAtom", kind)));
+        }
+        check_width(width, &self.columns)?;
+        if !self.table_created {
+            match self.inference.as_mut() {
+                Some(inference) => {
+                    // 5) Hold rows until the sample is complete
+                    inference.accept(&atom);
+                    let complete = inference.is_complete();
+                    self.pending.push(atom);
+                    return if complete { self.create() } else { Ok(()) };
+                }
+                None            => self.create()?,
             }
         }
-
-        Ok(())
+        // 6) Insert
+        self.insert(atom)
     }
 
     fn close(&mut self) {
-        // 7) Finalize by closing the connection (drop it)
+        // 7) Rows still held for the sample are written now, and a header
+        //    without rows still produces an (empty) table
+        if self.cx.is_some() && !self.columns.is_empty() && !self.table_created
+            && let Err(err) = self.create() {
+            warn!("{}", err);
+        }
+        // 8) Finalize by closing the connection (drop it)
         if let Some(cx) = self.cx.take() {
             match cx.close() {
             	Ok(_)         => info!("Successfully closed the connection."),
@@ -208,3 +261,88 @@ impl ProvidesMetrics for SqliteSink {
 		rv
 	}
 }
+
+/// `bytes` as a value of the column's type, or as text when they do not convert.
+fn convert(bytes: &[u8], (value_type, format): &(ValueType, Option<String>)) -> Value {
+    Value::from_bytes(bytes, *value_type, format.as_deref())
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(bytes).into_owned()))
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn check_width(count: usize, columns: &[String]) -> Result<(), Error> {
+    if count == columns.len() {
+        return Ok(());
+    }
+    Err(Error::General(format!(
+        "Row has {} columns but header has {}",
+        count,
+        columns.len()
+    )))
+}
+
+// Columns without a known type are TEXT.
+fn create_table(cx: &Connection, table: &str, columns: &[String], types: &[ValueType]) -> Result<(), Error> {
+    let col_defs: Vec<String> = columns.iter()
+        .enumerate()
+        .map(|(i, c)| {
+            let declared = types.get(i).copied().map(sqlite_column_type).unwrap_or("TEXT");
+            format!("{} {}", quote_identifier(c), declared)
+        })
+        .collect();
+    let create_sql = format!(
+        "CREATE TABLE IF NOT EXISTS {} ({})",
+        quote_identifier(table),
+        col_defs.join(", ")
+    );
+    cx.execute_batch(&create_sql)
+        .map_err(|e| Error::General(format!("Failed to create table: {}", e)))
+}
+
+fn record_metadata(cx: &Connection, bindings: &[(String, String)]) -> Result<(), Error> {
+    let table = quote_identifier(RUN_METADATA_TABLE);
+    cx.execute_batch(&format!("CREATE TABLE IF NOT EXISTS {} (name TEXT PRIMARY KEY, value TEXT)", table))
+        .map_err(|e| Error::General(format!("Failed to create {}: {}", RUN_METADATA_TABLE, e)))?;
+    let mut stmt = cx.prepare_cached(&format!("INSERT OR REPLACE INTO {} (name, value) VALUES (?1, ?2)", table))
+        .map_err(|e| Error::General(format!("Failed to prepare INSERT: {}", e)))?;
+    for (name, value) in bindings {
+        stmt.execute([name, value])
+            .map_err(|e| Error::General(format!("Failed to record {}: {}", name, e)))?;
+    }
+    Ok(())
+}
+
+fn insert_row<P: Params>(cx: &Connection, table: &str, columns: &[String], params: P) -> Result<(), Error> {
+    // Build INSERT statement with ? placeholders
+    let placeholders = vec!["?"; columns.len()].join(", ");
+    let insert_sql = format!(
+        "INSERT INTO {} ({}) VALUES ({})",
+        quote_identifier(table),
+        columns.iter().map(|c| quote_identifier(c)).collect::<Vec<_>>().join(", "),
+        placeholders
+    );
+    let mut stmt = cx.prepare_cached(&insert_sql)
+        .map_err(|e| Error::General(format!("Failed to prepare INSERT: {}", e)))?;
+    stmt.execute(params)
+        .map_err(|e| Error::General(format!("Failed to insert row: {}", e)))?;
+    Ok(())
+}
+
+/// Typed values are stored natively. Decimals, dates and timestamps are
+/// bound as their ISO text form and left to the column affinity.
+impl ToSql for Value {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        let output = match self {
+            Value::Null         => ToSqlOutput::Borrowed(ValueRef::Null),
+            Value::Boolean(v)   => ToSqlOutput::from(*v),
+            Value::Integer(v)   => ToSqlOutput::from(*v),
+            Value::Float(v)     => ToSqlOutput::from(*v),
+            Value::String(v)    => ToSqlOutput::Borrowed(ValueRef::Text(v.as_bytes())),
+            Value::Bytes(v)     => ToSqlOutput::Borrowed(ValueRef::Blob(v)),
+            other               => ToSqlOutput::from(other.to_string()),
+        };
+        Ok(output)
+    }
+}
@@ -3,12 +3,19 @@ pub mod csv_adapter;
 pub mod csv_byte_source;
 pub mod csv_string_source;
 pub mod path_buf_config;
+pub mod sqlite_source;
+pub mod synthetic_source;
+pub mod synthetic_spec;
 pub mod vector_source;
 
 #[cfg(test)]
 mod vector_source_tests;
 #[cfg(test)]
 mod csv_byte_source_tests;
+#[cfg(test)]
+mod sqlite_source_tests;
+#[cfg(test)]
+mod synthetic_source_tests;
 
 use std::fmt::{Debug, Display};
 use std::path::PathBuf;
@@ -21,7 +28,9 @@ use crate::error::Error;
 pub enum SourceType {
 	Csv,
 	Json,
+	Sqlite,
 	StaticData,
+	Synthetic,
 }
 
 /// Tracks where a producer is in its lifecycle.
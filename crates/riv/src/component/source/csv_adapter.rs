@@ -24,18 +24,20 @@ use crate::model::ir::string_row::StringRow;
 
 pub struct CsvState {
 	pub header_atom:      Option<Atom>,
+	pub header_width:     usize,
 	pub iterator:         csv::ByteRecordsIntoIter<File>,
 }
 
 impl CsvState {
 	pub fn new(file_path: &String) -> Result<Self, Error> {
 		let file        = File::open(file_path).map_err(IoErrorWrapper::from)?;
-		let mut reader  = ReaderBuilder::new().delimiter(b';').from_reader(file);
+		let mut reader  = ReaderBuilder::new().delimiter(b';').flexible(true).from_reader(file);
 		let headers     = reader.byte_headers().map_err(|e| Error::Parse(e.to_string()))?;
-		let header_atom = compute_headers(headers);
-		let header_atom = Some(header_atom);
-		let iterator    = reader.into_byte_records();
-		let csv_state   = CsvState {header_atom, iterator};
+		let header_width = headers.len();
+		let header_atom  = compute_headers(headers);
+		let header_atom  = Some(header_atom);
+		let iterator     = reader.into_byte_records();
+		let csv_state    = CsvState {header_atom, header_width, iterator};
 		Ok(csv_state)
 	}
 }
@@ -51,17 +53,20 @@ impl Debug for CsvState {
 pub fn compute_coordinate(r: &ByteRecord) -> Coordinate {
 	match r.position() {
 		None    => Coordinate::Undefined,
-		Some(p) => {
-			let line     = p.line();
-			let byte     = p.byte();
-			let column   = 0;
-			let location = TextLocation{line, column, byte};
-			Position(location)
-		}
+		Some(p) => compute_position(p),
 	}
 }
 
+pub fn compute_position(p: &csv::Position) -> Coordinate {
+	let line     = p.line();
+	let byte     = p.byte();
+	let column   = 1;
+	let location = TextLocation{line, column, byte};
+	Position(location)
+}
+
 pub fn compute_headers(r: &ByteRecord) -> Atom {
-	let data_record = StringRow::new(r);
+	let coordinate  = compute_coordinate(r);
+	let data_record = StringRow::new(r).with_coordinate(coordinate);
 	HeaderRow(data_record)
 }
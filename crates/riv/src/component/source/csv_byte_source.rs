@@ -8,20 +8,41 @@ use std::fmt::{Debug, Display};
 use std::fs::File;
 use std::io;
 use std::io::{BufReader, Read};
+use std::ops::Range;
+use std::sync::Arc;
 use csv::ReaderBuilder;
 use tracing::{info, instrument, warn};
 use crate::error::IoErrorWrapper;
 use crate::model::ir::atom::Atom::{ByteRowAtom, ErrorAtom, HeaderRow};
+use crate::model::coordinate::coordinate::Coordinate;
+use crate::model::coordinate::text_location::TextLocation;
 use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::row_block::RowBlock;
 
 const MAX_RECORD_SIZE:       usize = 1024 * 16;
 const MAX_FIELDS_PER_RECORD: usize =      1024;
 const CHUNK_SIZE:            usize = 1024 * 8;
+const ARENA_CAPACITY:        usize = CHUNK_SIZE + MAX_RECORD_SIZE;
 
 
 type CsvByteSourceState<R> = SourceState<ByteReaderState<R>>;
 
+/// Where a parsed record sits in the arena.
+#[derive(Debug)]
+struct RecordSpan {
+	start:      usize,
+	ends:       Range<usize>,
+	coordinate: Coordinate,
+}
 
+/// Parser state for `CsvByteSource`.
+///
+/// Records are parsed into an arena (`arena` and `arena_ends`). When the
+/// current chunk has been consumed the completed records are sealed into
+/// one shared `RowBlock`, so rows are handed out without a copy or an
+/// allocation each. A record still in progress is carried into the next
+/// arena.
+///
 #[derive(Debug)]
 pub struct ByteReaderState<R: Read> {
 	pub(crate)  start:          usize,
@@ -30,9 +51,20 @@ pub struct ByteReaderState<R: Read> {
 	pub(crate)  total_bytes:    usize,
 	pub(crate)  chunk_count:    usize,
 	pub(crate)  needs_header:   bool,
+	pub(crate)  header_width:   usize,
+	pub(crate)  eof:            bool,
+	pub(crate)  output_len:     usize,
+	pub(crate)  ends_len:       usize,
+	pub(crate)  record_offset:  usize,
+	pub(crate)  record_ends:    usize,
+	pub(crate)  record_start:   Coordinate,
+	records:                    Vec<RecordSpan>,
+	block:                      Option<Arc<RowBlock>>,
+	sealed:                     Range<usize>,
+	failure:                    Option<Error>,
 	pub(crate)  chunk_buffer:   [u8; CHUNK_SIZE],
-	pub(crate)  output_record:  [u8; MAX_RECORD_SIZE],
-	pub(crate)  field_indices:  [usize; MAX_FIELDS_PER_RECORD * 2],
+	pub(crate)  arena:          Vec<u8>,
+	pub(crate)  arena_ends:     Vec<usize>,
 	pub(crate)  buf_reader:     BufReader<R>,
 	pub(crate)  parser:         Reader,
 }
@@ -45,16 +77,35 @@ impl<R: Read> ByteReaderState<R> {
 		let total_bytes   = 0;
 		let chunk_count   = 0;
 		let needs_header  = true;
+		let header_width  = 0;
+		let eof           = false;
+		let output_len    = 0;
+		let ends_len      = 0;
+		let record_offset = 0;
+		let record_ends   = 0;
+		let record_start  = Coordinate::Undefined;
+		let records       = Vec::new();
+		let block         = None;
+		let sealed        = 0..0;
+		let failure       = None;
 		let chunk_buffer  = [0; CHUNK_SIZE];
-		let output_record = [0; MAX_RECORD_SIZE];
-		let field_indices = [0; MAX_FIELDS_PER_RECORD * 2];
-		ByteReaderState{start, end, input_offset, total_bytes, chunk_count, needs_header, chunk_buffer, output_record, field_indices, buf_reader, parser}
+		let arena         = Vec::with_capacity(ARENA_CAPACITY);
+		let arena_ends    = Vec::new();
+		ByteReaderState{start, end, input_offset, total_bytes, chunk_count, needs_header, header_width, eof,
+			output_len, ends_len, record_offset, record_ends, record_start, records, block, sealed, failure,
+			chunk_buffer, arena, arena_ends, buf_reader, parser}
 	}
 
 	// Have we parsed everything that has been read from the latest file read?
 	//
 	fn needs_fill(&self) -> bool {self.start == self.end}
 
+	// Are we between records, i.e. has no part of the next record been parsed yet?
+	//
+	fn at_record_boundary(&self) -> bool {
+		self.output_len == self.record_offset && self.ends_len == self.record_ends && self.record_start.is_none()
+	}
+
 	// Read the next chunk from the file
 	//
 	fn fill_buffer(&mut self) -> Result<bool, io::Error> {
@@ -74,6 +125,167 @@ impl<R: Read> ByteReaderState<R> {
 			Ok(true)
 		}
 	}
+
+	// Line terminators between records are skipped here rather than by the parser
+	// so the line and byte recorded for a record point at its first byte.
+	//
+	fn skip_line_terminators(&mut self) {
+		while self.start < self.end {
+			match self.chunk_buffer[self.start] {
+				b'\n' => self.parser.set_line(self.parser.line() + 1),
+				b'\r' => {},
+				_     => break,
+			}
+			self.start        += 1;
+			self.input_offset += 1;
+		}
+	}
+
+	fn mark_record_start(&mut self) {
+		let line     = self.parser.line();
+		let byte     = self.input_offset as u64;
+		let location = TextLocation::new(byte, line, 1);
+		self.record_start = Coordinate::at_location(location);
+	}
+
+	/// Parse the next complete record, refilling the chunk buffer as often as needed.
+	///
+	/// Returns `Ok(None)` once the input is exhausted.
+	///
+	fn next_atom(&mut self) -> Result<Option<Atom>, Error> {
+		loop {
+			if let Some(i)   = self.sealed.next()    { return Ok(Some(self.take_row(i))) }
+			if let Some(err) = self.failure.take()   { return Err(err)                   }
+			if self.sealed.end > 0 {
+				self.records.clear();                                               // Every sealed record has been handed out
+				self.sealed = 0..0;
+			}
+
+			if self.needs_fill() {
+				if !self.records.is_empty() {
+					self.seal_block();                                              // Hand out this chunk's rows before reading more
+					continue;
+				}
+				if !self.eof {
+					let filled = self.fill_buffer().map_err(IoErrorWrapper::from)?;
+					self.eof   = !filled;
+				}
+			}
+			if self.at_record_boundary() {
+				self.skip_line_terminators();
+				if self.needs_fill() && !self.eof { continue; }
+				self.mark_record_start();
+			}
+
+			let (room_bytes, room_ends) = self.reserve_record_room();
+			let input      = &self.chunk_buffer[self.start..self.end];
+			let output     = &mut self.arena[self.output_len..self.output_len + room_bytes];
+			let ends       = &mut self.arena_ends[self.ends_len..self.ends_len + room_ends];
+			let (result, bytes_read, bytes_written, field_count) = self.parser.read_record(input, output, ends);
+			for end in &mut self.arena_ends[self.ends_len..self.ends_len + field_count] {
+				*end += self.record_offset;                                         // The parser counts ends from the start of the record
+			}
+			self.start        += bytes_read;                                      // Slide forward in the chunk buffer
+			self.input_offset += bytes_read;
+			self.output_len   += bytes_written;
+			self.ends_len     += field_count;
+
+			match result {
+				ReadRecordResult::InputEmpty     => continue,                      // Need more input: loop back to refill
+				ReadRecordResult::Record         => self.finish_record(),
+				ReadRecordResult::End            => {
+					if self.records.is_empty() { return Ok(None) }                 // No more records
+					self.seal_block();
+				}
+				ReadRecordResult::OutputFull     => {
+					let msg = format!("Record too large for output buffer ({} bytes)", MAX_RECORD_SIZE);
+					self.fail(Error::General(msg));
+				}
+				ReadRecordResult::OutputEndsFull => {
+					let msg = format!("Too many fields. Limit ({})", MAX_FIELDS_PER_RECORD);
+					self.fail(Error::General(msg));
+				}
+			}
+		}
+	}
+
+	// Grow the arena so the parser can write everything the unread input
+	// could produce, up to the per-record limits. Returns the room available.
+	//
+	// The parser only reports `OutputFull` while input remains, so sizing the
+	// room to the input keeps the arena (and the zero fill) as small as the data.
+	//
+	fn reserve_record_room(&mut self) -> (usize, usize) {
+		let input      = self.end - self.start;
+		let room_bytes = (self.record_offset + MAX_RECORD_SIZE       - self.output_len).min(input);
+		let room_ends  = (self.record_ends   + MAX_FIELDS_PER_RECORD - self.ends_len).min(input + 1);
+		let bytes      = self.output_len + room_bytes;
+		let ends       = self.ends_len   + room_ends;
+		if self.arena.len()      < bytes { self.arena.resize(bytes, 0);                             }
+		if self.arena_ends.len() < ends  { self.arena_ends.resize(ends + MAX_FIELDS_PER_RECORD, 0); }  // Slack so this is rare
+		(room_bytes, room_ends)
+	}
+
+	fn finish_record(&mut self) {
+		let span = RecordSpan {
+			start:      self.record_offset,
+			ends:       self.record_ends..self.ends_len,
+			coordinate: self.record_start,
+		};
+		self.records.push(span);
+		self.record_offset = self.output_len;
+		self.record_ends   = self.ends_len;
+		self.record_start  = Coordinate::Undefined;
+	}
+
+	// Rows that completed before the error are still delivered first.
+	//
+	fn fail(&mut self, err: Error) {
+		if !self.records.is_empty() { self.seal_block() }
+		self.failure = Some(err);
+	}
+
+	// Freeze the completed records into a shared block and mark them for hand out.
+	// Any partially parsed record moves to the start of a fresh arena.
+	//
+	fn seal_block(&mut self) {
+		let used_bytes = self.record_offset;
+		let used_ends  = self.record_ends;
+
+		let mut arena = Vec::with_capacity(ARENA_CAPACITY);
+		arena.extend_from_slice(&self.arena[used_bytes..self.output_len]);
+		let arena_ends = self.arena_ends[used_ends..self.ends_len].iter().map(|e| e - used_bytes).collect();
+
+		let mut data = std::mem::replace(&mut self.arena, arena);
+		let mut ends = std::mem::replace(&mut self.arena_ends, arena_ends);
+		data.truncate(used_bytes);
+		ends.truncate(used_ends);
+		self.block = Some(Arc::new(RowBlock::new(data, ends)));
+
+		self.output_len   -= used_bytes;
+		self.ends_len     -= used_ends;
+		self.record_offset = 0;
+		self.record_ends   = 0;
+
+		self.sealed = 0..self.records.len();
+	}
+
+	fn take_row(&mut self, index: usize) -> Atom {
+		let block = self.block.as_ref().expect("Sealed records always have a block");
+		let span  = &self.records[index];
+		let row   = ByteRow::in_block(Arc::clone(block), span.start, span.ends.clone()).with_coordinate(span.coordinate);
+		let width = row.length() as usize;
+		if self.needs_header {
+			self.needs_header = false;
+			self.header_width = width;
+			HeaderRow(row.as_string_row())
+		} else if width != self.header_width {
+			let msg = format!("{} fields, expected {}", width, self.header_width);
+			ErrorAtom(Error::Parse(msg), row.coordinate())
+		} else {
+			ByteRowAtom(row)
+		}
+	}
 }
 
 /// This class uses the lower level csv_core crate to parse a CSV file.
@@ -189,65 +401,17 @@ impl<R: Read> Iterator for CsvByteSource<R> {
 			SourceState::Broken(_)            => handle_broken(),
 			SourceState::Completed            => handle_completed(),
 			SourceState::Ready(ref mut state) => {
-				if state.needs_fill() {
-					match state.fill_buffer() {
-						Err(e) => {
-							warn!("{}", e);
-							let source = IoErrorWrapper::from(e);
-							let err    = Error::from(source);
-							self.state = SourceState::Broken(err);
-							return None
-						},
-						Ok(false) => {
-							self.state = SourceState::Completed;
-							return None
-							},
-						Ok(true)  => {}  // Fall through and parse the next record ...
-					}
-				}
-				
-				let input = &state.chunk_buffer[state.start..state.end];
-				let (result, bytes_read, bytes_written, field_count) = state.parser.read_record(input, &mut state.output_record, &mut state.field_indices);
-				state.start      += bytes_read;                                         // Slide forward in the chunk buffer
-				match result {
-					ReadRecordResult::InputEmpty       => {                         // Need more input: loop back to refill if possible
+				match state.next_atom() {
+					Ok(Some(atom)) => Some(atom),
+					Ok(None)       => {
 						self.state = SourceState::Completed;
-						let error  = Error::Parse("Input empty despite latest fill".to_string());
-						let atom   = Atom::ErrorAtom(error);
-						Some(atom)
-					}              
-					ReadRecordResult::End              => {                         // No more records (trapped EOF in the middle of parser)
-						self.state = SourceState::Completed;
-						let error  = Error::Parse("End of data".to_string());
-						let atom   = Atom::ErrorAtom(error);
-						Some(atom)
-					}                          
-					ReadRecordResult::OutputFull       => { 
-						let msg    = format!("Record too large for output buffer ({} bytes)", MAX_RECORD_SIZE);
-						let err    = Error::General(msg);
-						self.state = SourceState::Broken(err);
 						None
 					}
-					ReadRecordResult::OutputEndsFull   => { 
-						let msg    = format!("Too many fields. Limit ({})", MAX_FIELDS_PER_RECORD);
-						let err    = Error::General(msg);
+					Err(err)       => {
+						warn!("{}", err);
 						self.state = SourceState::Broken(err);
 						None
 					}
-					ReadRecordResult::Record   => { 
-						let data   = &state.output_record[..bytes_written];
-						let bounds = &state.field_indices[..field_count  ];
-						let atom   = if state.needs_header {
-							state.needs_header = false;
-							let row            = ByteRow::new(data, bounds);
-							let row            = row.as_string_row();
-							HeaderRow(row)
-						} else {
-							let row    = ByteRow::new(data, bounds);
-							ByteRowAtom(row)
-						};
-						Some(atom)
-					}
 				}
 			}
 		}
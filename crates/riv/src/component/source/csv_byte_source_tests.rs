@@ -4,6 +4,7 @@ use zero::test_tools::failing_reader::FailingReader;
 use crate::component::source::csv_byte_source::CsvByteSource;
 use crate::component::source::{Source, SourceState};
 use crate::Error;
+use crate::model::ir::atom::Atom;
 use crate::utils::test_file::TestFile;
 
 
@@ -45,3 +46,79 @@ fn initialize_nonempty_file_sets_ready_state() {
 		panic!("Expected Ready state, found {:?}", src.state);
 	}
 }
+
+fn collect_atoms(content: &str) -> Vec<Atom> {
+	let tf  = TestFile::with_content(content).unwrap();
+	let src = CsvByteSource::new(File::open(tf.path()).unwrap());
+	src.collect()
+}
+
+#[test]
+fn records_carry_line_and_byte_of_their_start() {
+	let atoms = collect_atoms("City;Temperature\nTokyo;35.6897\nJakarta;-6.1750\n");
+	let lines = atoms.iter().map(|a| a.coordinate().line()).collect::<Vec<_>>();
+	let bytes = atoms.iter().map(|a| a.coordinate().byte()).collect::<Vec<_>>();
+	assert_eq!(lines, vec![Some(1), Some(2), Some(3)]);
+	assert_eq!(bytes, vec![Some(0), Some(17), Some(31)]);
+}
+
+#[test]
+fn crlf_line_endings_do_not_shift_coordinates() {
+	let atoms = collect_atoms("a;b\r\n1;2\r\n3;4\r\n");
+	let lines = atoms.iter().map(|a| a.coordinate().line()).collect::<Vec<_>>();
+	let bytes = atoms.iter().map(|a| a.coordinate().byte()).collect::<Vec<_>>();
+	assert_eq!(lines, vec![Some(1), Some(2), Some(3)]);
+	assert_eq!(bytes, vec![Some(0), Some(5), Some(10)]);
+}
+
+#[test]
+fn wrong_field_count_becomes_located_error_atom() {
+	let atoms = collect_atoms("a;b;c\n1;2;3\n4;5\n6;7;8\n");
+	assert_eq!(atoms.len(), 4);
+	match &atoms[2] {
+		Atom::ErrorAtom(Error::Parse(msg), coordinate) => {
+			assert_eq!(msg, "2 fields, expected 3");
+			assert_eq!(coordinate.line(), Some(3));
+			assert_eq!(coordinate.byte(), Some(12));
+		}
+		other => panic!("Expected a parse error atom, found {:?}", other),
+	}
+	assert!(matches!(atoms[3], Atom::ByteRowAtom(_)));
+}
+
+#[test]
+fn final_record_without_newline_is_emitted() {
+	let atoms = collect_atoms("h1;h2\nv1;v2");
+	assert_eq!(atoms.len(), 2);
+	assert!(matches!(atoms[1], Atom::ByteRowAtom(_)));
+}
+
+#[test]
+fn records_spanning_chunks_are_reassembled() {
+	let rows    = 2_000;
+	let mut csv = String::from("id;name\n");
+	for i in 0..rows {
+		csv.push_str(&format!("{};name-{}\n", i, i));
+	}
+	let atoms = collect_atoms(&csv);
+	assert_eq!(atoms.len(), rows + 1);
+	for (i, atom) in atoms.iter().skip(1).enumerate() {
+		let Atom::ByteRowAtom(row) = atom else { panic!("Expected a ByteRowAtom, got {:?}", atom) };
+		let name = format!("name-{}", i);
+		assert_eq!(row.get(0), Some(i.to_string().as_bytes()));
+		assert_eq!(row.get(1), Some(name.as_bytes()));
+	}
+	let last = atoms.last().unwrap().coordinate();
+	assert_eq!(last.line(), Some(rows as u64 + 1));
+	assert_eq!(last.byte(), Some((csv.len() - "1999;name-1999\n".len()) as u64));
+}
+
+#[test]
+fn rows_from_one_chunk_share_a_block() {
+	let atoms = collect_atoms("h1;h2\na;b\nc;d\n");
+	let (Atom::ByteRowAtom(first), Atom::ByteRowAtom(second)) = (&atoms[1], &atoms[2]) else {
+		panic!("Expected two data rows, got {:?}", atoms)
+	};
+	assert!(std::sync::Arc::ptr_eq(first.block(), second.block()));
+	assert_eq!(second.data(), b"cd");
+}
@@ -5,7 +5,9 @@ use crate::Error;
 use std::fmt::{Debug, Display};
 use tracing::instrument;
 use crate::error::IoErrorWrapper;
-use crate::model::ir::atom::Atom::StringRowAtom;
+use crate::component::source::csv_adapter::{compute_coordinate, compute_position};
+use crate::model::coordinate::coordinate::Coordinate;
+use crate::model::ir::atom::Atom::{ErrorAtom, StringRowAtom};
 use crate::model::ir::string_row::StringRow;
 
 type CsvSourceState = SourceState<CsvState>;
@@ -96,16 +98,24 @@ impl Iterator for CsvStringSource {
 					match s.iterator.next() {
 						None => None,
 						Some(r) => match r {
+							Ok(rec) if rec.len() != s.header_width => {
+								let coordinate = compute_coordinate(&rec);
+								let msg        = format!("{} fields, expected {}", rec.len(), s.header_width);
+								let atom       = ErrorAtom(Error::Parse(msg), coordinate);
+								Some(atom)
+							}
 							Ok(rec) => {
-								let data = StringRow::new(&rec);
-								let atom = StringRowAtom(data);
+								let coordinate = compute_coordinate(&rec);
+								let data       = StringRow::new(&rec).with_coordinate(coordinate);
+								let atom       = StringRowAtom(data);
 								Some(atom)
 							}
 							Err(x) => {
-								let msg = format!("Error reading CSV file: {}", x);
-								let err = Error::Parse(msg);
-								self.state = SourceState::Broken(err);
-								None
+								let coordinate = x.position().map(compute_position).unwrap_or(Coordinate::Undefined);
+								let msg        = format!("Error reading CSV file: {}", x);
+								let err        = Error::Parse(msg);
+								self.state     = SourceState::Broken(err.clone());
+								Some(ErrorAtom(err, coordinate))
 							}
 						}
 					}
@@ -0,0 +1,162 @@
+use std::path::Path;
+use rusqlite::{params_from_iter, Connection, OpenFlags};
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use tracing::{info, warn};
+use crate::Error;
+use crate::component::source::{Source, SourceType};
+use crate::model::coordinate::coordinate::Coordinate;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::string_row::StringRow;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+
+/// Rows read from the table by each query.
+const PAGE_ROWS: usize = 1_024;
+
+/// A `Source` that reads every row of one SQLite table.
+///
+/// Yields a header of the table's column names followed by one
+/// `TypedRowAtom` per row, in rowid order, or in primary key order for a
+/// table without a rowid. Rows are read a page at a time, each page
+/// starting after the last key read, so every page costs the same; a view,
+/// having neither, is read in one query. SQLite integers, reals, text and
+/// blobs become `Integer`, `Float`, `String` and `Bytes` values. The
+/// database is opened read-only; a failed read yields one `ErrorAtom` and
+/// ends the source.
+///
+pub struct SqliteSource {
+	connection: Connection,
+	table:      String,
+	columns:    Vec<String>,
+	key:        Vec<String>,
+	last:       Option<Vec<SqlValue>>,
+	header:     bool,
+	done:       bool,
+	failed:     bool,
+	rows:       std::vec::IntoIter<TypedRow>,
+}
+
+impl SqliteSource {
+	pub fn new<P: AsRef<Path>>(path: P, table: &str) -> Result<Self, Error> {
+		let connection = Connection::open_with_flags(path.as_ref(), OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+		let columns    = connection.prepare(&format!("SELECT * FROM {} LIMIT 0", quote(table)))?
+			.column_names().into_iter().map(str::to_owned).collect::<Vec<_>>();
+		let key        = page_key(&connection, table)?;
+		info!("[SqliteSource]: created. Table '{}' of {} with {} columns", table, path.as_ref().display(), columns.len());
+		Ok(SqliteSource {
+			connection,
+			table:   table.to_owned(),
+			columns,
+			key,
+			last:    None,
+			header:  false,
+			done:    false,
+			failed:  false,
+			rows:    Vec::new().into_iter(),
+		})
+	}
+
+	pub fn columns(&self) -> &[String] { &self.columns }
+
+	/// The next page: the rows whose key follows the last one read, in key order.
+	fn read_page(&mut self) -> Result<Vec<TypedRow>, Error> {
+		let table = quote(&self.table);
+		if self.key.is_empty() {
+			self.done = true;
+			return self.read(&format!("SELECT * FROM {}", table), 0, &[])
+		}
+		let keys  = self.key.join(", ");
+		let after = match &self.last {
+			Some(_) => format!("WHERE ({}) > ({})", keys, vec!["?"; self.key.len()].join(", ")),
+			None    => String::new(),
+		};
+		let sql   = format!("SELECT {}, * FROM {} {} ORDER BY {} LIMIT {}", keys, table, after, keys, PAGE_ROWS);
+		let last  = self.last.take().unwrap_or_default();
+		let rows  = self.read(&sql, self.key.len(), &last)?;
+		self.done = rows.len() < PAGE_ROWS;
+		Ok(rows)
+	}
+
+	/// Rows of `sql` whose first `skip` columns are the page key, kept as `last`.
+	fn read(&mut self, sql: &str, skip: usize, params: &[SqlValue]) -> Result<Vec<TypedRow>, Error> {
+		let mut query = self.connection.prepare(sql)?;
+		let width     = self.columns.len();
+		let mut last  = None;
+		let rows      = query.query_map(params_from_iter(params), |row| {
+				let key    = (0..skip).map(|i| row.get_ref(i).map(SqlValue::from)).collect::<Result<Vec<_>, _>>()?;
+				let values = (skip..skip + width).map(|i| row.get_ref(i).map(value)).collect::<Result<Vec<_>, _>>()?;
+				Ok((key, TypedRow::new(values)))
+			})?
+			.map(|row| row.map(|(key, row)| { last = Some(key); row }))
+			.collect::<Result<Vec<_>, _>>()?;
+		self.last = last;
+		Ok(rows)
+	}
+}
+
+impl Iterator for SqliteSource {
+	type Item = Atom;
+
+	fn next(&mut self) -> Option<Atom> {
+		if !self.header {
+			self.header = true;
+			return Some(Atom::HeaderRow(StringRow::from_values(self.columns.clone())))
+		}
+		if let Some(row) = self.rows.next() { return Some(Atom::TypedRowAtom(row)) }
+		if self.done || self.failed { return None }
+		match self.read_page() {
+			Ok(rows)   => {
+				self.rows = rows.into_iter();
+				self.rows.next().map(Atom::TypedRowAtom)
+			}
+			Err(error) => {
+				warn!("[SqliteSource]: cannot read table '{}': {}", self.table, error);
+				self.failed = true;
+				Some(Atom::ErrorAtom(error, Coordinate::Undefined))
+			}
+		}
+	}
+}
+
+impl Source for SqliteSource {
+	fn source_type(&self) -> SourceType { SourceType::Sqlite }
+
+	// Only return Ok(true) if every row has been read
+	//
+	fn close(&mut self) -> Result<bool, Error> {
+		Ok(self.done && !self.failed && self.rows.len() == 0)
+	}
+}
+
+/// What orders the table's pages: its rowid, else its primary key, else
+/// nothing for a view.
+fn page_key(connection: &Connection, table: &str) -> Result<Vec<String>, Error> {
+	let view: bool = connection.query_row("SELECT count(*) > 0 FROM sqlite_master WHERE type = 'view' AND name = ?1", [table], |row| row.get(0))?;
+	if view {
+		return Ok(Vec::new())
+	}
+	if connection.prepare(&format!("SELECT rowid FROM {} LIMIT 0", quote(table))).is_ok() {
+		return Ok(vec!["rowid".to_owned()])
+	}
+	let mut info = connection.prepare(&format!("PRAGMA table_info({})", quote(table)))?;
+	let mut key  = info.query_map([], |row| Ok((row.get::<_, i64>("pk")?, row.get::<_, String>("name")?)))?
+		.collect::<Result<Vec<_>, _>>()?;
+	key.retain(|(position, _)| *position > 0);
+	key.sort();
+	Ok(key.into_iter().map(|(_, name)| quote(&name)).collect())
+}
+
+/// A table or column name as a quoted SQL identifier.
+fn quote(name: &str) -> String {
+	format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn value(value: ValueRef<'_>) -> Value {
+	match value {
+		ValueRef::Null       => Value::Null,
+		ValueRef::Integer(i) => Value::Integer(i),
+		ValueRef::Real(f)    => Value::Float(f),
+		ValueRef::Text(t)    => Value::String(String::from_utf8_lossy(t).into_owned()),
+		ValueRef::Blob(b)    => Value::Bytes(b.to_vec()),
+	}
+}
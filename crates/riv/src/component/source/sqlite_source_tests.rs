@@ -0,0 +1,75 @@
+use rusqlite::{params, Connection};
+use tempfile::TempDir;
+use crate::component::source::Source;
+use crate::component::source::sqlite_source::SqliteSource;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::value::Value;
+
+fn database(dir: &TempDir, rows: i64) -> std::path::PathBuf {
+	let path       = dir.path().join("reference.db");
+	let connection = Connection::open(&path).unwrap();
+	connection.execute_batch("CREATE TABLE \"counter party\" (id INTEGER, name TEXT, rating REAL, logo BLOB); BEGIN").unwrap();
+	for id in 0..rows {
+		let name = if id == 1 { None } else { Some(format!("cp{}", id)) };
+		connection.execute("INSERT INTO \"counter party\" VALUES (?1, ?2, ?3, ?4)", params![id, name, id as f64 / 2.0, vec![id as u8]]).unwrap();
+	}
+	connection.execute_batch("COMMIT").unwrap();
+	path
+}
+
+#[test]
+fn reads_header_then_typed_rows_across_pages() {
+	let dir        = TempDir::new().unwrap();
+	let mut source = SqliteSource::new(database(&dir, 2500), "counter party").unwrap();
+	assert!(matches!(source.next(), Some(Atom::HeaderRow(h)) if h.iter_str().collect::<Vec<_>>() == ["id", "name", "rating", "logo"]));
+
+	let rows = source.by_ref().map(|atom| match atom {
+		Atom::TypedRowAtom(row) => row.iter().cloned().collect::<Vec<_>>(),
+		other                   => panic!("expected a typed row, got {:?}", other),
+	}).collect::<Vec<_>>();
+	assert_eq!(rows.len(), 2500);
+	assert_eq!(rows[0], [Value::Integer(0), Value::String("cp0".into()), Value::Float(0.0), Value::Bytes(vec![0])]);
+	assert_eq!(rows[1][1], Value::Null);
+	assert_eq!(rows[2499][0], Value::Integer(2499));
+	assert!(source.close().unwrap());
+}
+
+/// The first column of every row `table` yields.
+fn first_column(path: &std::path::Path, table: &str) -> Vec<Value> {
+	let source = SqliteSource::new(path, table).unwrap();
+	source.skip(1).map(|atom| match atom {
+		Atom::TypedRowAtom(row) => row.get(0).cloned().unwrap(),
+		other                   => panic!("expected a typed row, got {:?}", other),
+	}).collect()
+}
+
+#[test]
+fn rows_come_in_key_order_without_a_rowid_or_a_key() {
+	let dir        = TempDir::new().unwrap();
+	let path       = dir.path().join("keyed.db");
+	let connection = Connection::open(&path).unwrap();
+	connection.execute_batch("
+		CREATE TABLE pairs (b INTEGER, a INTEGER, PRIMARY KEY (a, b)) WITHOUT ROWID;
+		CREATE VIEW evens AS SELECT b FROM pairs WHERE b % 2 = 0;
+		BEGIN").unwrap();
+	for n in (0..2100).rev() {                                                          // Inserted against key order
+		connection.execute("INSERT INTO pairs VALUES (?1, ?2)", params![n, n % 3]).unwrap();
+	}
+	connection.execute_batch("COMMIT").unwrap();
+
+	let mut expected = (0..2100).collect::<Vec<i64>>();
+	expected.sort_by_key(|n| (n % 3, *n));
+	assert_eq!(first_column(&path, "pairs"), expected.into_iter().map(Value::Integer).collect::<Vec<_>>());
+	assert_eq!(first_column(&path, "evens").len(), 1050);
+}
+
+#[test]
+fn a_missing_table_or_database_fails_to_open() {
+	let dir = TempDir::new().unwrap();
+	assert!(SqliteSource::new(database(&dir, 0), "trades").is_err());
+	assert!(SqliteSource::new(dir.path().join("absent.db"), "counter party").is_err());
+
+	let mut empty = SqliteSource::new(database(&TempDir::new().unwrap(), 0), "counter party").unwrap();
+	assert!(matches!(empty.next(), Some(Atom::HeaderRow(_))));
+	assert!(empty.next().is_none());
+}
@@ -0,0 +1,152 @@
+use std::io::Write;
+use std::sync::Arc;
+use chrono::{Days, NaiveTime, TimeDelta};
+use tracing::info;
+use crate::Error;
+use crate::component::source::{Source, SourceType};
+use crate::component::source::synthetic_spec::{ColumnSpec, Distribution, SyntheticSpec};
+use crate::model::ir::atom::Atom;
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::row_block::RowBlock;
+use crate::model::ir::string_row::StringRow;
+use crate::model::ir::value::{ValueType, DEFAULT_DATE_FORMAT};
+use crate::utils::random::SplitMix64;
+
+/// Rows generated into each shared `RowBlock`.
+const BLOCK_ROWS: u64 = 1_024;
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+const ALPHANUMERIC:     &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+/// A `Source` that generates rows from a `SyntheticSpec`.
+///
+/// Yields a header followed by `spec.rows` `ByteRowAtom`s. Values come from
+/// a PRNG seeded with `spec.seed`, so the same spec always produces the same
+/// rows. Rows are generated a block at a time and share that block, just as
+/// `CsvByteSource` rows do.
+///
+pub struct SyntheticSource {
+	spec:      SyntheticSpec,
+	random:    SplitMix64,
+	header:    bool,
+	generated: u64,
+	rows:      std::vec::IntoIter<ByteRow>,
+}
+
+impl SyntheticSource {
+	pub fn new(spec: SyntheticSpec) -> Result<Self, Error> {
+		spec.validate()?;
+		info!("[SyntheticSource]: created. {}", spec);
+		let random = SplitMix64::new(spec.seed);
+		Ok(SyntheticSource{spec, random, header: false, generated: 0, rows: Vec::new().into_iter()})
+	}
+
+	pub fn spec(&self) -> &SyntheticSpec { &self.spec }
+
+	fn header_row(&self) -> StringRow {
+		let names = self.spec.columns.iter().map(|c| c.name.as_str()).collect::<Vec<_>>();
+		let ends  = names.iter().scan(0, |end, n| { *end += n.len(); Some(*end) }).collect::<Vec<_>>();
+		ByteRow::new(names.concat().as_bytes(), &ends).as_string_row()
+	}
+
+	fn generate_block(&mut self) -> Vec<ByteRow> {
+		let count    = BLOCK_ROWS.min(self.spec.rows - self.generated);
+		let width    = self.spec.columns.len();
+		let mut data = Vec::with_capacity(count as usize * width * 8);
+		let mut ends = Vec::with_capacity(count as usize * width);
+		for row in self.generated..self.generated + count {
+			for column in &self.spec.columns {
+				write_value(column, row, &mut self.random, &mut data);
+				ends.push(data.len());
+			}
+		}
+		self.generated += count;
+
+		let block = Arc::new(RowBlock::new(data, ends));
+		(0..count as usize)
+			.map(|r| {
+				let fields = r * width..(r + 1) * width;
+				let start  = if fields.start == 0 { 0 } else { block.ends()[fields.start - 1] };
+				ByteRow::in_block(Arc::clone(&block), start, fields)
+			})
+			.collect()
+	}
+}
+
+impl Iterator for SyntheticSource {
+	type Item = Atom;
+
+	fn next(&mut self) -> Option<Atom> {
+		if !self.header {
+			self.header = true;
+			return Some(Atom::HeaderRow(self.header_row()))
+		}
+		if let Some(row) = self.rows.next() { return Some(Atom::ByteRowAtom(row)) }
+		if self.generated == self.spec.rows { return None }
+		self.rows = self.generate_block().into_iter();
+		self.rows.next().map(Atom::ByteRowAtom)
+	}
+}
+
+impl Source for SyntheticSource {
+	fn source_type(&self) -> SourceType { SourceType::Synthetic }
+
+	// Only return Ok(true) if every row has been produced
+	//
+	fn close(&mut self) -> Result<bool, Error> {
+		Ok(self.generated == self.spec.rows && self.rows.len() == 0)
+	}
+}
+
+fn write_value(column: &ColumnSpec, row: u64, random: &mut SplitMix64, out: &mut Vec<u8>) {
+	if column.null_ratio > 0.0 && random.unit() < column.null_ratio { return }
+
+	// Writing to a Vec cannot fail
+	let _ = match &column.distribution {
+		Distribution::Sequence{start, step}    => write!(out, "{}", start.wrapping_add(step.wrapping_mul(row as i64))),
+		Distribution::Uniform{min, max, scale} => {
+			if column.value_type == ValueType::Integer {
+				let low    = min.ceil() as i64;
+				let high   = max.floor() as i64;                         // Validation keeps low <= high
+				// The full i64 span has 2^64 values, one more than a u64 holds
+				let offset = match high.abs_diff(low).checked_add(1) {
+					Some(span) => random.below(span),
+					None       => random.next_u64(),
+				};
+				write!(out, "{}", low.wrapping_add(offset as i64))
+			} else {
+				write_number(out, min + random.unit() * (max - min), column.value_type, *scale)
+			}
+		}
+		Distribution::Normal{mean, std_dev, scale} => {
+			write_number(out, mean + std_dev * random.normal(), column.value_type, *scale)
+		}
+		Distribution::Dates{start, end} => {
+			let days = (*end - *start).num_days() as u64;
+			if column.value_type == ValueType::Timestamp {
+				let second = random.below((days + 1) * 86_400) as i64;
+				let stamp  = start.and_time(NaiveTime::MIN) + TimeDelta::seconds(second);
+				write!(out, "{}", stamp.format(TIMESTAMP_FORMAT))
+			} else {
+				let date = *start + Days::new(random.below(days + 1));
+				write!(out, "{}", date.format(DEFAULT_DATE_FORMAT))
+			}
+		}
+		Distribution::PickList{values} => {
+			out.extend_from_slice(values[random.below(values.len() as u64) as usize].as_bytes());
+			Ok(())
+		}
+		Distribution::RandomString{min_len, max_len} => {
+			let len = min_len + random.below((max_len - min_len) as u64 + 1) as usize;
+			out.extend((0..len).map(|_| ALPHANUMERIC[random.below(ALPHANUMERIC.len() as u64) as usize]));
+			Ok(())
+		}
+	};
+}
+
+fn write_number(out: &mut Vec<u8>, value: f64, value_type: ValueType, scale: u32) -> std::io::Result<()> {
+	match value_type {
+		ValueType::Integer => write!(out, "{}", value.round() as i64),
+		_                  => write!(out, "{:.*}", scale as usize, value),
+	}
+}
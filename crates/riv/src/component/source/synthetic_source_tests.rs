@@ -0,0 +1,124 @@
+use chrono::NaiveDate;
+use crate::Error;
+use crate::component::source::Source;
+use crate::component::source::synthetic_source::SyntheticSource;
+use crate::component::source::synthetic_spec::{ColumnSpec, Distribution, SyntheticSpec};
+use crate::model::blueprint::Blueprint;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::value::ValueType;
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate { NaiveDate::from_ymd_opt(y, m, d).unwrap() }
+
+fn spec(rows: u64, seed: u64) -> SyntheticSpec {
+	let columns = vec![
+		ColumnSpec::new("id",      ValueType::Integer, Distribution::Sequence{start: 1, step: 1}),
+		ColumnSpec::new("qty",     ValueType::Integer, Distribution::Uniform{min: 1.0, max: 10.0, scale: 0}),
+		ColumnSpec::new("price",   ValueType::Decimal, Distribution::Normal{mean: 50.0, std_dev: 5.0, scale: 2}),
+		ColumnSpec::new("shipped", ValueType::Date,    Distribution::Dates{start: date(2025, 1, 1), end: date(2025, 1, 31)}),
+		ColumnSpec::new("status",  ValueType::String,  Distribution::PickList{values: vec!["open".into(), "closed".into()]}),
+		ColumnSpec::new("code",    ValueType::String,  Distribution::RandomString{min_len: 4, max_len: 8}).with_null_ratio(0.25),
+	];
+	SyntheticSpec::new(columns, rows, seed)
+}
+
+fn rows(spec: SyntheticSpec) -> Vec<Vec<String>> {
+	SyntheticSource::new(spec).unwrap()
+		.filter_map(|atom| match atom {
+			Atom::ByteRowAtom(row) => Some(row.iter_str().map(str::to_owned).collect()),
+			_                      => None,
+		})
+		.collect()
+}
+
+#[test]
+fn yields_a_header_then_the_requested_rows() -> Result<(), Error> {
+	let mut source = SyntheticSource::new(spec(2_500, 7))?;
+	match source.next() {
+		Some(Atom::HeaderRow(header)) => assert_eq!(header.iter_str().collect::<Vec<_>>(), ["id", "qty", "price", "shipped", "status", "code"]),
+		other                         => panic!("expected a header, got {:?}", other),
+	}
+	assert!(!source.close()?);
+	assert_eq!(source.by_ref().count(), 2_500);
+	assert!(source.close()?);
+	Ok(())
+}
+
+#[test]
+fn same_seed_gives_same_rows() {
+	assert_eq!(rows(spec(100, 42)), rows(spec(100, 42)));
+	assert_ne!(rows(spec(100, 42)), rows(spec(100, 43)));
+}
+
+#[test]
+fn values_follow_their_distributions() {
+	let rows = rows(spec(2_000, 1));
+	for (i, row) in rows.iter().enumerate() {
+		assert_eq!(row[0], (i + 1).to_string());
+		assert!((1..=10).contains(&row[1].parse::<i64>().unwrap()));
+		assert_eq!(row[2].split('.').nth(1).map(str::len), Some(2));
+		let shipped = NaiveDate::parse_from_str(&row[3], "%Y-%m-%d").unwrap();
+		assert!((date(2025, 1, 1)..=date(2025, 1, 31)).contains(&shipped));
+		assert!(row[4] == "open" || row[4] == "closed");
+		assert!(row[5].is_empty() || (4..=8).contains(&row[5].len()));
+	}
+	let nulls = rows.iter().filter(|r| r[5].is_empty()).count();
+	assert!((400..600).contains(&nulls), "{} nulls for a ratio of 0.25", nulls);
+}
+
+#[test]
+fn generated_rows_infer_to_the_spec_schema() {
+	let spec      = spec(500, 3);
+	let expected  = spec.schema();
+	let source    = SyntheticSource::new(spec).unwrap();
+	let blueprint = Blueprint::from_atoms("synthetic", source, 500);
+	assert_eq!(blueprint.schema().value_types(), expected.value_types());
+	assert_eq!(blueprint.schema().names(), expected.names());
+}
+
+#[test]
+fn spec_round_trips_through_json() -> Result<(), Error> {
+	let json = r#"{
+		"rows": 10,
+		"seed": 9,
+		"columns": [
+			{"name": "id",   "type": "integer", "distribution": {"kind": "sequence", "start": 100}},
+			{"name": "when", "type": "timestamp", "distribution": {"kind": "dates", "start": "2024-02-28", "end": "2024-03-01"}, "null_ratio": 0.1}
+		]
+	}"#;
+	let spec = SyntheticSpec::from_json(json)?;
+	assert_eq!(spec.columns[0].distribution, Distribution::Sequence{start: 100, step: 1});
+	assert_eq!(SyntheticSpec::from_json(&spec.to_json()?)?, spec);
+	assert_eq!(rows(spec)[9][0], "109");
+	Ok(())
+}
+
+#[test]
+fn invalid_specs_are_rejected() {
+	let mismatched = ColumnSpec::new("id", ValueType::String, Distribution::Sequence{start: 0, step: 1});
+	let backwards  = ColumnSpec::new("n",  ValueType::Float,  Distribution::Uniform{min: 5.0, max: 1.0, scale: 1});
+	let unbounded  = ColumnSpec::new("n",  ValueType::Float,  Distribution::Uniform{min: 0.0, max: f64::INFINITY, scale: 1});
+	let no_integer = ColumnSpec::new("n",  ValueType::Integer, Distribution::Uniform{min: 1.2, max: 1.8, scale: 0});
+	let too_wide   = ColumnSpec::new("n",  ValueType::Integer, Distribution::Uniform{min: -1e30, max: -1e29, scale: 0});
+	let not_a_mean = ColumnSpec::new("n",  ValueType::Float,  Distribution::Normal{mean: f64::NAN, std_dev: 1.0, scale: 1});
+	let empty      = ColumnSpec::new("s",  ValueType::String, Distribution::PickList{values: vec![]});
+	let too_null   = ColumnSpec::new("s",  ValueType::String, Distribution::RandomString{min_len: 1, max_len: 1}).with_null_ratio(1.5);
+	for column in [mismatched, backwards, unbounded, no_integer, too_wide, not_a_mean, empty, too_null] {
+		let result = SyntheticSource::new(SyntheticSpec::new(vec![column], 1, 0));
+		assert!(matches!(result, Err(Error::InvalidConfig(_))));
+	}
+	assert!(SyntheticSource::new(SyntheticSpec::new(vec![], 1, 0)).is_err());
+}
+
+#[test]
+fn uniform_integers_cover_the_full_i64_span() {
+	let column = ColumnSpec::new("n", ValueType::Integer, Distribution::Uniform{min: i64::MIN as f64, max: i64::MAX as f64, scale: 0});
+	for row in rows(SyntheticSpec::new(vec![column], 50, 3)) {
+		assert!(row[0].parse::<i64>().is_ok());
+	}
+}
+
+#[test]
+fn uniform_integers_stay_within_fractional_bounds() {
+	let column = ColumnSpec::new("n", ValueType::Integer, Distribution::Uniform{min: 0.5, max: 1.5, scale: 0});
+	assert!(rows(SyntheticSpec::new(vec![column], 20, 3)).iter().all(|row| row[0] == "1"));
+}
@@ -0,0 +1,155 @@
+use std::fmt;
+use std::path::PathBuf;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use crate::Error;
+use crate::component::source::{SourceConfig, SourceType};
+use crate::model::ir::value::ValueType;
+use crate::model::schema::Schema;
+use crate::model::schema::field::Field;
+
+/// Name under which `SourceConfig::string_value` hands out the spec as JSON.
+pub const SPEC_KEY: &str = "spec";
+
+/// How the values of one synthetic column are drawn.
+///
+/// Numeric distributions render as integers for `Integer` columns and with
+/// `scale` decimal places otherwise. `Dates` draws days for `Date` columns
+/// and seconds for `Timestamp` columns, both inclusive of `end`.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Distribution {
+	Sequence     {start: i64, #[serde(default = "one")] step: i64},
+	Uniform      {min: f64, max: f64, #[serde(default = "two")] scale: u32},
+	Normal       {mean: f64, std_dev: f64, #[serde(default = "two")] scale: u32},
+	Dates        {start: NaiveDate, end: NaiveDate},
+	PickList     {values: Vec<String>},
+	RandomString {min_len: usize, max_len: usize},
+}
+
+fn one() -> i64 { 1 }
+fn two() -> u32 { 2 }
+
+/// One generated column.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ColumnSpec {
+	pub name:         String,
+	#[serde(rename = "type")]
+	pub value_type:   ValueType,
+	pub distribution: Distribution,
+	/// Fraction of values left empty, from 0 to 1.
+	#[serde(default)]
+	pub null_ratio:   f64,
+}
+
+impl ColumnSpec {
+	pub fn new(name: impl Into<String>, value_type: ValueType, distribution: Distribution) -> Self {
+		let name = name.into();
+		ColumnSpec{name, value_type, distribution, null_ratio: 0.0}
+	}
+
+	pub fn with_null_ratio(mut self, null_ratio: f64) -> Self {
+		self.null_ratio = null_ratio;
+		self
+	}
+
+	fn validate(&self) -> Result<(), Error> {
+		let fail = |msg: &str| Err(Error::InvalidConfig(format!("Synthetic column '{}': {}", self.name, msg)));
+		if !(0.0..=1.0).contains(&self.null_ratio) { return fail("null_ratio must be between 0 and 1") }
+
+		let fits = match &self.distribution {
+			Distribution::Sequence{..}     => self.value_type == ValueType::Integer,
+			Distribution::Uniform{..}      |
+			Distribution::Normal{..}       => self.value_type.is_numeric(),
+			Distribution::Dates{..}        => matches!(self.value_type, ValueType::Date | ValueType::Timestamp),
+			Distribution::PickList{..}     => true,
+			Distribution::RandomString{..} => matches!(self.value_type, ValueType::String | ValueType::Bytes),
+		};
+		if !fits { return fail(&format!("cannot generate {} values from this distribution", self.value_type)) }
+
+		let finite = match &self.distribution {
+			Distribution::Uniform{min, max, ..}     => min.is_finite() && max.is_finite(),
+			Distribution::Normal{mean, std_dev, ..} => mean.is_finite() && std_dev.is_finite(),
+			_                                       => true,
+		};
+		if !finite { return fail("numeric parameters must be finite") }
+
+		if let Distribution::Uniform{min, max, ..} = &self.distribution && self.value_type == ValueType::Integer {
+			if min.ceil() > max.floor() { return fail("no integer lies between min and max") }
+			if min.ceil() < i64::MIN as f64 || max.floor() > i64::MAX as f64 { return fail("integer bounds must fit in an i64") }
+		}
+
+		match &self.distribution {
+			Distribution::Uniform{min, max, ..}        if min > max          => fail("min is greater than max"),
+			Distribution::Normal{std_dev, ..}          if *std_dev < 0.0     => fail("std_dev is negative"),
+			Distribution::Dates{start, end}            if start > end        => fail("start is after end"),
+			Distribution::PickList{values}             if values.is_empty()  => fail("pick list is empty"),
+			Distribution::RandomString{min_len, max_len} if min_len > max_len => fail("min_len is greater than max_len"),
+			_                                                                => Ok(()),
+		}
+	}
+}
+
+/// Declarative description of a synthetic data set: its columns, how many
+/// rows to generate and the seed that makes every run identical.
+///
+/// The spec doubles as the `SourceConfig` for a `SyntheticSource`, so it can
+/// be handed to the pipeline builder in place of a file.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SyntheticSpec {
+	pub columns: Vec<ColumnSpec>,
+	pub rows:    u64,
+	#[serde(default)]
+	pub seed:    u64,
+}
+
+impl SyntheticSpec {
+	pub fn new(columns: Vec<ColumnSpec>, rows: u64, seed: u64) -> Self {
+		SyntheticSpec{columns, rows, seed}
+	}
+
+	pub fn validate(&self) -> Result<(), Error> {
+		if self.columns.is_empty() {
+			return Err(Error::InvalidConfig("Synthetic spec has no columns".to_owned()))
+		}
+		self.columns.iter().try_for_each(ColumnSpec::validate)
+	}
+
+	/// The schema the generated rows conform to.
+	pub fn schema(&self) -> Schema {
+		let fields = self.columns.iter()
+			.map(|c| Field::new(c.name.clone(), c.value_type, c.null_ratio > 0.0))
+			.collect();
+		Schema::new(fields)
+	}
+
+	pub fn to_json(&self) -> Result<String, Error> {
+		serde_json::to_string_pretty(self).map_err(|e| Error::General(format!("Failed to serialize synthetic spec: {}", e)))
+	}
+
+	pub fn from_json(json: &str) -> Result<SyntheticSpec, Error> {
+		serde_json::from_str(json).map_err(|e| Error::InvalidConfig(format!("Invalid synthetic spec: {}", e)))
+	}
+}
+
+impl SourceConfig for SyntheticSpec {
+	fn source_type(&self) -> SourceType { SourceType::Synthetic }
+
+	fn path_buf(&self)                   -> Option<&PathBuf> { None }
+	fn integer_value(&self, _name: &str) -> Option<i32>      { None }
+	fn float_value(&self,   _name: &str) -> Option<f32>      { None }
+	fn bool_value(&self,    _name: &str) -> Option<bool>     { None }
+
+	fn string_value(&self, name: &str) -> Option<String> {
+		(name == SPEC_KEY).then(|| self.to_json().ok()).flatten()
+	}
+}
+
+impl fmt::Display for SyntheticSpec {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "synthetic: {} columns x {} rows (seed {})", self.columns.len(), self.rows, self.seed)
+	}
+}
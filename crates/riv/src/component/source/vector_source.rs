@@ -2,6 +2,7 @@
 use std::collections::VecDeque;
 use std::fmt::Display;
 use tracing::info;
+use crate::model::coordinate::coordinate::Coordinate;
 use crate::model::ir::atom::Atom;
 use crate::error::Error;
 use crate::model::ir::atom::Atom::ErrorAtom;
@@ -44,7 +45,7 @@ impl Iterator for VectorSource {
 				else {
 					self.error_atom_sent = true;
 					let x                = err.clone();
-					let a                = ErrorAtom(x);
+					let a                = ErrorAtom(x, Coordinate::Undefined);
 					Some(a)
 				}
 			}
@@ -1,4 +1,7 @@
 pub mod ir;
 pub mod coordinate;
+pub mod schema;
+pub mod blueprint;
+pub mod expression;
 
 pub use crate::error::*;
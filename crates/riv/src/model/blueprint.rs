@@ -0,0 +1,79 @@
+pub mod data_dictionary;
+pub mod ddl;
+pub mod json_schema;
+
+#[cfg(test)]
+mod blueprint_tests;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::Error;
+use crate::error::IoErrorWrapper;
+use crate::model::ir::atom::Atom;
+use crate::model::schema::inference::{ColumnInference, SchemaInference};
+use crate::model::schema::Schema;
+
+/// Everything learned about a source that is needed to onboard it: the
+/// inferred schema plus per-column observations (lengths, null counts,
+/// example values).
+///
+/// The artifacts are plain text so they can be reviewed before any table
+/// is created.
+///
+#[derive(Clone, Debug)]
+pub struct Blueprint {
+	table:   String,
+	schema:  Schema,
+	columns: Vec<ColumnInference>,
+	sampled: usize,
+}
+
+impl Blueprint {
+	/// Sample up to `sample_size` rows from `atoms`.
+	pub fn from_atoms<I: Iterator<Item = Atom>>(table: &str, atoms: I, sample_size: usize) -> Self {
+		let mut inference = SchemaInference::new(sample_size);
+		for atom in atoms {
+			if !inference.accept(&atom) { break }
+		}
+		Self::from_inference(table, &inference)
+	}
+
+	pub fn from_inference(table: &str, inference: &SchemaInference) -> Self {
+		let schema      = inference.schema();
+		let mut columns = inference.columns().to_vec();
+		columns.resize_with(schema.len(), ColumnInference::new);   // Header-only columns were never observed
+		Blueprint{table: table.to_owned(), schema, columns, sampled: inference.sampled()}
+	}
+
+	pub fn table(&self)   -> &str                { &self.table   }
+	pub fn schema(&self)  -> &Schema             { &self.schema  }
+	pub fn columns(&self) -> &[ColumnInference]  { &self.columns }
+	pub fn sampled(&self) -> usize               { self.sampled  }
+
+	pub fn sqlite_ddl(&self)      -> String { ddl::sqlite_create_table(self)     }
+	pub fn sql_server_ddl(&self)  -> String { ddl::sql_server_create_table(self) }
+	pub fn data_dictionary(&self) -> String { data_dictionary::markdown(self)    }
+
+	pub fn json_schema(&self) -> Result<String, Error> {
+		let document = json_schema::document(self);
+		serde_json::to_string_pretty(&document).map_err(|e| Error::General(format!("Failed to serialize JSON Schema: {}", e)))
+	}
+
+	/// Write every artifact into `dir`, creating it if needed, and return the files written.
+	pub fn write_artifacts(&self, dir: &Path) -> Result<Vec<PathBuf>, Error> {
+		fs::create_dir_all(dir).map_err(IoErrorWrapper::from)?;
+		let artifacts = [
+			(format!("{}.sqlite.sql",    self.table), self.sqlite_ddl()),
+			(format!("{}.sqlserver.sql", self.table), self.sql_server_ddl()),
+			(format!("{}.schema.json",   self.table), self.json_schema()?),
+			(format!("{}.md",            self.table), self.data_dictionary()),
+		];
+		let mut written = Vec::with_capacity(artifacts.len());
+		for (name, content) in artifacts {
+			let path = dir.join(name);
+			fs::write(&path, content).map_err(IoErrorWrapper::from)?;
+			written.push(path);
+		}
+		Ok(written)
+	}
+}
@@ -0,0 +1,80 @@
+use crate::model::blueprint::Blueprint;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::byte_row::ByteRow;
+
+fn row(fields: &[&str]) -> ByteRow {
+	let data = fields.concat();
+	let ends = fields.iter().scan(0, |end, f| { *end += f.len(); Some(*end) }).collect::<Vec<_>>();
+	ByteRow::new(data.as_bytes(), &ends)
+}
+
+fn blueprint() -> Blueprint {
+	let header = ["id", "price", "shipped", "note", "active"];
+	let rows: [&[&str]; 3] = [
+		&["1", "12.50",  "2025-01-31", "first",   "true"],
+		&["2", "3.125",  "2025-02-01", "",        "false"],
+		&["3", "100.00", "",           "a | b",   "true"],
+	];
+	let mut atoms = vec![Atom::HeaderRow(row(&header).as_string_row())];
+	atoms.extend(rows.iter().map(|r| Atom::ByteRowAtom(row(r))));
+	Blueprint::from_atoms("orders", atoms.into_iter(), 100)
+}
+
+#[test]
+fn sqlite_ddl_uses_storage_classes() {
+	let expected = "CREATE TABLE \"orders\" (\n\
+		\t\"id\" INTEGER NOT NULL,\n\
+		\t\"price\" NUMERIC NOT NULL,\n\
+		\t\"shipped\" TEXT,\n\
+		\t\"note\" TEXT,\n\
+		\t\"active\" INTEGER NOT NULL\n);\n";
+	assert_eq!(blueprint().sqlite_ddl(), expected);
+}
+
+#[test]
+fn sql_server_ddl_sizes_columns_from_the_sample() {
+	let expected = "CREATE TABLE [orders] (\n\
+		\t[id] BIGINT NOT NULL,\n\
+		\t[price] DECIMAL(6,3) NOT NULL,\n\
+		\t[shipped] DATE,\n\
+		\t[note] NVARCHAR(5),\n\
+		\t[active] BIT NOT NULL\n);\n";
+	assert_eq!(blueprint().sql_server_ddl(), expected);
+}
+
+#[test]
+fn json_schema_marks_nullable_columns() {
+	let json   = blueprint().json_schema().unwrap();
+	let schema = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+	assert_eq!(schema["title"], "orders");
+	assert_eq!(schema["properties"]["id"]["type"], "integer");
+	assert_eq!(schema["properties"]["shipped"]["type"], serde_json::json!(["string", "null"]));
+	assert_eq!(schema["properties"]["shipped"]["format"], "date");
+	assert_eq!(schema["properties"]["id"]["examples"], serde_json::json!(["1", "2", "3"]));
+	assert_eq!(schema["required"], serde_json::json!(["id", "price", "active"]));
+}
+
+#[test]
+fn data_dictionary_lists_every_column() {
+	let markdown = blueprint().data_dictionary();
+	assert!(markdown.starts_with("# orders\n\nInferred from a sample of 3 rows."));
+	assert!(markdown.contains("| shipped | date (`%Y-%m-%d`) | yes | 33.3% | `2025-01-31`, `2025-02-01` |"));
+	assert!(markdown.contains("| note | string | yes | 33.3% | `first`, `a \\| b` |"));
+}
+
+#[test]
+fn header_only_source_still_produces_columns() {
+	let atoms     = vec![Atom::HeaderRow(row(&["a", "b"]).as_string_row())];
+	let blueprint = Blueprint::from_atoms("empty", atoms.into_iter(), 10);
+	assert_eq!(blueprint.columns().len(), 2);
+	assert!(blueprint.sql_server_ddl().contains("[a] NVARCHAR(MAX)"));
+}
+
+#[test]
+fn write_artifacts_creates_one_file_per_artifact() {
+	let dir     = tempfile::tempdir().unwrap();
+	let written = blueprint().write_artifacts(dir.path()).unwrap();
+	let names   = written.iter().map(|p| p.file_name().unwrap().to_str().unwrap().to_owned()).collect::<Vec<_>>();
+	assert_eq!(names, ["orders.sqlite.sql", "orders.sqlserver.sql", "orders.schema.json", "orders.md"]);
+	assert!(written.iter().all(|p| p.exists()));
+}
@@ -0,0 +1,30 @@
+use crate::model::blueprint::Blueprint;
+
+/// Markdown table with one row per column: name, inferred type, whether it
+/// may be null, the share of sampled cells that were null and a few examples.
+///
+pub fn markdown(blueprint: &Blueprint) -> String {
+	let mut out = format!("# {}\n\n", escape(blueprint.table()));
+	out.push_str(&format!("Inferred from a sample of {} rows.\n\n", blueprint.sampled()));
+	out.push_str("| Column | Type | Nullable | Null ratio | Examples |\n");
+	out.push_str("|---|---|---|---|---|\n");
+	for (field, column) in blueprint.schema().fields().iter().zip(blueprint.columns()) {
+		let value_type = match &field.format {
+			Some(format) => format!("{} (`{}`)", field.value_type, format),
+			None         => field.value_type.to_string(),
+		};
+		let nullable = if field.nullable { "yes" } else { "no" };
+		let examples = column.examples().iter()
+			.map(|e| format!("`{}`", escape(e)))
+			.collect::<Vec<_>>()
+			.join(", ");
+		out.push_str(&format!("| {} | {} | {} | {:.1}% | {} |\n",
+			escape(&field.name), value_type, nullable, column.null_ratio() * 100.0, examples));
+	}
+	out
+}
+
+// Pipes would end the table cell early; newlines would end the row.
+fn escape(text: &str) -> String {
+	text.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
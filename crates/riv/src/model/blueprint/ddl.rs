@@ -0,0 +1,73 @@
+use crate::model::blueprint::Blueprint;
+use crate::model::ir::value::ValueType;
+use crate::model::schema::inference::ColumnInference;
+
+/// SQL Server stores at most 4,000 characters in a sized `NVARCHAR`.
+pub const SQL_SERVER_MAX_NVARCHAR: usize = 4_000;
+/// SQL Server's `DECIMAL` precision limit.
+pub const SQL_SERVER_MAX_PRECISION: u32 = 38;
+
+pub fn sqlite_create_table(blueprint: &Blueprint) -> String {
+	let columns = blueprint.schema().fields().iter()
+		.map(|f| column_line(&quote_sqlite(&f.name), sqlite_column_type(f.value_type), f.nullable))
+		.collect::<Vec<_>>();
+	create_table(&quote_sqlite(blueprint.table()), &columns)
+}
+
+pub fn sql_server_create_table(blueprint: &Blueprint) -> String {
+	let columns = blueprint.schema().fields().iter()
+		.zip(blueprint.columns())
+		.map(|(f, c)| column_line(&quote_sql_server(&f.name), &sql_server_column_type(f.value_type, c), f.nullable))
+		.collect::<Vec<_>>();
+	create_table(&quote_sql_server(blueprint.table()), &columns)
+}
+
+/// SQLite column type used to store values of the given type.
+pub fn sqlite_column_type(value_type: ValueType) -> &'static str {
+	match value_type {
+		ValueType::Boolean   => "INTEGER",
+		ValueType::Integer   => "INTEGER",
+		ValueType::Float     => "REAL",
+		ValueType::Decimal   => "NUMERIC",
+		ValueType::Bytes     => "BLOB",
+		_                    => "TEXT",
+	}
+}
+
+/// SQL Server type wide enough for every value observed in the column.
+pub fn sql_server_column_type(value_type: ValueType, column: &ColumnInference) -> String {
+	match value_type {
+		ValueType::Boolean   => "BIT".to_owned(),
+		ValueType::Integer   => "BIGINT".to_owned(),
+		ValueType::Float     => "FLOAT".to_owned(),
+		ValueType::Decimal   => {
+			let (precision, scale) = column.precision_and_scale();
+			let precision          = precision.clamp(1, SQL_SERVER_MAX_PRECISION);
+			format!("DECIMAL({},{})", precision, scale.min(precision))
+		}
+		ValueType::Date      => "DATE".to_owned(),
+		ValueType::Timestamp => "DATETIME2".to_owned(),
+		ValueType::Bytes     => "VARBINARY(MAX)".to_owned(),
+		ValueType::String    if (1..=SQL_SERVER_MAX_NVARCHAR).contains(&column.max_length()) => {
+			format!("NVARCHAR({})", column.max_length())
+		}
+		ValueType::String | ValueType::Null => "NVARCHAR(MAX)".to_owned(),
+	}
+}
+
+fn create_table(table: &str, columns: &[String]) -> String {
+	format!("CREATE TABLE {} (\n{}\n);\n", table, columns.join(",\n"))
+}
+
+fn column_line(name: &str, sql_type: &str, nullable: bool) -> String {
+	let constraint = if nullable { "" } else { " NOT NULL" };
+	format!("\t{} {}{}", name, sql_type, constraint)
+}
+
+fn quote_sqlite(name: &str) -> String {
+	format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn quote_sql_server(name: &str) -> String {
+	format!("[{}]", name.replace(']', "]]"))
+}
@@ -0,0 +1,62 @@
+use serde_json::{json, Map, Value as Json};
+use crate::model::blueprint::Blueprint;
+use crate::model::ir::value::ValueType;
+use crate::model::schema::field::Field;
+use crate::model::schema::inference::ColumnInference;
+
+pub const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// JSON Schema describing one row of the blueprint as an object.
+///
+/// Nullable columns accept `null` in addition to their own type and are
+/// left out of `required`.
+///
+pub fn document(blueprint: &Blueprint) -> Json {
+	let mut properties = Map::new();
+	for (field, column) in blueprint.schema().fields().iter().zip(blueprint.columns()) {
+		properties.insert(field.name.clone(), property(field, column));
+	}
+	let required = blueprint.schema().fields().iter()
+		.filter(|f| !f.nullable)
+		.map(|f| f.name.clone())
+		.collect::<Vec<_>>();
+	json!({
+		"$schema":              JSON_SCHEMA_DIALECT,
+		"title":                blueprint.table(),
+		"type":                 "object",
+		"properties":           properties,
+		"required":             required,
+		"additionalProperties": false,
+	})
+}
+
+fn property(field: &Field, column: &ColumnInference) -> Json {
+	let (json_type, format) = match field.value_type {
+		ValueType::Null      => ("null",    None),
+		ValueType::Boolean   => ("boolean", None),
+		ValueType::Integer   => ("integer", None),
+		ValueType::Float     => ("number",  None),
+		ValueType::Decimal   => ("number",  None),
+		ValueType::Date      => ("string",  Some("date")),
+		ValueType::Timestamp => ("string",  Some("date-time")),
+		ValueType::String    => ("string",  None),
+		ValueType::Bytes     => ("string",  None),
+	};
+	let mut property = Map::new();
+	let json_type = if field.nullable && field.value_type != ValueType::Null {
+		json!([json_type, "null"])
+	} else {
+		json!(json_type)
+	};
+	property.insert("type".to_owned(), json_type);
+	if let Some(format) = format {
+		property.insert("format".to_owned(), json!(format));
+	}
+	if field.value_type == ValueType::Bytes {
+		property.insert("contentEncoding".to_owned(), json!("base16"));
+	}
+	if !column.examples().is_empty() {
+		property.insert("examples".to_owned(), json!(column.examples()));
+	}
+	Json::Object(property)
+}
@@ -20,6 +20,19 @@ impl Coordinate {
 	// Predicates
 	pub fn is_none(&self) -> bool { *self == Coordinate::Undefined }
 
+	// Accessors
+	/// The location where this coordinate begins, if it is defined.
+	pub fn start(&self) -> Option<TextLocation> {
+		match self {
+			Coordinate::Undefined          => None,
+			Coordinate::Position(v)        => Some(*v),
+			Coordinate::Extent{start, ..}  => Some(*start),
+		}
+	}
+
+	pub fn line(&self) -> Option<u64> { self.start().map(|v| v.line) }
+	pub fn byte(&self) -> Option<u64> { self.start().map(|v| v.byte) }
+
 	// Convenience methods
 	pub fn byte_count(&self) -> u64 {
 		match self {
@@ -0,0 +1,109 @@
+pub mod ast;
+pub mod lexer;
+pub mod parser;
+
+#[cfg(test)]
+mod expression_tests;
+
+use std::fmt;
+use crate::Error;
+use crate::model::expression::ast::{truth, Expr};
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::row_batch::RowBatch;
+use crate::model::ir::string_row::StringRow;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+
+/// Access to the cells of one row by column index.
+///
+/// Text cells come back as `Value::String`, blank text as `Value::Null`;
+/// an expression reads the text as whatever type it is compared with.
+///
+pub trait Fields {
+	fn value(&self, index: usize) -> Value;
+}
+
+fn text_value(bytes: Option<&[u8]>) -> Value {
+	match bytes {
+		Some(b) if !b.trim_ascii().is_empty() => Value::String(String::from_utf8_lossy(b).into_owned()),
+		_                                     => Value::Null,
+	}
+}
+
+impl Fields for ByteRow {
+	fn value(&self, index: usize) -> Value { text_value(self.get(index)) }
+}
+
+impl Fields for StringRow {
+	fn value(&self, index: usize) -> Value { text_value(self.get(index).map(str::as_bytes)) }
+}
+
+impl Fields for TypedRow {
+	fn value(&self, index: usize) -> Value { self.get(index).cloned().unwrap_or(Value::Null) }
+}
+
+/// Row `.1` of batch `.0`.
+impl Fields for (&RowBatch, usize) {
+	fn value(&self, index: usize) -> Value { text_value(self.0.get(self.1, index)) }
+}
+
+/// An expression over named columns, such as
+/// `status IN ('open', 'held') AND amount >= 100 AND NOT note IS NULL`.
+///
+/// Supported: comparisons (`= != <> < <= > >=`), `AND`, `OR`, `NOT`,
+/// `IN (…)`, `LIKE` with `%` and `_`, `MATCHES 'regex'`,
+/// `BETWEEN … AND …`, `IS [NOT] NULL`, and `DATE '…'` and `TIMESTAMP '…'`
+/// literals. Names that are not plain identifiers go in double quotes.
+///
+/// Values can be computed with `+ - * / %`, `||` (concatenation),
+/// `CASE [x] WHEN … THEN … [ELSE …] END` and the functions `UPPER`,
+/// `LOWER`, `TRIM`, `LENGTH`, `SUBSTR(s, start[, count])`, `CONCAT`,
+/// `COALESCE`, `FORMAT_DATE(d, '%d.%m.%Y')`, `ROUND(x[, digits])`, `ABS`
+/// and `CORRELATION_ID()`.
+///
+/// Parse once, then `bind` to a header to resolve names to positions.
+///
+#[derive(Clone, Debug)]
+pub struct Expression {
+	text: String,
+	expr: Expr,
+}
+
+impl Expression {
+	pub fn parse(text: &str) -> Result<Self, Error> {
+		let expr = parser::parse(text)?;
+		Ok(Expression{text: text.to_owned(), expr})
+	}
+
+	pub fn text(&self) -> &str { &self.text }
+
+	/// Resolve every column name against `names`. Errors name the first missing column.
+	pub fn bind<S: AsRef<str>>(&self, names: &[S]) -> Result<Expression, Error> {
+		let mut bound = self.clone();
+		for column in bound.expr.columns_mut() {
+			let index = names.iter().position(|n| n.as_ref() == column.name)
+				.ok_or_else(|| Error::InvalidConfig(format!("Unknown column '{}' in expression: {}", column.name, self.text)))?;
+			column.index = Some(index);
+		}
+		Ok(bound)
+	}
+
+	/// Give every reference to the run variable `name` a value.
+	pub fn set_variable(&mut self, name: &str, value: &Value) {
+		for variable in self.expr.variables_mut().into_iter().filter(|v| v.name == name) {
+			variable.value = value.clone();
+		}
+	}
+
+	/// The value of the expression for `row`. Unbound columns read as null.
+	pub fn evaluate(&self, row: &dyn Fields) -> Value { self.expr.evaluate(row) }
+
+	/// True only when the expression is true; false and null reject the row.
+	pub fn matches(&self, row: &dyn Fields) -> bool {
+		truth(&self.evaluate(row)) == Some(true)
+	}
+}
+
+impl fmt::Display for Expression {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { f.write_str(&self.text) }
+}
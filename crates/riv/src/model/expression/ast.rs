@@ -0,0 +1,489 @@
+use std::cmp::Ordering;
+use std::fmt::Write;
+use chrono::{Days, NaiveDate, NaiveDateTime, NaiveTime};
+use regex::Regex;
+use crate::model::expression::Fields;
+use crate::model::ir::decimal::Decimal;
+use crate::model::ir::value::{parse_bool, Value, ValueType};
+use crate::model::schema::inference::{DATE_FORMATS, TIMESTAMP_FORMATS};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompareOp {
+	Eq,
+	NotEq,
+	Lt,
+	LtEq,
+	Gt,
+	GtEq,
+}
+
+impl CompareOp {
+	fn holds(self, ordering: Ordering) -> bool {
+		match self {
+			CompareOp::Eq    => ordering == Ordering::Equal,
+			CompareOp::NotEq => ordering != Ordering::Equal,
+			CompareOp::Lt    => ordering == Ordering::Less,
+			CompareOp::LtEq  => ordering != Ordering::Greater,
+			CompareOp::Gt    => ordering == Ordering::Greater,
+			CompareOp::GtEq  => ordering != Ordering::Less,
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArithOp {
+	Add,
+	Sub,
+	Mul,
+	Div,
+	Rem,
+}
+
+/// A built-in function. Names are matched case-insensitively.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Function {
+	Upper,
+	Lower,
+	Trim,
+	Length,
+	Substr,
+	Concat,
+	Coalesce,
+	FormatDate,
+	Round,
+	Abs,
+}
+
+impl Function {
+	pub fn lookup(name: &str) -> Option<Function> {
+		let function = match name.to_ascii_uppercase().as_str() {
+			"UPPER"       => Function::Upper,
+			"LOWER"       => Function::Lower,
+			"TRIM"        => Function::Trim,
+			"LENGTH"      => Function::Length,
+			"SUBSTR"      => Function::Substr,
+			"CONCAT"      => Function::Concat,
+			"COALESCE"    => Function::Coalesce,
+			"FORMAT_DATE" => Function::FormatDate,
+			"ROUND"       => Function::Round,
+			"ABS"         => Function::Abs,
+			_             => return None,
+		};
+		Some(function)
+	}
+
+	/// Smallest and largest number of arguments accepted.
+	pub fn arity(self) -> (usize, usize) {
+		match self {
+			Function::Upper | Function::Lower | Function::Trim | Function::Length | Function::Abs => (1, 1),
+			Function::Substr     => (2, 3),
+			Function::Concat     => (1, usize::MAX),
+			Function::Coalesce   => (1, usize::MAX),
+			Function::FormatDate => (2, 2),
+			Function::Round      => (1, 2),
+		}
+	}
+
+	fn call(self, args: &[Value]) -> Value {
+		let text = |i: usize| args.get(i).filter(|v| !v.is_null()).map(Value::to_string);
+		match self {
+			Function::Upper      => text(0).map_or(Value::Null, |s| Value::String(s.to_uppercase())),
+			Function::Lower      => text(0).map_or(Value::Null, |s| Value::String(s.to_lowercase())),
+			Function::Trim       => text(0).map_or(Value::Null, |s| Value::String(s.trim().to_owned())),
+			Function::Length     => text(0).map_or(Value::Null, |s| Value::Integer(s.chars().count() as i64)),
+			Function::Substr     => {
+				let (Some(s), Some(start)) = (text(0), args.get(1).and_then(integer)) else { return Value::Null };
+				let skip  = (start.max(1) - 1) as usize;
+				let chars = s.chars().skip(skip);
+				match args.get(2) {
+					None        => Value::String(chars.collect()),
+					Some(count) => match integer(count) {
+						Some(n) if n >= 0 => Value::String(chars.take(n as usize).collect()),
+						_                 => Value::Null,
+					},
+				}
+			}
+			Function::Concat     => Value::String(args.iter().filter(|v| !v.is_null()).map(Value::to_string).collect()),
+			Function::Coalesce   => args.iter().find(|v| !v.is_null()).cloned().unwrap_or(Value::Null),
+			Function::FormatDate => {
+				let Some(format) = text(1) else { return Value::Null };
+				let mut out = String::new();
+				let written = match temporal(&args[0]) {
+					Some(Value::Date(d))      => write!(out, "{}", d.format(&format)),
+					Some(Value::Timestamp(t)) => write!(out, "{}", t.format(&format)),
+					_                         => return Value::Null,
+				};
+				if written.is_ok() { Value::String(out) } else { Value::Null }   // An invalid format is not a panic
+			}
+			Function::Round      => {
+				let digits = match args.get(1) {
+					None    => 0,
+					Some(v) => match integer(v).and_then(|d| u32::try_from(d).ok()) {
+						Some(d) => d,
+						None    => return Value::Null,
+					},
+				};
+				match numeric(&args[0]) {
+					Some(Value::Decimal(d)) => Value::Decimal(d.round(digits)),
+					Some(Value::Float(f))   => {
+						let factor = 10f64.powi(digits as i32);
+						Value::Float((f * factor).round() / factor)
+					}
+					Some(other)             => other,
+					None                    => Value::Null,
+				}
+			}
+			Function::Abs        => match numeric(&args[0]) {
+				Some(Value::Integer(i)) => i.checked_abs().map_or(Value::Null, Value::Integer),
+				Some(Value::Decimal(d)) => d.mantissa().checked_abs().and_then(|m| Decimal::new(m, d.scale()).ok()).map_or(Value::Null, Value::Decimal),
+				Some(Value::Float(f))   => Value::Float(f.abs()),
+				_                       => Value::Null,
+			},
+		}
+	}
+}
+
+/// A column reference; `index` is filled in when the expression is bound to a header.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Column {
+	pub name:  String,
+	pub index: Option<usize>,
+}
+
+/// A value supplied by the run rather than the row, such as the
+/// correlation id; null until the relay that owns the expression sets it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Variable {
+	pub name:  String,
+	pub value: Value,
+}
+
+/// Parsed expression tree.
+///
+/// `LIKE` and `MATCHES` patterns are compiled once, when parsed.
+///
+#[derive(Clone, Debug)]
+pub enum Expr {
+	Literal(Value),
+	Column(Column),
+	Variable(Variable),
+	Not(Box<Expr>),
+	And(Box<Expr>, Box<Expr>),
+	Or(Box<Expr>, Box<Expr>),
+	Compare(Box<Expr>, CompareOp, Box<Expr>),
+	In      {expr: Box<Expr>, list: Vec<Expr>, negated: bool},
+	Matches {expr: Box<Expr>, pattern: Regex, negated: bool},
+	IsNull  {expr: Box<Expr>, negated: bool},
+	Between {expr: Box<Expr>, low: Box<Expr>, high: Box<Expr>, negated: bool},
+	Arith(Box<Expr>, ArithOp, Box<Expr>),
+	Concat(Box<Expr>, Box<Expr>),
+	Call    {function: Function, args: Vec<Expr>},
+	Case    {operand: Option<Box<Expr>>, branches: Vec<(Expr, Expr)>, otherwise: Option<Box<Expr>>},
+}
+
+impl Expr {
+	/// Every column reference, in order of appearance.
+	pub fn columns_mut(&mut self) -> Vec<&mut Column> {
+		let mut columns = Vec::new();
+		self.visit_leaves(&mut |leaf| if let Expr::Column(c) = leaf { columns.push(c) });
+		columns
+	}
+
+	/// Every run variable, in order of appearance.
+	pub fn variables_mut(&mut self) -> Vec<&mut Variable> {
+		let mut variables = Vec::new();
+		self.visit_leaves(&mut |leaf| if let Expr::Variable(v) = leaf { variables.push(v) });
+		variables
+	}
+
+	fn visit_leaves<'a>(&'a mut self, f: &mut dyn FnMut(&'a mut Expr)) {
+		match self {
+			Expr::Literal(_)                |
+			Expr::Column(_)                 |
+			Expr::Variable(_)               => f(self),
+			Expr::Not(e)                    |
+			Expr::Matches{expr: e, ..}      |
+			Expr::IsNull{expr: e, ..}       => e.visit_leaves(f),
+			Expr::And(a, b)                 |
+			Expr::Or(a, b)                  |
+			Expr::Compare(a, _, b)          |
+			Expr::Arith(a, _, b)            |
+			Expr::Concat(a, b)              => { a.visit_leaves(f); b.visit_leaves(f); }
+			Expr::In{expr, list, ..}        => {
+				expr.visit_leaves(f);
+				list.iter_mut().for_each(|e| e.visit_leaves(f));
+			}
+			Expr::Between{expr, low, high, ..} => {
+				expr.visit_leaves(f);
+				low.visit_leaves(f);
+				high.visit_leaves(f);
+			}
+			Expr::Call{args, ..}            => args.iter_mut().for_each(|e| e.visit_leaves(f)),
+			Expr::Case{operand, branches, otherwise} => {
+				if let Some(e) = operand { e.visit_leaves(f) }
+				for (when, then) in branches {
+					when.visit_leaves(f);
+					then.visit_leaves(f);
+				}
+				if let Some(e) = otherwise { e.visit_leaves(f) }
+			}
+		}
+	}
+
+	/// Evaluate against one row. Predicates yield `Boolean` or, when an
+	/// operand is null, `Null` (SQL's three-valued logic).
+	pub fn evaluate(&self, row: &dyn Fields) -> Value {
+		match self {
+			Expr::Literal(value)   => value.clone(),
+			Expr::Column(column)   => column.index.map_or(Value::Null, |i| row.value(i)),
+			Expr::Variable(v)      => v.value.clone(),
+			Expr::Not(e)           => boolean(truth(&e.evaluate(row)).map(|b| !b)),
+			Expr::And(a, b)        => {
+				match truth(&a.evaluate(row)) {
+					Some(false) => Value::Boolean(false),
+					left        => match (left, truth(&b.evaluate(row))) {
+						(_, Some(false))         => Value::Boolean(false),
+						(Some(true), Some(true)) => Value::Boolean(true),
+						_                        => Value::Null,
+					},
+				}
+			}
+			Expr::Or(a, b)         => {
+				match truth(&a.evaluate(row)) {
+					Some(true) => Value::Boolean(true),
+					left       => match (left, truth(&b.evaluate(row))) {
+						(_, Some(true))            => Value::Boolean(true),
+						(Some(false), Some(false)) => Value::Boolean(false),
+						_                          => Value::Null,
+					},
+				}
+			}
+			Expr::Compare(a, op, b) => {
+				boolean(compare(&a.evaluate(row), &b.evaluate(row)).map(|o| op.holds(o)))
+			}
+			Expr::In{expr, list, negated} => {
+				let value = expr.evaluate(row);
+				if value.is_null() { return Value::Null }
+				let mut unknown = false;
+				for item in list {
+					match compare(&value, &item.evaluate(row)) {
+						Some(Ordering::Equal) => return Value::Boolean(!negated),
+						None                  => unknown = true,
+						_                     => {}
+					}
+				}
+				if unknown { Value::Null } else { Value::Boolean(*negated) }
+			}
+			Expr::Matches{expr, pattern, negated} => {
+				match expr.evaluate(row) {
+					Value::Null => Value::Null,
+					value       => Value::Boolean(pattern.is_match(&value.to_string()) != *negated),
+				}
+			}
+			Expr::IsNull{expr, negated} => Value::Boolean(expr.evaluate(row).is_null() != *negated),
+			Expr::Between{expr, low, high, negated} => {
+				let value  = expr.evaluate(row);
+				let above  = compare(&value, &low.evaluate(row)).map(|o| o != Ordering::Less);
+				let below  = compare(&value, &high.evaluate(row)).map(|o| o != Ordering::Greater);
+				let within = match (above, below) {
+					(Some(false), _) | (_, Some(false)) => Some(false),
+					(Some(true), Some(true))            => Some(true),
+					_                                   => None,
+				};
+				boolean(within.map(|b| b != *negated))
+			}
+			Expr::Arith(a, op, b)  => arithmetic(&a.evaluate(row), *op, &b.evaluate(row)),
+			Expr::Concat(a, b)     => match (a.evaluate(row), b.evaluate(row)) {
+				(Value::Null, _) | (_, Value::Null) => Value::Null,
+				(x, y)                              => Value::String(format!("{}{}", x, y)),
+			},
+			Expr::Call{function, args} => {
+				let args = args.iter().map(|e| e.evaluate(row)).collect::<Vec<_>>();
+				function.call(&args)
+			}
+			Expr::Case{operand, branches, otherwise} => {
+				let subject = operand.as_ref().map(|e| e.evaluate(row));
+				for (when, then) in branches {
+					let hit = match &subject {
+						Some(value) => compare(value, &when.evaluate(row)) == Some(Ordering::Equal),
+						None        => truth(&when.evaluate(row)) == Some(true),
+					};
+					if hit { return then.evaluate(row) }
+				}
+				otherwise.as_ref().map_or(Value::Null, |e| e.evaluate(row))
+			}
+		}
+	}
+}
+
+fn boolean(value: Option<bool>) -> Value {
+	value.map_or(Value::Null, Value::Boolean)
+}
+
+/// Truth of a value used as a condition. Text is read as a boolean.
+pub fn truth(value: &Value) -> Option<bool> {
+	match value {
+		Value::Boolean(b) => Some(*b),
+		Value::Integer(i) => Some(*i != 0),
+		Value::String(s)  => parse_bool(s.trim()),
+		_                 => None,
+	}
+}
+
+/// Order two values, reading text as whatever the other side is.
+///
+/// Column values arrive as text, so `amount > 10` parses `amount` as a
+/// number and `shipped < DATE '2025-01-01'` parses it as a date. Two texts
+/// compare as numbers when both are numbers, otherwise as strings. Values
+/// that cannot be compared (null, unparseable text) give `None`.
+///
+pub fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+	match (a, b) {
+		(Value::Null, _) | (_, Value::Null)     => None,
+		(Value::String(x), Value::String(y))    => match (number(x), number(y)) {
+			(Some(p), Some(q)) => compare(&p, &q),
+			_                  => Some(x.cmp(y)),
+		},
+		(Value::String(x), other)               => compare(&coerce(x, other.value_type())?, other),
+		(other, Value::String(y))               => compare(other, &coerce(y, other.value_type())?),
+		(Value::Boolean(x), Value::Boolean(y))  => Some(x.cmp(y)),
+		(Value::Date(x), Value::Date(y))        => Some(x.cmp(y)),
+		(Value::Timestamp(x), Value::Timestamp(y)) => Some(x.cmp(y)),
+		(Value::Date(x), Value::Timestamp(y))   => Some(x.and_time(NaiveTime::MIN).cmp(y)),
+		(Value::Timestamp(x), Value::Date(y))   => Some(x.cmp(&y.and_time(NaiveTime::MIN))),
+		(Value::Integer(x), Value::Integer(y))  => Some(x.cmp(y)),
+		(Value::Integer(_) | Value::Decimal(_), Value::Integer(_) | Value::Decimal(_)) => {
+			Some(exact(a)?.cmp(&exact(b)?))
+		}
+		_ => a.as_f64()?.partial_cmp(&b.as_f64()?),
+	}
+}
+
+fn exact(value: &Value) -> Option<Decimal> {
+	match value {
+		Value::Integer(i) => Decimal::new(*i as i128, 0).ok(),
+		Value::Decimal(d) => Some(*d),
+		_                 => None,
+	}
+}
+
+/// Text as a number: an integer if it is one, else a decimal, else a float.
+pub fn number(text: &str) -> Option<Value> {
+	let text = text.trim();
+	if let Ok(i) = text.parse::<i64>()     { return Some(Value::Integer(i)) }
+	if let Ok(d) = text.parse::<Decimal>() { return Some(Value::Decimal(d)) }
+	text.parse::<f64>().ok().filter(|f| f.is_finite() && text.bytes().any(|b| b.is_ascii_digit())).map(Value::Float)
+}
+
+/// Apply an arithmetic operator.
+///
+/// Text is read as a number, or failing that as a date. Integers stay
+/// integers while the result fits; decimals stay exact for `+ - *`;
+/// anything else, and every inexact division, is computed as a float.
+/// A date plus or minus an integer moves by days, and the difference
+/// of two dates is a number of days. Null operands, overflow, division
+/// by zero and operands that are not numbers give null.
+///
+pub fn arithmetic(a: &Value, op: ArithOp, b: &Value) -> Value {
+	let (Some(a), Some(b)) = (operand(a), operand(b)) else { return Value::Null };
+	match (&a, &b) {
+		(Value::Integer(x), Value::Integer(y)) => {
+			let result = match op {
+				ArithOp::Add => x.checked_add(*y),
+				ArithOp::Sub => x.checked_sub(*y),
+				ArithOp::Mul => x.checked_mul(*y),
+				ArithOp::Rem => x.checked_rem(*y),
+				ArithOp::Div => match x.checked_rem(*y) {
+					Some(0) => x.checked_div(*y),
+					Some(_) => return Value::Float(*x as f64 / *y as f64),
+					None    => None,
+				},
+			};
+			result.map_or(Value::Null, Value::Integer)
+		}
+		(Value::Date(d), Value::Integer(n)) | (Value::Integer(n), Value::Date(d)) if op == ArithOp::Add => shift(*d, *n),
+		(Value::Date(d), Value::Integer(n)) if op == ArithOp::Sub => n.checked_neg().map_or(Value::Null, |n| shift(*d, n)),
+		(Value::Date(x), Value::Date(y)) if op == ArithOp::Sub => Value::Integer((*x - *y).num_days()),
+		(Value::Integer(_) | Value::Decimal(_), Value::Integer(_) | Value::Decimal(_)) if matches!(op, ArithOp::Add | ArithOp::Sub | ArithOp::Mul) => {
+			let (Some(x), Some(y)) = (exact(&a), exact(&b)) else { return Value::Null };
+			let result = match op {
+				ArithOp::Add => x.checked_add(&y),
+				ArithOp::Sub => x.checked_sub(&y),
+				_            => x.checked_mul(&y),
+			};
+			result.map_or_else(|| float(a.as_f64(), op, b.as_f64()), Value::Decimal)
+		}
+		_ => float(a.as_f64(), op, b.as_f64()),
+	}
+}
+
+fn float(a: Option<f64>, op: ArithOp, b: Option<f64>) -> Value {
+	let (Some(x), Some(y)) = (a, b) else { return Value::Null };
+	let result = match op {
+		ArithOp::Add => x + y,
+		ArithOp::Sub => x - y,
+		ArithOp::Mul => x * y,
+		ArithOp::Div => x / y,
+		ArithOp::Rem => x % y,
+	};
+	if result.is_finite() { Value::Float(result) } else { Value::Null }
+}
+
+fn shift(date: NaiveDate, days: i64) -> Value {
+	let moved = match u64::try_from(days) {
+		Ok(n)  => date.checked_add_days(Days::new(n)),
+		Err(_) => date.checked_sub_days(Days::new(days.unsigned_abs())),
+	};
+	moved.map_or(Value::Null, Value::Date)
+}
+
+/// A value as an arithmetic operand: numbers and dates, with text parsed.
+fn operand(value: &Value) -> Option<Value> {
+	match value {
+		Value::String(s) => number(s).or_else(|| coerce_date(s.trim()).map(Value::Date)),
+		Value::Integer(_) | Value::Decimal(_) | Value::Float(_) | Value::Date(_) => Some(value.clone()),
+		_                => None,
+	}
+}
+
+fn numeric(value: &Value) -> Option<Value> {
+	operand(value).filter(|v| !matches!(v, Value::Date(_)))
+}
+
+fn integer(value: &Value) -> Option<i64> {
+	match numeric(value)? {
+		Value::Integer(i) => Some(i),
+		Value::Decimal(d) => d.to_i64(),
+		_                 => None,
+	}
+}
+
+/// A date or timestamp, with text parsed as either.
+fn temporal(value: &Value) -> Option<Value> {
+	match value {
+		Value::Date(_) | Value::Timestamp(_) => Some(value.clone()),
+		Value::String(s)                     => {
+			let text = s.trim();
+			coerce_date(text).map(Value::Date).or_else(|| coerce(text, ValueType::Timestamp))
+		}
+		_                                    => None,
+	}
+}
+
+fn coerce(text: &str, value_type: ValueType) -> Option<Value> {
+	let text = text.trim();
+	match value_type {
+		ValueType::Integer | ValueType::Float | ValueType::Decimal => number(text),
+		ValueType::Boolean   => parse_bool(text).map(Value::Boolean),
+		ValueType::Date      => coerce_date(text).map(Value::Date),
+		ValueType::Timestamp => {
+			let stamp = TIMESTAMP_FORMATS.iter().find_map(|f| NaiveDateTime::parse_from_str(text, f).ok());
+			stamp.or_else(|| coerce_date(text).map(|d| d.and_time(NaiveTime::MIN))).map(Value::Timestamp)   // A date is its midnight
+		}
+		_                    => Some(Value::String(text.to_owned())),
+	}
+}
+
+fn coerce_date(text: &str) -> Option<NaiveDate> {
+	DATE_FORMATS.iter().find_map(|f| NaiveDate::parse_from_str(text, f).ok())
+}
@@ -0,0 +1,164 @@
+use crate::Error;
+use crate::model::expression::Expression;
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+
+const HEADER: [&str; 6] = ["id", "status", "amount", "shipped", "note", "Unit Price"];
+
+fn row(fields: &[&str]) -> ByteRow {
+	let data = fields.concat();
+	let ends = fields.iter().scan(0, |end, f| { *end += f.len(); Some(*end) }).collect::<Vec<_>>();
+	ByteRow::new(data.as_bytes(), &ends)
+}
+
+fn sample() -> ByteRow { row(&["7", "open", "120.50", "2025-03-17", "", "9.99"]) }
+
+fn matches(text: &str) -> bool {
+	Expression::parse(text).unwrap().bind(&HEADER).unwrap().matches(&sample())
+}
+
+#[test]
+fn comparisons_read_text_as_the_other_side() {
+	assert!(matches("id = 7"));
+	assert!(matches("amount > 100"));
+	assert!(matches("amount >= 120.5 AND amount <= 120.50"));
+	assert!(matches("amount < 1000"));                               // Numeric, not lexical
+	assert!(matches("status != 'closed'"));
+	assert!(matches("shipped < DATE '2025-04-01'"));
+	assert!(matches("shipped >= TIMESTAMP '2025-03-17T00:00:00'"));
+	assert!(matches("id > -1"));
+	assert!(!matches("status = 7"));                                 // Not comparable: null rejects
+}
+
+#[test]
+fn boolean_logic_and_precedence() {
+	assert!(matches("id = 1 OR id = 7 AND status = 'open'"));
+	assert!(!matches("(id = 1 OR id = 7) AND status = 'closed'"));
+	assert!(matches("NOT status = 'closed'"));
+	assert!(matches("NOT (id = 1 OR amount < 10)"));
+}
+
+#[test]
+fn null_checks_and_three_valued_logic() {
+	assert!(matches("note IS NULL"));
+	assert!(matches("status IS NOT NULL"));
+	assert!(!matches("note = 'x'"));
+	assert!(!matches("NOT note = 'x'"));                             // NOT unknown is unknown
+	assert!(matches("note = 'x' OR id = 7"));
+}
+
+#[test]
+fn in_like_matches_and_between() {
+	assert!(matches("status IN ('open', 'held')"));
+	assert!(matches("id NOT IN (1, 2, 3)"));
+	assert!(matches("status LIKE 'op%'"));
+	assert!(matches("status LIKE '_pen'"));
+	assert!(!matches("status LIKE 'OP%'"));
+	assert!(matches("status MATCHES '^(open|held)$'"));
+	assert!(matches("status NOT MATCHES '\\d'"));
+	assert!(matches("amount BETWEEN 100 AND 200"));
+	assert!(matches("shipped NOT BETWEEN DATE '2024-01-01' AND DATE '2024-12-31'"));
+}
+
+#[test]
+fn quoted_names_and_literals() {
+	assert!(matches("\"Unit Price\" < 10"));
+	let quote = Expression::parse("note = 'it''s'").unwrap();
+	assert_eq!(quote.evaluate(&row(&["", "", "", "", "it's", ""])), Value::Null);  // Unbound reads as null
+	let bound = quote.bind(&HEADER).unwrap();
+	assert!(bound.matches(&row(&["", "", "", "", "it's", ""])));
+}
+
+#[test]
+fn typed_rows_compare_natively() {
+	let expr = Expression::parse("a > 2 AND b = 'x'").unwrap().bind(&["a", "b"]).unwrap();
+	assert!(expr.matches(&TypedRow::new(vec![Value::Integer(3), Value::String("x".into())])));
+	assert!(!expr.matches(&TypedRow::new(vec![Value::Integer(1), Value::String("x".into())])));
+}
+
+#[test]
+fn errors_are_reported() {
+	assert!(matches!(Expression::parse("id = "),             Err(Error::Parse(_))));
+	assert!(matches!(Expression::parse("id = 'open"),        Err(Error::Parse(_))));
+	assert!(matches!(Expression::parse("id IS 3"),           Err(Error::Parse(_))));
+	assert!(matches!(Expression::parse("id MATCHES '('"),    Err(Error::Parse(_))));
+	assert!(matches!(Expression::parse("id = 1 id"),         Err(Error::Parse(_))));
+	assert!(matches!(Expression::parse("shipped < DATE 'x'"), Err(Error::Parse(_))));
+	let unknown = Expression::parse("missing = 1").unwrap().bind(&HEADER);
+	assert!(matches!(unknown, Err(Error::InvalidConfig(msg)) if msg.contains("'missing'")));
+}
+
+fn value(text: &str) -> Value {
+	Expression::parse(text).unwrap().bind(&HEADER).unwrap().evaluate(&sample())
+}
+
+fn text(s: &str) -> Value { Value::String(s.to_owned()) }
+
+#[test]
+fn arithmetic_keeps_integers_and_decimals_exact() {
+	assert_eq!(value("id * 2 + 1"),       Value::Integer(15));
+	assert_eq!(value("id - 10"),          Value::Integer(-3));
+	assert_eq!(value("-id"),              Value::Integer(-7));
+	assert_eq!(value("id % 4"),           Value::Integer(3));
+	assert_eq!(value("id / 7"),           Value::Integer(1));
+	assert_eq!(value("id / 2"),           Value::Float(3.5));
+	assert_eq!(value("amount * 2").to_string(), "241.00");
+	assert_eq!(value("amount + 0.1").to_string(), "120.60");
+	assert_eq!(value("id / 0"),           Value::Null);
+	assert_eq!(value("note + 1"),         Value::Null);
+	assert_eq!(value("status * 2"),       Value::Null);
+	assert!(matches("amount * 2 > 240 AND id + 1 = 8"));
+}
+
+#[test]
+fn dates_move_by_days() {
+	assert_eq!(value("shipped + 14").to_string(), "2025-03-31");
+	assert_eq!(value("shipped - DATE '2025-03-01'"), Value::Integer(16));
+	assert_eq!(value("FORMAT_DATE(shipped, '%d.%m.%Y')"), text("17.03.2025"));
+	assert_eq!(value("FORMAT_DATE(TIMESTAMP '2025-03-17T08:30:00', '%H:%M')"), text("08:30"));
+	assert_eq!(value("FORMAT_DATE(status, '%Y')"), Value::Null);
+	assert_eq!(value("FORMAT_DATE(shipped, '%Q')"), Value::Null);
+}
+
+#[test]
+fn strings_and_functions() {
+	assert_eq!(value("status || '-' || id"),         text("open-7"));
+	assert_eq!(value("status || note"),              Value::Null);
+	assert_eq!(value("CONCAT(status, note, '!')"),   text("open!"));
+	assert_eq!(value("upper(status)"),               text("OPEN"));
+	assert_eq!(value("LOWER('MiXeD')"),              text("mixed"));
+	assert_eq!(value("TRIM('  x ')"),                text("x"));
+	assert_eq!(value("LENGTH(\"Unit Price\")"),      Value::Integer(4));
+	assert_eq!(value("SUBSTR(shipped, 1, 4)"),       text("2025"));
+	assert_eq!(value("SUBSTR(status, 3)"),           text("en"));
+	assert_eq!(value("COALESCE(note, status)"),      text("open"));
+	assert_eq!(value("ROUND(amount, 0)").to_string(), "121");
+	assert_eq!(value("ABS(id - 10)"),                Value::Integer(3));
+}
+
+#[test]
+fn case_picks_the_first_true_branch() {
+	assert_eq!(value("CASE WHEN amount > 1000 THEN 'large' WHEN amount > 100 THEN 'medium' ELSE 'small' END"), text("medium"));
+	assert_eq!(value("CASE status WHEN 'held' THEN 1 WHEN 'open' THEN 2 END"), Value::Integer(2));
+	assert_eq!(value("CASE WHEN note = 'x' THEN 1 END"), Value::Null);
+	assert!(matches("CASE WHEN id > 5 THEN TRUE ELSE FALSE END"));
+}
+
+#[test]
+fn correlation_id_is_a_run_variable() {
+	let mut expr = Expression::parse("'run ' || CORRELATION_ID()").unwrap();
+	assert_eq!(expr.evaluate(&sample()), Value::Null);
+	expr.set_variable("correlation_id", &text("abc"));
+	assert_eq!(expr.evaluate(&sample()), text("run abc"));
+}
+
+#[test]
+fn function_and_case_errors_are_reported() {
+	assert!(matches!(Expression::parse("NOPE(id)"),                 Err(Error::Parse(msg)) if msg.contains("NOPE")));
+	assert!(matches!(Expression::parse("SUBSTR(id)"),               Err(Error::Parse(msg)) if msg.contains("2 to 3")));
+	assert!(matches!(Expression::parse("CORRELATION_ID(id)"),       Err(Error::Parse(_))));
+	assert!(matches!(Expression::parse("CASE WHEN id = 1 THEN 2"),  Err(Error::Parse(_))));
+	assert!(matches!(Expression::parse("CASE END"),                 Err(Error::Parse(_))));
+	assert!(matches!(Expression::parse("id * "),                    Err(Error::Parse(_))));
+}
@@ -0,0 +1,89 @@
+use crate::Error;
+
+/// One lexical element of an expression.
+///
+/// Keywords are recognised case-insensitively and surface as `Keyword`
+/// in upper case; anything else that looks like a name is an `Ident`.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token {
+	Ident(String),
+	Keyword(&'static str),
+	Number(String),
+	Text(String),
+	Op(&'static str),
+	LParen,
+	RParen,
+	Comma,
+}
+
+pub const KEYWORDS: [&str; 18] = [
+	"AND", "OR", "NOT", "IN", "LIKE", "MATCHES", "IS", "NULL", "BETWEEN", "TRUE", "FALSE", "DATE", "TIMESTAMP",
+	"CASE", "WHEN", "THEN", "ELSE", "END",
+];
+
+// Longest first, so `<=` wins over `<`
+const OPERATORS: [&str; 14] = ["<=", ">=", "<>", "!=", "==", "||", "=", "<", ">", "+", "-", "*", "/", "%"];
+
+/// Split `text` into tokens. Errors name the byte offset of the problem.
+pub fn tokenize(text: &str) -> Result<Vec<Token>, Error> {
+	let bytes      = text.as_bytes();
+	let mut tokens = Vec::new();
+	let mut i      = 0;
+	while i < bytes.len() {
+		let c = bytes[i];
+		match c {
+			b if b.is_ascii_whitespace() => { i += 1; }
+			b'(' => { tokens.push(Token::LParen); i += 1; }
+			b')' => { tokens.push(Token::RParen); i += 1; }
+			b',' => { tokens.push(Token::Comma);  i += 1; }
+			b'\'' => {
+				let (value, next) = quoted(text, i, '\'')?;
+				tokens.push(Token::Text(value));
+				i = next;
+			}
+			b'"' => {
+				let (value, next) = quoted(text, i, '"')?;
+				tokens.push(Token::Ident(value));
+				i = next;
+			}
+			b if b.is_ascii_digit() || (b == b'.' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit)) => {
+				let start = i;
+				while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'.') { i += 1 }
+				tokens.push(Token::Number(text[start..i].to_owned()));
+			}
+			b if b.is_ascii_alphabetic() || b == b'_' => {
+				let start = i;
+				while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') { i += 1 }
+				let word  = &text[start..i];
+				match KEYWORDS.iter().find(|k| k.eq_ignore_ascii_case(word)) {
+					Some(keyword) => tokens.push(Token::Keyword(keyword)),
+					None          => tokens.push(Token::Ident(word.to_owned())),
+				}
+			}
+			_ => {
+				let op = OPERATORS.iter().find(|op| text[i..].starts_with(*op))
+					.ok_or_else(|| Error::Parse(format!("Unexpected '{}' at {} in expression", &text[i..].chars().next().unwrap_or(' '), i)))?;
+				tokens.push(Token::Op(op));
+				i += op.len();
+			}
+		}
+	}
+	Ok(tokens)
+}
+
+/// Read a `quote`-delimited run starting at `start`; a doubled quote stands for itself.
+fn quoted(text: &str, start: usize, quote: char) -> Result<(String, usize), Error> {
+	let mut value = String::new();
+	let mut chars = text[start + 1..].char_indices().peekable();
+	while let Some((offset, c)) = chars.next() {
+		if c != quote { value.push(c); continue }
+		if chars.peek().is_some_and(|&(_, next)| next == quote) {
+			value.push(quote);
+			chars.next();
+			continue
+		}
+		return Ok((value, start + 1 + offset + 1))
+	}
+	Err(Error::Parse(format!("Unterminated {} starting at {} in expression", quote, start)))
+}
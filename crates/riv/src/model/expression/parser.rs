@@ -0,0 +1,288 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use regex::Regex;
+use crate::Error;
+use crate::model::expression::ast::{ArithOp, Column, CompareOp, Expr, Function, Variable};
+use crate::model::expression::lexer::{tokenize, Token};
+use crate::model::ir::decimal::Decimal;
+use crate::model::ir::value::{Value, DEFAULT_DATE_FORMAT};
+use crate::model::schema::inference::TIMESTAMP_FORMATS;
+
+/// Parse `text` into an expression tree.
+///
+/// Precedence, loosest first: `OR`, `AND`, `NOT`, then a single
+/// comparison, `IN`, `LIKE`, `MATCHES`, `BETWEEN` or `IS NULL` test,
+/// then `+`, `-` and `||`, then `*`, `/` and `%`, then unary `-`.
+///
+pub fn parse(text: &str) -> Result<Expr, Error> {
+	let mut parser = Parser{tokens: tokenize(text)?, pos: 0};
+	let expr       = parser.or()?;
+	match parser.peek() {
+		None        => Ok(expr),
+		Some(token) => Err(Error::Parse(format!("Unexpected {} after the end of the expression", describe(token)))),
+	}
+}
+
+struct Parser {
+	tokens: Vec<Token>,
+	pos:    usize,
+}
+
+impl Parser {
+	fn peek(&self) -> Option<&Token> { self.tokens.get(self.pos) }
+
+	fn next(&mut self) -> Option<Token> {
+		let token = self.tokens.get(self.pos).cloned();
+		self.pos += 1;
+		token
+	}
+
+	fn eat_keyword(&mut self, keyword: &str) -> bool {
+		let found = matches!(self.peek(), Some(Token::Keyword(k)) if *k == keyword);
+		if found { self.pos += 1 }
+		found
+	}
+
+	fn expect(&mut self, expected: Token) -> Result<(), Error> {
+		match self.next() {
+			Some(token) if token == expected => Ok(()),
+			other                            => Err(unexpected(other.as_ref(), &describe(&expected))),
+		}
+	}
+
+	fn or(&mut self) -> Result<Expr, Error> {
+		let mut expr = self.and()?;
+		while self.eat_keyword("OR") {
+			expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+		}
+		Ok(expr)
+	}
+
+	fn and(&mut self) -> Result<Expr, Error> {
+		let mut expr = self.not()?;
+		while self.eat_keyword("AND") {
+			expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+		}
+		Ok(expr)
+	}
+
+	fn not(&mut self) -> Result<Expr, Error> {
+		if self.eat_keyword("NOT") {
+			return Ok(Expr::Not(Box::new(self.not()?)))
+		}
+		self.predicate()
+	}
+
+	fn predicate(&mut self) -> Result<Expr, Error> {
+		let expr = Box::new(self.additive()?);
+		if let Some(Token::Op(op)) = self.peek() {
+			let op = match *op {
+				"=" | "=="  => CompareOp::Eq,
+				"!=" | "<>" => CompareOp::NotEq,
+				"<"         => CompareOp::Lt,
+				"<="        => CompareOp::LtEq,
+				">"         => CompareOp::Gt,
+				">="        => CompareOp::GtEq,
+				other       => return Err(Error::Parse(format!("Unexpected '{}' in expression", other))),
+			};
+			self.pos += 1;
+			return Ok(Expr::Compare(expr, op, Box::new(self.additive()?)))
+		}
+		if self.eat_keyword("IS") {
+			let negated = self.eat_keyword("NOT");
+			if !self.eat_keyword("NULL") { return Err(unexpected(self.peek(), "NULL")) }
+			return Ok(Expr::IsNull{expr, negated})
+		}
+
+		let negated = self.eat_keyword("NOT");
+		if self.eat_keyword("IN") {
+			self.expect(Token::LParen)?;
+			let mut list = vec![self.additive()?];
+			while matches!(self.peek(), Some(Token::Comma)) {
+				self.pos += 1;
+				list.push(self.additive()?);
+			}
+			self.expect(Token::RParen)?;
+			return Ok(Expr::In{expr, list, negated})
+		}
+		if self.eat_keyword("LIKE") {
+			let pattern = like_regex(&self.pattern("LIKE")?)?;
+			return Ok(Expr::Matches{expr, pattern, negated})
+		}
+		if self.eat_keyword("MATCHES") {
+			let text    = self.pattern("MATCHES")?;
+			let pattern = Regex::new(&text).map_err(|e| Error::Parse(format!("Invalid regular expression '{}': {}", text, e)))?;
+			return Ok(Expr::Matches{expr, pattern, negated})
+		}
+		if self.eat_keyword("BETWEEN") {
+			let low = Box::new(self.additive()?);
+			if !self.eat_keyword("AND") { return Err(unexpected(self.peek(), "AND")) }
+			let high = Box::new(self.additive()?);
+			return Ok(Expr::Between{expr, low, high, negated})
+		}
+		if negated { return Err(unexpected(self.peek(), "IN, LIKE, MATCHES or BETWEEN after NOT")) }
+		Ok(*expr)
+	}
+
+	fn pattern(&mut self, after: &str) -> Result<String, Error> {
+		match self.next() {
+			Some(Token::Text(text)) => Ok(text),
+			other                   => Err(unexpected(other.as_ref(), &format!("a quoted pattern after {}", after))),
+		}
+	}
+
+	fn additive(&mut self) -> Result<Expr, Error> {
+		let mut expr = self.multiplicative()?;
+		while let Some(Token::Op(op @ ("+" | "-" | "||"))) = self.peek() {
+			let op    = *op;
+			self.pos += 1;
+			let right = Box::new(self.multiplicative()?);
+			expr = match op {
+				"+" => Expr::Arith(Box::new(expr), ArithOp::Add, right),
+				"-" => Expr::Arith(Box::new(expr), ArithOp::Sub, right),
+				_   => Expr::Concat(Box::new(expr), right),
+			};
+		}
+		Ok(expr)
+	}
+
+	fn multiplicative(&mut self) -> Result<Expr, Error> {
+		let mut expr = self.unary()?;
+		while let Some(Token::Op(op @ ("*" | "/" | "%"))) = self.peek() {
+			let op = match *op {
+				"*" => ArithOp::Mul,
+				"/" => ArithOp::Div,
+				_   => ArithOp::Rem,
+			};
+			self.pos += 1;
+			expr = Expr::Arith(Box::new(expr), op, Box::new(self.unary()?));
+		}
+		Ok(expr)
+	}
+
+	fn unary(&mut self) -> Result<Expr, Error> {
+		if self.peek() != Some(&Token::Op("-")) { return self.operand() }
+		self.pos += 1;
+		if let Some(Token::Number(text)) = self.peek().cloned() {
+			self.pos += 1;
+			return number_literal(&text, true)
+		}
+		Ok(Expr::Arith(Box::new(Expr::Literal(Value::Integer(0))), ArithOp::Sub, Box::new(self.unary()?)))
+	}
+
+	fn operand(&mut self) -> Result<Expr, Error> {
+		match self.next() {
+			Some(Token::Ident(name)) if self.peek() == Some(&Token::LParen) => self.call(name),
+			Some(Token::Ident(name))       => Ok(Expr::Column(Column{name, index: None})),
+			Some(Token::Text(text))        => Ok(Expr::Literal(Value::String(text))),
+			Some(Token::Number(text))      => number_literal(&text, false),
+			Some(Token::Keyword("CASE"))   => self.case(),
+			Some(Token::Keyword("TRUE"))   => Ok(Expr::Literal(Value::Boolean(true))),
+			Some(Token::Keyword("FALSE"))  => Ok(Expr::Literal(Value::Boolean(false))),
+			Some(Token::Keyword("NULL"))   => Ok(Expr::Literal(Value::Null)),
+			Some(Token::Keyword("DATE"))   => {
+				let text = self.pattern("DATE")?;
+				let date = NaiveDate::parse_from_str(&text, DEFAULT_DATE_FORMAT)
+					.map_err(|_| Error::Parse(format!("'{}' is not a date; use YYYY-MM-DD", text)))?;
+				Ok(Expr::Literal(Value::Date(date)))
+			}
+			Some(Token::Keyword("TIMESTAMP")) => {
+				let text  = self.pattern("TIMESTAMP")?;
+				let stamp = TIMESTAMP_FORMATS.iter().find_map(|f| NaiveDateTime::parse_from_str(&text, f).ok())
+					.ok_or_else(|| Error::Parse(format!("'{}' is not a timestamp", text)))?;
+				Ok(Expr::Literal(Value::Timestamp(stamp)))
+			}
+			Some(Token::LParen)            => {
+				let expr = self.or()?;
+				self.expect(Token::RParen)?;
+				Ok(expr)
+			}
+			other => Err(unexpected(other.as_ref(), "a column, value or '('")),
+		}
+	}
+
+	/// `name(args…)`; the opening parenthesis is next.
+	fn call(&mut self, name: String) -> Result<Expr, Error> {
+		self.expect(Token::LParen)?;
+		let mut args = Vec::new();
+		if self.peek() != Some(&Token::RParen) {
+			args.push(self.or()?);
+			while matches!(self.peek(), Some(Token::Comma)) {
+				self.pos += 1;
+				args.push(self.or()?);
+			}
+		}
+		self.expect(Token::RParen)?;
+
+		if name.eq_ignore_ascii_case(CORRELATION_ID) {
+			if !args.is_empty() { return Err(Error::Parse(format!("{}() takes no arguments", name))) }
+			return Ok(Expr::Variable(Variable{name: CORRELATION_ID.to_owned(), value: Value::Null}))
+		}
+		let function = Function::lookup(&name).ok_or_else(|| Error::Parse(format!("Unknown function '{}'", name)))?;
+		let (min, max) = function.arity();
+		if args.len() < min || args.len() > max {
+			let expected = if min == max { min.to_string() } else if max == usize::MAX { format!("at least {}", min) } else { format!("{} to {}", min, max) };
+			return Err(Error::Parse(format!("{}() takes {} arguments, got {}", name, expected, args.len())))
+		}
+		Ok(Expr::Call{function, args})
+	}
+
+	/// `CASE [operand] WHEN … THEN … [ELSE …] END`; `CASE` has been read.
+	fn case(&mut self) -> Result<Expr, Error> {
+		let operand = if matches!(self.peek(), Some(Token::Keyword("WHEN"))) { None } else { Some(Box::new(self.or()?)) };
+		let mut branches = Vec::new();
+		while self.eat_keyword("WHEN") {
+			let when = self.or()?;
+			if !self.eat_keyword("THEN") { return Err(unexpected(self.peek(), "THEN")) }
+			branches.push((when, self.or()?));
+		}
+		if branches.is_empty() { return Err(unexpected(self.peek(), "WHEN")) }
+		let otherwise = if self.eat_keyword("ELSE") { Some(Box::new(self.or()?)) } else { None };
+		if !self.eat_keyword("END") { return Err(unexpected(self.peek(), "END")) }
+		Ok(Expr::Case{operand, branches, otherwise})
+	}
+}
+
+/// The variable `CORRELATION_ID()` reads; see `Expression::set_variable`.
+pub const CORRELATION_ID: &str = "correlation_id";
+
+fn number_literal(text: &str, negative: bool) -> Result<Expr, Error> {
+	let signed = if negative { format!("-{}", text) } else { text.to_owned() };
+	if let Ok(i) = signed.parse::<i64>() { return Ok(Expr::Literal(Value::Integer(i))) }
+	signed.parse::<Decimal>()
+		.map(|d| Expr::Literal(Value::Decimal(d)))
+		.map_err(|_| Error::Parse(format!("'{}' is not a number", signed)))
+}
+
+/// SQL `LIKE`: `%` is any run of characters, `_` any single character.
+fn like_regex(pattern: &str) -> Result<Regex, Error> {
+	let mut regex = String::from("(?s)^");
+	for c in pattern.chars() {
+		match c {
+			'%' => regex.push_str(".*"),
+			'_' => regex.push('.'),
+			c   => regex.push_str(&regex::escape(&c.to_string())),
+		}
+	}
+	regex.push('$');
+	Regex::new(&regex).map_err(|e| Error::Parse(format!("Invalid LIKE pattern '{}': {}", pattern, e)))
+}
+
+fn describe(token: &Token) -> String {
+	match token {
+		Token::Ident(name)     => format!("column \"{}\"", name),
+		Token::Keyword(k)      => k.to_string(),
+		Token::Number(n)       => n.clone(),
+		Token::Text(t)         => format!("'{}'", t),
+		Token::Op(op)          => format!("'{}'", op),
+		Token::LParen          => "'('".to_owned(),
+		Token::RParen          => "')'".to_owned(),
+		Token::Comma           => "','".to_owned(),
+	}
+}
+
+fn unexpected(found: Option<&Token>, expected: &str) -> Error {
+	match found {
+		Some(token) => Error::Parse(format!("Expected {} but found {}", expected, describe(token))),
+		None        => Error::Parse(format!("Expected {} but the expression ended", expected)),
+	}
+}
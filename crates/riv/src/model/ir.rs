@@ -1,13 +1,24 @@
 pub mod atom;
 pub mod atom_type;
 pub mod byte_row;
+pub mod decimal;
 pub mod external_metadata;
 pub mod nv_strings;
+pub mod row_batch;
+pub mod row_block;
 pub mod string_row;
+pub mod typed_row;
+pub mod value;
 
 #[cfg(test)]
 mod external_metadata_tests;
 #[cfg(test)]
 mod byte_row_tests;
 #[cfg(test)]
+mod row_batch_tests;
+#[cfg(test)]
 mod string_row_tests;
+#[cfg(test)]
+mod typed_row_tests;
+#[cfg(test)]
+mod value_tests;
@@ -1,9 +1,12 @@
 use crate::Error;
+use crate::model::coordinate::coordinate::Coordinate;
 use crate::model::ir::atom_type::AtomType;
 use crate::model::ir::byte_row::ByteRow;
 use crate::model::ir::external_metadata::SourceVariant;
 use crate::model::ir::nv_strings::NVStrings;
+use crate::model::ir::row_batch::RowBatch;
 use crate::model::ir::string_row::StringRow;
+use crate::model::ir::typed_row::TypedRow;
 
 #[derive(Debug)]
 pub enum Atom
@@ -11,11 +14,13 @@ pub enum Atom
 	// Control
 	StartTask(SourceVariant),
 	EndTask,
-	ErrorAtom(Error),
+	ErrorAtom(Error, Coordinate),     // Where in the source the error was detected
 	
 	// Data
 	StringRowAtom(StringRow),        // Source supplies strings
 	ByteRowAtom(ByteRow),            // Source supplies raw bytes
+	TypedRowAtom(TypedRow),          // Values converted against a schema
+	RowBatchAtom(RowBatch),          // Many byte rows, stored by column
 	StringNVAtom(NVStrings),         // TODO: Figure out how I want to model this ...
 	ByteNVAtom(u8),            // TODO: Figure out how I want to model this ...
 	
@@ -23,7 +28,7 @@ pub enum Atom
 	HeaderRow(StringRow),
 	CommentRow(String),
 	BlankLine,
-	InternalMetadata           // TODO: Model this
+	InternalMetadata(Vec<(String, String)>),   // Name = value bindings recorded during the run
 }
 
 impl Atom {
@@ -34,9 +39,26 @@ impl Atom {
 			Atom::StringNVAtom(_)    => AtomType::Data,
 			Atom::StringRowAtom(_)  => AtomType::Data,
 			Atom::ByteRowAtom(_)    => AtomType::Data,
+			Atom::TypedRowAtom(_)   => AtomType::Data,
+			Atom::RowBatchAtom(_)   => AtomType::Data,
+			Atom::InternalMetadata(_) => AtomType::Metadata,
 			_                       => AtomType::Control,
 		}
 	}
+
+	/// Location in the source text the atom was produced from, when the source tracks one.
+	pub fn coordinate(&self) -> Coordinate {
+		match self
+		{
+			Atom::ErrorAtom(_, c)   => *c,
+			Atom::StringRowAtom(r)  => r.coordinate(),
+			Atom::ByteRowAtom(r)    => r.coordinate(),
+			Atom::TypedRowAtom(r)   => r.coordinate(),
+			Atom::RowBatchAtom(b)   => b.coordinate(0),
+			Atom::HeaderRow(r)      => r.coordinate(),
+			_                       => Coordinate::Undefined,
+		}
+	}
 }
 
 
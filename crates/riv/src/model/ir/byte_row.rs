@@ -1,27 +1,78 @@
 use std::ops::Range;
 use std::fmt;
+use std::sync::Arc;
+use crate::model::coordinate::coordinate::Coordinate;
+use crate::model::ir::row_block::RowBlock;
 use crate::model::ir::string_row::StringRow;
 
+/// One record's fields as raw bytes.
+///
+/// The bytes live in a shared `RowBlock`; a row is a view of it, so rows
+/// from the same block cost no further allocations and cloning a row only
+/// bumps a reference count.
+///
+#[derive(Clone)]
 pub struct ByteRow {
-	values:     Box<[u8]>,
-	bounds:     ByteRowBounds,
+	block:      Arc<RowBlock>,
+	start:      usize,
+	fields:     Range<usize>,
+	coordinate: Coordinate,
 }
 
 impl ByteRow {
+	/// A row that owns a copy of `data`. `ends` are the end offsets of each field.
 	pub fn new(data: &[u8], ends: &[usize]) -> Self {
-		let values = data.to_vec().into_boxed_slice();
-		let bounds = ByteRowBounds::new(ends);
-		ByteRow{values, bounds}
+		let block = RowBlock::new(data.to_vec(), ends.to_vec());
+		ByteRow::in_block(Arc::new(block), 0, 0..ends.len())
 	}
 
-	pub fn length(&self)   -> u32  { self.bounds.count()      }
-	pub fn is_empty(&self) -> bool { self.bounds.count() == 0 }
+	/// A row holding a copy of each of `fields`, in order.
+	pub fn from_fields<'a, I: IntoIterator<Item = &'a [u8]>>(fields: I) -> Self {
+		let mut data = Vec::new();
+		let mut ends = Vec::new();
+		for field in fields {
+			data.extend_from_slice(field);
+			ends.push(data.len());
+		}
+		let count = ends.len();
+		ByteRow::in_block(Arc::new(RowBlock::new(data, ends)), 0, 0..count)
+	}
+
+	/// A row that shares `block`. Its first field starts at byte `start`;
+	/// `fields` selects this row's entries in the block's field ends.
+	pub fn in_block(block: Arc<RowBlock>, start: usize, fields: Range<usize>) -> Self {
+		debug_assert!(fields.end <= block.ends().len(), "row fields past the end of the block");
+		let coordinate = Coordinate::Undefined;
+		ByteRow{block, start, fields, coordinate}
+	}
+
+	/// Records where this row starts in the source text.
+	pub fn with_coordinate(mut self, coordinate: Coordinate) -> Self {
+		self.coordinate = coordinate;
+		self
+	}
+
+	pub fn length(&self)     -> u32            { self.fields.len() as u32 }
+	pub fn is_empty(&self)   -> bool           { self.fields.is_empty()   }
+	pub fn coordinate(&self) -> Coordinate     { self.coordinate          }
+	pub fn block(&self)      -> &Arc<RowBlock> { &self.block              }
 
 	pub fn get(&self, index: usize) -> Option<&[u8]> {
-		let range = self.bounds.get(index)?;
-		Some(&self.values[range.start..range.end])
+		if index >= self.fields.len() { return None }
+		let ends  = self.block.ends();
+		let end   = ends[self.fields.start + index];
+		let start = if index == 0 { self.start } else { ends[self.fields.start + index - 1] };
+		Some(&self.block.data()[start..end])
+	}
+
+	/// All of this row's field bytes, back to back.
+	pub fn data(&self) -> &[u8] {
+		let end = self.fields.clone().last().map_or(self.start, |i| self.block.ends()[i]);
+		&self.block.data()[self.start..end]
 	}
-	
+
+	/// Copies every field into an owned `String`. Prefer `iter_str` where
+	/// borrowed text will do.
 	pub fn as_string_row(&self) -> StringRow {
 		StringRow::new_from_byte_row(self)
 	}
@@ -109,7 +160,7 @@ impl ByteRow {
 impl fmt::Debug for ByteRow {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         // Collect each field slice as a UTF-8 String (using lossless conversion).
-        let fields: Vec<String> = (0..self.bounds.count())
+        let fields: Vec<String> = (0..self.length())
             .filter_map(|i| {
                 self.get(i as usize).map(|bytes| {
                     // Convert &[u8] to String, replacing invalid UTF-8 with �
@@ -1,5 +1,7 @@
 
+use std::sync::Arc;
 use crate::model::ir::byte_row::{ByteRow, ByteRowBounds};
+use crate::model::ir::row_block::RowBlock;
 
 //
 // Bounds tests
@@ -123,3 +125,27 @@ fn test_iterate_over_str() {
         println!("{:?}", field_str);
     }
 }
+
+#[test]
+fn rows_in_a_shared_block_see_only_their_fields() {
+	// Two records "ab","c" and "de","fg" parsed back to back
+	let block = Arc::new(RowBlock::new(b"abcdefg".to_vec(), vec![2, 3, 5, 7]));
+	let row_a = ByteRow::in_block(Arc::clone(&block), 0, 0..2);
+	let row_b = ByteRow::in_block(Arc::clone(&block), 3, 2..4);
+
+	assert_eq!(row_a.get(0), Some(&b"ab"[..]));
+	assert_eq!(row_a.get(1), Some(&b"c"[..]));
+	assert_eq!(row_a.get(2), None);
+	assert_eq!(row_b.get(0), Some(&b"de"[..]));
+	assert_eq!(row_b.get(1), Some(&b"fg"[..]));
+	assert_eq!(row_b.data(), b"defg");
+	assert_eq!(Arc::strong_count(&block), 3);
+}
+
+#[test]
+fn clone_shares_the_block() {
+	let row   = ByteRow::new(b"abc", &[1, 3]);
+	let copy  = row.clone();
+	assert!(Arc::ptr_eq(row.block(), copy.block()));
+	assert_eq!(copy.get(1), Some(&b"bc"[..]));
+}
@@ -0,0 +1,163 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use crate::Error;
+
+const MAX_SCALE: u32 = 28;
+
+/// A fixed-point decimal number: `mantissa * 10^-scale`.
+///
+/// Used for values such as prices and amounts where a binary float
+/// would change the digits the source supplied. "1234.50" keeps its
+/// trailing zero (mantissa 123450, scale 2).
+///
+/// Equality and ordering compare numeric value, so "1.5" == "1.50".
+///
+#[derive(Clone, Copy)]
+pub struct Decimal {
+	mantissa: i128,
+	scale:    u32,
+}
+
+impl Decimal {
+	/// `mantissa * 10^-scale`; the scale may be at most 28.
+	pub fn new(mantissa: i128, scale: u32) -> Result<Self, Error> {
+		if scale > MAX_SCALE {
+			return Err(Error::InvalidInput(format!("decimal scale {} is above the maximum of {}", scale, MAX_SCALE)))
+		}
+		Ok(Decimal{mantissa, scale})
+	}
+
+	pub fn mantissa(&self) -> i128 { self.mantissa }
+	pub fn scale(&self)    -> u32  { self.scale    }
+
+	pub fn to_f64(&self) -> f64 {
+		self.mantissa as f64 / 10f64.powi(self.scale as i32)
+	}
+
+	/// Returns the value as an integer when it has no fractional part.
+	pub fn to_i64(&self) -> Option<i64> {
+		let factor = 10i128.checked_pow(self.scale)?;
+		if self.mantissa % factor != 0 { return None }
+		i64::try_from(self.mantissa / factor).ok()
+	}
+
+	/// Exact sum at the larger of the two scales; `None` on overflow.
+	pub fn checked_add(&self, other: &Decimal) -> Option<Decimal> {
+		let scale = self.scale.max(other.scale);
+		Some(Decimal{mantissa: self.rescaled(scale)?.checked_add(other.rescaled(scale)?)?, scale})
+	}
+
+	/// Exact difference at the larger of the two scales; `None` on overflow.
+	pub fn checked_sub(&self, other: &Decimal) -> Option<Decimal> {
+		self.checked_add(&Decimal{mantissa: other.mantissa.checked_neg()?, scale: other.scale})
+	}
+
+	/// Exact product; the scales add. `None` on overflow.
+	pub fn checked_mul(&self, other: &Decimal) -> Option<Decimal> {
+		let scale = self.scale + other.scale;
+		if scale > MAX_SCALE { return None }
+		Some(Decimal{mantissa: self.mantissa.checked_mul(other.mantissa)?, scale})
+	}
+
+	/// Rounded half away from zero to at most `scale` fractional digits.
+	pub fn round(&self, scale: u32) -> Decimal {
+		if self.scale <= scale { return *self }
+		let factor   = 10i128.pow(self.scale - scale);
+		let mut kept = self.mantissa / factor;
+		if (self.mantissa % factor).unsigned_abs() * 2 >= factor.unsigned_abs() {
+			kept += self.mantissa.signum();
+		}
+		Decimal{mantissa: kept, scale}
+	}
+
+	// Same value with trailing fractional zeros removed.
+	fn normalized(&self) -> (i128, u32) {
+		let (mut mantissa, mut scale) = (self.mantissa, self.scale);
+		while scale > 0 && mantissa % 10 == 0 {
+			mantissa /= 10;
+			scale    -= 1;
+		}
+		(mantissa, scale)
+	}
+
+	// Mantissa expressed at a larger scale, if it fits.
+	fn rescaled(&self, scale: u32) -> Option<i128> {
+		let factor = 10i128.checked_pow(scale - self.scale)?;
+		self.mantissa.checked_mul(factor)
+	}
+}
+
+impl FromStr for Decimal {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let invalid            = || Error::Parse(format!("'{}' is not a decimal number", s));
+		let text               = s.trim();
+		let (negative, digits) = match text.as_bytes().first() {
+			Some(b'-') => (true,  &text[1..]),
+			Some(b'+') => (false, &text[1..]),
+			_          => (false, text),
+		};
+		let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+		if whole.is_empty() && fraction.is_empty() { return Err(invalid()) }
+		if !whole.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) { return Err(invalid()) }
+		let scale = fraction.len() as u32;
+		if scale > MAX_SCALE { return Err(invalid()) }
+
+		let mut mantissa: i128 = 0;
+		for b in whole.bytes().chain(fraction.bytes()) {
+			mantissa = mantissa
+				.checked_mul(10)
+				.and_then(|m| m.checked_add((b - b'0') as i128))
+				.ok_or_else(invalid)?;
+		}
+		if negative { mantissa = -mantissa }
+		Ok(Decimal{mantissa, scale})
+	}
+}
+
+impl PartialEq for Decimal {
+	fn eq(&self, other: &Self) -> bool { self.normalized() == other.normalized() }
+}
+
+impl Eq for Decimal {}
+
+impl Hash for Decimal {
+	fn hash<H: Hasher>(&self, state: &mut H) { self.normalized().hash(state) }
+}
+
+impl Ord for Decimal {
+	fn cmp(&self, other: &Self) -> Ordering {
+		let scale = self.scale.max(other.scale);
+		match (self.rescaled(scale), other.rescaled(scale)) {
+			(Some(a), Some(b)) => a.cmp(&b),
+			_                  => self.to_f64().total_cmp(&other.to_f64()),
+		}
+	}
+}
+
+impl PartialOrd for Decimal {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl fmt::Display for Decimal {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let digits = self.mantissa.unsigned_abs().to_string();
+		let sign   = if self.mantissa < 0 { "-" } else { "" };
+		let scale  = self.scale as usize;
+		if scale == 0 {
+			return write!(f, "{}{}", sign, digits)
+		}
+		let padded        = format!("{:0>width$}", digits, width = scale + 1);
+		let (whole, frac) = padded.split_at(padded.len() - scale);
+		write!(f, "{}{}.{}", sign, whole, frac)
+	}
+}
+
+impl fmt::Debug for Decimal {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "Decimal({})", self)
+	}
+}
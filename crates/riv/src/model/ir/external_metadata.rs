@@ -16,7 +16,7 @@ type ExternalMetadataMap = HashMap<String, String>;
 
 // Common behavior shared by external metadata
 //
-trait TaskMetadata {
+pub trait TaskMetadata {
 	fn as_map(&self)         -> ExternalMetadataMap;
 	fn correlation_id(&self) -> &str;
 	fn sha_256(&self)        -> &str;
@@ -0,0 +1,182 @@
+use std::sync::Arc;
+use crate::Error;
+use crate::model::coordinate::coordinate::Coordinate;
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::row_block::RowBlock;
+
+/// One column of a `RowBatch`.
+///
+/// Every row's value is packed into `values`; `offsets[i]..offsets[i + 1]`
+/// selects row `i`. This is Arrow's variable-width layout, so a whole
+/// column can be scanned without touching the other columns.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct ByteColumn {
+	values:  Vec<u8>,
+	offsets: Vec<usize>,
+}
+
+impl Default for ByteColumn {
+	fn default() -> Self { Self::new() }
+}
+
+impl ByteColumn {
+	pub fn new() -> Self {
+		ByteColumn{values: Vec::new(), offsets: vec![0]}
+	}
+
+	pub fn len(&self)      -> usize    { self.offsets.len() - 1 }
+	pub fn is_empty(&self) -> bool     { self.len() == 0        }
+	pub fn values(&self)   -> &[u8]    { &self.values           }
+	pub fn offsets(&self)  -> &[usize] { &self.offsets          }
+
+	pub fn push(&mut self, value: &[u8]) {
+		self.values.extend_from_slice(value);
+		self.offsets.push(self.values.len());
+	}
+
+	pub fn get(&self, row: usize) -> Option<&[u8]> {
+		let end   = *self.offsets.get(row + 1)?;
+		let start = self.offsets[row];
+		Some(&self.values[start..end])
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = &[u8]> + '_ {
+		self.offsets.windows(2).map(|w| &self.values[w[0]..w[1]])
+	}
+
+	fn split_off(&mut self, at: usize) -> ByteColumn {
+		let base    = self.offsets[at];
+		let values  = self.values.split_off(base);
+		let offsets = self.offsets[at..].iter().map(|o| o - base).collect();
+		self.offsets.truncate(at + 1);
+		ByteColumn{values, offsets}
+	}
+
+	fn append(&mut self, other: &ByteColumn) {
+		let base = self.values.len();
+		self.values.extend_from_slice(&other.values);
+		self.offsets.extend(other.offsets[1..].iter().map(|o| o + base));
+	}
+}
+
+/// Many rows of the same width, stored column by column.
+///
+/// Batches let a source hand a whole run of records downstream as one
+/// atom, so relays and sinks pay one call and one match per batch rather
+/// than per row, and can work on a column at a time.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct RowBatch {
+	columns:     Vec<ByteColumn>,
+	coordinates: Vec<Coordinate>,
+}
+
+impl RowBatch {
+	pub fn new(width: usize) -> Self {
+		let columns     = (0..width).map(|_| ByteColumn::new()).collect();
+		let coordinates = Vec::new();
+		RowBatch{columns, coordinates}
+	}
+
+	/// Gather `rows` into one batch. All rows must have the same width.
+	pub fn from_rows<'a, I: IntoIterator<Item = &'a ByteRow>>(rows: I) -> Result<Self, Error> {
+		let mut rows  = rows.into_iter().peekable();
+		let width     = rows.peek().map_or(0, |r| r.length() as usize);
+		let mut batch = RowBatch::new(width);
+		for row in rows {
+			batch.push_row(row)?;
+		}
+		Ok(batch)
+	}
+
+	pub fn len(&self)      -> usize         { self.coordinates.len()      }
+	pub fn is_empty(&self) -> bool          { self.coordinates.is_empty() }
+	pub fn width(&self)    -> usize         { self.columns.len()          }
+	pub fn columns(&self)  -> &[ByteColumn] { &self.columns               }
+
+	pub fn column(&self, index: usize) -> Option<&ByteColumn> { self.columns.get(index) }
+
+	/// Where row `index` starts in the source text.
+	pub fn coordinate(&self, index: usize) -> Coordinate {
+		self.coordinates.get(index).copied().unwrap_or(Coordinate::Undefined)
+	}
+
+	pub fn push_row(&mut self, row: &ByteRow) -> Result<(), Error> {
+		if row.length() as usize != self.width() {
+			let msg = format!("{} fields, expected {}", row.length(), self.width());
+			return Err(Error::Parse(msg))
+		}
+		for (column, value) in self.columns.iter_mut().zip(row) {
+			column.push(value);
+		}
+		self.coordinates.push(row.coordinate());
+		Ok(())
+	}
+
+	/// Field `column` of row `row`.
+	pub fn get(&self, row: usize, column: usize) -> Option<&[u8]> {
+		self.columns.get(column)?.get(row)
+	}
+
+	/// A new batch made of the columns at `indices`, in that order. Panics if an index is out of range.
+	pub fn select_columns(&self, indices: &[usize]) -> RowBatch {
+		let columns = indices.iter().map(|&i| self.columns[i].clone()).collect();
+		RowBatch{columns, coordinates: self.coordinates.clone()}
+	}
+
+	/// A new batch of the rows for which `keep` returns true.
+	pub fn filter_rows<F: FnMut(usize) -> bool>(&self, mut keep: F) -> RowBatch {
+		let mut kept = RowBatch::new(self.width());
+		for row in (0..self.len()).filter(|&r| keep(r)) {
+			for (column, source) in kept.columns.iter_mut().zip(&self.columns) {
+				column.push(source.get(row).unwrap_or_default());
+			}
+			kept.coordinates.push(self.coordinates[row]);
+		}
+		kept
+	}
+
+	/// Rebuild the rows. They share a single `RowBlock`.
+	pub fn to_rows(&self) -> Vec<ByteRow> {
+		let width    = self.width();
+		let capacity = self.columns.iter().map(|c| c.values.len()).sum();
+		let mut data = Vec::with_capacity(capacity);
+		let mut ends = Vec::with_capacity(self.len() * width);
+		for row in 0..self.len() {
+			for column in &self.columns {
+				data.extend_from_slice(column.get(row).unwrap_or_default());
+				ends.push(data.len());
+			}
+		}
+		let block = Arc::new(RowBlock::new(data, ends));
+		(0..self.len())
+			.map(|row| {
+				let fields = row * width..(row + 1) * width;
+				let start  = if fields.start == 0 { 0 } else { block.ends()[fields.start - 1] };
+				ByteRow::in_block(Arc::clone(&block), start, fields).with_coordinate(self.coordinate(row))
+			})
+			.collect()
+	}
+
+	/// Split the batch in two at row `at`, like `Vec::split_off`. Panics if `at > len`.
+	pub fn split_off(&mut self, at: usize) -> RowBatch {
+		assert!(at <= self.len(), "split index {} past the end of a batch of {}", at, self.len());
+		let columns     = self.columns.iter_mut().map(|c| c.split_off(at)).collect();
+		let coordinates = self.coordinates.split_off(at);
+		RowBatch{columns, coordinates}
+	}
+
+	/// Move every row of `other` onto the end of this batch.
+	pub fn append(&mut self, other: RowBatch) -> Result<(), Error> {
+		if other.width() != self.width() {
+			let msg = format!("Cannot merge a batch of {} columns into one of {}", other.width(), self.width());
+			return Err(Error::InvalidInput(msg))
+		}
+		for (column, more) in self.columns.iter_mut().zip(&other.columns) {
+			column.append(more);
+		}
+		self.coordinates.extend(other.coordinates);
+		Ok(())
+	}
+}
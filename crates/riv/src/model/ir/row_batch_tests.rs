@@ -0,0 +1,85 @@
+use crate::model::coordinate::coordinate::Coordinate;
+use crate::model::coordinate::text_location::TextLocation;
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::row_batch::{ByteColumn, RowBatch};
+
+fn row(fields: &[&str]) -> ByteRow {
+	let data = fields.concat();
+	let ends = fields.iter().scan(0, |end, f| { *end += f.len(); Some(*end) }).collect::<Vec<_>>();
+	ByteRow::new(data.as_bytes(), &ends)
+}
+
+fn batch(rows: &[&[&str]]) -> RowBatch {
+	let rows = rows.iter().map(|r| row(r)).collect::<Vec<_>>();
+	RowBatch::from_rows(&rows).unwrap()
+}
+
+#[test]
+fn column_uses_offsets_into_packed_values() {
+	let mut column = ByteColumn::new();
+	column.push(b"ab");
+	column.push(b"");
+	column.push(b"cde");
+	assert_eq!(column.values(),  b"abcde");
+	assert_eq!(column.offsets(), &[0, 2, 2, 5]);
+	assert_eq!(column.get(2), Some(&b"cde"[..]));
+	assert_eq!(column.get(3), None);
+	assert_eq!(column.iter().collect::<Vec<_>>(), [&b"ab"[..], b"", b"cde"]);
+}
+
+#[test]
+fn rows_are_stored_by_column() {
+	let batch = batch(&[&["1", "alpha"], &["2", "beta"]]);
+	assert_eq!(batch.len(),   2);
+	assert_eq!(batch.width(), 2);
+	assert_eq!(batch.column(1).unwrap().values(), b"alphabeta");
+	assert_eq!(batch.get(1, 0), Some(&b"2"[..]));
+}
+
+#[test]
+fn width_mismatch_is_rejected() {
+	let mut batch = RowBatch::new(2);
+	assert!(batch.push_row(&row(&["only one"])).is_err());
+	assert!(batch.is_empty());
+}
+
+#[test]
+fn to_rows_round_trips_with_coordinates() {
+	let at       = Coordinate::at_location(TextLocation::new(6, 2, 1));
+	let mut b    = RowBatch::new(2);
+	b.push_row(&row(&["1", "alpha"]).with_coordinate(at)).unwrap();
+	b.push_row(&row(&["2", "beta"])).unwrap();
+	let rows = b.to_rows();
+	assert_eq!(rows[0].iter_str().collect::<Vec<_>>(), ["1", "alpha"]);
+	assert_eq!(rows[1].iter_str().collect::<Vec<_>>(), ["2", "beta"]);
+	assert_eq!(rows[0].coordinate(), at);
+	assert!(std::sync::Arc::ptr_eq(rows[0].block(), rows[1].block()));
+}
+
+#[test]
+fn split_and_append_are_inverse() {
+	let mut head = batch(&[&["1", "a"], &["2", "bb"], &["3", "ccc"]]);
+	let original = head.clone();
+	let tail     = head.split_off(1);
+	assert_eq!(head.len(), 1);
+	assert_eq!(tail.len(), 2);
+	assert_eq!(tail.get(0, 1), Some(&b"bb"[..]));
+	head.append(tail).unwrap();
+	assert_eq!(head, original);
+}
+
+#[test]
+fn append_rejects_other_widths() {
+	let mut narrow = batch(&[&["1"]]);
+	assert!(narrow.append(batch(&[&["1", "2"]])).is_err());
+}
+
+#[test]
+fn filter_rows_keeps_values_and_coordinates() {
+	let batch = RowBatch::from_rows(&[row(&["a", "1"]), row(&["b", "2"]), row(&["c", "3"])]).unwrap();
+	let kept  = batch.filter_rows(|r| r != 1);
+	assert_eq!(kept.len(), 2);
+	assert_eq!(kept.get(1, 0), Some(&b"c"[..]));
+	assert_eq!(kept.get(1, 1), Some(&b"3"[..]));
+	assert_eq!(kept.coordinate(1), batch.coordinate(2));
+}
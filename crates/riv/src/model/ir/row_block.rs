@@ -0,0 +1,39 @@
+use std::fmt;
+
+/// The parsed bytes and field ends of a run of consecutive records.
+///
+/// A source parses many records into one block and hands out `ByteRow`s
+/// that share it through an `Arc`, so producing a row costs a reference
+/// count rather than an allocation and a copy.
+///
+/// `ends` holds the end offset of every field of every record, relative
+/// to the start of `data`. A row knows where its own first field starts
+/// and which slice of `ends` belongs to it.
+///
+pub struct RowBlock {
+	data: Vec<u8>,
+	ends: Vec<usize>,
+}
+
+impl RowBlock {
+	pub fn new(data: Vec<u8>, ends: Vec<usize>) -> Self {
+		debug_assert!(ends.iter().all(|&e| e <= data.len()), "field end past the end of the block");
+		RowBlock{data, ends}
+	}
+
+	pub fn data(&self) -> &[u8]    { &self.data }
+	pub fn ends(&self) -> &[usize] { &self.ends }
+
+	/// Bytes held by the block.
+	pub fn len(&self)      -> usize { self.data.len()      }
+	pub fn is_empty(&self) -> bool  { self.data.is_empty() }
+}
+
+impl fmt::Debug for RowBlock {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("RowBlock")
+			.field("bytes",  &self.data.len())
+			.field("fields", &self.ends.len())
+			.finish()
+	}
+}
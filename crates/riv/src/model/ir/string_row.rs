@@ -1,26 +1,42 @@
 use csv::ByteRecord;
 use std::str;
+use crate::model::coordinate::coordinate::Coordinate;
 use crate::model::ir::byte_row::ByteRow;
 
 /// A row of owned `String` values (e.g. converted from a `ByteRecord`).
 #[derive(Debug)]
 pub struct StringRow {
-	values: Box<[String]>,
+	values:     Box<[String]>,
+	coordinate: Coordinate,
 }
 
 impl StringRow {
 	pub fn new(r: &ByteRecord) -> Self {
-		let values = extract_strings(r);
-		let values = values.into_boxed_slice();
-		StringRow{values}
+		let values     = extract_strings(r);
+		let values     = values.into_boxed_slice();
+		let coordinate = Coordinate::Undefined;
+		StringRow{values, coordinate}
 	}
 	pub fn new_from_byte_row(r: &ByteRow) -> Self {
 		let vx = r.iter_str().map(|s| s.to_string()).collect::<Vec<String>>();
-		StringRow{values: vx.into_boxed_slice()}
+		StringRow{values: vx.into_boxed_slice(), coordinate: r.coordinate()}
+	}
+
+	pub fn from_values(values: Vec<String>) -> Self {
+		StringRow{values: values.into_boxed_slice(), coordinate: Coordinate::Undefined}
+	}
+
+	/// Records where this row starts in the source text.
+	pub fn with_coordinate(mut self, coordinate: Coordinate) -> Self {
+		self.coordinate = coordinate;
+		self
 	}
 	
-	pub fn count(&self)    -> u32  { self.values.len() as u32}
-	pub fn is_empty(&self) -> bool { self.values.is_empty()  }
+	pub fn count(&self)      -> u32        { self.values.len() as u32}
+	pub fn is_empty(&self)   -> bool       { self.values.is_empty()  }
+	pub fn coordinate(&self) -> Coordinate { self.coordinate         }
+
+	pub fn get(&self, index: usize) -> Option<&str> { self.values.get(index).map(String::as_str) }
 	
     /// Returns an iterator over `&str` for each field.
     pub fn iter_str(&self) -> StringRowStrIter<'_> {
@@ -0,0 +1,92 @@
+use crate::Error;
+use crate::model::coordinate::coordinate::Coordinate;
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::string_row::StringRow;
+use crate::model::ir::value::{Value, ValueType};
+
+/// A row of typed values, produced by converting text rows against the
+/// column types of a schema.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypedRow {
+	values:     Box<[Value]>,
+	coordinate: Coordinate,
+}
+
+impl TypedRow {
+	pub fn new(values: Vec<Value>) -> Self {
+		let values     = values.into_boxed_slice();
+		let coordinate = Coordinate::Undefined;
+		TypedRow{values, coordinate}
+	}
+
+	/// Convert a `ByteRow` using one column type per field.
+	pub fn from_byte_row(row: &ByteRow, types: &[ValueType]) -> Result<Self, Error> {
+		check_width(row.length() as usize, types.len())?;
+		let mut values = Vec::with_capacity(types.len());
+		for (i, (bytes, value_type)) in row.into_iter().zip(types).enumerate() {
+			let value = Value::from_bytes(bytes, *value_type, None).map_err(|e| column_error(i, e))?;
+			values.push(value);
+		}
+		Ok(TypedRow::new(values).with_coordinate(row.coordinate()))
+	}
+
+	/// Convert a `StringRow` using one column type per field.
+	pub fn from_string_row(row: &StringRow, types: &[ValueType]) -> Result<Self, Error> {
+		check_width(row.count() as usize, types.len())?;
+		let mut values = Vec::with_capacity(types.len());
+		for (i, (text, value_type)) in row.iter_str().zip(types).enumerate() {
+			let value = Value::parse(text, *value_type).map_err(|e| column_error(i, e))?;
+			values.push(value);
+		}
+		Ok(TypedRow::new(values).with_coordinate(row.coordinate()))
+	}
+
+	/// Records where this row starts in the source text.
+	pub fn with_coordinate(mut self, coordinate: Coordinate) -> Self {
+		self.coordinate = coordinate;
+		self
+	}
+
+	pub fn count(&self)      -> u32        { self.values.len() as u32 }
+	pub fn is_empty(&self)   -> bool       { self.values.is_empty()   }
+	pub fn coordinate(&self) -> Coordinate { self.coordinate          }
+
+	pub fn get(&self, index: usize) -> Option<&Value> { self.values.get(index) }
+
+	pub fn iter(&self) -> std::slice::Iter<'_, Value> { self.values.iter() }
+
+	pub fn value_types(&self) -> Vec<ValueType> {
+		self.values.iter().map(Value::value_type).collect()
+	}
+}
+
+impl<'a> IntoIterator for &'a TypedRow {
+	type Item     = &'a Value;
+	type IntoIter = std::slice::Iter<'a, Value>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.values.iter()
+	}
+}
+
+impl IntoIterator for TypedRow {
+	type Item     = Value;
+	type IntoIter = std::vec::IntoIter<Value>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.values.into_vec().into_iter()
+	}
+}
+
+fn check_width(fields: usize, types: usize) -> Result<(), Error> {
+	if fields == types { return Ok(()) }
+	let msg = format!("{} fields, expected {}", fields, types);
+	Err(Error::Parse(msg))
+}
+
+fn column_error(index: usize, err: Error) -> Error {
+	match err {
+		Error::Parse(msg) => Error::Parse(format!("column {}: {}", index + 1, msg)),
+		other             => other,
+	}
+}
@@ -0,0 +1,43 @@
+use crate::Error;
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::{Value, ValueType};
+
+fn byte_row(fields: &[&str]) -> ByteRow {
+	let data = fields.concat();
+	let ends = fields.iter().scan(0, |end, f| { *end += f.len(); Some(*end) }).collect::<Vec<_>>();
+	ByteRow::new(data.as_bytes(), &ends)
+}
+
+#[test]
+fn converts_byte_row_by_column_type() {
+	let row   = byte_row(&["Tokyo", "35", "", "true"]);
+	let types = [ValueType::String, ValueType::Integer, ValueType::Float, ValueType::Boolean];
+	let typed = TypedRow::from_byte_row(&row, &types).unwrap();
+	let expected = vec![Value::String("Tokyo".into()), Value::Integer(35), Value::Null, Value::Boolean(true)];
+	assert_eq!(typed.iter().cloned().collect::<Vec<_>>(), expected);
+	assert_eq!(typed.value_types(), vec![ValueType::String, ValueType::Integer, ValueType::Null, ValueType::Boolean]);
+}
+
+#[test]
+fn converts_string_row() {
+	let row   = byte_row(&["Jakarta", "-6.1750"]).as_string_row();
+	let types = [ValueType::String, ValueType::Decimal];
+	let typed = TypedRow::from_string_row(&row, &types).unwrap();
+	assert_eq!(typed.get(1).unwrap().to_string(), "-6.1750");
+}
+
+#[test]
+fn conversion_error_names_the_column() {
+	let row   = byte_row(&["Delhi", "hot"]);
+	let types = [ValueType::String, ValueType::Float];
+	let err   = TypedRow::from_byte_row(&row, &types).unwrap_err();
+	assert_eq!(err, Error::Parse("column 2: 'hot' is not a valid float".into()));
+}
+
+#[test]
+fn conversion_requires_one_type_per_field() {
+	let row = byte_row(&["a", "b"]);
+	let err = TypedRow::from_byte_row(&row, &[ValueType::String]).unwrap_err();
+	assert_eq!(err, Error::Parse("2 fields, expected 1".into()));
+}
@@ -0,0 +1,178 @@
+use std::fmt;
+use std::str;
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use crate::Error;
+use crate::model::ir::decimal::Decimal;
+
+pub const DEFAULT_DATE_FORMAT:      &str = "%Y-%m-%d";
+pub const DEFAULT_TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+
+const TIMESTAMP_FORMATS: [&str; 2] = [DEFAULT_TIMESTAMP_FORMAT, "%Y-%m-%d %H:%M:%S%.f"];
+
+/// The kinds of value a column can hold.
+///
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueType
+{
+	Null,
+	Boolean,
+	Integer,
+	Float,
+	Decimal,
+	Date,
+	Timestamp,
+	String,
+	Bytes,
+}
+
+impl ValueType {
+	pub fn all() -> Vec<ValueType> {
+		vec![ValueType::Null, ValueType::Boolean, ValueType::Integer, ValueType::Float, ValueType::Decimal,
+			ValueType::Date, ValueType::Timestamp, ValueType::String, ValueType::Bytes]
+	}
+
+	pub fn is_numeric(&self) -> bool {
+		matches!(self, ValueType::Integer | ValueType::Float | ValueType::Decimal)
+	}
+}
+
+impl fmt::Display for ValueType {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let label = match self {
+			ValueType::Null      => "null",
+			ValueType::Boolean   => "boolean",
+			ValueType::Integer   => "integer",
+			ValueType::Float     => "float",
+			ValueType::Decimal   => "decimal",
+			ValueType::Date      => "date",
+			ValueType::Timestamp => "timestamp",
+			ValueType::String    => "string",
+			ValueType::Bytes     => "bytes",
+		};
+		f.write_str(label)
+	}
+}
+
+/// A single typed cell.
+///
+/// Sources supply text; a `Value` is what that text means once a column
+/// type is known, so sinks can write native types instead of re-parsing.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value
+{
+	Null,
+	Boolean(bool),
+	Integer(i64),
+	Float(f64),
+	Decimal(Decimal),
+	Date(NaiveDate),
+	Timestamp(NaiveDateTime),
+	String(String),
+	Bytes(Vec<u8>),
+}
+
+impl Value {
+	pub fn value_type(&self) -> ValueType {
+		match self {
+			Value::Null         => ValueType::Null,
+			Value::Boolean(_)   => ValueType::Boolean,
+			Value::Integer(_)   => ValueType::Integer,
+			Value::Float(_)     => ValueType::Float,
+			Value::Decimal(_)   => ValueType::Decimal,
+			Value::Date(_)      => ValueType::Date,
+			Value::Timestamp(_) => ValueType::Timestamp,
+			Value::String(_)    => ValueType::String,
+			Value::Bytes(_)     => ValueType::Bytes,
+		}
+	}
+
+	pub fn is_null(&self) -> bool { matches!(self, Value::Null) }
+
+	/// Numeric view of the value, when it has one.
+	pub fn as_f64(&self) -> Option<f64> {
+		match self {
+			Value::Integer(v) => Some(*v as f64),
+			Value::Float(v)   => Some(*v),
+			Value::Decimal(v) => Some(v.to_f64()),
+			_                 => None,
+		}
+	}
+
+	/// Parse `text` as a value of the given type using the default formats.
+	pub fn parse(text: &str, value_type: ValueType) -> Result<Value, Error> {
+		Self::parse_with_format(text, value_type, None)
+	}
+
+	/// Parse `text` as a value of the given type.
+	///
+	/// `format` is a chrono pattern used for dates and timestamps; it is
+	/// ignored for other types. Empty text is `Null` for every type but
+	/// `String`.
+	///
+	pub fn parse_with_format(text: &str, value_type: ValueType, format: Option<&str>) -> Result<Value, Error> {
+		let invalid = || Error::Parse(format!("'{}' is not a valid {}", text, value_type));
+		let trimmed = text.trim();
+		if trimmed.is_empty() && value_type != ValueType::String {
+			return Ok(Value::Null)
+		}
+		match value_type {
+			ValueType::Null      => Err(invalid()),
+			ValueType::Boolean   => parse_bool(trimmed).map(Value::Boolean).ok_or_else(invalid),
+			ValueType::Integer   => trimmed.parse::<i64>().map(Value::Integer).map_err(|_| invalid()),
+			ValueType::Float     => trimmed.parse::<f64>().map(Value::Float).map_err(|_| invalid()),
+			ValueType::Decimal   => trimmed.parse::<Decimal>().map(Value::Decimal).map_err(|_| invalid()),
+			ValueType::Date      => {
+				let format = format.unwrap_or(DEFAULT_DATE_FORMAT);
+				NaiveDate::parse_from_str(trimmed, format).map(Value::Date).map_err(|_| invalid())
+			}
+			ValueType::Timestamp => {
+				let parsed = match format {
+					Some(f) => NaiveDateTime::parse_from_str(trimmed, f).ok(),
+					None    => TIMESTAMP_FORMATS.iter().find_map(|f| NaiveDateTime::parse_from_str(trimmed, f).ok()),
+				};
+				parsed.map(Value::Timestamp).ok_or_else(invalid)
+			}
+			ValueType::String    => Ok(Value::String(text.to_owned())),
+			ValueType::Bytes     => Ok(Value::Bytes(text.as_bytes().to_vec())),
+		}
+	}
+
+	/// Parse raw field bytes. `Bytes` columns keep the bytes as-is; every
+	/// other type requires valid UTF-8.
+	pub fn from_bytes(bytes: &[u8], value_type: ValueType, format: Option<&str>) -> Result<Value, Error> {
+		if value_type == ValueType::Bytes {
+			return Ok(Value::Bytes(bytes.to_vec()))
+		}
+		let text = str::from_utf8(bytes).map_err(|e| Error::Parse(format!("Invalid UTF-8: {}", e)))?;
+		Self::parse_with_format(text, value_type, format)
+	}
+}
+
+pub(crate) fn parse_bool(text: &str) -> Option<bool> {
+	match text.to_ascii_lowercase().as_str() {
+		"true"  | "t" | "yes" | "y" | "1" => Some(true),
+		"false" | "f" | "no"  | "n" | "0" => Some(false),
+		_                                 => None,
+	}
+}
+
+/// Text form used by text-oriented sinks. Dates and timestamps are ISO 8601,
+/// bytes are hex encoded and `Null` is empty.
+impl fmt::Display for Value {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Value::Null         => Ok(()),
+			Value::Boolean(v)   => write!(f, "{}", v),
+			Value::Integer(v)   => write!(f, "{}", v),
+			Value::Float(v)     => write!(f, "{}", v),
+			Value::Decimal(v)   => write!(f, "{}", v),
+			Value::Date(v)      => write!(f, "{}", v.format(DEFAULT_DATE_FORMAT)),
+			Value::Timestamp(v) => write!(f, "{}", v.format(DEFAULT_TIMESTAMP_FORMAT)),
+			Value::String(v)    => f.write_str(v),
+			Value::Bytes(v)     => f.write_str(&hex::encode(v)),
+		}
+	}
+}
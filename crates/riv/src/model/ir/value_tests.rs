@@ -0,0 +1,83 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use crate::Error;
+use crate::model::ir::decimal::Decimal;
+use crate::model::ir::value::{Value, ValueType};
+
+#[test]
+fn empty_text_is_null_except_for_strings() {
+	for value_type in ValueType::all() {
+		let value = Value::parse("  ", value_type);
+		match value_type {
+			ValueType::String => assert_eq!(value, Ok(Value::String("  ".into()))),
+			_                 => assert_eq!(value, Ok(Value::Null), "{}", value_type),
+		}
+	}
+}
+
+#[test]
+fn parse_numbers() {
+	assert_eq!(Value::parse(" 42 ", ValueType::Integer), Ok(Value::Integer(42)));
+	assert_eq!(Value::parse("-6.175", ValueType::Float), Ok(Value::Float(-6.175)));
+	assert_eq!(Value::parse("1234.50", ValueType::Decimal), Ok(Value::Decimal(Decimal::new(123450, 2).unwrap())));
+	assert!(Value::parse("4x", ValueType::Integer).is_err());
+	assert!(Value::parse("1.2.3", ValueType::Decimal).is_err());
+}
+
+#[test]
+fn parse_booleans() {
+	assert_eq!(Value::parse("TRUE", ValueType::Boolean), Ok(Value::Boolean(true)));
+	assert_eq!(Value::parse("n",    ValueType::Boolean), Ok(Value::Boolean(false)));
+	assert!(Value::parse("maybe", ValueType::Boolean).is_err());
+}
+
+#[test]
+fn parse_dates_and_timestamps() {
+	let date = NaiveDate::from_ymd_opt(2025, 3, 17).unwrap();
+	assert_eq!(Value::parse("2025-03-17", ValueType::Date), Ok(Value::Date(date)));
+	assert_eq!(Value::parse_with_format("03/17/2025", ValueType::Date, Some("%m/%d/%Y")), Ok(Value::Date(date)));
+
+	let ts = NaiveDateTime::parse_from_str("2025-03-17 08:30:00", "%Y-%m-%d %H:%M:%S").unwrap();
+	assert_eq!(Value::parse("2025-03-17 08:30:00", ValueType::Timestamp), Ok(Value::Timestamp(ts)));
+	assert_eq!(Value::parse("2025-03-17T08:30:00", ValueType::Timestamp), Ok(Value::Timestamp(ts)));
+}
+
+#[test]
+fn display_uses_iso_and_empty_null() {
+	let date = NaiveDate::from_ymd_opt(2025, 3, 17).unwrap();
+	assert_eq!(Value::Date(date).to_string(), "2025-03-17");
+	assert_eq!(Value::Null.to_string(), "");
+	assert_eq!(Value::Bytes(vec![0xca, 0xfe]).to_string(), "cafe");
+}
+
+#[test]
+fn decimal_round_trip_keeps_digits() {
+	for text in ["0.05", "-0.05", "1234.50", "-17", "0"] {
+		let d: Decimal = text.parse().unwrap();
+		assert_eq!(d.to_string(), text);
+	}
+}
+
+#[test]
+fn decimal_compares_numerically() {
+	let a: Decimal = "1.5".parse().unwrap();
+	let b: Decimal = "1.50".parse().unwrap();
+	let c: Decimal = "-2".parse().unwrap();
+	assert_eq!(a, b);
+	assert!(c < a);
+	assert_eq!(b.to_i64(), None);
+	assert_eq!("12.00".parse::<Decimal>().unwrap().to_i64(), Some(12));
+}
+
+#[test]
+fn decimal_arithmetic_is_exact() {
+	let d = |text: &str| text.parse::<Decimal>().unwrap();
+	assert_eq!(d("0.1").checked_add(&d("0.2")).unwrap().to_string(), "0.3");
+	assert_eq!(d("1.50").checked_sub(&d("2")).unwrap().to_string(), "-0.50");
+	assert_eq!(d("1.5").checked_mul(&d("-0.25")).unwrap().to_string(), "-0.375");
+	assert_eq!(d("2.345").round(2).to_string(), "2.35");
+	assert_eq!(d("-2.345").round(0).to_string(), "-2");
+	assert_eq!(d("-2.5").round(0).to_string(), "-3");
+	assert!(Decimal::new(i128::MAX, 0).unwrap().checked_add(&d("1")).is_none());
+	assert!(matches!(Decimal::new(1, 29), Err(Error::InvalidInput(_))));                       // So round cannot overflow
+	assert_eq!(Decimal::new(15, 28).unwrap().round(0).to_string(), "0");
+}
@@ -0,0 +1,92 @@
+pub mod field;
+pub mod inference;
+
+#[cfg(test)]
+mod inference_tests;
+#[cfg(test)]
+mod schema_tests;
+
+use serde::{Deserialize, Serialize};
+use crate::Error;
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::string_row::StringRow;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::{Value, ValueType};
+use crate::model::schema::field::Field;
+
+/// Ordered description of the columns in a data set.
+///
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Schema {
+	fields: Vec<Field>,
+}
+
+impl Schema {
+	pub fn new(fields: Vec<Field>) -> Self { Schema{fields} }
+
+	pub fn len(&self)      -> usize     { self.fields.len()      }
+	pub fn is_empty(&self) -> bool      { self.fields.is_empty() }
+	pub fn fields(&self)   -> &[Field]  { &self.fields           }
+
+	pub fn field(&self, name: &str) -> Option<&Field> {
+		self.fields.iter().find(|f| f.name == name)
+	}
+
+	pub fn index_of(&self, name: &str) -> Option<usize> {
+		self.fields.iter().position(|f| f.name == name)
+	}
+
+	pub fn names(&self) -> Vec<&str> {
+		self.fields.iter().map(|f| f.name.as_str()).collect()
+	}
+
+	pub fn value_types(&self) -> Vec<ValueType> {
+		self.fields.iter().map(|f| f.value_type).collect()
+	}
+
+	/// Convert a `ByteRow` to typed values, honoring each field's format.
+	pub fn convert_byte_row(&self, row: &ByteRow) -> Result<TypedRow, Error> {
+		self.check_width(row.length() as usize)?;
+		let mut values = Vec::with_capacity(self.fields.len());
+		for (i, (bytes, field)) in row.into_iter().zip(&self.fields).enumerate() {
+			let value = Value::from_bytes(bytes, field.value_type, field.format.as_deref());
+			values.push(self.checked(i, value)?);
+		}
+		Ok(TypedRow::new(values).with_coordinate(row.coordinate()))
+	}
+
+	/// Convert a `StringRow` to typed values, honoring each field's format.
+	pub fn convert_string_row(&self, row: &StringRow) -> Result<TypedRow, Error> {
+		self.check_width(row.count() as usize)?;
+		let mut values = Vec::with_capacity(self.fields.len());
+		for (i, (text, field)) in row.iter_str().zip(&self.fields).enumerate() {
+			let value = Value::parse_with_format(text, field.value_type, field.format.as_deref());
+			values.push(self.checked(i, value)?);
+		}
+		Ok(TypedRow::new(values).with_coordinate(row.coordinate()))
+	}
+
+	pub fn to_json(&self) -> Result<String, Error> {
+		serde_json::to_string_pretty(self).map_err(|e| Error::General(format!("Failed to serialize schema: {}", e)))
+	}
+
+	pub fn from_json(json: &str) -> Result<Schema, Error> {
+		serde_json::from_str(json).map_err(|e| Error::Parse(format!("Invalid schema: {}", e)))
+	}
+
+	fn check_width(&self, count: usize) -> Result<(), Error> {
+		if count == self.fields.len() { return Ok(()) }
+		Err(Error::Parse(format!("{} fields, expected {}", count, self.fields.len())))
+	}
+
+	// Attach the field name to conversion failures and enforce nullability.
+	fn checked(&self, index: usize, value: Result<Value, Error>) -> Result<Value, Error> {
+		let field = &self.fields[index];
+		match value {
+			Ok(Value::Null) if !field.nullable => Err(Error::Parse(format!("{}: null in non-nullable field", field.name))),
+			Ok(v)                              => Ok(v),
+			Err(Error::Parse(msg))             => Err(Error::Parse(format!("{}: {}", field.name, msg))),
+			Err(e)                             => Err(e),
+		}
+	}
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+use crate::model::ir::value::ValueType;
+
+/// One column of a `Schema`.
+///
+/// `format` is the pattern observed for dates and timestamps (a chrono
+/// format string such as `%d-%b-%Y`); it is `None` for other types.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Field {
+	pub name:       String,
+	#[serde(rename = "type")]
+	pub value_type: ValueType,
+	pub nullable:   bool,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub format:     Option<String>,
+}
+
+impl Field {
+	pub fn new(name: impl Into<String>, value_type: ValueType, nullable: bool) -> Self {
+		let name   = name.into();
+		let format = None;
+		Field{name, value_type, nullable, format}
+	}
+
+	pub fn with_format(mut self, format: impl Into<String>) -> Self {
+		self.format = Some(format.into());
+		self
+	}
+}
@@ -0,0 +1,271 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use crate::model::ir::atom::Atom;
+use crate::model::ir::decimal::Decimal;
+use crate::model::ir::value::{Value, ValueType};
+use crate::model::schema::field::Field;
+use crate::model::schema::Schema;
+
+pub const DEFAULT_SAMPLE_SIZE: usize = 1_000;
+pub const MAX_EXAMPLES:        usize = 3;
+
+/// Date patterns tried during inference, in order of preference.
+pub const DATE_FORMATS: [&str; 6] = ["%Y-%m-%d", "%m/%d/%Y", "%d/%m/%Y", "%d-%b-%Y", "%d.%m.%Y", "%Y/%m/%d"];
+
+/// Timestamp patterns tried during inference, in order of preference.
+pub const TIMESTAMP_FORMATS: [&str; 5] = [
+	"%Y-%m-%dT%H:%M:%S%.f",
+	"%Y-%m-%d %H:%M:%S%.f",
+	"%m/%d/%Y %H:%M:%S",
+	"%d/%m/%Y %H:%M:%S",
+	"%d-%b-%Y %H:%M:%S",
+];
+
+/// Tracks which types every value seen so far in one column converts to.
+///
+/// Each observation can only remove candidates, so the resulting type is
+/// the narrowest one that all sampled values share. Conflicts widen:
+/// integer → decimal → float, and anything else falls back to string.
+///
+/// Typed values count by their own type rather than by how their text
+/// reads; a column whose typed values disagree, or that mixes them with
+/// text, is a string.
+///
+/// Alongside the type it keeps what DDL and documentation need: the
+/// longest value, the widest decimal and a few distinct example values.
+///
+#[derive(Clone, Debug)]
+pub struct ColumnInference {
+	values:     u64,
+	nulls:      u64,
+	max_length: usize,
+	max_whole:  u32,
+	max_scale:  u32,
+	examples:   Vec<String>,
+	boolean:    bool,
+	integer:    bool,
+	decimal:    bool,
+	float:      bool,
+	dates:      Vec<&'static str>,
+	timestamps: Vec<&'static str>,
+	typed:      Option<ValueType>,
+	typed_seen: u64,
+}
+
+impl Default for ColumnInference {
+	fn default() -> Self {
+		ColumnInference {
+			values:     0,
+			nulls:      0,
+			max_length: 0,
+			max_whole:  0,
+			max_scale:  0,
+			examples:   Vec::new(),
+			boolean:    true,
+			integer:    true,
+			decimal:    true,
+			float:      true,
+			dates:      DATE_FORMATS.to_vec(),
+			timestamps: TIMESTAMP_FORMATS.to_vec(),
+			typed:      None,
+			typed_seen: 0,
+		}
+	}
+}
+
+impl ColumnInference {
+	pub fn new() -> Self { Self::default() }
+
+	pub fn values(&self)     -> u64       { self.values     }
+	pub fn nulls(&self)      -> u64       { self.nulls      }
+	pub fn max_length(&self) -> usize     { self.max_length }
+	pub fn examples(&self)   -> &[String] { &self.examples  }
+
+	/// Total digits and digits after the point needed to hold every decimal seen.
+	pub fn precision_and_scale(&self) -> (u32, u32) {
+		(self.max_whole + self.max_scale, self.max_scale)
+	}
+
+	/// Fraction of observed cells that were null.
+	pub fn null_ratio(&self) -> f64 {
+		let total = self.values + self.nulls;
+		if total == 0 { 0.0 } else { self.nulls as f64 / total as f64 }
+	}
+
+	/// Record one cell. Blank text counts as null.
+	pub fn observe(&mut self, text: &str) {
+		let text = text.trim();
+		if text.is_empty() {
+			self.nulls += 1;
+			return
+		}
+		self.note(text);
+		if self.is_string() { return }
+
+		self.boolean &= matches!(text.to_ascii_lowercase().as_str(), "true" | "false" | "yes" | "no");
+		self.integer &= text.parse::<i64>().is_ok();
+		if self.decimal {
+			match text.parse::<Decimal>() {
+				Ok(d)  => self.measure(&d),
+				Err(_) => self.decimal = false,
+			}
+		}
+		self.float   &= text.bytes().any(|b| b.is_ascii_digit()) && text.parse::<f64>().is_ok();
+		self.dates.retain(|f| NaiveDate::parse_from_str(text, f).is_ok());
+		self.timestamps.retain(|f| NaiveDateTime::parse_from_str(text, f).is_ok());
+	}
+
+	/// Record one typed value. Strings are read like text, nulls count as null.
+	pub fn observe_value(&mut self, value: &Value) {
+		let value_type = match value {
+			Value::Null       => { self.nulls += 1; return }
+			Value::String(s)  => return self.observe(s),
+			Value::Decimal(d) => { self.measure(d); ValueType::Decimal }
+			other             => other.value_type(),
+		};
+		self.note(&value.to_string());
+		self.typed_seen += 1;
+		self.typed       = match self.typed {
+			Some(seen) if seen != value_type => Some(ValueType::String),
+			_                                => Some(value_type),
+		};
+	}
+
+	/// The inferred type; `Null` when only blank values were seen.
+	pub fn value_type(&self) -> ValueType {
+		if self.values == 0 { return ValueType::Null      }
+		if let Some(typed) = self.typed {
+			return if self.typed_seen == self.values { typed } else { ValueType::String }
+		}
+		if self.boolean     { return ValueType::Boolean   }
+		if self.integer     { return ValueType::Integer   }
+		if self.decimal     { return ValueType::Decimal   }
+		if self.float       { return ValueType::Float     }
+		if !self.dates.is_empty()      { return ValueType::Date      }
+		if !self.timestamps.is_empty() { return ValueType::Timestamp }
+		ValueType::String
+	}
+
+	/// The date or timestamp pattern every value matched.
+	pub fn format(&self) -> Option<&'static str> {
+		match self.value_type() {
+			ValueType::Date      => self.dates.first().copied(),
+			ValueType::Timestamp => self.timestamps.first().copied(),
+			_                    => None,
+		}
+	}
+
+	pub fn field(&self, name: &str) -> Field {
+		let nullable = self.nulls > 0 || self.values == 0;
+		let field    = Field::new(name, self.value_type(), nullable);
+		match self.format() {
+			Some(f) => field.with_format(f),
+			None    => field,
+		}
+	}
+
+	fn note(&mut self, text: &str) {
+		self.values    += 1;
+		self.max_length = self.max_length.max(text.chars().count());
+		if self.examples.len() < MAX_EXAMPLES && !self.examples.iter().any(|e| e == text) {
+			self.examples.push(text.to_owned());
+		}
+	}
+
+	fn measure(&mut self, d: &Decimal) {
+		let digits     = d.mantissa().unsigned_abs().to_string().len() as u32;
+		self.max_whole = self.max_whole.max(digits.saturating_sub(d.scale()));
+		self.max_scale = self.max_scale.max(d.scale());
+	}
+
+	fn is_string(&self) -> bool {
+		!(self.boolean || self.integer || self.decimal || self.float) && self.dates.is_empty() && self.timestamps.is_empty()
+	}
+}
+
+/// Infers a `Schema` from the first `sample_size` data atoms that follow the `HeaderRow`.
+///
+/// Columns are named from the header; if data arrives without one, they
+/// are named `column_1`, `column_2`, …
+///
+#[derive(Debug)]
+pub struct SchemaInference {
+	sample_size: usize,
+	sampled:     usize,
+	names:       Option<Vec<String>>,
+	columns:     Vec<ColumnInference>,
+}
+
+impl SchemaInference {
+	pub fn new(sample_size: usize) -> Self {
+		SchemaInference{sample_size, sampled: 0, names: None, columns: Vec::new()}
+	}
+
+	/// Sample atoms from `atoms` until enough rows have been seen, then return the schema.
+	pub fn infer<I: Iterator<Item = Atom>>(atoms: I, sample_size: usize) -> Schema {
+		let mut inference = SchemaInference::new(sample_size);
+		for atom in atoms {
+			if !inference.accept(&atom) { break }
+		}
+		inference.schema()
+	}
+
+	pub fn sampled(&self)     -> usize                { self.sampled  }
+	pub fn columns(&self)     -> &[ColumnInference]   { &self.columns }
+	pub fn is_complete(&self) -> bool                 { self.sampled >= self.sample_size }
+
+	/// Feed one atom. Returns `false` once the sample is complete.
+	pub fn accept(&mut self, atom: &Atom) -> bool {
+		if self.is_complete() { return false }
+		match atom {
+			Atom::HeaderRow(row) if self.names.is_none() => {
+				self.names = Some(row.iter_str().map(str::to_owned).collect());
+			}
+			Atom::StringRowAtom(row) => self.observe(row.iter_str().map(Some)),
+			Atom::ByteRowAtom(row)   => self.observe(row.into_iter().map(|b| std::str::from_utf8(b).ok())),
+			Atom::TypedRowAtom(row)  => self.observe_with(row.iter(), ColumnInference::observe_value),
+			Atom::RowBatchAtom(batch) => {
+				for row in 0..batch.len() {
+					if self.is_complete() { break }
+					self.observe(batch.columns().iter().map(|c| std::str::from_utf8(c.get(row).unwrap_or_default()).ok()));
+				}
+			}
+			_                        => {}
+		}
+		!self.is_complete()
+	}
+
+	pub fn schema(&self) -> Schema {
+		let width  = self.names.as_ref().map_or(0, |n| n.len()).max(self.columns.len());
+		let fields = (0..width)
+			.map(|i| {
+				let name = self.names.as_ref()
+					.and_then(|n| n.get(i).cloned())
+					.unwrap_or_else(|| format!("column_{}", i + 1));
+				self.columns.get(i).cloned().unwrap_or_default().field(&name)
+			})
+			.collect();
+		Schema::new(fields)
+	}
+
+	// Invalid UTF-8 (None) can only be stored as bytes, so it is treated as a string.
+	fn observe<'a, I: Iterator<Item = Option<&'a str>>>(&mut self, cells: I) {
+		self.observe_with(cells, |column, cell| column.observe(cell.unwrap_or("\u{fffd}")));
+	}
+
+	fn observe_with<T, I: Iterator<Item = T>>(&mut self, cells: I, observe: impl Fn(&mut ColumnInference, T)) {
+		let mut count = 0;
+		for (i, cell) in cells.enumerate() {
+			if self.columns.len() <= i {
+				let mut column = ColumnInference::new();
+				column.nulls   = self.sampled as u64;                 // Earlier rows were missing this column
+				self.columns.push(column);
+			}
+			observe(&mut self.columns[i], cell);
+			count += 1;
+		}
+		for column in self.columns.iter_mut().skip(count) {
+			column.nulls += 1;                                       // Short rows leave trailing columns empty
+		}
+		self.sampled += 1;
+	}
+}
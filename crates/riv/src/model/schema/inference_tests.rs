@@ -0,0 +1,118 @@
+use crate::model::ir::atom::Atom;
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::value::ValueType;
+use crate::model::schema::field::Field;
+use crate::model::schema::inference::{ColumnInference, SchemaInference};
+
+fn row(fields: &[&str]) -> ByteRow {
+	let data = fields.concat();
+	let ends = fields.iter().scan(0, |end, f| { *end += f.len(); Some(*end) }).collect::<Vec<_>>();
+	ByteRow::new(data.as_bytes(), &ends)
+}
+
+fn atoms(header: &[&str], rows: &[&[&str]]) -> Vec<Atom> {
+	let mut atoms = vec![Atom::HeaderRow(row(header).as_string_row())];
+	atoms.extend(rows.iter().map(|r| Atom::ByteRowAtom(row(r))));
+	atoms
+}
+
+fn column_type(values: &[&str]) -> ValueType {
+	let mut column = ColumnInference::new();
+	values.iter().for_each(|v| column.observe(v));
+	column.value_type()
+}
+
+#[test]
+fn numeric_conflicts_widen() {
+	assert_eq!(column_type(&["1", "2", "3"]),      ValueType::Integer);
+	assert_eq!(column_type(&["1", "2.50", "3"]),   ValueType::Decimal);
+	assert_eq!(column_type(&["1", "2.5", "3e10"]), ValueType::Float);
+	assert_eq!(column_type(&["1", "two"]),         ValueType::String);
+}
+
+#[test]
+fn other_types() {
+	assert_eq!(column_type(&["true", "FALSE"]),                    ValueType::Boolean);
+	assert_eq!(column_type(&["2025-01-31", "2025-03-17"]),         ValueType::Date);
+	assert_eq!(column_type(&["2025-01-31 10:00:00"]),              ValueType::Timestamp);
+	assert_eq!(column_type(&["2025-01-31", "2025-01-31 10:00:00"]), ValueType::String);
+	assert_eq!(column_type(&["", " "]),                            ValueType::Null);
+}
+
+#[test]
+fn date_format_is_recorded() {
+	let mut column = ColumnInference::new();
+	column.observe("31-JAN-2025");
+	column.observe("17-Mar-2025");
+	assert_eq!(column.value_type(), ValueType::Date);
+	assert_eq!(column.format(), Some("%d-%b-%Y"));
+}
+
+#[test]
+fn ambiguous_dates_are_resolved_by_later_values() {
+	let mut column = ColumnInference::new();
+	column.observe("03/04/2025");
+	assert_eq!(column.format(), Some("%m/%d/%Y"));
+	column.observe("17/03/2025");
+	assert_eq!(column.format(), Some("%d/%m/%Y"));
+}
+
+#[test]
+fn schema_from_header_and_rows() {
+	let atoms  = atoms(&["City", "Temperature", "Updated"], &[
+		&["Tokyo",   "35.6897", "2025-03-17"],
+		&["Jakarta", "-6.1750", ""],
+	]);
+	let schema = SchemaInference::infer(atoms.into_iter(), 10);
+	let expected = vec![
+		Field::new("City",        ValueType::String,  false),
+		Field::new("Temperature", ValueType::Decimal, false),
+		Field::new("Updated",     ValueType::Date,    true).with_format("%Y-%m-%d"),
+	];
+	assert_eq!(schema.fields(), expected.as_slice());
+}
+
+#[test]
+fn sampling_stops_after_sample_size() {
+	let atoms         = atoms(&["n"], &[&["1"], &["2"], &["x"]]);
+	let mut inference = SchemaInference::new(2);
+	let accepted      = atoms.iter().take_while(|a| inference.accept(a)).count();
+	assert_eq!(accepted, 2);                                          // Header and first row
+	assert!(!inference.accept(&atoms[2]));
+	assert!(inference.is_complete());
+	assert_eq!(inference.schema().fields()[0].value_type, ValueType::Integer);
+}
+
+#[test]
+fn missing_header_names_columns() {
+	let atoms  = vec![Atom::ByteRowAtom(row(&["1", "a"]))];
+	let schema = SchemaInference::infer(atoms.into_iter(), 10);
+	assert_eq!(schema.names(), vec!["column_1", "column_2"]);
+}
+
+#[test]
+fn batches_are_sampled_row_by_row() {
+	use crate::model::ir::row_batch::RowBatch;
+	let rows      = [row(&["1", "x"]), row(&["2", ""]), row(&["3", "z"])];
+	let batch     = RowBatch::from_rows(&rows).unwrap();
+	let mut infer = SchemaInference::new(2);
+	infer.accept(&Atom::HeaderRow(row(&["id", "name"]).as_string_row()));
+	infer.accept(&Atom::RowBatchAtom(batch));
+	assert_eq!(infer.sampled(), 2);
+	assert_eq!(infer.schema().fields()[0], Field::new("id", ValueType::Integer, false));
+	assert!(infer.schema().fields()[1].nullable);
+}
+
+#[test]
+fn typed_values_keep_their_type() {
+	use crate::model::ir::typed_row::TypedRow;
+	use crate::model::ir::value::Value;
+	let typed      = |a: Value, b: Value| Atom::TypedRowAtom(TypedRow::new(vec![a, b]));
+	let mut infer  = SchemaInference::new(10);
+	infer.accept(&Atom::HeaderRow(row(&["reading", "label"]).as_string_row()));
+	infer.accept(&typed(Value::Null,       Value::Integer(1)));
+	infer.accept(&typed(Value::Float(2.0), Value::String("x".into())));
+	let fields = infer.schema().fields().to_vec();
+	assert_eq!((fields[0].value_type, fields[0].nullable), (ValueType::Float, true));
+	assert_eq!(fields[1].value_type, ValueType::String);                // Typed and text values disagree
+}
@@ -0,0 +1,46 @@
+use chrono::NaiveDate;
+use crate::Error;
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::value::{Value, ValueType};
+use crate::model::schema::field::Field;
+use crate::model::schema::Schema;
+
+fn trade_schema() -> Schema {
+	Schema::new(vec![
+		Field::new("trade_id",   ValueType::Integer, false),
+		Field::new("trade_date", ValueType::Date,    true).with_format("%d-%b-%Y"),
+	])
+}
+
+#[test]
+fn json_round_trip() {
+	let schema = trade_schema();
+	let json   = schema.to_json().unwrap();
+	assert!(json.contains(r#""type": "integer""#));
+	assert!(json.contains(r#""format": "%d-%b-%Y""#));
+	assert_eq!(Schema::from_json(&json).unwrap(), schema);
+}
+
+#[test]
+fn lookup_by_name() {
+	let schema = trade_schema();
+	assert_eq!(schema.index_of("trade_date"), Some(1));
+	assert_eq!(schema.field("missing"), None);
+	assert_eq!(schema.value_types(), vec![ValueType::Integer, ValueType::Date]);
+}
+
+#[test]
+fn convert_uses_field_format() {
+	let row   = ByteRow::new(b"1731-JAN-2025", &[2, 13]);
+	let typed = trade_schema().convert_byte_row(&row).unwrap();
+	let date  = NaiveDate::from_ymd_opt(2025, 1, 31).unwrap();
+	assert_eq!(typed.get(0), Some(&Value::Integer(17)));
+	assert_eq!(typed.get(1), Some(&Value::Date(date)));
+}
+
+#[test]
+fn convert_rejects_null_in_required_field() {
+	let row = ByteRow::new(b"31-JAN-2025", &[0, 11]);
+	let err = trade_schema().convert_byte_row(&row).unwrap_err();
+	assert_eq!(err, Error::Parse("trade_id: null in non-nullable field".into()));
+}
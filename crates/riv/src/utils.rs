@@ -1,8 +1,13 @@
-
 pub mod digest;
+pub mod random;
+pub mod spill;
 pub mod test_file;
 
 #[cfg(test)]
 mod digest_tests;
 #[cfg(test)]
+mod random_tests;
+#[cfg(test)]
+mod spill_tests;
+#[cfg(test)]
 mod test_file_tests;
@@ -37,3 +37,21 @@ pub fn sha256_digest_bytes(input: &[u8]) -> String {
 	let encoded = hex::encode(result);
 	encoded
 }
+
+/// HMAC-SHA256 of `input` under `key` (RFC 2104), hex encoded.
+pub fn hmac_sha256_digest(key: &[u8], input: &[u8]) -> String {
+	const BLOCK: usize = 64;
+	let mut block = [0u8; BLOCK];
+	if key.len() > BLOCK {
+		block[..32].copy_from_slice(&Sha256::digest(key));
+	} else {
+		block[..key.len()].copy_from_slice(key);
+	}
+	let mut inner = Sha256::new();
+	inner.update(block.map(|b| b ^ 0x36));
+	inner.update(input);
+	let mut outer = Sha256::new();
+	outer.update(block.map(|b| b ^ 0x5c));
+	outer.update(inner.finalize());
+	hex::encode(outer.finalize())
+}
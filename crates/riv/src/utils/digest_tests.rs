@@ -1,3 +1,4 @@
+use crate::utils::digest::hmac_sha256_digest;
 use crate::utils::digest::sha256_digest_file;
 use crate::utils::digest::sha256_digest_string;
 use crate::utils::digest::SHA256_EMPTY_STRING;
@@ -68,3 +69,13 @@ fn test_existing_file_correct_hash() {
 		eprintln!("Warning: Failed to remove temporary test file '{}': {}", test_file_path, e);
 	}
 }
+
+// Test cases 2 and 6 of RFC 4231; the second key is longer than a block.
+//
+#[test]
+fn test_hmac_sha256() {
+	let actual = hmac_sha256_digest(b"Jefe", b"what do ya want for nothing?");
+	assert_eq!(actual, "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+	let actual = hmac_sha256_digest(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First");
+	assert_eq!(actual, "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54");
+}
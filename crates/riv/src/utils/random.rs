@@ -0,0 +1,36 @@
+/// SplitMix64: small, fast and good enough for test data and sampling.
+/// Kept in-house so a seed gives the same numbers regardless of
+/// dependency versions.
+///
+pub struct SplitMix64 {
+	state: u64,
+}
+
+impl SplitMix64 {
+	pub fn new(seed: u64) -> Self { SplitMix64{state: seed} }
+
+	pub fn next_u64(&mut self) -> u64 {
+		self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+		let mut z  = self.state;
+		z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+		z ^ (z >> 31)
+	}
+
+	/// Uniform in `[0, 1)`.
+	pub fn unit(&mut self) -> f64 {
+		(self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+	}
+
+	/// Uniform in `[0, bound)`.
+	pub fn below(&mut self, bound: u64) -> u64 {
+		((self.next_u64() as u128 * bound as u128) >> 64) as u64
+	}
+
+	/// Standard normal, by Box-Muller.
+	pub fn normal(&mut self) -> f64 {
+		let u1 = 1.0 - self.unit();
+		let u2 = self.unit();
+		(-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+	}
+}
@@ -0,0 +1,20 @@
+use crate::utils::random::SplitMix64;
+
+// Reference outputs of SplitMix64 seeded with 0
+//
+#[test]
+fn matches_the_reference_sequence() {
+	let mut random = SplitMix64::new(0);
+	assert_eq!(random.next_u64(), 0xE220_A839_7B1D_CDAF);
+	assert_eq!(random.next_u64(), 0x6E78_9E6A_A1B9_65F4);
+}
+
+#[test]
+fn draws_stay_in_range() {
+	let mut random = SplitMix64::new(7);
+	for _ in 0..1_000 {
+		assert!(random.below(3) < 3);
+		assert!((0.0..1.0).contains(&random.unit()));
+	}
+	assert_eq!(SplitMix64::new(7).below(1), 0);
+}
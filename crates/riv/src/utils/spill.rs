@@ -0,0 +1,217 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use chrono::{DateTime, Datelike, NaiveDate};
+use crate::Error;
+use crate::error::IoErrorWrapper;
+use crate::model::coordinate::coordinate::Coordinate;
+use crate::model::coordinate::text_location::TextLocation;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::decimal::Decimal;
+use crate::model::ir::string_row::StringRow;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+
+const BYTE_ROW:   u8 = 0;
+const STRING_ROW: u8 = 1;
+const TYPED_ROW:  u8 = 2;
+
+/// Data rows written to an anonymous temporary file and read back in the
+/// same order, so a relay can hold more rows than fit in memory.
+///
+/// Byte, string and typed rows keep their kind, values and coordinate.
+/// The file is removed when the spill, or the reader made from it, is
+/// dropped.
+///
+pub struct RowSpill {
+	writer: BufWriter<File>,
+	rows:   u64,
+}
+
+impl RowSpill {
+	pub fn new() -> Result<Self, Error> {
+		let file = tempfile::tempfile().map_err(IoErrorWrapper::from)?;
+		Ok(RowSpill{writer: BufWriter::new(file), rows: 0})
+	}
+
+	pub fn rows(&self) -> u64 { self.rows }
+
+	/// Append a data row; any other atom is refused.
+	pub fn push(&mut self, atom: &Atom) -> Result<(), Error> {
+		let mut record = Vec::new();
+		match atom {
+			Atom::ByteRowAtom(row) => {
+				record.push(BYTE_ROW);
+				put_coordinate(&mut record, row.coordinate());
+				put_u32(&mut record, row.length());
+				(0..row.length() as usize).for_each(|i| put_bytes(&mut record, row.get(i).unwrap_or_default()));
+			}
+			Atom::StringRowAtom(row) => {
+				record.push(STRING_ROW);
+				put_coordinate(&mut record, row.coordinate());
+				put_u32(&mut record, row.count());
+				row.iter_str().for_each(|s| put_bytes(&mut record, s.as_bytes()));
+			}
+			Atom::TypedRowAtom(row) => {
+				record.push(TYPED_ROW);
+				put_coordinate(&mut record, row.coordinate());
+				put_u32(&mut record, row.count());
+				row.iter().for_each(|v| put_value(&mut record, v));
+			}
+			other => return Err(Error::InvalidInput(format!("Only data rows can be spilled, not {:?}", other.atom_type()))),
+		}
+		self.writer.write_all(&record).map_err(IoErrorWrapper::from)?;
+		self.rows += 1;
+		Ok(())
+	}
+
+	/// Finish writing and read the rows back from the start.
+	pub fn into_reader(self) -> Result<RowSpillReader, Error> {
+		let mut file = self.writer.into_inner().map_err(|e| IoErrorWrapper::from(e.into_error()))?;
+		file.seek(SeekFrom::Start(0)).map_err(IoErrorWrapper::from)?;
+		Ok(RowSpillReader{reader: BufReader::new(file), remaining: self.rows})
+	}
+}
+
+/// Rows of a `RowSpill`, in the order they were pushed.
+pub struct RowSpillReader {
+	reader:    BufReader<File>,
+	remaining: u64,
+}
+
+impl RowSpillReader {
+	fn read_row(&mut self) -> Result<Atom, Error> {
+		let kind       = self.u8()?;
+		let coordinate = self.coordinate()?;
+		let count      = self.u32()? as usize;
+		match kind {
+			BYTE_ROW   => {
+				let fields = (0..count).map(|_| self.bytes()).collect::<Result<Vec<_>, _>>()?;
+				Ok(Atom::ByteRowAtom(ByteRow::from_fields(fields.iter().map(Vec::as_slice)).with_coordinate(coordinate)))
+			}
+			STRING_ROW => {
+				let values = (0..count).map(|_| self.string()).collect::<Result<Vec<_>, _>>()?;
+				Ok(Atom::StringRowAtom(StringRow::from_values(values).with_coordinate(coordinate)))
+			}
+			TYPED_ROW  => {
+				let values = (0..count).map(|_| self.value()).collect::<Result<Vec<_>, _>>()?;
+				Ok(Atom::TypedRowAtom(TypedRow::new(values).with_coordinate(coordinate)))
+			}
+			other      => Err(corrupt(format!("row kind {}", other))),
+		}
+	}
+
+	fn exact<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+		let mut buffer = [0u8; N];
+		self.reader.read_exact(&mut buffer).map_err(IoErrorWrapper::from)?;
+		Ok(buffer)
+	}
+
+	fn u8(&mut self)  -> Result<u8, Error>  { Ok(self.exact::<1>()?[0]) }
+	fn u32(&mut self) -> Result<u32, Error> { Ok(u32::from_le_bytes(self.exact()?)) }
+	fn u64(&mut self) -> Result<u64, Error> { Ok(u64::from_le_bytes(self.exact()?)) }
+	fn i64(&mut self) -> Result<i64, Error> { Ok(i64::from_le_bytes(self.exact()?)) }
+
+	fn bytes(&mut self) -> Result<Vec<u8>, Error> {
+		let mut buffer = vec![0u8; self.u32()? as usize];
+		self.reader.read_exact(&mut buffer).map_err(IoErrorWrapper::from)?;
+		Ok(buffer)
+	}
+
+	fn string(&mut self) -> Result<String, Error> {
+		String::from_utf8(self.bytes()?).map_err(|_| corrupt("text".to_owned()))
+	}
+
+	fn location(&mut self) -> Result<TextLocation, Error> {
+		Ok(TextLocation{line: self.u64()?, column: self.u32()?, byte: self.u64()?})
+	}
+
+	fn coordinate(&mut self) -> Result<Coordinate, Error> {
+		match self.u8()? {
+			0 => Ok(Coordinate::Undefined),
+			1 => Ok(Coordinate::Position(self.location()?)),
+			2 => Ok(Coordinate::Extent{start: self.location()?, end: self.location()?}),
+			n => Err(corrupt(format!("coordinate kind {}", n))),
+		}
+	}
+
+	fn value(&mut self) -> Result<Value, Error> {
+		let value = match self.u8()? {
+			0 => Value::Null,
+			1 => Value::Boolean(self.u8()? != 0),
+			2 => Value::Integer(self.i64()?),
+			3 => Value::Float(f64::from_bits(self.u64()?)),
+			4 => Value::Decimal(Decimal::new(i128::from_le_bytes(self.exact()?), self.u32()?).map_err(|_| corrupt("decimal".to_owned()))?),
+			5 => {
+				let days = i32::from_le_bytes(self.exact()?);
+				Value::Date(NaiveDate::from_num_days_from_ce_opt(days).ok_or_else(|| corrupt("date".to_owned()))?)
+			}
+			6 => {
+				let (seconds, nanos) = (self.i64()?, self.u32()?);
+				Value::Timestamp(DateTime::from_timestamp(seconds, nanos).ok_or_else(|| corrupt("timestamp".to_owned()))?.naive_utc())
+			}
+			7 => Value::String(self.string()?),
+			8 => Value::Bytes(self.bytes()?),
+			n => return Err(corrupt(format!("value kind {}", n))),
+		};
+		Ok(value)
+	}
+}
+
+impl Iterator for RowSpillReader {
+	type Item = Result<Atom, Error>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.remaining == 0 { return None }
+		self.remaining -= 1;
+		Some(self.read_row())
+	}
+}
+
+fn corrupt(what: String) -> Error {
+	Error::General(format!("Spill file is corrupt: bad {}", what))
+}
+
+fn put_u32(out: &mut Vec<u8>, n: u32) { out.extend_from_slice(&n.to_le_bytes()) }
+
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+	put_u32(out, bytes.len() as u32);
+	out.extend_from_slice(bytes);
+}
+
+fn put_location(out: &mut Vec<u8>, location: TextLocation) {
+	out.extend_from_slice(&location.line.to_le_bytes());
+	put_u32(out, location.column);
+	out.extend_from_slice(&location.byte.to_le_bytes());
+}
+
+fn put_coordinate(out: &mut Vec<u8>, coordinate: Coordinate) {
+	match coordinate {
+		Coordinate::Undefined          => out.push(0),
+		Coordinate::Position(at)       => { out.push(1); put_location(out, at); }
+		Coordinate::Extent{start, end} => { out.push(2); put_location(out, start); put_location(out, end); }
+	}
+}
+
+fn put_value(out: &mut Vec<u8>, value: &Value) {
+	match value {
+		Value::Null         => out.push(0),
+		Value::Boolean(b)   => { out.push(1); out.push(*b as u8); }
+		Value::Integer(i)   => { out.push(2); out.extend_from_slice(&i.to_le_bytes()); }
+		Value::Float(f)     => { out.push(3); out.extend_from_slice(&f.to_bits().to_le_bytes()); }
+		Value::Decimal(d)   => {
+			out.push(4);
+			out.extend_from_slice(&d.mantissa().to_le_bytes());
+			put_u32(out, d.scale());
+		}
+		Value::Date(d)      => { out.push(5); out.extend_from_slice(&d.num_days_from_ce().to_le_bytes()); }
+		Value::Timestamp(t) => {
+			let utc = t.and_utc();
+			out.push(6);
+			out.extend_from_slice(&utc.timestamp().to_le_bytes());
+			put_u32(out, utc.timestamp_subsec_nanos());
+		}
+		Value::String(s)    => { out.push(7); put_bytes(out, s.as_bytes()); }
+		Value::Bytes(b)     => { out.push(8); put_bytes(out, b); }
+	}
+}
@@ -0,0 +1,44 @@
+use chrono::NaiveDate;
+use crate::Error;
+use crate::model::coordinate::coordinate::Coordinate;
+use crate::model::coordinate::text_location::TextLocation;
+use crate::model::ir::atom::Atom;
+use crate::model::ir::byte_row::ByteRow;
+use crate::model::ir::decimal::Decimal;
+use crate::model::ir::string_row::StringRow;
+use crate::model::ir::typed_row::TypedRow;
+use crate::model::ir::value::Value;
+use crate::utils::spill::RowSpill;
+
+#[test]
+fn rows_come_back_in_order_with_their_kind_and_coordinate() {
+	let at      = Coordinate::at_location(TextLocation{line: 3, column: 1, byte: 40});
+	let stamp   = NaiveDate::from_ymd_opt(2025, 3, 17).unwrap().and_hms_nano_opt(8, 30, 0, 5).unwrap();
+	let typed   = vec![
+		Value::Null, Value::Boolean(true), Value::Integer(-7), Value::Float(0.25), Value::Decimal(Decimal::new(12345, 2).unwrap()),
+		Value::Date(stamp.date()), Value::Timestamp(stamp), Value::String("né".into()), Value::Bytes(vec![0, 255]),
+	];
+	let mut spill = RowSpill::new().unwrap();
+	spill.push(&Atom::ByteRowAtom(ByteRow::from_fields([&b"a"[..], b"", b"c;d"]).with_coordinate(at))).unwrap();
+	spill.push(&Atom::StringRowAtom(StringRow::from_values(vec!["x".into(), "".into()]))).unwrap();
+	spill.push(&Atom::TypedRowAtom(TypedRow::new(typed.clone()))).unwrap();
+	assert_eq!(spill.rows(), 3);
+
+	let rows = spill.into_reader().unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+	match &rows[..] {
+		[Atom::ByteRowAtom(b), Atom::StringRowAtom(s), Atom::TypedRowAtom(t)] => {
+			assert_eq!(b.as_string_row().iter_str().collect::<Vec<_>>(), ["a", "", "c;d"]);
+			assert_eq!(b.coordinate(), at);
+			assert_eq!(s.iter_str().collect::<Vec<_>>(), ["x", ""]);
+			assert_eq!(t.iter().cloned().collect::<Vec<_>>(), typed);
+		}
+		other => panic!("unexpected rows {:?}", other),
+	}
+}
+
+#[test]
+fn only_data_rows_can_be_spilled() {
+	let mut spill = RowSpill::new().unwrap();
+	assert!(matches!(spill.push(&Atom::EndTask), Err(Error::InvalidInput(_))));
+	assert_eq!(spill.into_reader().unwrap().count(), 0);
+}
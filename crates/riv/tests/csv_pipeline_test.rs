@@ -35,16 +35,20 @@ pub fn test_csv_pipeline() -> Result<(), Error> {
 	assert_eq!(target_msg, ());
 
 	tracing::info!("Pulling data through the pipeline");
+	let mut out = Vec::new();
 	for atom in &mut src {
-		let _ = match relay.accept(atom) {
-			Some(revised) => dst.accept(revised),
-			None          => Ok(()),
-		};
+		relay.accept(atom, &mut out);
+		for revised in out.drain(..) {
+			let _ = dst.accept(revised);
+		}
 	}
 
 	tracing::info!("Finishing components");
 	let source_ok = src.close()?;
-	let relay_ok  = relay.finish();
+	let relay_ok  = relay.finish(&mut out).is_ok();
+	for revised in out.drain(..) {
+		let _ = dst.accept(revised);
+	}
 	dst.close();
 	assert!(source_ok);
 	assert!(relay_ok);
@@ -1,4 +1,6 @@
+use riv::component::batching::Batched;
 use riv::component::relay::console_relay::ConsoleRelay;
+use riv::component::relay::header_relay::{HeaderRelay, HEADER_MAPPING_PREFIX};
 use riv::component::relay::Relay;
 use riv::component::source::Source;
 use riv::{data_file_path_as_str, Error};
@@ -8,6 +10,15 @@ use riv::component::source::csv_byte_source::CsvByteSource;
 use std::fs::File;
 use riv::component::relay::empty_relay_config::EmptyRelayConfig;
 use riv::component::sink::sink_settings::SinkSettings;
+use riv::component::sink::sqlite_sink::RUN_METADATA_TABLE;
+use riv::model::ir::atom::Atom;
+use riv::model::ir::byte_row::ByteRow;
+use riv::model::ir::typed_row::TypedRow;
+use riv::model::ir::value::{Value, ValueType};
+use riv::model::schema::Schema;
+use riv::model::schema::field::Field;
+use rusqlite::Connection;
+use tempfile::NamedTempFile;
 
 #[test]
 pub fn run_sqlite_pipeline() -> Result<(), Error> {
@@ -40,18 +51,166 @@ pub fn run_sqlite_pipeline() -> Result<(), Error> {
 	assert_eq!(target_msg, ());
 
 	tracing::info!("Pulling data through the pipeline");
+	let mut out = Vec::new();
 	for atom in &mut src {
-		let _ = match relay.accept(atom) {
-			Some(revised) => dst.accept(revised),
-			None          => Ok(()),
-		};
+		relay.accept(atom, &mut out);
+		for revised in out.drain(..) {
+			let _ = dst.accept(revised);
+		}
 	}
 
 	tracing::info!("Finishing components");
 	let source_ok = src.close()?;
-	let relay_ok  = relay.finish();
+	let relay_ok  = relay.finish(&mut out).is_ok();
+	for revised in out.drain(..) {
+		let _ = dst.accept(revised);
+	}
 	dst.close();
 	assert!(source_ok);
 	assert!(relay_ok);
 	Ok(())
 }
+
+#[test]
+pub fn typed_rows_are_stored_natively() -> Result<(), Error> {
+	let db_file    = NamedTempFile::new().expect("temp file");
+	let target_cfg = SinkSettings::sqlite(db_file.path(), "typed");
+	let (tx, _)    = std::sync::mpsc::channel();
+	let mut dst    = target_cfg.build_sink(402, tx)?;
+	dst.initialize(&target_cfg)?;
+
+	let header = ByteRow::new(b"CityTemperature", &[4, 15]).as_string_row();
+	let row    = TypedRow::new(vec![Value::String("Tokyo".into()), Value::Float(35.6897)]);
+	dst.accept(Atom::HeaderRow(header))?;
+	dst.accept(Atom::TypedRowAtom(row))?;
+	dst.close();
+
+	let cx             = Connection::open(db_file.path())?;
+	let (kind, value)  = cx.query_row("SELECT typeof(Temperature), Temperature FROM typed", [], |r| {
+		Ok((r.get::<_, String>(0)?, r.get::<_, f64>(1)?))
+	})?;
+	assert_eq!(kind,  "real");
+	assert_eq!(value, 35.6897);
+	Ok(())
+}
+
+#[test]
+pub fn row_batches_are_inserted_directly() -> Result<(), Error> {
+	let db_file    = NamedTempFile::new().expect("temp file");
+	let target_cfg = SinkSettings::sqlite(db_file.path(), "batched");
+	let (tx, _)    = std::sync::mpsc::channel();
+	let mut dst    = target_cfg.build_sink(403, tx)?;
+	dst.initialize(&target_cfg)?;
+
+	let input = "City;Temperature\nTokyo;35.6\nOslo;-3.1\nLima;18.0\n";
+	let src   = Batched::new(CsvByteSource::new(input.as_bytes()), 2);
+	let mut batches = 0;
+	for atom in src {
+		batches += matches!(atom, Atom::RowBatchAtom(_)) as usize;
+		dst.accept(atom)?;
+	}
+	dst.close();
+	assert_eq!(batches, 2);
+
+	let cx    = Connection::open(db_file.path())?;
+	let count = cx.query_row("SELECT count(*) FROM batched", [], |r| r.get::<_, i64>(0))?;
+	let last  = cx.query_row("SELECT City FROM batched WHERE rowid = 3", [], |r| r.get::<_, String>(0))?;
+	assert_eq!(count, 3);
+	assert_eq!(last,  "Lima");
+	Ok(())
+}
+
+fn declared_type(cx: &Connection, table: &str, column: &str) -> rusqlite::Result<String> {
+	cx.query_row("SELECT type FROM pragma_table_info(?1) WHERE name = ?2", [table, column], |r| r.get(0))
+}
+
+#[test]
+pub fn column_types_are_inferred_past_leading_nulls() -> Result<(), Error> {
+	let db_file    = NamedTempFile::new().expect("temp file");
+	let target_cfg = SinkSettings::sqlite(db_file.path(), "inferred");
+	let (tx, _)    = std::sync::mpsc::channel();
+	let mut dst    = target_cfg.build_sink(404, tx)?;
+	dst.initialize(&target_cfg)?;
+
+	let input = "City;Population\nTokyo;\nOslo;709037\n";
+	for atom in CsvByteSource::new(input.as_bytes()) {
+		dst.accept(atom)?;
+	}
+	let header = ByteRow::new(b"CityReading", &[4, 11]).as_string_row();
+	dst.accept(Atom::HeaderRow(header))?;                                        // Writes the rows held for the first
+	dst.accept(Atom::TypedRowAtom(TypedRow::new(vec![Value::String("Lima".into()), Value::Null])))?;
+	dst.accept(Atom::TypedRowAtom(TypedRow::new(vec![Value::String("Quito".into()), Value::Float(0.5)])))?;
+	dst.close();
+
+	let cx = Connection::open(db_file.path())?;
+	assert_eq!(declared_type(&cx, "inferred", "Population")?, "INTEGER");
+	let (kind, count) = cx.query_row("SELECT typeof(Population), count(*) FROM inferred WHERE City = 'Oslo'", [], |r| {
+		Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?))
+	})?;
+	assert_eq!((kind.as_str(), count), ("integer", 1));
+
+	let target_cfg = SinkSettings::sqlite(db_file.path(), "readings");
+	let (tx, _)    = std::sync::mpsc::channel();
+	let mut dst    = target_cfg.build_sink(406, tx)?;
+	dst.initialize(&target_cfg)?;
+	dst.accept(Atom::HeaderRow(ByteRow::new(b"CityReading", &[4, 11]).as_string_row()))?;
+	dst.accept(Atom::TypedRowAtom(TypedRow::new(vec![Value::String("Lima".into()), Value::Null])))?;
+	dst.accept(Atom::TypedRowAtom(TypedRow::new(vec![Value::String("Quito".into()), Value::Float(0.5)])))?;
+	dst.close();
+	let cx = Connection::open(db_file.path())?;
+	assert_eq!(declared_type(&cx, "readings", "Reading")?, "REAL");
+	Ok(())
+}
+
+#[test]
+pub fn a_schema_drives_the_conversion() -> Result<(), Error> {
+	let db_file    = NamedTempFile::new().expect("temp file");
+	let schema     = Schema::new(vec![Field::new("id", ValueType::Integer, false), Field::new("day", ValueType::Date, true).with_format("%d.%m.%Y")]);
+	let target_cfg = SinkSettings::sqlite_with_schema(db_file.path(), "declared", schema);
+	let (tx, _)    = std::sync::mpsc::channel();
+	let mut dst    = target_cfg.build_sink(405, tx)?;
+	dst.initialize(&target_cfg)?;
+
+	for atom in CsvByteSource::new("id;day\n1;17.03.2025\nn/a;\n".as_bytes()) {
+		dst.accept(atom)?;
+	}
+	dst.close();
+
+	let cx    = Connection::open(db_file.path())?;
+	let kinds = cx.prepare("SELECT typeof(id), quote(day) FROM declared ORDER BY rowid")?
+		.query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?)))?
+		.collect::<Result<Vec<_>, _>>()?;
+	assert_eq!(declared_type(&cx, "declared", "id")?, "INTEGER");
+	assert_eq!(kinds, [("integer".to_owned(), "'2025-03-17'".to_owned()), ("text".to_owned(), "NULL".to_owned())]);
+	Ok(())
+}
+
+#[test]
+pub fn a_header_mapping_is_kept_as_run_metadata() -> Result<(), Error> {
+	let db_file    = NamedTempFile::new().expect("temp file");
+	let target_cfg = SinkSettings::sqlite(db_file.path(), "renamed");
+	let (tx, _)    = std::sync::mpsc::channel();
+	let mut dst    = target_cfg.build_sink(407, tx)?;
+	let mut relay  = HeaderRelay::new();
+	dst.initialize(&target_cfg)?;
+	relay.initialize(&EmptyRelayConfig)?;
+
+	let mut out = Vec::new();
+	for atom in CsvByteSource::new("Amount;Amount;\n1;2;3\n".as_bytes()) {
+		relay.accept(atom, &mut out);
+	}
+	for atom in out {
+		dst.accept(atom)?;
+	}
+	dst.close();
+
+	let cx       = Connection::open(db_file.path())?;
+	let metadata = cx.prepare(&format!("SELECT name, value FROM {} ORDER BY name", RUN_METADATA_TABLE))?
+		.query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?)))?
+		.collect::<Result<Vec<_>, _>>()?;
+	let expected = [("1.name", "amount"), ("1.original", "Amount"), ("2.name", "amount_2"), ("2.original", "Amount"), ("3.name", "column_3"), ("3.original", "")]
+		.map(|(name, value)| (format!("{}{}", HEADER_MAPPING_PREFIX, name), value.to_owned()));
+	assert_eq!(metadata, expected);
+	assert_eq!(cx.query_row("SELECT amount_2 FROM renamed", [], |r| r.get::<_, i64>(0))?, 2);
+	Ok(())
+}
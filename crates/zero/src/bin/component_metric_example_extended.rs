@@ -56,6 +56,7 @@ impl ProvidesMetrics for FileProcessor {
             byte_count:       self.bytes_processed,
             record_count:     self.lines_read, // Here, a "record" is a "line"
             error_count:      self.errors_encountered,
+            dropped_count:    0,
         }
     }
     fn take_metrics(&mut self) -> ComponentMetrics {
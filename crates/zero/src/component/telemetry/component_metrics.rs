@@ -19,6 +19,7 @@ pub struct ComponentMetrics{
     pub byte_count:       u64,
     pub record_count:     u64,
     pub error_count:      u64,
+    pub dropped_count:    u64,
 }
 
 
@@ -32,6 +33,7 @@ impl ComponentMetrics {
 			byte_count:     0, 
 			record_count:   0,
 			error_count:    0,
+			dropped_count:  0,
 		}
 	}
 	
@@ -44,6 +46,7 @@ impl ComponentMetrics {
 			byte_count:     4994932, 
 			record_count:   1339,
 			error_count:    0,
+			dropped_count:  0,
 		}
 	}
 	pub fn sample_idle(id: u32) -> Self {
@@ -55,6 +58,7 @@ impl ComponentMetrics {
 			byte_count:     7102, 
 			record_count:   1,
 			error_count:    1,
+			dropped_count:  0,
 		}
 	}
 }
@@ -86,6 +90,7 @@ impl ComponentMetrics {
 		self.byte_count      = 0;
 		self.record_count    = 0;
 		self.error_count     = 0;
+		self.dropped_count   = 0;
 		self
 	}
 
@@ -113,6 +118,12 @@ impl ComponentMetrics {
 		 self
 	}
 
+	/// Adds to the count of records deliberately discarded, such as duplicates.
+	pub fn add_dropped(&mut self, records: u64) -> &mut Self {
+		 self.dropped_count += records;
+		 self
+	}
+
 	/// Increments the error count by one.
 	pub fn increment_errors(&mut self) -> &mut Self {
 		 self.error_count += 1;
@@ -204,6 +215,7 @@ impl Add for ComponentMetrics {
             byte_count:     self.byte_count    + rhs.byte_count,
             record_count:   self.record_count  + rhs.record_count,
             error_count:    self.error_count   + rhs.error_count,
+            dropped_count:  self.dropped_count + rhs.dropped_count,
         }
     }
 }
@@ -222,5 +234,6 @@ impl AddAssign for ComponentMetrics {
         self.byte_count      += rhs.byte_count;
         self.record_count    += rhs.record_count;
         self.error_count     += rhs.error_count;
+        self.dropped_count   += rhs.dropped_count;
     }
 }
\ No newline at end of file